@@ -0,0 +1,143 @@
+use std::hint::black_box;
+
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use criterion::Throughput;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use paddler::agent::jsonrpc::Response as AgentJsonRpcResponse;
+use paddler::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::Message as ManagementJsonRpcMessage;
+use paddler::embedding::Embedding;
+use paddler::embedding_normalization_method::EmbeddingNormalizationMethod;
+use paddler::embedding_result::EmbeddingResult;
+use paddler::jsonrpc::ResponseEnvelope;
+use paddler::pooling_type::PoolingType;
+
+const DIMENSIONS: [usize; 3] = [384, 1024, 4096];
+const DOCUMENTS_IN_BATCH: usize = 100;
+
+/// The agent sends one message per embedded document
+fn make_embedding_batch(dimensions: usize) -> Vec<ManagementJsonRpcMessage> {
+    (0..DOCUMENTS_IN_BATCH)
+        .map(|document_index| {
+            ManagementJsonRpcMessage::Response(ResponseEnvelope {
+                request_id: "V1StGXR8_Z5jdHi6B-myT".to_string(),
+                response: AgentJsonRpcResponse::Embedding(EmbeddingResult::Embedding(Embedding {
+                    embedding: (0..dimensions)
+                        .map(|index| ((index * 31 + document_index) % 997) as f32 / 997.0 - 0.5)
+                        .collect(),
+                    normalization_method: EmbeddingNormalizationMethod::None,
+                    pooling_type: PoolingType::Mean,
+                    source_document_id: format!("document-{document_index}"),
+                })),
+            })
+        })
+        .collect()
+}
+
+fn decode_embedding_batch(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("decode_embedding_batch");
+
+    group.throughput(Throughput::Elements(DOCUMENTS_IN_BATCH as u64));
+
+    for dimensions in DIMENSIONS {
+        let embedding_batch = make_embedding_batch(dimensions);
+        let json_batch: Vec<String> = embedding_batch
+            .iter()
+            .map(|message| serde_json::to_string(message).unwrap())
+            .collect();
+        let message_pack_batch: Vec<Vec<u8>> = embedding_batch
+            .iter()
+            .map(|message| rmp_serde::to_vec_named(message).unwrap())
+            .collect();
+
+        group.bench_with_input(
+            BenchmarkId::new("json", dimensions),
+            &json_batch,
+            |bencher, json_batch| {
+                bencher.iter(|| {
+                    for text in json_batch {
+                        black_box(serde_json::from_str::<ManagementJsonRpcMessage>(text).unwrap());
+                    }
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("message_pack", dimensions),
+            &message_pack_batch,
+            |bencher, message_pack_batch| {
+                bencher.iter(|| {
+                    for bytes in message_pack_batch {
+                        black_box(
+                            rmp_serde::from_slice::<ManagementJsonRpcMessage>(bytes).unwrap(),
+                        );
+                    }
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn encode_embedding_batch(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("encode_embedding_batch");
+
+    group.throughput(Throughput::Elements(DOCUMENTS_IN_BATCH as u64));
+
+    for dimensions in DIMENSIONS {
+        let embedding_batch = make_embedding_batch(dimensions);
+
+        group.bench_with_input(
+            BenchmarkId::new("json", dimensions),
+            &embedding_batch,
+            |bencher, embedding_batch| {
+                bencher.iter(|| {
+                    for message in embedding_batch {
+                        black_box(serde_json::to_string(message).unwrap());
+                    }
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("message_pack", dimensions),
+            &embedding_batch,
+            |bencher, embedding_batch| {
+                bencher.iter(|| {
+                    for message in embedding_batch {
+                        black_box(rmp_serde::to_vec_named(message).unwrap());
+                    }
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// Bytes on the wire matter as much as the CPU time, so they are printed next to the results
+fn report_encoded_sizes(_criterion: &mut Criterion) {
+    for dimensions in DIMENSIONS {
+        let embedding_batch = make_embedding_batch(dimensions);
+        let json_size: usize = embedding_batch
+            .iter()
+            .map(|message| serde_json::to_string(message).unwrap().len())
+            .sum();
+        let message_pack_size: usize = embedding_batch
+            .iter()
+            .map(|message| rmp_serde::to_vec_named(message).unwrap().len())
+            .sum();
+
+        println!(
+            "{DOCUMENTS_IN_BATCH} embeddings with {dimensions} dimensions: JSON {json_size} bytes, MessagePack {message_pack_size} bytes"
+        );
+    }
+}
+
+criterion_group!(
+    benches,
+    report_encoded_sizes,
+    encode_embedding_batch,
+    decode_embedding_batch
+);
+criterion_main!(benches);
@@ -9,6 +9,26 @@ export function AgentIssues({ issues }: { issues: Array<AgentIssue> }) {
   return (
     <ul className={agentIssues}>
       {issues.map(function (issue, index) {
+        if ("BalancerRejectedAgent" in issue) {
+          return (
+            <li className={agentIssues__issue} key={index}>
+              <strong>
+                Balancer rejected the agent: {issue.BalancerRejectedAgent}
+              </strong>
+              <strong>What will Paddler do?</strong>{" "}
+              <p>
+                The agent will keep reconnecting to the balancer every few
+                seconds.
+              </p>
+              <strong>What can you do?</strong>{" "}
+              <p>
+                Make sure the agent token matches one of the tokens in the
+                balancer's agent tokens file.
+              </p>
+            </li>
+          );
+        }
+
         if ("ChatTemplateDoesNotCompile" in issue) {
           return (
             <li className={agentIssues__issue} key={index}>
@@ -78,6 +98,35 @@ export function AgentIssues({ issues }: { issues: Array<AgentIssue> }) {
           );
         }
 
+        if ("IncompatibleProtocolVersion" in issue) {
+          const {
+            agent_protocol_version_range,
+            balancer_protocol_version_range,
+          } = issue.IncompatibleProtocolVersion;
+
+          return (
+            <li className={agentIssues__issue} key={index}>
+              <strong>
+                Agent supports protocol versions{" "}
+                {agent_protocol_version_range.min}-
+                {agent_protocol_version_range.max}, but the balancer supports{" "}
+                {balancer_protocol_version_range.min}-
+                {balancer_protocol_version_range.max}
+              </strong>
+              <strong>What will Paddler do?</strong>{" "}
+              <p>
+                The agent will keep reconnecting to the balancer, but it will
+                not receive any requests until the versions are compatible.
+              </p>
+              <strong>What can you do?</strong>{" "}
+              <p>
+                Upgrade the older of the two, so both run compatible Paddler
+                versions.
+              </p>
+            </li>
+          );
+        }
+
         if ("ModelCannotBeLoaded" in issue) {
           return (
             <li className={agentIssues__issue} key={index}>
@@ -6,7 +6,14 @@ import { agentListAgentStatus__progress } from "./AgentListAgentStatus.module.cs
 
 export function AgentListAgentStatus({
   agent: {
+    circuit_consecutive_failures,
+    circuit_recent_failure_ratio,
+    circuit_state,
     desired_slots_total,
+    is_cordoned,
+    is_draining,
+    requests_failed,
+    requests_succeeded,
     slots_processing,
     slots_total,
     state_application_status,
@@ -14,6 +21,49 @@ export function AgentListAgentStatus({
 }: {
   agent: Agent;
 }) {
+  if (is_draining) {
+    return (
+      <div className={agentListAgentStatus__progress}>
+        <abbr title="Agent is shutting down and finishing its current requests">
+          🌙 <i>Draining ({slots_processing} left)</i>
+        </abbr>
+      </div>
+    );
+  }
+
+  if (is_cordoned) {
+    return (
+      <div className={agentListAgentStatus__progress}>
+        <abbr title="Agent finishes its current requests, but does not get new ones">
+          🚧 <i>Cordoned ({slots_processing} processing)</i>
+        </abbr>
+      </div>
+    );
+  }
+
+  switch (circuit_state) {
+    case "HalfOpen":
+      return (
+        <div className={agentListAgentStatus__progress}>
+          <abbr title="Checking if the agent recovered with a single request">
+            🩺 <i>Probing</i>
+          </abbr>
+        </div>
+      );
+    case "Open":
+      return (
+        <div className={agentListAgentStatus__progress}>
+          <abbr
+            title={`${circuit_consecutive_failures} consecutive failures, ${Math.round(circuit_recent_failure_ratio * 100)}% of the recent requests failed (${requests_failed} failed, ${requests_succeeded} succeeded in total)`}
+          >
+            ⛔ <i>Not receiving requests</i>
+          </abbr>
+        </div>
+      );
+    case "Closed":
+      break;
+  }
+
   switch (state_application_status) {
     case "Applied":
       return (
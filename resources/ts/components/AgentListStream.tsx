@@ -2,6 +2,7 @@ import React, { useContext } from "react";
 
 import { PaddlerConfigurationContext } from "../contexts/PaddlerConfigurationContext";
 import { useEventSourceUpdates } from "../hooks/useEventSourceUpdates";
+import { withManagementAccessToken } from "../managementRequest";
 import { matchEventSourceUpdateState } from "../matchEventSourceUpdateState";
 import { AgentsResponseSchema } from "../schemas/AgentsResponse";
 import { AgentList } from "./AgentList";
@@ -10,10 +11,15 @@ import { agentListStream__placeholder } from "./AgentListStream.module.css";
 import { dashboardSectionStreamLoader } from "./dashboardSectionStreamLoader.module.css";
 
 export function AgentListStream() {
-  const { managementAddr } = useContext(PaddlerConfigurationContext);
+  const { managementAddr, managementToken } = useContext(
+    PaddlerConfigurationContext,
+  );
   const eventSourceUpdateState = useEventSourceUpdates({
     schema: AgentsResponseSchema,
-    endpoint: `//${managementAddr}/api/v1/agents/stream`,
+    endpoint: withManagementAccessToken(
+      `//${managementAddr}/api/v1/agents/stream`,
+      managementToken,
+    ),
   });
 
   return matchEventSourceUpdateState(eventSourceUpdateState, {
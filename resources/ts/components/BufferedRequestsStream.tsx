@@ -2,6 +2,7 @@ import React, { useContext } from "react";
 
 import { PaddlerConfigurationContext } from "../contexts/PaddlerConfigurationContext";
 import { useEventSourceUpdates } from "../hooks/useEventSourceUpdates";
+import { withManagementAccessToken } from "../managementRequest";
 import { matchEventSourceUpdateState } from "../matchEventSourceUpdateState";
 import { BufferedRequestsResponseSchema } from "../schemas/BufferedRequestsResponse";
 import { BufferedRequests } from "./BufferedRequests";
@@ -9,11 +10,16 @@ import { BufferedRequests } from "./BufferedRequests";
 import { dashboardSectionStreamLoader } from "./dashboardSectionStreamLoader.module.css";
 
 export function BufferedRequestsStream() {
-  const { managementAddr } = useContext(PaddlerConfigurationContext);
+  const { managementAddr, managementToken } = useContext(
+    PaddlerConfigurationContext,
+  );
 
   const eventSourceUpdateState = useEventSourceUpdates({
     schema: BufferedRequestsResponseSchema,
-    endpoint: `//${managementAddr}/api/v1/buffered_requests/stream`,
+    endpoint: withManagementAccessToken(
+      `//${managementAddr}/api/v1/buffered_requests/stream`,
+      managementToken,
+    ),
   });
 
   return matchEventSourceUpdateState(eventSourceUpdateState, {
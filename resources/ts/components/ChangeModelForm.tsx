@@ -11,6 +11,7 @@ import { ChatTemplateContext } from "../contexts/ChatTemplateContext";
 import { InferenceParametersContext } from "../contexts/InferenceParametersContext";
 import { PaddlerConfigurationContext } from "../contexts/PaddlerConfigurationContext";
 import { useAgentDesiredModelUrl } from "../hooks/useAgentDesiredModelUrl";
+import { managementRequestHeaders } from "../managementRequest";
 import { type BalancerDesiredState } from "../schemas/BalancerDesiredState";
 import { ChatTemplateBehavior } from "./ChatTemplateBehavior";
 import { InferenceParameterCheckbox } from "./InferenceParameterCheckbox";
@@ -34,14 +35,18 @@ import {
 
 export function ChangeModelForm({
   defaultModelUri,
+  etag,
 }: {
   defaultModelUri: null | string;
+  etag: null | string;
 }) {
   const [, navigate] = useLocation();
   const { chatTemplateOverride, useChatTemplateOverride } =
     useContext(ChatTemplateContext);
   const { parameters } = useContext(InferenceParametersContext);
-  const { managementAddr } = useContext(PaddlerConfigurationContext);
+  const { managementAddr, managementToken } = useContext(
+    PaddlerConfigurationContext,
+  );
   const { agentDesiredModelState, modelUri, setModelUri } =
     useAgentDesiredModelUrl({
       defaultModelUri,
@@ -85,14 +90,19 @@ export function ChangeModelForm({
 
       fetch(`//${managementAddr}/api/v1/balancer_desired_state`, {
         method: "PUT",
-        headers: {
+        headers: managementRequestHeaders(managementToken, {
           "Content-Type": "application/json",
-        },
+          "If-Match": etag ?? "*",
+        }),
         body: JSON.stringify(balancerDesiredState),
       })
         .then(function (response) {
           if (response.ok) {
             navigate("/");
+          } else if (response.status === 412) {
+            throw new Error(
+              "Desired state was changed by someone else in the meantime; reload the page to see the current state",
+            );
           } else {
             throw new Error(
               `Failed to update agent desired state: ${response.statusText}`,
@@ -103,7 +113,7 @@ export function ChangeModelForm({
           console.error("Error updating agent desired state:", error);
         });
     },
-    [managementAddr, navigate, balancerDesiredState],
+    [managementAddr, managementToken, navigate, balancerDesiredState, etag],
   );
 
   return (
@@ -46,6 +46,7 @@ export function ChangeModelPage() {
       return <FloatingStatus>Loading desired state...</FloatingStatus>;
     },
     ok({
+      headers,
       response: {
         chat_template_override,
         inference_parameters,
@@ -61,7 +62,10 @@ export function ChangeModelPage() {
           <InferenceParametersContextProvider
             defaultInferenceParameters={inference_parameters}
           >
-            <ChangeModelForm defaultModelUri={modelSchemaToUrl(model)} />
+            <ChangeModelForm
+              defaultModelUri={modelSchemaToUrl(model)}
+              etag={headers.get("ETag")}
+            />
           </InferenceParametersContextProvider>
         </ChatTemplateContextProvider>
       );
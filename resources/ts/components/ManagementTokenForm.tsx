@@ -0,0 +1,50 @@
+import React, { useCallback, type FormEvent } from "react";
+
+import {
+  managementTokenForm,
+  managementTokenForm__formLabel,
+  managementTokenForm__input,
+  managementTokenForm__submitButton,
+} from "./ManagementTokenForm.module.css";
+
+export function ManagementTokenForm({
+  onManagementToken,
+}: {
+  onManagementToken(managementToken: string): void;
+}) {
+  const onSubmit = useCallback(
+    function (evt: FormEvent<HTMLFormElement>) {
+      evt.preventDefault();
+
+      const managementToken = new FormData(evt.currentTarget).get(
+        "management_token",
+      );
+
+      onManagementToken(
+        typeof managementToken === "string" ? managementToken.trim() : "",
+      );
+    },
+    [onManagementToken],
+  );
+
+  return (
+    <form className={managementTokenForm} onSubmit={onSubmit}>
+      <p>
+        The management API requires a token. Leave it empty if the balancer
+        allows anonymous management access.
+      </p>
+      <label className={managementTokenForm__formLabel}>
+        Management token
+        <input
+          autoComplete="off"
+          className={managementTokenForm__input}
+          name="management_token"
+          type="password"
+        />
+      </label>
+      <button className={managementTokenForm__submitButton} type="submit">
+        Open dashboard
+      </button>
+    </form>
+  );
+}
@@ -5,6 +5,7 @@ export type PaddlerConfigurationContextValue = {
   compatOpenAIAddr: string;
   inferenceAddr: string;
   managementAddr: string;
+  managementToken: string;
   maxBufferedRequests: number;
   statsdAddr: string;
   statsdPrefix: string;
@@ -25,6 +26,9 @@ export const PaddlerConfigurationContext =
     get managementAddr(): never {
       throw new Error("PaddlerConfigurationContext not provided");
     },
+    get managementToken(): never {
+      throw new Error("PaddlerConfigurationContext not provided");
+    },
     get maxBufferedRequests(): never {
       throw new Error("PaddlerConfigurationContext not provided");
     },
@@ -2,8 +2,12 @@ import React from "react";
 import { createRoot } from "react-dom/client";
 
 import { Home } from "./components/Home";
+import { ManagementTokenForm } from "./components/ManagementTokenForm";
 import { PaddlerConfigurationContext } from "./contexts/PaddlerConfigurationContext";
 
+// Kept only for the browser tab, so the token never ends up in the page itself.
+const MANAGEMENT_TOKEN_STORAGE_KEY = "paddler_management_token";
+
 class RootNode {
   constructor(private rootNodeElement: HTMLElement) {}
 
@@ -32,23 +36,43 @@ const rootNode = new RootNode(rootNodeElement);
 
 const root = createRoot(rootNodeElement);
 
-root.render(
-  <PaddlerConfigurationContext.Provider
-    value={{
-      bufferedRequestTimeoutMillis: rootNode.getIntFromDataset(
-        "bufferedRequestTimeoutMillis",
-      ),
-      compatOpenAIAddr: rootNode.getStringFromDataset("compatOpenaiAddr"),
-      inferenceAddr: rootNode.getStringFromDataset("inferenceAddr"),
-      managementAddr: rootNode.getStringFromDataset("managementAddr"),
-      maxBufferedRequests: rootNode.getIntFromDataset("maxBufferedRequests"),
-      statsdAddr: rootNode.getStringFromDataset("statsdAddr"),
-      statsdPrefix: rootNode.getStringFromDataset("statsdPrefix"),
-      statsdReportingIntervalMillis: rootNode.getIntFromDataset(
-        "statsdReportingIntervalMillis",
-      ),
-    }}
-  >
-    <Home />
-  </PaddlerConfigurationContext.Provider>,
+function renderDashboard(managementToken: string) {
+  root.render(
+    <PaddlerConfigurationContext.Provider
+      value={{
+        bufferedRequestTimeoutMillis: rootNode.getIntFromDataset(
+          "bufferedRequestTimeoutMillis",
+        ),
+        compatOpenAIAddr: rootNode.getStringFromDataset("compatOpenaiAddr"),
+        inferenceAddr: rootNode.getStringFromDataset("inferenceAddr"),
+        managementAddr: rootNode.getStringFromDataset("managementAddr"),
+        managementToken,
+        maxBufferedRequests: rootNode.getIntFromDataset("maxBufferedRequests"),
+        statsdAddr: rootNode.getStringFromDataset("statsdAddr"),
+        statsdPrefix: rootNode.getStringFromDataset("statsdPrefix"),
+        statsdReportingIntervalMillis: rootNode.getIntFromDataset(
+          "statsdReportingIntervalMillis",
+        ),
+      }}
+    >
+      <Home />
+    </PaddlerConfigurationContext.Provider>,
+  );
+}
+
+const storedManagementToken = sessionStorage.getItem(
+  MANAGEMENT_TOKEN_STORAGE_KEY,
 );
+
+if (storedManagementToken === null) {
+  root.render(
+    <ManagementTokenForm
+      onManagementToken={function (managementToken: string) {
+        sessionStorage.setItem(MANAGEMENT_TOKEN_STORAGE_KEY, managementToken);
+        renderDashboard(managementToken);
+      }}
+    />,
+  );
+} else {
+  renderDashboard(storedManagementToken);
+}
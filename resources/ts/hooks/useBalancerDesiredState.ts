@@ -1,5 +1,7 @@
-import { useCallback } from "react";
+import { useCallback, useContext } from "react";
 
+import { PaddlerConfigurationContext } from "../contexts/PaddlerConfigurationContext";
+import { managementRequestHeaders } from "../managementRequest";
 import { BalancerDesiredStateSchema } from "../schemas/BalancerDesiredState";
 import { useFetchJson } from "./useFetchJson";
 
@@ -8,13 +10,15 @@ export function useBalancerDesiredState({
 }: {
   managementAddr: string;
 }) {
+  const { managementToken } = useContext(PaddlerConfigurationContext);
   const produceFetchPromise = useCallback(
     function (signal: AbortSignal) {
       return fetch(`//${managementAddr}/api/v1/balancer_desired_state`, {
+        headers: managementRequestHeaders(managementToken),
         signal,
       });
     },
-    [managementAddr],
+    [managementAddr, managementToken],
   );
 
   const result = useFetchJson({
@@ -1,5 +1,7 @@
-import { useCallback } from "react";
+import { useCallback, useContext } from "react";
 
+import { PaddlerConfigurationContext } from "../contexts/PaddlerConfigurationContext";
+import { managementRequestHeaders } from "../managementRequest";
 import { ChatTemplateSchema } from "../schemas/ChatTemplate";
 import { useFetchJson } from "./useFetchJson";
 
@@ -12,16 +14,18 @@ export function useChatTemplateOverride({
   agentId: string;
   managementAddr: string;
 }) {
+  const { managementToken } = useContext(PaddlerConfigurationContext);
   const produceFetchPromise = useCallback(
     function (signal: AbortSignal) {
       return fetch(
         `//${managementAddr}/api/v1/agent/${agentId}/chat_template_override`,
         {
+          headers: managementRequestHeaders(managementToken),
           signal,
         },
       );
     },
-    [agentId, managementAddr],
+    [agentId, managementAddr, managementToken],
   );
 
   const result = useFetchJson({
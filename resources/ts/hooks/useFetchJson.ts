@@ -28,6 +28,7 @@ export type LoadingState = {
 export type SuccessState<TResult> = {
   empty: false;
   error: null;
+  headers: Headers;
   loading: false;
   response: TResult;
   ok: true;
@@ -84,20 +85,27 @@ export function useFetchJson<TResponseSchema extends z.ZodType>({
       setFetchState(loadingState);
 
       fetchPromise
-        .then(function (response) {
+        .then(async function (response) {
           if (!response.ok) {
             throw new Error(`HTTP error status: ${response.status}`);
           }
 
-          return response.json();
+          return {
+            headers: response.headers,
+            result: responseSchema.parse(await response.json()),
+          };
         })
-        .then(function (result: unknown) {
-          return responseSchema.parse(result);
-        })
-        .then(function (result: z.infer<TResponseSchema>) {
+        .then(function ({
+          headers,
+          result,
+        }: {
+          headers: Headers;
+          result: z.infer<TResponseSchema>;
+        }) {
           setFetchState({
             empty: false,
             error: null,
+            headers,
             loading: false,
             response: result,
             ok: true,
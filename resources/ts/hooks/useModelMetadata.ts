@@ -1,6 +1,8 @@
-import { useCallback } from "react";
+import { useCallback, useContext } from "react";
 import { z } from "zod";
 
+import { PaddlerConfigurationContext } from "../contexts/PaddlerConfigurationContext";
+import { managementRequestHeaders } from "../managementRequest";
 import { useFetchJson } from "./useFetchJson";
 
 const responseSchema = z
@@ -17,16 +19,18 @@ export function useModelMetadata({
   agentId: string;
   managementAddr: string;
 }) {
+  const { managementToken } = useContext(PaddlerConfigurationContext);
   const produceFetchPromise = useCallback(
     function (signal: AbortSignal) {
       return fetch(
         `//${managementAddr}/api/v1/agent/${agentId}/model_metadata`,
         {
+          headers: managementRequestHeaders(managementToken),
           signal,
         },
       );
     },
-    [agentId, managementAddr],
+    [agentId, managementAddr, managementToken],
   );
 
   const result = useFetchJson({
@@ -0,0 +1,25 @@
+export function managementRequestHeaders(
+  managementToken: string,
+  headers: Record<string, string> = {},
+): Record<string, string> {
+  if (!managementToken) {
+    return headers;
+  }
+
+  return {
+    ...headers,
+    Authorization: `Bearer ${managementToken}`,
+  };
+}
+
+// EventSource cannot send headers, so the token goes into the query string.
+export function withManagementAccessToken(
+  endpoint: string,
+  managementToken: string,
+): string {
+  if (!managementToken) {
+    return endpoint;
+  }
+
+  return `${endpoint}?access_token=${encodeURIComponent(managementToken)}`;
+}
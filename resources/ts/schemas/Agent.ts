@@ -4,14 +4,22 @@ import { AgentIssueSchema } from "./AgentIssue";
 
 export const AgentSchema = z
   .object({
+    circuit_consecutive_failures: z.number(),
+    circuit_recent_failure_ratio: z.number(),
+    circuit_state: z.enum(["Closed", "HalfOpen", "Open"]),
     desired_slots_total: z.number(),
     download_current: z.number(),
     download_filename: z.string().nullable(),
     download_total: z.number(),
     id: z.string(),
+    is_cordoned: z.boolean(),
+    is_draining: z.boolean(),
     issues: z.array(AgentIssueSchema),
     model_path: z.string().nullable(),
     name: z.string().nullable(),
+    protocol_version: z.number(),
+    requests_failed: z.number(),
+    requests_succeeded: z.number(),
     slots_processing: z.number(),
     slots_total: z.number(),
     state_application_status: z.enum([
@@ -1,6 +1,16 @@
 import { z } from "zod";
 
+const ProtocolVersionRangeSchema = z
+  .object({
+    max: z.number(),
+    min: z.number(),
+  })
+  .strict();
+
 export const AgentIssueSchema = z.union([
+  z.object({
+    BalancerRejectedAgent: z.string(),
+  }),
   z.object({
     ChatTemplateDoesNotCompile: z.object({
       error: z.string(),
@@ -13,6 +23,12 @@ export const AgentIssueSchema = z.union([
   z.object({
     HuggingFaceModelDoesNotExist: z.string(),
   }),
+  z.object({
+    IncompatibleProtocolVersion: z.object({
+      agent_protocol_version_range: ProtocolVersionRangeSchema,
+      balancer_protocol_version_range: ProtocolVersionRangeSchema,
+    }),
+  }),
   z.object({
     ModelCannotBeLoaded: z.string(),
   }),
@@ -3,6 +3,15 @@ import { z } from "zod";
 export const BufferedRequestsResponseSchema = z
   .object({
     buffered_requests_current: z.number(),
+    queue: z.array(
+      z
+        .object({
+          priority_class: z.string(),
+          request_id: z.string(),
+          waiting_millis: z.number(),
+        })
+        .strict(),
+    ),
   })
   .strict();
 
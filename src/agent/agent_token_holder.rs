@@ -0,0 +1,29 @@
+use std::sync::RwLock;
+
+/// Token presented to the management server. It is read again on SIGHUP, and the new value
+/// is used from the next connection on
+pub struct AgentTokenHolder {
+    agent_token: RwLock<Option<String>>,
+}
+
+impl AgentTokenHolder {
+    pub fn new(agent_token: Option<String>) -> Self {
+        Self {
+            agent_token: RwLock::new(agent_token),
+        }
+    }
+
+    pub fn get_agent_token(&self) -> Option<String> {
+        self.agent_token
+            .read()
+            .expect("Failed to acquire read lock on agent token")
+            .clone()
+    }
+
+    pub fn set_agent_token(&self, agent_token: Option<String>) {
+        *self
+            .agent_token
+            .write()
+            .expect("Failed to acquire write lock on agent token") = agent_token;
+    }
+}
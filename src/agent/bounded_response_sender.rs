@@ -0,0 +1,94 @@
+use anyhow::Result;
+use anyhow::anyhow;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+
+use crate::full_buffer_policy::FullBufferPolicy;
+
+/// Sends the responses of a single request from a slot. Slots run on their own threads,
+/// so pausing blocks the slot until the responses are forwarded to the balancer.
+#[derive(Debug)]
+pub struct BoundedResponseSender<TResponse> {
+    full_buffer_policy: FullBufferPolicy,
+    response_tx: mpsc::Sender<TResponse>,
+}
+
+impl<TResponse> BoundedResponseSender<TResponse> {
+    pub fn new(full_buffer_policy: FullBufferPolicy, response_tx: mpsc::Sender<TResponse>) -> Self {
+        Self {
+            full_buffer_policy,
+            response_tx,
+        }
+    }
+
+    pub fn get_buffer_depth(&self) -> usize {
+        self.response_tx.max_capacity() - self.response_tx.capacity()
+    }
+
+    pub fn send(&self, response: TResponse) -> Result<()> {
+        match self.full_buffer_policy {
+            FullBufferPolicy::Abort => match self.response_tx.try_send(response) {
+                Ok(()) => Ok(()),
+                Err(TrySendError::Closed(_)) => Err(anyhow!("Response receiver is closed")),
+                Err(TrySendError::Full(_)) => Err(anyhow!(
+                    "Response buffer is full ({} responses), aborting the request",
+                    self.response_tx.max_capacity()
+                )),
+            },
+            FullBufferPolicy::Pause => self
+                .response_tx
+                .blocking_send(response)
+                .map_err(|_| anyhow!("Response receiver is closed")),
+        }
+    }
+}
+
+impl<TResponse> Clone for BoundedResponseSender<TResponse> {
+    fn clone(&self) -> Self {
+        Self {
+            full_buffer_policy: self.full_buffer_policy,
+            response_tx: self.response_tx.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_abort_when_full() -> Result<()> {
+        let (response_tx, mut response_rx) = mpsc::channel::<u32>(2);
+        let sender = BoundedResponseSender::new(FullBufferPolicy::Abort, response_tx);
+
+        sender.send(1)?;
+        sender.send(2)?;
+
+        assert_eq!(sender.get_buffer_depth(), 2);
+        assert!(sender.send(3).is_err());
+        assert_eq!(response_rx.try_recv()?, 1);
+
+        sender.send(3)?;
+
+        assert_eq!(sender.get_buffer_depth(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pause_until_there_is_room() -> Result<()> {
+        let (response_tx, mut response_rx) = mpsc::channel::<u32>(1);
+        let sender = BoundedResponseSender::new(FullBufferPolicy::Pause, response_tx);
+
+        sender.send(1)?;
+
+        let paused_sender = sender.clone();
+        let handle = std::thread::spawn(move || paused_sender.send(2));
+
+        assert_eq!(response_rx.blocking_recv(), Some(1));
+        assert_eq!(response_rx.blocking_recv(), Some(2));
+        assert!(handle.join().is_ok_and(|result| result.is_ok()));
+
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@ use actix::Message;
 use anyhow::Result;
 use tokio::sync::mpsc;
 
+use crate::agent::bounded_response_sender::BoundedResponseSender;
 use crate::agent::from_request_params::FromRequestParams;
 use crate::generated_token_result::GeneratedTokenResult;
 use crate::request_params::ContinueFromConversationHistoryParams;
@@ -11,7 +12,7 @@ use crate::request_params::continue_from_conversation_history_params::tool::tool
 #[rtype(result = "Result<()>")]
 pub struct ContinueFromConversationHistoryRequest {
     pub generate_tokens_stop_rx: mpsc::UnboundedReceiver<()>,
-    pub generated_tokens_tx: mpsc::UnboundedSender<GeneratedTokenResult>,
+    pub generated_tokens_tx: BoundedResponseSender<GeneratedTokenResult>,
     pub params: ContinueFromConversationHistoryParams<ValidatedParametersSchema>,
 }
 
@@ -21,7 +22,7 @@ impl FromRequestParams for ContinueFromConversationHistoryRequest {
 
     fn from_request_params(
         params: Self::RequestParams,
-        generated_tokens_tx: mpsc::UnboundedSender<Self::Response>,
+        generated_tokens_tx: BoundedResponseSender<Self::Response>,
         generate_tokens_stop_rx: mpsc::UnboundedReceiver<()>,
     ) -> Self {
         ContinueFromConversationHistoryRequest {
@@ -2,6 +2,7 @@ use actix::Message;
 use anyhow::Result;
 use tokio::sync::mpsc;
 
+use crate::agent::bounded_response_sender::BoundedResponseSender;
 use crate::agent::from_request_params::FromRequestParams;
 use crate::generated_token_result::GeneratedTokenResult;
 use crate::request_params::ContinueFromRawPromptParams;
@@ -10,7 +11,7 @@ use crate::request_params::ContinueFromRawPromptParams;
 #[rtype(result = "Result<()>")]
 pub struct ContinueFromRawPromptRequest {
     pub generate_tokens_stop_rx: mpsc::UnboundedReceiver<()>,
-    pub generated_tokens_tx: mpsc::UnboundedSender<GeneratedTokenResult>,
+    pub generated_tokens_tx: BoundedResponseSender<GeneratedTokenResult>,
     pub params: ContinueFromRawPromptParams,
 }
 
@@ -20,7 +21,7 @@ impl FromRequestParams for ContinueFromRawPromptRequest {
 
     fn from_request_params(
         params: Self::RequestParams,
-        generated_tokens_tx: mpsc::UnboundedSender<Self::Response>,
+        generated_tokens_tx: BoundedResponseSender<Self::Response>,
         generate_tokens_stop_rx: mpsc::UnboundedReceiver<()>,
     ) -> Self {
         ContinueFromRawPromptRequest {
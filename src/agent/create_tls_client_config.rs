@@ -0,0 +1,51 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use anyhow::anyhow;
+use rustls::ClientConfig;
+use rustls::RootCertStore;
+use rustls::crypto::ring::default_provider;
+
+use crate::read_pem_certificates::read_pem_certificates;
+use crate::read_pem_private_key::read_pem_private_key;
+use crate::read_root_cert_store::read_root_cert_store;
+
+/// Without a CA file, the management server certificate is checked against the public
+/// certificate authorities.
+pub fn create_tls_client_config(
+    management_ca_file: Option<&Path>,
+    tls_cert_file: Option<&Path>,
+    tls_key_file: Option<&Path>,
+) -> Result<ClientConfig> {
+    let root_cert_store = match management_ca_file {
+        Some(management_ca_file) => read_root_cert_store(management_ca_file)?,
+        None => RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        },
+    };
+    let builder = ClientConfig::builder_with_provider(Arc::new(default_provider()))
+        .with_safe_default_protocol_versions()
+        .context("Unable to configure TLS protocol versions")?
+        .with_root_certificates(root_cert_store);
+
+    match (tls_cert_file, tls_key_file) {
+        (Some(tls_cert_file), Some(tls_key_file)) => builder
+            .with_client_auth_cert(
+                read_pem_certificates(tls_cert_file)?,
+                read_pem_private_key(tls_key_file)?,
+            )
+            .with_context(|| {
+                format!(
+                    "Private key {} does not match the certificate {}",
+                    tls_key_file.display(),
+                    tls_cert_file.display()
+                )
+            }),
+        (None, None) => Ok(builder.with_no_client_auth()),
+        _ => Err(anyhow!(
+            "Client certificate requires both the certificate and the private key file"
+        )),
+    }
+}
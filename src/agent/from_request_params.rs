@@ -1,14 +1,16 @@
 use tokio::sync::mpsc;
 
+use crate::agent::bounded_response_sender::BoundedResponseSender;
 use crate::agent::jsonrpc::response::Response;
+use crate::streamable_result::StreamableResult;
 
 pub trait FromRequestParams: Send + Sync {
     type RequestParams;
-    type Response: Into<Response>;
+    type Response: Clone + Into<Response> + StreamableResult;
 
     fn from_request_params(
         params: Self::RequestParams,
-        response_tx: mpsc::UnboundedSender<Self::Response>,
+        response_tx: BoundedResponseSender<Self::Response>,
         stop_rx: mpsc::UnboundedReceiver<()>,
     ) -> Self;
 }
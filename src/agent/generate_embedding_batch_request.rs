@@ -2,6 +2,7 @@ use actix::Message;
 use anyhow::Result;
 use tokio::sync::mpsc;
 
+use crate::agent::bounded_response_sender::BoundedResponseSender;
 use crate::agent::from_request_params::FromRequestParams;
 use crate::embedding_result::EmbeddingResult;
 use crate::request_params::GenerateEmbeddingBatchParams;
@@ -10,7 +11,7 @@ use crate::request_params::GenerateEmbeddingBatchParams;
 #[rtype(result = "Result<()>")]
 pub struct GenerateEmbeddingBatchRequest {
     pub generate_embedding_stop_rx: mpsc::UnboundedReceiver<()>,
-    pub generated_embedding_tx: mpsc::UnboundedSender<EmbeddingResult>,
+    pub generated_embedding_tx: BoundedResponseSender<EmbeddingResult>,
     pub params: GenerateEmbeddingBatchParams,
 }
 
@@ -20,7 +21,7 @@ impl FromRequestParams for GenerateEmbeddingBatchRequest {
 
     fn from_request_params(
         params: Self::RequestParams,
-        generated_embedding_tx: mpsc::UnboundedSender<Self::Response>,
+        generated_embedding_tx: BoundedResponseSender<Self::Response>,
         generate_embedding_stop_rx: mpsc::UnboundedReceiver<()>,
     ) -> Self {
         GenerateEmbeddingBatchRequest {
@@ -17,3 +17,138 @@ pub enum Message {
 }
 
 impl RpcMessage for Message {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anyhow::Result;
+    use serde_json::json;
+
+    use super::*;
+    use crate::agent::jsonrpc::notification_params::AgentRegisteredParams;
+    use crate::agent::jsonrpc::notification_params::ReattachRequestParams;
+    use crate::agent::jsonrpc::notification_params::RegistrationRejectedParams;
+    use crate::agent::jsonrpc::notification_params::SetStateParams;
+    use crate::agent::jsonrpc::notification_params::VersionParams;
+    use crate::agent_desired_state::AgentDesiredState;
+    use crate::embedding_input_document::EmbeddingInputDocument;
+    use crate::embedding_normalization_method::EmbeddingNormalizationMethod;
+    use crate::protocol_feature::ProtocolFeature;
+    use crate::protocol_version_range::ProtocolVersionRange;
+    use crate::request_params::ContinueFromRawPromptParams;
+    use crate::request_params::GenerateEmbeddingBatchParams;
+
+    /// Messages are compared through their JSON form, which is what the other peers still use
+    fn assert_survives_message_pack(message: Message) -> Result<()> {
+        let decoded_message: Message = rmp_serde::from_slice(&rmp_serde::to_vec_named(&message)?)?;
+
+        assert_eq!(
+            serde_json::to_value(decoded_message)?,
+            serde_json::to_value(message)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_survives_message_pack() -> Result<()> {
+        assert_survives_message_pack(Message::Error(ErrorEnvelope {
+            request_id: "request-1".to_string(),
+            error: Error {
+                code: 503,
+                description: "Request request-1 was aborted before it finished".to_string(),
+            },
+        }))
+    }
+
+    #[test]
+    fn test_notifications_survive_message_pack() -> Result<()> {
+        for notification in [
+            Notification::AgentRegistered(AgentRegisteredParams {
+                resume_secret: "resume-secret".to_string(),
+            }),
+            Notification::Heartbeat,
+            Notification::PauseRespondingTo("request-1".to_string()),
+            Notification::ReattachRequest(ReattachRequestParams {
+                received_responses: 2,
+                request_id: "request-1".to_string(),
+            }),
+            Notification::RegistrationRejected(RegistrationRejectedParams {
+                reason: "Invalid agent token".to_string(),
+            }),
+            Notification::ResumeRespondingTo("request-1".to_string()),
+            Notification::SetState(SetStateParams {
+                desired_state: AgentDesiredState::default(),
+            }),
+            Notification::StopRespondingTo("request-1".to_string()),
+            Notification::Version(VersionParams {
+                features: BTreeSet::from(ProtocolFeature::SUPPORTED),
+                protocol_version_range: Some(ProtocolVersionRange::SUPPORTED),
+                version: "1.0.0".to_string(),
+            }),
+        ] {
+            assert_survives_message_pack(Message::Notification(notification))?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_requests_survive_message_pack() -> Result<()> {
+        // Covers both the tool without parameters and the one with the parameters schema,
+        // because the parameters are untagged and the tools are tagged by their type
+        let continue_from_conversation_history_params = serde_json::from_value(json!({
+            "add_generation_prompt": true,
+            "conversation_history": [{"content": "What is the weather?", "role": "user"}],
+            "enable_thinking": false,
+            "max_tokens": 100,
+            "tools": [
+                {
+                    "type": "function",
+                    "function": {
+                        "name": "get_time",
+                        "description": "Returns the current time",
+                    },
+                },
+                {
+                    "type": "function",
+                    "function": {
+                        "name": "get_weather",
+                        "description": "Returns the weather in the city",
+                        "parameters": {
+                            "type": "object",
+                            "properties": {"city": {"type": "string"}},
+                            "required": ["city"],
+                            "additionalProperties": false,
+                        },
+                    },
+                },
+            ],
+        }))?;
+
+        for request in [
+            Request::ContinueFromConversationHistory(continue_from_conversation_history_params),
+            Request::ContinueFromRawPrompt(ContinueFromRawPromptParams {
+                max_tokens: 10,
+                raw_prompt: "Hello".to_string(),
+            }),
+            Request::GenerateEmbeddingBatch(GenerateEmbeddingBatchParams {
+                input_batch: vec![EmbeddingInputDocument {
+                    content: "Hello".to_string(),
+                    id: "document-1".to_string(),
+                }],
+                normalization_method: EmbeddingNormalizationMethod::RmsNorm { epsilon: 0.5 },
+            }),
+            Request::GetChatTemplateOverride,
+            Request::GetModelMetadata,
+        ] {
+            assert_survives_message_pack(Message::Request(RequestEnvelope {
+                id: "request-1".to_string(),
+                request,
+            }))?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,12 +1,22 @@
 use serde::Deserialize;
 use serde::Serialize;
 
+use super::notification_params::AgentRegisteredParams;
+use super::notification_params::ReattachRequestParams;
+use super::notification_params::RegistrationRejectedParams;
 use super::notification_params::SetStateParams;
 use super::notification_params::VersionParams;
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub enum Notification {
+    /// Sent only to the agents that support the request resumption
+    AgentRegistered(AgentRegisteredParams),
+    Heartbeat,
+    PauseRespondingTo(String),
+    ReattachRequest(ReattachRequestParams),
+    RegistrationRejected(RegistrationRejectedParams),
+    ResumeRespondingTo(String),
     SetState(SetStateParams),
     StopRespondingTo(String),
     Version(VersionParams),
@@ -0,0 +1,10 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AgentRegisteredParams {
+    /// Lets the next connection of the agent take over the registration before the management
+    /// server notices that this one is closed
+    pub resume_secret: String,
+}
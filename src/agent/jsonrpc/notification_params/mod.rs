@@ -1,5 +1,11 @@
+mod agent_registered_params;
+mod reattach_request_params;
+mod registration_rejected_params;
 mod set_state_params;
 mod version_params;
 
+pub use self::agent_registered_params::AgentRegisteredParams;
+pub use self::reattach_request_params::ReattachRequestParams;
+pub use self::registration_rejected_params::RegistrationRejectedParams;
 pub use self::set_state_params::SetStateParams;
 pub use self::version_params::VersionParams;
@@ -0,0 +1,10 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReattachRequestParams {
+    /// The agent sends again the responses after these, because they were lost with the connection
+    pub received_responses: usize,
+    pub request_id: String,
+}
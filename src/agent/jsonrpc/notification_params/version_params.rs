@@ -1,8 +1,52 @@
+use std::collections::BTreeSet;
+
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::protocol_feature::ProtocolFeature;
+use crate::protocol_version_range::ProtocolVersionRange;
+
+/// Sent by the balancer as the first message after the agent connects
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct VersionParams {
+    /// Sent only to the peers that announce their protocol version range
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub features: BTreeSet<ProtocolFeature>,
+    /// Peers from before the negotiation was introduced neither send nor accept it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol_version_range: Option<ProtocolVersionRange>,
     pub version: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_accepts_version_from_older_balancer() -> Result<()> {
+        let version_params: VersionParams = serde_json::from_value(json!({"version": "1.0.0"}))?;
+
+        assert!(version_params.features.is_empty());
+        assert_eq!(version_params.protocol_version_range, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sends_older_agents_only_the_version() -> Result<()> {
+        assert_eq!(
+            serde_json::to_value(VersionParams {
+                features: BTreeSet::new(),
+                protocol_version_range: None,
+                version: "1.0.0".to_string(),
+            })?,
+            json!({"version": "1.0.0"})
+        );
+
+        Ok(())
+    }
+}
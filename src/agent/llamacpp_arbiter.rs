@@ -286,10 +286,12 @@ mod tests {
     use tokio::sync::mpsc;
 
     use super::*;
+    use crate::agent::bounded_response_sender::BoundedResponseSender;
     use crate::agent::continue_from_raw_prompt_request::ContinueFromRawPromptRequest;
     use crate::agent_desired_model::AgentDesiredModel;
     use crate::agent_desired_state::AgentDesiredState;
     use crate::converts_to_applicable_state::ConvertsToApplicableState as _;
+    use crate::full_buffer_policy::FullBufferPolicy;
     use crate::huggingface_model_reference::HuggingFaceModelReference;
     use crate::inference_parameters::InferenceParameters;
     use crate::request_params::ContinueFromRawPromptParams;
@@ -334,7 +336,9 @@ mod tests {
 
         let raw_prompt =
             "<|im_start|>user\nHow can I make a cat happy?<|im_end|>\n<|im_start|>assistant\n";
-        let (generated_tokens_tx, mut generated_tokens_rx) = mpsc::unbounded_channel();
+        let (generated_tokens_tx, mut generated_tokens_rx) = mpsc::channel(100);
+        let generated_tokens_tx =
+            BoundedResponseSender::new(FullBufferPolicy::Pause, generated_tokens_tx);
 
         let (_, generate_tokens_stop_rx_1) = mpsc::unbounded_channel::<()>();
         let (_, generate_tokens_stop_rx_2) = mpsc::unbounded_channel::<()>();
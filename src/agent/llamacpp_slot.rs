@@ -23,6 +23,7 @@ use rand::Rng as _;
 use rand::rngs::ThreadRng;
 use tokio::sync::mpsc;
 
+use crate::agent::bounded_response_sender::BoundedResponseSender;
 use crate::agent::continue_from_conversation_history_request::ContinueFromConversationHistoryRequest;
 use crate::agent::continue_from_raw_prompt_request::ContinueFromRawPromptRequest;
 use crate::agent::generate_embedding_batch_request::GenerateEmbeddingBatchRequest;
@@ -123,7 +124,7 @@ impl LlamaCppSlot {
         &mut self,
         batch: &mut LlamaBatch,
         current_batch_embeddings: &Vec<&EmbeddingInputTokenized>,
-        generated_embedding_tx: &mpsc::UnboundedSender<EmbeddingResult>,
+        generated_embedding_tx: &BoundedResponseSender<EmbeddingResult>,
         normalization_method: &EmbeddingNormalizationMethod,
     ) -> Result<()> {
         self.llama_context.clear_kv_cache();
@@ -154,7 +155,7 @@ impl LlamaCppSlot {
     fn continue_from_raw_prompt(
         &mut self,
         mut generate_tokens_stop_rx: mpsc::UnboundedReceiver<()>,
-        generated_tokens_tx: mpsc::UnboundedSender<GeneratedTokenResult>,
+        generated_tokens_tx: BoundedResponseSender<GeneratedTokenResult>,
         max_tokens: i32,
         prompt: String,
     ) -> Result<()> {
@@ -166,6 +167,9 @@ impl LlamaCppSlot {
             .slot_context
             .model
             .str_to_token(&prompt, AddBos::Always)?;
+
+        generated_tokens_tx.send(GeneratedTokenResult::PromptTokens(tokens_list.len()))?;
+
         let mut batch = LlamaBatch::new(self.slot_context.inference_parameters.batch_n_tokens, 1);
         let last_index = tokens_list.len() as i32 - 1;
 
@@ -275,6 +279,13 @@ impl LlamaCppSlot {
             .collect::<Result<Vec<EmbeddingInputTokenized>, _>>()
             .context("failed to tokenize embedding input batch")?;
 
+        generated_embedding_tx.send(EmbeddingResult::InputTokens(
+            tokens_lines_list
+                .iter()
+                .map(|embedding_input_tokenized| embedding_input_tokenized.llama_tokens.len())
+                .sum(),
+        ))?;
+
         let mut batch = LlamaBatch::new(self.slot_context.inference_parameters.batch_n_tokens, 1);
         let mut current_batch_embeddings: Vec<&EmbeddingInputTokenized> = Vec::new();
 
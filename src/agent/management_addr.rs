@@ -0,0 +1,149 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use anyhow::anyhow;
+use tokio::net::TcpStream;
+use tokio::net::UnixStream;
+
+use crate::agent::management_stream::ManagementStream;
+
+/// Address of a management server as given by the user. Host names are resolved again
+/// on every connection attempt, so the agent follows a balancer whose IP changes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ManagementAddr {
+    Tcp { host: String, port: u16 },
+    Unix(PathBuf),
+}
+
+impl ManagementAddr {
+    pub async fn connect(&self) -> Result<Box<dyn ManagementStream>> {
+        Ok(match self {
+            ManagementAddr::Tcp { host, port } => Box::new(
+                TcpStream::connect((host.as_str(), *port))
+                    .await
+                    .with_context(|| format!("Unable to connect to {self}"))?,
+            ),
+            ManagementAddr::Unix(path) => Box::new(
+                UnixStream::connect(path)
+                    .await
+                    .with_context(|| format!("Unable to connect to {self}"))?,
+            ),
+        })
+    }
+
+    pub fn socket_url(&self, is_tls: bool, agent_id: &str) -> String {
+        let scheme = if is_tls { "wss" } else { "ws" };
+
+        match self {
+            ManagementAddr::Tcp { host, port } if host.contains(':') => {
+                format!("{scheme}://[{host}]:{port}/api/v1/agent_socket/{agent_id}")
+            }
+            ManagementAddr::Tcp { host, port } => {
+                format!("{scheme}://{host}:{port}/api/v1/agent_socket/{agent_id}")
+            }
+            // The host is only sent in the request headers
+            ManagementAddr::Unix(_) => {
+                format!("{scheme}://localhost/api/v1/agent_socket/{agent_id}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ManagementAddr {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManagementAddr::Tcp { host, port } if host.contains(':') => {
+                write!(formatter, "[{host}]:{port}")
+            }
+            ManagementAddr::Tcp { host, port } => write!(formatter, "{host}:{port}"),
+            ManagementAddr::Unix(path) => write!(formatter, "unix:{}", path.display()),
+        }
+    }
+}
+
+impl FromStr for ManagementAddr {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        if let Some(path) = value.strip_prefix("unix:") {
+            if path.is_empty() {
+                return Err(anyhow!("Unix socket path is empty"));
+            }
+
+            return Ok(ManagementAddr::Unix(PathBuf::from(path)));
+        }
+
+        let (host, port) = value
+            .rsplit_once(':')
+            .ok_or_else(|| anyhow!("Invalid management address {value:?}. Expected host:port"))?;
+        let host = host
+            .strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(host);
+
+        if host.is_empty() {
+            return Err(anyhow!(
+                "Invalid management address {value:?}. Host is empty"
+            ));
+        }
+
+        Ok(ManagementAddr::Tcp {
+            host: host.to_string(),
+            port: port
+                .parse()
+                .context(format!("Invalid port in management address {value:?}"))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        assert_eq!(
+            "balancer.internal:8060".parse::<ManagementAddr>()?,
+            ManagementAddr::Tcp {
+                host: "balancer.internal".to_string(),
+                port: 8060,
+            }
+        );
+        assert_eq!(
+            "[::1]:8060".parse::<ManagementAddr>()?,
+            ManagementAddr::Tcp {
+                host: "::1".to_string(),
+                port: 8060,
+            }
+        );
+        assert_eq!(
+            "unix:/run/paddler/management.sock".parse::<ManagementAddr>()?,
+            ManagementAddr::Unix(PathBuf::from("/run/paddler/management.sock"))
+        );
+        assert!("balancer.internal".parse::<ManagementAddr>().is_err());
+        assert!(":8060".parse::<ManagementAddr>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_socket_url() -> Result<()> {
+        assert_eq!(
+            "[::1]:8060"
+                .parse::<ManagementAddr>()?
+                .socket_url(true, "agent_id"),
+            "wss://[::1]:8060/api/v1/agent_socket/agent_id"
+        );
+        assert_eq!(
+            "unix:/run/paddler/management.sock"
+                .parse::<ManagementAddr>()?
+                .socket_url(false, "agent_id"),
+            "ws://localhost/api/v1/agent_socket/agent_id"
+        );
+
+        Ok(())
+    }
+}
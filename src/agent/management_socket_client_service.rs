@@ -1,11 +1,14 @@
+use std::collections::BTreeSet;
 use std::sync::Arc;
 
 use actix_web::rt;
 use actix_web::web::Bytes;
 use anyhow::Context;
 use anyhow::Result;
+use anyhow::anyhow;
 use async_trait::async_trait;
 use futures_util::SinkExt as _;
+use futures_util::Stream;
 use log::debug;
 use futures_util::StreamExt;
 use log::error;
@@ -13,24 +16,52 @@ use log::info;
 use log::warn;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc;
+use tokio::sync::watch;
 use tokio::time::interval;
+use tokio::time::sleep;
+use tokio::time::sleep_until;
+use tokio::time::timeout;
 use tokio::time::Duration;
+use tokio::time::Instant;
 use tokio::time::MissedTickBehavior;
-use tokio_tungstenite::connect_async;
+use rustls::ClientConfig;
+use tokio_tungstenite::Connector;
+use tokio_tungstenite::client_async_tls_with_config;
+use tokio_tungstenite::tungstenite::Error as WebSocketError;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest as _;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::http::header::AUTHORIZATION;
 use tokio_tungstenite::tungstenite::protocol::Message;
 
 use crate::agent_desired_state::AgentDesiredState;
+use crate::agent_issue::AgentIssue;
+use crate::agent_issue_fix::AgentIssueFix;
+use crate::agent_issue_params::IncompatibleProtocolVersionParams;
+use crate::drain_status::DrainStatus;
+use crate::agent::agent_token_holder::AgentTokenHolder;
+use crate::agent::bounded_response_sender::BoundedResponseSender;
+use crate::agent::receive_stream_pauser_collection::ReceiveStreamPauserCollection;
+use crate::agent::receive_stream_reattacher_collection::ReceiveStreamReattacherCollection;
+use crate::agent::receive_stream_reattachment::ReceiveStreamReattachment;
+use crate::agent::response_replay_buffer::ResponseReplayBuffer;
+use crate::agent::resume_secret_holder::ResumeSecretHolder;
 use crate::agent::receive_stream_stopper_collection::ReceiveStreamStopperCollection;
 use crate::agent::jsonrpc::Message as JsonRpcMessage;
 use crate::agent::jsonrpc::Notification as JsonRpcNotification;
 use crate::agent::jsonrpc::Request as JsonRpcRequest;
 use crate::agent::jsonrpc::Response as JsonRpcResponse;
 use crate::agent_applicable_state_holder::AgentApplicableStateHolder;
+use crate::agent::jsonrpc::notification_params::AgentRegisteredParams;
+use crate::agent::jsonrpc::notification_params::ReattachRequestParams;
+use crate::agent::jsonrpc::notification_params::RegistrationRejectedParams;
 use crate::agent::jsonrpc::notification_params::SetStateParams;
 use crate::agent::continue_from_raw_prompt_request::ContinueFromRawPromptRequest;
 use crate::agent::jsonrpc::notification_params::VersionParams;
 use crate::slot_aggregated_status::SlotAggregatedStatus;
 use crate::agent::from_request_params::FromRequestParams;
+use crate::agent::management_addr::ManagementAddr;
+use crate::agent::reconnect_backoff::ReconnectBackoff;
 use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::Message as ManagementJsonRpcMessage;
 use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::Notification as ManagementJsonRpcNotification;
 use crate::agent::continue_from_conversation_history_request::ContinueFromConversationHistoryRequest;
@@ -41,75 +72,264 @@ use crate::jsonrpc::Error as JsonRpcError;
 use crate::jsonrpc::ResponseEnvelope;
 use crate::jsonrpc::RequestEnvelope;
 use crate::produces_snapshot::ProducesSnapshot;
+use crate::protocol_feature::ProtocolFeature;
+use crate::protocol_version_range::ProtocolVersionRange;
 use crate::jsonrpc::ErrorEnvelope;
+use crate::message_framing::MessageFraming;
+use crate::heartbeat_configuration::HeartbeatConfiguration;
+use crate::heartbeat_monitor::HeartbeatMonitor;
+use crate::response_buffer_configuration::ResponseBufferConfiguration;
 use crate::service::Service;
+use crate::streamable_result::StreamableResult as _;
 use crate::agent::model_metadata_holder::ModelMetadataHolder;
 
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Responses of all the requests that wait to be written to the connection
+const OUTGOING_RESPONSE_QUEUE_CAPACITY: usize = 64;
+
 struct IncomingMessageContext {
     agent_applicable_state_holder: Arc<AgentApplicableStateHolder>,
     agent_desired_state_tx: mpsc::UnboundedSender<AgentDesiredState>,
-    connection_close_tx: broadcast::Sender<()>,
+    connection_close_rx: broadcast::Receiver<()>,
     continue_from_conversation_history_request_tx:
         mpsc::UnboundedSender<ContinueFromConversationHistoryRequest>,
     continue_from_raw_prompt_request_tx: mpsc::UnboundedSender<ContinueFromRawPromptRequest>,
+    drain_status: Arc<DrainStatus>,
     generate_embedding_batch_request_tx: mpsc::UnboundedSender<GenerateEmbeddingBatchRequest>,
     model_metadata_holder: Arc<ModelMetadataHolder>,
+    receive_stream_pauser_collection: Arc<ReceiveStreamPauserCollection>,
+    receive_stream_reattacher_collection: Arc<ReceiveStreamReattacherCollection>,
     receive_stream_stopper_collection: Arc<ReceiveStreamStopperCollection>,
-    message_tx: mpsc::UnboundedSender<ManagementJsonRpcMessage>,
+    message_tx: mpsc::Sender<ManagementJsonRpcMessage>,
+    /// Whether the management server counts the prompt and embedding input tokens
+    reports_token_usage: bool,
+    response_buffer_configuration: ResponseBufferConfiguration,
+    /// Zero if the management server cannot resume the requests
+    resume_grace_period: Duration,
+    resume_secret_holder: Arc<ResumeSecretHolder>,
+    slot_aggregated_status: Arc<SlotAggregatedStatus>,
 }
 
 pub struct ManagementSocketClientService {
     pub agent_applicable_state_holder: Arc<AgentApplicableStateHolder>,
     pub agent_desired_state_tx: mpsc::UnboundedSender<AgentDesiredState>,
+    /// Stays the same across the reconnections
+    pub agent_id: String,
+    pub agent_token_holder: Arc<AgentTokenHolder>,
     pub continue_from_conversation_history_request_tx:
         mpsc::UnboundedSender<ContinueFromConversationHistoryRequest>,
     pub continue_from_raw_prompt_request_tx: mpsc::UnboundedSender<ContinueFromRawPromptRequest>,
+    pub drain_status: Arc<DrainStatus>,
     pub generate_embedding_batch_request_tx: mpsc::UnboundedSender<GenerateEmbeddingBatchRequest>,
+    pub heartbeat_configuration: HeartbeatConfiguration,
+    /// The agent moves on to the next address every time it fails to connect or loses the connection
+    pub management_addrs: Vec<ManagementAddr>,
     pub model_metadata_holder: Arc<ModelMetadataHolder>,
     pub name: Option<String>,
+    pub receive_stream_pauser_collection: Arc<ReceiveStreamPauserCollection>,
+    pub receive_stream_reattacher_collection: Arc<ReceiveStreamReattacherCollection>,
     pub receive_stream_stopper_collection: Arc<ReceiveStreamStopperCollection>,
+    pub reconnect_initial_delay: Duration,
+    pub reconnect_max_delay: Duration,
+    pub response_buffer_configuration: ResponseBufferConfiguration,
+    /// How long the requests keep running after the connection is lost, waiting to be resumed
+    pub resume_grace_period: Duration,
+    pub resume_secret_holder: Arc<ResumeSecretHolder>,
     pub slot_aggregated_status: Arc<SlotAggregatedStatus>,
-    pub socket_url: String,
+    pub tls_client_config: Option<Arc<ClientConfig>>,
 }
 
 impl ManagementSocketClientService {
     async fn generate_responses<TRequest: FromRequestParams + 'static>(
-        connection_close_tx: broadcast::Sender<()>,
+        mut connection_close_rx: broadcast::Receiver<()>,
+        drain_status: Arc<DrainStatus>,
         id: String,
-        message_tx: mpsc::UnboundedSender<ManagementJsonRpcMessage>,
+        mut message_tx: mpsc::Sender<ManagementJsonRpcMessage>,
         request_params: TRequest::RequestParams,
+        receive_stream_pauser_collection: Arc<ReceiveStreamPauserCollection>,
+        receive_stream_reattacher_collection: Arc<ReceiveStreamReattacherCollection>,
         receive_stream_stopper_collection: Arc<ReceiveStreamStopperCollection>,
+        reports_token_usage: bool,
         request_tx: mpsc::UnboundedSender<TRequest>,
+        response_buffer_configuration: ResponseBufferConfiguration,
+        resume_grace_period: Duration,
     ) -> Result<()> {
-        let (response_tx, mut response_rx) = mpsc::unbounded_channel::<TRequest::Response>();
+        // The management server might have sent the request before it learned about the drain
+        if drain_status.is_draining() {
+            message_tx
+                .send(ManagementJsonRpcMessage::Error(ErrorEnvelope {
+                    request_id: id.clone(),
+                    error: JsonRpcError {
+                        code: 503,
+                        description: format!("Agent is draining and does not accept request {id}"),
+                    },
+                }))
+                .await?;
+
+            return Ok(());
+        }
+
+        let (pause_tx, mut pause_rx) = watch::channel(false);
+        let (reattach_tx, mut reattach_rx) = mpsc::unbounded_channel::<ReceiveStreamReattachment>();
+        let (response_tx, mut response_rx) =
+            mpsc::channel::<TRequest::Response>(response_buffer_configuration.capacity);
         let (stop_tx, stop_rx) = mpsc::unbounded_channel::<()>();
 
-        let _guard = receive_stream_stopper_collection
+        let _pauser_guard = receive_stream_pauser_collection
+            .register_pauser_with_guard(id.clone(), pause_tx)
+            .context(format!("Failed to register pauser for request: {id}"))?;
+        let _reattacher_guard = receive_stream_reattacher_collection
+            .register_reattacher_with_guard(id.clone(), reattach_tx)
+            .context(format!("Failed to register reattacher for request: {id}"))?;
+        let _stopper_guard = receive_stream_stopper_collection
             .register_stopper_with_guard(id.clone(), stop_tx)
             .context(format!("Failed to register stopper for request: {id}"))?;
 
         request_tx.send(TRequest::from_request_params(
             request_params,
-            response_tx,
+            BoundedResponseSender::new(
+                response_buffer_configuration.full_buffer_policy,
+                response_tx,
+            ),
             stop_rx,
         ))?;
 
-        let mut connection_close_rx = connection_close_tx.subscribe();
+        // Set while the request waits to be resumed on the next connection
+        let mut detached_until: Option<Instant> = None;
+        let mut has_finished_generating = false;
+        let mut is_done = false;
+        let mut response_replay_buffer = ResponseReplayBuffer::new(resume_grace_period);
+        let stop_generating = || {
+            // The slot might have finished the request already
+            if let Err(err) = receive_stream_stopper_collection.stop(id.clone()) {
+                debug!("Unable to stop generating the responses: {err}");
+            }
+        };
 
         loop {
+            if let Some(resume_deadline) = detached_until {
+                // The responses generated in the meantime are sent after the agent reconnects
+                tokio::select! {
+                    receive_stream_reattachment = reattach_rx.recv() => {
+                        let ReceiveStreamReattachment {
+                            connection_close_rx: reattached_connection_close_rx,
+                            message_tx: reattached_message_tx,
+                            received_responses,
+                        } = match receive_stream_reattachment {
+                            Some(receive_stream_reattachment) => receive_stream_reattachment,
+                            None => {
+                                stop_generating();
+
+                                return Ok(());
+                            }
+                        };
+
+                        connection_close_rx = reattached_connection_close_rx;
+                        message_tx = reattached_message_tx;
+                        detached_until = None;
+
+                        match response_replay_buffer.get_responses_after(received_responses) {
+                            Ok(lost_responses) => {
+                                info!(
+                                    "Resumed request {id:?}, sending {} lost responses again",
+                                    lost_responses.len()
+                                );
+
+                                for response in lost_responses {
+                                    if message_tx
+                                        .send(ManagementJsonRpcMessage::Response(ResponseEnvelope {
+                                            request_id: id.clone(),
+                                            response: response.into(),
+                                        }))
+                                        .await
+                                        .is_err()
+                                    {
+                                        detached_until = Self::resume_deadline(&id, resume_grace_period);
+
+                                        break;
+                                    }
+                                }
+                            }
+                            Err(err) => {
+                                warn!("Unable to resume request {id:?}: {err}");
+
+                                stop_generating();
+
+                                break;
+                            }
+                        }
+
+                        if detached_until.is_none() && (is_done || has_finished_generating) {
+                            break;
+                        }
+                    }
+                    _ = sleep_until(resume_deadline) => {
+                        warn!("Request {id:?} was not resumed in time, stopping it");
+
+                        stop_generating();
+
+                        return Ok(());
+                    }
+                    response = response_rx.recv(), if !has_finished_generating => {
+                        match response {
+                            Some(response) if !reports_token_usage && response.is_usage_report() => {}
+                            Some(response) => {
+                                is_done = response.is_done();
+
+                                response_replay_buffer.push(response);
+                            }
+                            None => has_finished_generating = true,
+                        }
+                    }
+                }
+
+                continue;
+            }
+
             tokio::select! {
-                _ = connection_close_rx.recv() => break,
-                response = response_rx.recv() => {
+                _ = connection_close_rx.recv() => {
+                    detached_until = Self::resume_deadline(&id, resume_grace_period);
+
+                    if detached_until.is_none() {
+                        stop_generating();
+
+                        return Ok(());
+                    }
+                }
+                _ = pause_rx.changed() => {
+                    debug!(
+                        "Request {id:?} paused: {}, buffered responses: {}",
+                        *pause_rx.borrow(),
+                        response_rx.len()
+                    );
+                }
+                response = response_rx.recv(), if !*pause_rx.borrow() => {
                     match response {
+                        Some(response) if !reports_token_usage && response.is_usage_report() => {}
                         Some(response) => {
-                            message_tx.send(
+                            is_done = response.is_done();
+
+                            response_replay_buffer.push(response.clone());
+
+                            // The response is sent again if the agent reconnects in time
+                            if message_tx.send(
                                 ManagementJsonRpcMessage::Response(
                                     ResponseEnvelope {
                                         request_id: id.clone(),
                                         response: response.into(),
                                     }
                                 ),
-                            )?;
+                            ).await.is_err() {
+                                detached_until = Self::resume_deadline(&id, resume_grace_period);
+
+                                if detached_until.is_none() {
+                                    stop_generating();
+
+                                    return Ok(());
+                                }
+                            } else if is_done {
+                                break;
+                            }
                         }
                         None => break,
                     }
@@ -117,20 +337,54 @@ impl ManagementSocketClientService {
             }
         }
 
+        // The slot gives up on the request without finishing it when its response buffer
+        // overflows, or when it fails. Requests that cannot be resumed end the same way
+        if !is_done {
+            message_tx
+                .send(ManagementJsonRpcMessage::Error(ErrorEnvelope {
+                    request_id: id.clone(),
+                    error: JsonRpcError {
+                        code: 503,
+                        description: format!("Request {id} was aborted before it finished"),
+                    },
+                }))
+                .await?;
+        }
+
         Ok(())
     }
 
+    /// Requests outlive the connection for the grace period, so the management server can resume
+    /// them after the agent reconnects
+    fn resume_deadline(id: &str, resume_grace_period: Duration) -> Option<Instant> {
+        if resume_grace_period.is_zero() {
+            return None;
+        }
+
+        info!("Connection closed, request {id:?} waits to be resumed");
+
+        Some(Instant::now() + resume_grace_period)
+    }
+
     async fn handle_deserialized_message(
         IncomingMessageContext {
             agent_applicable_state_holder,
             agent_desired_state_tx,
-            connection_close_tx,
+            connection_close_rx,
             continue_from_conversation_history_request_tx,
             continue_from_raw_prompt_request_tx,
+            drain_status,
             generate_embedding_batch_request_tx,
             message_tx,
             model_metadata_holder,
+            receive_stream_pauser_collection,
+            receive_stream_reattacher_collection,
             receive_stream_stopper_collection,
+            reports_token_usage,
+            response_buffer_configuration,
+            resume_grace_period,
+            resume_secret_holder,
+            slot_aggregated_status,
         }: IncomingMessageContext,
         deserialized_message: JsonRpcMessage,
     ) -> Result<()> {
@@ -145,6 +399,78 @@ impl ManagementSocketClientService {
 
                 Ok(())
             }
+            JsonRpcMessage::Notification(JsonRpcNotification::AgentRegistered(
+                AgentRegisteredParams { resume_secret },
+            )) => {
+                resume_secret_holder.set_resume_secret(resume_secret);
+
+                Ok(())
+            }
+            JsonRpcMessage::Notification(JsonRpcNotification::Heartbeat) => Ok(()),
+            JsonRpcMessage::Notification(JsonRpcNotification::PauseRespondingTo(request_id)) => {
+                // The request might have finished before the notification arrived
+                if let Err(err) = receive_stream_pauser_collection.pause(request_id) {
+                    debug!("Unable to pause the responses: {err}");
+                }
+
+                Ok(())
+            }
+            JsonRpcMessage::Notification(JsonRpcNotification::ReattachRequest(
+                ReattachRequestParams {
+                    received_responses,
+                    request_id,
+                },
+            )) => {
+                // The request might have been paused when the previous connection was lost
+                let reattach_result = receive_stream_pauser_collection
+                    .resume(request_id.clone())
+                    .and_then(|()| {
+                        receive_stream_reattacher_collection.reattach(
+                            request_id.clone(),
+                            ReceiveStreamReattachment {
+                                connection_close_rx,
+                                message_tx: message_tx.clone(),
+                                received_responses,
+                            },
+                        )
+                    });
+
+                if let Err(err) = reattach_result {
+                    warn!("Unable to resume request {request_id:?}: {err}");
+
+                    // Request that waits for the responses fails right away
+                    message_tx
+                        .send(ManagementJsonRpcMessage::Error(ErrorEnvelope {
+                            request_id: request_id.clone(),
+                            error: JsonRpcError {
+                                code: 503,
+                                description: format!(
+                                    "Request {request_id} can no longer be resumed"
+                                ),
+                            },
+                        }))
+                        .await?;
+                }
+
+                Ok(())
+            }
+            JsonRpcMessage::Notification(JsonRpcNotification::RegistrationRejected(
+                RegistrationRejectedParams { reason },
+            )) => {
+                error!("Management server rejected the agent: {reason}");
+
+                slot_aggregated_status.register_issue(AgentIssue::BalancerRejectedAgent(reason));
+
+                Ok(())
+            }
+            JsonRpcMessage::Notification(JsonRpcNotification::ResumeRespondingTo(request_id)) => {
+                // The request might have finished before the notification arrived
+                if let Err(err) = receive_stream_pauser_collection.resume(request_id) {
+                    debug!("Unable to resume the responses: {err}");
+                }
+
+                Ok(())
+            }
             JsonRpcMessage::Notification(JsonRpcNotification::SetState(SetStateParams {
                 desired_state,
             })) => {
@@ -160,17 +486,15 @@ impl ManagementSocketClientService {
                         "Failed to stop generating tokens for request ID: {request_id}"
                     ))?;
 
+                // A paused request has to drain its buffer, so the slot notices that it was stopped
+                receive_stream_pauser_collection
+                    .resume(request_id.clone())
+                    .context(format!("Failed to resume request ID: {request_id}"))?;
+
                 Ok(())
             }
-            JsonRpcMessage::Notification(JsonRpcNotification::Version(VersionParams {
-                version,
-            })) => {
-                if version != env!("CARGO_PKG_VERSION") {
-                    warn!(
-                        "Version mismatch: server version is {version}, client version is {}",
-                        env!("CARGO_PKG_VERSION")
-                    );
-                }
+            JsonRpcMessage::Notification(JsonRpcNotification::Version(_)) => {
+                warn!("Management server sent its version again after the handshake");
 
                 Ok(())
             }
@@ -182,12 +506,18 @@ impl ManagementSocketClientService {
                     ),
             }) => {
                 Self::generate_responses(
-                    connection_close_tx,
+                    connection_close_rx,
+                    drain_status,
                     id,
                     message_tx,
                     continue_from_conversation_history_params,
+                    receive_stream_pauser_collection,
+                    receive_stream_reattacher_collection,
                     receive_stream_stopper_collection,
+                    reports_token_usage,
                     continue_from_conversation_history_request_tx,
+                    response_buffer_configuration,
+                    resume_grace_period,
                 )
                 .await
             }
@@ -196,12 +526,18 @@ impl ManagementSocketClientService {
                 request: JsonRpcRequest::ContinueFromRawPrompt(generate_tokens_params),
             }) => {
                 Self::generate_responses(
-                    connection_close_tx,
+                    connection_close_rx,
+                    drain_status,
                     id,
                     message_tx,
                     generate_tokens_params,
+                    receive_stream_pauser_collection,
+                    receive_stream_reattacher_collection,
                     receive_stream_stopper_collection,
+                    reports_token_usage,
                     continue_from_raw_prompt_request_tx,
+                    response_buffer_configuration,
+                    resume_grace_period,
                 )
                 .await
             }
@@ -210,20 +546,26 @@ impl ManagementSocketClientService {
                 request: JsonRpcRequest::GenerateEmbeddingBatch(generate_embedding_batch_params),
             }) => {
                 Self::generate_responses(
-                    connection_close_tx,
+                    connection_close_rx,
+                    drain_status,
                     id,
                     message_tx,
                     generate_embedding_batch_params,
+                    receive_stream_pauser_collection,
+                    receive_stream_reattacher_collection,
                     receive_stream_stopper_collection,
+                    reports_token_usage,
                     generate_embedding_batch_request_tx,
+                    response_buffer_configuration,
+                    resume_grace_period,
                 )
                 .await
             }
             JsonRpcMessage::Request(RequestEnvelope {
                 id,
                 request: JsonRpcRequest::GetChatTemplateOverride,
-            }) => Ok(
-                message_tx.send(ManagementJsonRpcMessage::Response(ResponseEnvelope {
+            }) => Ok(message_tx
+                .send(ManagementJsonRpcMessage::Response(ResponseEnvelope {
                     request_id: id.clone(),
                     response: JsonRpcResponse::ChatTemplateOverride(
                         if let Some(agent_applicable_state) =
@@ -234,19 +576,19 @@ impl ManagementSocketClientService {
                             None
                         },
                     ),
-                }))?,
-            ),
+                }))
+                .await?),
             JsonRpcMessage::Request(RequestEnvelope {
                 id,
                 request: JsonRpcRequest::GetModelMetadata,
-            }) => Ok(
-                message_tx.send(ManagementJsonRpcMessage::Response(ResponseEnvelope {
+            }) => Ok(message_tx
+                .send(ManagementJsonRpcMessage::Response(ResponseEnvelope {
                     request_id: id.clone(),
                     response: JsonRpcResponse::ModelMetadata(
                         model_metadata_holder.get_model_metadata(),
                     ),
-                }))?,
-            ),
+                }))
+                .await?),
         }
     }
 
@@ -257,34 +599,20 @@ impl ManagementSocketClientService {
     ) -> Result<()> {
         match msg {
             Message::Text(text) => {
-                let mut connection_close_rx =
-                    incoming_message_context.connection_close_tx.subscribe();
-
-                rt::spawn(async move {
-                    tokio::select! {
-                        _ = connection_close_rx.recv() => {
-                            info!("Connection close signal received, shutting down");
-                        }
-                        result = Self::handle_deserialized_message(
-                            incoming_message_context,
-                            match serde_json::from_str::<JsonRpcMessage>(&text).context(format!("Failed to parse JSON-RPC message: {text}")) {
-                                Ok(message) => message,
-                                Err(err) => {
-                                    error!("Failed to deserialize message: {err}");
-
-                                    return;
-                                }
-                            },
-                        ) => if let Err(err) = result {
-                            error!("Error handling incoming message: {err}");
-                        }
-                    }
-                });
+                Self::spawn_deserialized_message_handler(
+                    incoming_message_context,
+                    serde_json::from_str::<JsonRpcMessage>(&text)
+                        .context(format!("Failed to parse JSON-RPC message: {text}")),
+                );
 
                 Ok(())
             }
-            Message::Binary(_) => {
-                error!("Received binary message, which is not expected");
+            Message::Binary(bytes) => {
+                Self::spawn_deserialized_message_handler(
+                    incoming_message_context,
+                    rmp_serde::from_slice::<JsonRpcMessage>(&bytes)
+                        .context("Failed to decode MessagePack message"),
+                );
 
                 Ok(())
             }
@@ -306,15 +634,165 @@ impl ManagementSocketClientService {
         }
     }
 
-    async fn keep_connection_alive(&self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
-        info!("Connecting to management server at {}", self.socket_url);
+    fn encode_message(
+        message_framing: MessageFraming,
+        message: &ManagementJsonRpcMessage,
+    ) -> Result<Message> {
+        Ok(match message_framing {
+            MessageFraming::Json => Message::Text(serde_json::to_string(message)?.into()),
+            MessageFraming::MessagePack => {
+                Message::Binary(rmp_serde::to_vec_named(message)?.into())
+            }
+        })
+    }
+
+    fn spawn_deserialized_message_handler(
+        incoming_message_context: IncomingMessageContext,
+        deserialized_message: Result<JsonRpcMessage>,
+    ) {
+        let deserialized_message = match deserialized_message {
+            Ok(deserialized_message) => deserialized_message,
+            Err(err) => {
+                error!("Failed to deserialize message: {err}");
+
+                return;
+            }
+        };
+
+        // Handlers are not cancelled when the connection closes. The requests decide by themselves
+        // whether to wait to be resumed after the agent reconnects
+        rt::spawn(async move {
+            if let Err(err) =
+                Self::handle_deserialized_message(incoming_message_context, deserialized_message)
+                    .await
+            {
+                error!("Error handling incoming message: {err}");
+            }
+        });
+    }
+
+    /// The management server sends its version before anything else
+    async fn receive_version_params(
+        read: &mut (impl Stream<Item = Result<Message, WebSocketError>> + Unpin),
+    ) -> Result<VersionParams> {
+        while let Some(msg) = read.next().await {
+            if let Message::Text(text) = msg? {
+                return match serde_json::from_str::<JsonRpcMessage>(&text)
+                    .context(format!("Failed to parse JSON-RPC message: {text}"))?
+                {
+                    JsonRpcMessage::Notification(JsonRpcNotification::Version(version_params)) => {
+                        Ok(version_params)
+                    }
+                    _ => Err(anyhow!(
+                        "Management server did not start with sending its version"
+                    )),
+                };
+            }
+        }
+
+        Err(anyhow!(
+            "Connection closed before the management server sent its version"
+        ))
+    }
+
+    async fn keep_connection_alive(
+        &self,
+        management_addr: &ManagementAddr,
+        reconnect_backoff: &mut ReconnectBackoff,
+        mut shutdown: broadcast::Receiver<()>,
+    ) -> Result<()> {
+        let socket_url =
+            management_addr.socket_url(self.tls_client_config.is_some(), &self.agent_id);
+
+        info!("Connecting to management server at {socket_url}");
+
+        let mut request = socket_url.as_str().into_client_request()?;
+
+        request.headers_mut().insert(
+            ProtocolVersionRange::HEADER_NAME,
+            HeaderValue::from_str(&ProtocolVersionRange::SUPPORTED.to_string())?,
+        );
+
+        if let Some(agent_token) = self.agent_token_holder.get_agent_token() {
+            request.headers_mut().insert(
+                AUTHORIZATION,
+                HeaderValue::from_str(&format!("Bearer {agent_token}"))?,
+            );
+        }
+
+        let connector = self.tls_client_config.clone().map(Connector::Rustls);
+        let stream = management_addr.connect().await?;
+
+        let mut ws_stream =
+            match client_async_tls_with_config(request, stream, None, connector).await {
+                Ok((ws_stream, _response)) => ws_stream,
+                Err(WebSocketError::Http(response))
+                    if response.status() == StatusCode::UNAUTHORIZED =>
+                {
+                    self.slot_aggregated_status
+                        .register_issue(AgentIssue::BalancerRejectedAgent(
+                            "Agent token is missing or invalid".to_string(),
+                        ));
 
-        let (ws_stream, _response) = connect_async(self.socket_url.clone()).await?;
+                    return Err(anyhow!("Management server rejected the agent token"));
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+        let VersionParams {
+            features: balancer_features,
+            protocol_version_range: balancer_protocol_version_range,
+            version: balancer_version,
+        } = timeout(
+            HANDSHAKE_TIMEOUT,
+            Self::receive_version_params(&mut ws_stream),
+        )
+        .await
+        .context("Timed out waiting for the management server version")??;
 
-        info!("Connected to management server");
+        // Older management servers reject the handshake fields they do not know
+        let announces_protocol_version = balancer_protocol_version_range.is_some();
+        let balancer_protocol_version_range =
+            balancer_protocol_version_range.unwrap_or_else(ProtocolVersionRange::legacy);
+
+        let protocol_version = match ProtocolVersionRange::SUPPORTED
+            .negotiate(&balancer_protocol_version_range)
+        {
+            Some(protocol_version) => protocol_version,
+            None => {
+                self.slot_aggregated_status.register_issue(
+                    AgentIssue::IncompatibleProtocolVersion(IncompatibleProtocolVersionParams {
+                        agent_protocol_version_range: ProtocolVersionRange::SUPPORTED,
+                        balancer_protocol_version_range: balancer_protocol_version_range.clone(),
+                    }),
+                );
+
+                return Err(anyhow!(
+                    "Management server {balancer_version} supports protocol versions {balancer_protocol_version_range}, but the agent supports {}",
+                    ProtocolVersionRange::SUPPORTED
+                ));
+            }
+        };
 
+        info!(
+            "Connected to management server {balancer_version} (protocol version {protocol_version})"
+        );
+
+        self.slot_aggregated_status
+            .register_fix(AgentIssueFix::BalancerProtocolIsCompatible);
+
+        self.slot_aggregated_status
+            .register_fix(AgentIssueFix::BalancerAcceptedConnection);
+
+        let resumes_requests = balancer_features.contains(&ProtocolFeature::RequestResumption);
         let (connection_close_tx, mut connection_close_rx) = broadcast::channel::<()>(1);
-        let (message_tx, mut message_rx) = mpsc::unbounded_channel::<ManagementJsonRpcMessage>();
+        // Registration, status updates and heartbeats do not wait behind the responses
+        let (control_message_tx, mut control_message_rx) =
+            mpsc::unbounded_channel::<ManagementJsonRpcMessage>();
+        let heartbeat_monitor = HeartbeatMonitor::new(self.heartbeat_configuration.clone());
+        let (message_tx, mut message_rx) =
+            mpsc::channel::<ManagementJsonRpcMessage>(OUTGOING_RESPONSE_QUEUE_CAPACITY);
+        let message_framing = MessageFraming::from_features(&balancer_features);
         let (pong_tx, mut pong_rx) = mpsc::unbounded_channel::<Bytes>();
         let (mut write, mut read) = ws_stream.split();
 
@@ -324,6 +802,8 @@ impl ManagementSocketClientService {
         let message_forward_handle = rt::spawn(async move {
             loop {
                 tokio::select! {
+                    biased;
+
                     _ = connection_close_rx_resubscribed.recv() => {
                         break;
                     }
@@ -346,13 +826,29 @@ impl ManagementSocketClientService {
 
                         break;
                     }
+                    message = control_message_rx.recv() => {
+                        match message {
+                            Some(msg) => {
+                                match Self::encode_message(message_framing, &msg) {
+                                    Ok(message) => {
+                                        if let Err(err) = write.send(message).await {
+                                            error!("Failed to send message: {err}");
+                                            break;
+                                        }
+                                    },
+                                    Err(err) => {
+                                        error!("Failed to serialize message: {err}");
+                                    }
+                                };
+                            }
+                            None => break,
+                        }
+                    }
                     message = message_rx.recv() => {
                         match message {
                             Some(msg) => {
-                                match serde_json::to_string(&msg) {
-                                    Ok(serialized_message) => {
-                                        let message = Message::Text(serialized_message.into());
-
+                                match Self::encode_message(message_framing, &msg) {
+                                    Ok(message) => {
                                         if let Err(err) = write.send(message).await {
                                             error!("Failed to send message: {err}");
                                             break;
@@ -382,10 +878,23 @@ impl ManagementSocketClientService {
 
         match self.slot_aggregated_status.make_snapshot() {
             Ok(slot_aggregated_status_snapshot) => {
-                message_tx
+                control_message_tx
                     .send(ManagementJsonRpcMessage::Notification(
                         ManagementJsonRpcNotification::RegisterAgent(RegisterAgentParams {
+                            features: if announces_protocol_version {
+                                BTreeSet::from(ProtocolFeature::SUPPORTED)
+                            } else {
+                                BTreeSet::new()
+                            },
                             name: self.name.clone(),
+                            protocol_version_range: announces_protocol_version
+                                .then_some(ProtocolVersionRange::SUPPORTED),
+                            // Older management servers reject the fields they do not know
+                            resume_secret: if resumes_requests {
+                                self.resume_secret_holder.get_resume_secret()
+                            } else {
+                                None
+                            },
                             slot_aggregated_status_snapshot,
                         }),
                     ))
@@ -402,7 +911,7 @@ impl ManagementSocketClientService {
 
         let do_send_status_update = || match self.slot_aggregated_status.make_snapshot() {
             Ok(slot_aggregated_status_snapshot) => {
-                message_tx
+                control_message_tx
                     .send(ManagementJsonRpcMessage::Notification(
                         ManagementJsonRpcNotification::UpdateAgentStatus(UpdateAgentStatusParams {
                             slot_aggregated_status_snapshot,
@@ -415,8 +924,40 @@ impl ManagementSocketClientService {
             Err(err) => error!("Failed to create slot aggregated status snapshot: {err}"),
         };
 
+        let mut has_sent_deregister_agent = false;
+        let mut do_deregister_if_draining = || {
+            if has_sent_deregister_agent || !self.drain_status.is_draining() {
+                return;
+            }
+
+            info!("Agent is draining, deregistering from the management server");
+
+            control_message_tx
+                .send(ManagementJsonRpcMessage::Notification(
+                    ManagementJsonRpcNotification::DeregisterAgent,
+                ))
+                .unwrap_or_else(|err| {
+                    error!("Failed to send deregister agent notification: {err}");
+                });
+
+            has_sent_deregister_agent = true;
+        };
+
+        // Agent might have started draining while it was reconnecting
+        do_deregister_if_draining();
+
+        let mut has_missed_heartbeats = false;
+        let resume_grace_period = if resumes_requests {
+            self.resume_grace_period
+        } else {
+            Duration::ZERO
+        };
+        let reports_token_usage = balancer_features.contains(&ProtocolFeature::TokenUsageReporting);
+        let sends_heartbeats = balancer_features.contains(&ProtocolFeature::Heartbeat);
+        let mut heartbeat_ticker = interval(self.heartbeat_configuration.interval);
         let mut ticker = interval(Duration::from_secs(1));
 
+        heartbeat_ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
         ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
         loop {
@@ -427,22 +968,60 @@ impl ManagementSocketClientService {
                     break;
                 }
                 _ = shutdown.recv() => break,
+                _ = self.drain_status.update_notifier.notified() => do_deregister_if_draining(),
                 _ = self.slot_aggregated_status.update_notifier.notified() => do_send_status_update(),
-                _ = ticker.tick() => do_send_status_update(),
+                _ = heartbeat_ticker.tick() => {
+                    // Management servers without the heartbeats can stay silent for a long time
+                    if sends_heartbeats && heartbeat_monitor.has_missed_beats() {
+                        warn!("Management server stopped sending heartbeats, closing the connection");
+
+                        has_missed_heartbeats = true;
+
+                        if let Err(err) = connection_close_tx.send(()) {
+                            error!("Failed to send connection close signal: {err}");
+                        }
+
+                        break;
+                    }
+
+                    if sends_heartbeats {
+                        control_message_tx
+                            .send(ManagementJsonRpcMessage::Notification(
+                                ManagementJsonRpcNotification::Heartbeat,
+                            ))
+                            .unwrap_or_else(|err| {
+                                error!("Failed to send heartbeat: {err}");
+                            });
+                    }
+                }
+                _ = ticker.tick() => {
+                    do_send_status_update();
+                    do_deregister_if_draining();
+                }
                 msg = read.next() => {
                     let should_close = match msg {
                         Some(Ok(msg)) => {
+                            heartbeat_monitor.record_beat();
+
                             if let Err(err) = Self::handle_incoming_message(
                                     IncomingMessageContext {
                                         agent_applicable_state_holder: self.agent_applicable_state_holder.clone(),
                                         agent_desired_state_tx: self.agent_desired_state_tx.clone(),
-                                        connection_close_tx: connection_close_tx.clone(),
+                                        connection_close_rx: connection_close_tx.subscribe(),
                                         continue_from_conversation_history_request_tx: self.continue_from_conversation_history_request_tx.clone(),
                                         continue_from_raw_prompt_request_tx: self.continue_from_raw_prompt_request_tx.clone(),
+                                        drain_status: self.drain_status.clone(),
                                         generate_embedding_batch_request_tx: self.generate_embedding_batch_request_tx.clone(),
                                         model_metadata_holder: self.model_metadata_holder.clone(),
+                                        receive_stream_pauser_collection: self.receive_stream_pauser_collection.clone(),
+                                        receive_stream_reattacher_collection: self.receive_stream_reattacher_collection.clone(),
                                         receive_stream_stopper_collection: self.receive_stream_stopper_collection.clone(),
                                         message_tx: message_tx.clone(),
+                                        reports_token_usage,
+                                        response_buffer_configuration: self.response_buffer_configuration.clone(),
+                                        resume_grace_period,
+                                        resume_secret_holder: self.resume_secret_holder.clone(),
+                                        slot_aggregated_status: self.slot_aggregated_status.clone(),
                                     },
                                     msg,
                                     pong_tx.clone(),
@@ -478,6 +1057,18 @@ impl ManagementSocketClientService {
             .await
             .context("Failed to join message forwarding task")?;
 
+        // Agent that was rejected keeps backing off instead of registering again right away
+        if !self
+            .slot_aggregated_status
+            .has_issue_like(|issue| matches!(issue, AgentIssue::BalancerRejectedAgent(_)))
+        {
+            reconnect_backoff.reset();
+        }
+
+        if has_missed_heartbeats {
+            return Err(anyhow!("Management server stopped responding"));
+        }
+
         Ok(())
     }
 }
@@ -489,23 +1080,47 @@ impl Service for ManagementSocketClientService {
     }
 
     async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
-        let mut ticker = interval(Duration::from_secs(1));
-
-        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut management_addr_index = 0;
+        let mut reconnect_backoff =
+            ReconnectBackoff::new(self.reconnect_initial_delay, self.reconnect_max_delay);
 
         loop {
+            match self
+                .keep_connection_alive(
+                    &self.management_addrs[management_addr_index],
+                    &mut reconnect_backoff,
+                    shutdown.resubscribe(),
+                )
+                .await
+            {
+                Err(err) => {
+                    error!("Failed to keep the connection alive: {err:?}");
+                }
+                Ok(()) => {
+                    info!("Gracefully closed connection to management server");
+                }
+            }
+
+            if !matches!(
+                shutdown.try_recv(),
+                Err(broadcast::error::TryRecvError::Empty)
+            ) {
+                break Ok(());
+            }
+
+            management_addr_index = (management_addr_index + 1) % self.management_addrs.len();
+
+            let reconnect_delay = reconnect_backoff.next_delay();
+
+            info!(
+                "Reconnecting to management server at {} in {}ms",
+                self.management_addrs[management_addr_index],
+                reconnect_delay.as_millis()
+            );
+
             tokio::select! {
                 _ = shutdown.recv() => break Ok(()),
-                _ = ticker.tick() => {
-                    match self.keep_connection_alive(shutdown.resubscribe()).await {
-                        Err(err) => {
-                            error!("Failed to keep the connection alive: {err:?}");
-                        }
-                        Ok(()) => {
-                            info!("Gracefully closed connection to management server");
-                        }
-                    }
-                }
+                _ = sleep(reconnect_delay) => {}
             }
         }
     }
@@ -0,0 +1,7 @@
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+
+/// Connection to the management server, either over TCP or a Unix socket.
+pub trait ManagementStream: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<TStream> ManagementStream for TStream where TStream: AsyncRead + AsyncWrite + Send + Unpin {}
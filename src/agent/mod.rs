@@ -1,5 +1,8 @@
+pub mod agent_token_holder;
+pub mod bounded_response_sender;
 pub mod continue_from_conversation_history_request;
 pub mod continue_from_raw_prompt_request;
+pub mod create_tls_client_config;
 mod from_request_params;
 pub mod generate_embedding_batch_request;
 pub mod jsonrpc;
@@ -9,8 +12,18 @@ mod llamacpp_arbiter_handle;
 pub mod llamacpp_arbiter_service;
 mod llamacpp_slot;
 mod llamacpp_slot_context;
+pub mod management_addr;
 pub mod management_socket_client_service;
+pub mod management_stream;
 pub mod model_metadata_holder;
+pub mod receive_stream_pauser_collection;
+mod receive_stream_pauser_drop_guard;
+pub mod receive_stream_reattacher_collection;
+mod receive_stream_reattacher_drop_guard;
+pub mod receive_stream_reattachment;
 pub mod receive_stream_stopper_collection;
 mod receive_stream_stopper_drop_guard;
 pub mod reconciliation_service;
+pub mod reconnect_backoff;
+mod response_replay_buffer;
+pub mod resume_secret_holder;
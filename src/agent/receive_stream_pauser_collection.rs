@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use anyhow::anyhow;
+use dashmap::DashMap;
+use tokio::sync::watch;
+
+use crate::agent::receive_stream_pauser_drop_guard::ReceiveStreamPauserDropGuard;
+
+/// Lets the balancer pause forwarding the responses of a request while its client catches up
+pub struct ReceiveStreamPauserCollection {
+    receive_pausers: DashMap<String, watch::Sender<bool>>,
+}
+
+impl ReceiveStreamPauserCollection {
+    pub fn deregister_pauser(&self, request_id: String) -> Result<()> {
+        if let Some(pauser) = self.receive_pausers.remove(&request_id) {
+            drop(pauser);
+
+            Ok(())
+        } else {
+            Err(anyhow!("No pauser found for request_id {request_id}"))
+        }
+    }
+
+    pub fn pause(&self, request_id: String) -> Result<()> {
+        self.set_paused(request_id, true)
+    }
+
+    pub fn register_pauser(&self, request_id: String, pauser: watch::Sender<bool>) -> Result<()> {
+        if self.receive_pausers.contains_key(&request_id) {
+            return Err(anyhow!("Pauser for request_id {request_id} already exists"));
+        }
+
+        self.receive_pausers.insert(request_id, pauser);
+
+        Ok(())
+    }
+
+    pub fn register_pauser_with_guard(
+        self: &Arc<Self>,
+        request_id: String,
+        pauser: watch::Sender<bool>,
+    ) -> Result<ReceiveStreamPauserDropGuard> {
+        self.register_pauser(request_id.clone(), pauser)?;
+
+        Ok(ReceiveStreamPauserDropGuard {
+            receive_stream_pauser_collection: self.clone(),
+            request_id,
+        })
+    }
+
+    pub fn resume(&self, request_id: String) -> Result<()> {
+        self.set_paused(request_id, false)
+    }
+
+    fn set_paused(&self, request_id: String, is_paused: bool) -> Result<()> {
+        if let Some(pauser) = self.receive_pausers.get(&request_id) {
+            pauser.send_replace(is_paused);
+
+            Ok(())
+        } else {
+            Err(anyhow!("No pauser found for request_id {request_id}"))
+        }
+    }
+}
+
+impl Default for ReceiveStreamPauserCollection {
+    fn default() -> Self {
+        Self {
+            receive_pausers: DashMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_and_resume() -> Result<()> {
+        let receive_stream_pauser_collection = Arc::new(ReceiveStreamPauserCollection::default());
+        let (pauser, is_paused) = watch::channel(false);
+
+        {
+            let _guard = receive_stream_pauser_collection
+                .register_pauser_with_guard("request".to_string(), pauser)?;
+
+            receive_stream_pauser_collection.pause("request".to_string())?;
+
+            assert!(*is_paused.borrow());
+
+            receive_stream_pauser_collection.resume("request".to_string())?;
+
+            assert!(!*is_paused.borrow());
+        }
+
+        assert!(
+            receive_stream_pauser_collection
+                .pause("request".to_string())
+                .is_err()
+        );
+
+        Ok(())
+    }
+}
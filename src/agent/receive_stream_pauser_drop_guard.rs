@@ -0,0 +1,24 @@
+use std::sync::Arc;
+
+use log::error;
+
+use crate::agent::receive_stream_pauser_collection::ReceiveStreamPauserCollection;
+
+pub struct ReceiveStreamPauserDropGuard {
+    pub receive_stream_pauser_collection: Arc<ReceiveStreamPauserCollection>,
+    pub request_id: String,
+}
+
+impl Drop for ReceiveStreamPauserDropGuard {
+    fn drop(&mut self) {
+        if let Err(err) = self
+            .receive_stream_pauser_collection
+            .deregister_pauser(self.request_id.clone())
+        {
+            error!(
+                "Failed to deregister pauser for request_id {}: {}",
+                self.request_id, err
+            );
+        }
+    }
+}
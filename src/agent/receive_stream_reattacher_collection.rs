@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use anyhow::anyhow;
+use dashmap::DashMap;
+use tokio::sync::mpsc;
+
+use crate::agent::receive_stream_reattacher_drop_guard::ReceiveStreamReattacherDropGuard;
+use crate::agent::receive_stream_reattachment::ReceiveStreamReattachment;
+
+/// Lets the requests that outlived a connection continue on the next one
+pub struct ReceiveStreamReattacherCollection {
+    receive_reattachers: DashMap<String, mpsc::UnboundedSender<ReceiveStreamReattachment>>,
+}
+
+impl ReceiveStreamReattacherCollection {
+    pub fn deregister_reattacher(&self, request_id: String) -> Result<()> {
+        if let Some(reattacher) = self.receive_reattachers.remove(&request_id) {
+            drop(reattacher);
+
+            Ok(())
+        } else {
+            Err(anyhow!("No reattacher found for request_id {request_id}"))
+        }
+    }
+
+    pub fn reattach(
+        &self,
+        request_id: String,
+        receive_stream_reattachment: ReceiveStreamReattachment,
+    ) -> Result<()> {
+        if let Some(reattacher) = self.receive_reattachers.get(&request_id) {
+            reattacher
+                .send(receive_stream_reattachment)
+                .map_err(|_| anyhow!("Request {request_id} is no longer waiting to be resumed"))?;
+
+            Ok(())
+        } else {
+            Err(anyhow!("No reattacher found for request_id {request_id}"))
+        }
+    }
+
+    pub fn register_reattacher(
+        &self,
+        request_id: String,
+        reattacher: mpsc::UnboundedSender<ReceiveStreamReattachment>,
+    ) -> Result<()> {
+        if self.receive_reattachers.contains_key(&request_id) {
+            return Err(anyhow!(
+                "Reattacher for request_id {request_id} already exists"
+            ));
+        }
+
+        self.receive_reattachers.insert(request_id, reattacher);
+
+        Ok(())
+    }
+
+    pub fn register_reattacher_with_guard(
+        self: &Arc<Self>,
+        request_id: String,
+        reattacher: mpsc::UnboundedSender<ReceiveStreamReattachment>,
+    ) -> Result<ReceiveStreamReattacherDropGuard> {
+        self.register_reattacher(request_id.clone(), reattacher)?;
+
+        Ok(ReceiveStreamReattacherDropGuard {
+            receive_stream_reattacher_collection: self.clone(),
+            request_id,
+        })
+    }
+}
+
+impl Default for ReceiveStreamReattacherCollection {
+    fn default() -> Self {
+        Self {
+            receive_reattachers: DashMap::new(),
+        }
+    }
+}
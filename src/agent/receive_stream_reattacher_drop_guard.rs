@@ -0,0 +1,24 @@
+use std::sync::Arc;
+
+use log::error;
+
+use crate::agent::receive_stream_reattacher_collection::ReceiveStreamReattacherCollection;
+
+pub struct ReceiveStreamReattacherDropGuard {
+    pub receive_stream_reattacher_collection: Arc<ReceiveStreamReattacherCollection>,
+    pub request_id: String,
+}
+
+impl Drop for ReceiveStreamReattacherDropGuard {
+    fn drop(&mut self) {
+        if let Err(err) = self
+            .receive_stream_reattacher_collection
+            .deregister_reattacher(self.request_id.clone())
+        {
+            error!(
+                "Failed to deregister reattacher for request_id {}: {}",
+                self.request_id, err
+            );
+        }
+    }
+}
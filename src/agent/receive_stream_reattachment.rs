@@ -0,0 +1,11 @@
+use tokio::sync::broadcast;
+use tokio::sync::mpsc;
+
+use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::Message as ManagementJsonRpcMessage;
+
+/// Connection that the responses of a request continue on after the agent reconnects
+pub struct ReceiveStreamReattachment {
+    pub connection_close_rx: broadcast::Receiver<()>,
+    pub message_tx: mpsc::Sender<ManagementJsonRpcMessage>,
+    pub received_responses: usize,
+}
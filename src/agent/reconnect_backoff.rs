@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use rand::Rng as _;
+
+/// Exponential backoff between the connection attempts. Every delay is randomized
+/// between half and all of its exponential value, so the agents that lost the same
+/// balancer do not all reconnect at once.
+pub struct ReconnectBackoff {
+    consecutive_failures: u32,
+    initial_delay: Duration,
+    max_delay: Duration,
+}
+
+impl ReconnectBackoff {
+    pub fn new(initial_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            consecutive_failures: 0,
+            initial_delay,
+            max_delay,
+        }
+    }
+
+    pub fn next_delay(&mut self) -> Duration {
+        let exponential_delay = self
+            .initial_delay
+            .saturating_mul(2_u32.saturating_pow(self.consecutive_failures))
+            .min(self.max_delay);
+        let half_delay_millis = exponential_delay.as_millis() as u64 / 2;
+
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+
+        Duration::from_millis(half_delay_millis + rand::rng().random_range(0..=half_delay_millis))
+    }
+
+    /// Called once the agent is connected, so the next disconnection starts over
+    /// from the initial delay.
+    pub fn reset(&mut self) {
+        self.consecutive_failures = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_grows_up_to_the_limit() {
+        let mut reconnect_backoff =
+            ReconnectBackoff::new(Duration::from_millis(1000), Duration::from_millis(5000));
+
+        for expected_delay_millis in [1000, 2000, 4000, 5000, 5000] {
+            let delay = reconnect_backoff.next_delay();
+
+            assert!(delay >= Duration::from_millis(expected_delay_millis / 2));
+            assert!(delay <= Duration::from_millis(expected_delay_millis));
+        }
+    }
+
+    #[test]
+    fn test_reset_starts_over() {
+        let mut reconnect_backoff =
+            ReconnectBackoff::new(Duration::from_millis(1000), Duration::from_millis(60000));
+
+        for _ in 0..10 {
+            reconnect_backoff.next_delay();
+        }
+
+        reconnect_backoff.reset();
+
+        assert!(reconnect_backoff.next_delay() <= Duration::from_millis(1000));
+    }
+}
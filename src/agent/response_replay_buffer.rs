@@ -0,0 +1,95 @@
+use std::collections::VecDeque;
+
+use anyhow::Result;
+use anyhow::anyhow;
+use tokio::time::Duration;
+use tokio::time::Instant;
+
+/// Keeps the responses of the last resume grace period, so the ones that were lost with
+/// the connection, or generated while the agent was reconnecting, can be sent after it reconnects
+pub struct ResponseReplayBuffer<TResponse> {
+    responses: VecDeque<(Instant, TResponse)>,
+    retention: Duration,
+    total_responses: usize,
+}
+
+impl<TResponse: Clone> ResponseReplayBuffer<TResponse> {
+    pub fn new(retention: Duration) -> Self {
+        Self {
+            responses: VecDeque::new(),
+            retention,
+            total_responses: 0,
+        }
+    }
+
+    pub fn get_responses_after(&self, received_responses: usize) -> Result<Vec<TResponse>> {
+        let first_kept_response = self.total_responses - self.responses.len();
+
+        if received_responses > self.total_responses {
+            return Err(anyhow!(
+                "Management server received {received_responses} responses, but only {} were generated",
+                self.total_responses
+            ));
+        }
+
+        if received_responses < first_kept_response {
+            return Err(anyhow!(
+                "Management server received {received_responses} responses, but only the last {} of {} are kept",
+                self.responses.len(),
+                self.total_responses
+            ));
+        }
+
+        Ok(self
+            .responses
+            .iter()
+            .skip(received_responses - first_kept_response)
+            .map(|(_, response)| response.clone())
+            .collect())
+    }
+
+    pub fn push(&mut self, response: TResponse) {
+        let now = Instant::now();
+
+        while self
+            .responses
+            .front()
+            .is_some_and(|(pushed_at, _)| now.duration_since(*pushed_at) > self.retention)
+        {
+            self.responses.pop_front();
+        }
+
+        self.responses.push_back((now, response));
+        self.total_responses += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::advance;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_get_responses_after() -> Result<()> {
+        let mut response_replay_buffer = ResponseReplayBuffer::new(Duration::from_secs(10));
+
+        response_replay_buffer.push(1);
+        advance(Duration::from_secs(6)).await;
+        response_replay_buffer.push(2);
+        response_replay_buffer.push(3);
+        advance(Duration::from_secs(5)).await;
+        response_replay_buffer.push(4);
+
+        assert_eq!(
+            response_replay_buffer.get_responses_after(1)?,
+            vec![2, 3, 4]
+        );
+        assert_eq!(response_replay_buffer.get_responses_after(3)?, vec![4]);
+        assert!(response_replay_buffer.get_responses_after(4)?.is_empty());
+        assert!(response_replay_buffer.get_responses_after(0).is_err());
+        assert!(response_replay_buffer.get_responses_after(5).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+use std::sync::RwLock;
+
+/// Resume secret the management server issued to the most recent connection
+pub struct ResumeSecretHolder {
+    resume_secret: RwLock<Option<String>>,
+}
+
+impl ResumeSecretHolder {
+    pub fn get_resume_secret(&self) -> Option<String> {
+        self.resume_secret
+            .read()
+            .expect("Failed to acquire read lock on resume secret")
+            .clone()
+    }
+
+    pub fn set_resume_secret(&self, resume_secret: String) {
+        *self
+            .resume_secret
+            .write()
+            .expect("Failed to acquire write lock on resume secret") = Some(resume_secret);
+    }
+}
+
+impl Default for ResumeSecretHolder {
+    fn default() -> Self {
+        Self {
+            resume_secret: RwLock::new(None),
+        }
+    }
+}
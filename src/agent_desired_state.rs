@@ -12,7 +12,7 @@ use crate::converts_to_applicable_state::ConvertsToApplicableState;
 use crate::inference_parameters::InferenceParameters;
 use crate::slot_aggregated_status::SlotAggregatedStatus;
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct AgentDesiredState {
     pub chat_template_override: Option<ChatTemplate>,
@@ -2,14 +2,17 @@ use serde::Deserialize;
 use serde::Serialize;
 
 use crate::agent_issue_params::ChatTemplateDoesNotCompileParams;
+use crate::agent_issue_params::IncompatibleProtocolVersionParams;
 use crate::agent_issue_params::SlotCannotStartParams;
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 #[serde(deny_unknown_fields)]
 pub enum AgentIssue {
+    BalancerRejectedAgent(String),
     ChatTemplateDoesNotCompile(ChatTemplateDoesNotCompileParams),
     HuggingFaceCannotAcquireLock(String),
     HuggingFaceModelDoesNotExist(String),
+    IncompatibleProtocolVersion(IncompatibleProtocolVersionParams),
     ModelCannotBeLoaded(String),
     ModelFileDoesNotExist(String),
     SlotCannotStart(SlotCannotStartParams),
@@ -2,6 +2,8 @@ use crate::agent_issue::AgentIssue;
 use crate::agent_issue_params::SlotCannotStartParams;
 
 pub enum AgentIssueFix {
+    BalancerAcceptedConnection,
+    BalancerProtocolIsCompatible,
     ChatTemplateIsCompiled,
     HuggingFaceDownloadedModel,
     HuggingFaceStartedDownloading,
@@ -15,6 +17,9 @@ pub enum AgentIssueFix {
 impl AgentIssueFix {
     pub fn can_fix(&self, issue: &AgentIssue) -> bool {
         match issue {
+            AgentIssue::BalancerRejectedAgent(_) => {
+                matches!(self, AgentIssueFix::BalancerAcceptedConnection)
+            }
             AgentIssue::ChatTemplateDoesNotCompile(_) => matches!(
                 self,
                 AgentIssueFix::ChatTemplateIsCompiled | AgentIssueFix::ModelStateIsReconciled
@@ -31,6 +36,9 @@ impl AgentIssueFix {
                     | AgentIssueFix::HuggingFaceStartedDownloading
                     | AgentIssueFix::ModelStateIsReconciled
             ),
+            AgentIssue::IncompatibleProtocolVersion(_) => {
+                matches!(self, AgentIssueFix::BalancerProtocolIsCompatible)
+            }
             AgentIssue::ModelCannotBeLoaded(_) => matches!(self, AgentIssueFix::ModelIsLoaded),
             AgentIssue::ModelFileDoesNotExist(_) => matches!(self, AgentIssueFix::ModelFileExists),
             AgentIssue::SlotCannotStart(SlotCannotStartParams {
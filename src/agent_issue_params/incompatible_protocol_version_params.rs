@@ -0,0 +1,11 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::protocol_version_range::ProtocolVersionRange;
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct IncompatibleProtocolVersionParams {
+    pub agent_protocol_version_range: ProtocolVersionRange,
+    pub balancer_protocol_version_range: ProtocolVersionRange,
+}
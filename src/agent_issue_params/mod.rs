@@ -1,5 +1,7 @@
 mod chat_template_does_not_compile_params;
+mod incompatible_protocol_version_params;
 mod slot_cannot_start_params;
 
 pub use self::chat_template_does_not_compile_params::ChatTemplateDoesNotCompileParams;
+pub use self::incompatible_protocol_version_params::IncompatibleProtocolVersionParams;
 pub use self::slot_cannot_start_params::SlotCannotStartParams;
@@ -0,0 +1,89 @@
+use serde_json::Value;
+
+/// JSON merge patch (RFC 7396): objects are merged recursively, `null` removes
+/// a key, and every other value replaces the target.
+pub fn apply_json_merge_patch(target: &mut Value, patch: &Value) {
+    match patch {
+        Value::Object(patch_object) => {
+            if !target.is_object() {
+                *target = Value::Object(Default::default());
+            }
+
+            if let Value::Object(target_object) = target {
+                for (key, patch_value) in patch_object {
+                    if patch_value.is_null() {
+                        target_object.remove(key);
+                    } else {
+                        apply_json_merge_patch(
+                            target_object.entry(key.clone()).or_insert(Value::Null),
+                            patch_value,
+                        );
+                    }
+                }
+            }
+        }
+        _ => *target = patch.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_merges_nested_objects() {
+        let mut target = json!({
+            "inference_parameters": {
+                "temperature": 0.6,
+                "top_k": 40,
+            },
+            "model": {
+                "LocalToAgent": "model.gguf",
+            },
+        });
+
+        apply_json_merge_patch(
+            &mut target,
+            &json!({
+                "inference_parameters": {
+                    "temperature": 0.2,
+                },
+            }),
+        );
+
+        assert_eq!(
+            target,
+            json!({
+                "inference_parameters": {
+                    "temperature": 0.2,
+                    "top_k": 40,
+                },
+                "model": {
+                    "LocalToAgent": "model.gguf",
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_null_removes_key_and_arrays_are_replaced() {
+        let mut target = json!({
+            "chat_template_override": {
+                "content": "{{ messages }}",
+            },
+            "stop": ["a", "b"],
+        });
+
+        apply_json_merge_patch(
+            &mut target,
+            &json!({
+                "chat_template_override": null,
+                "stop": ["c"],
+            }),
+        );
+
+        assert_eq!(target, json!({ "stop": ["c"] }));
+    }
+}
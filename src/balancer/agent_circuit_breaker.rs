@@ -0,0 +1,392 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::atomic::AtomicUsize;
+use std::time::Instant;
+
+use log::info;
+use log::warn;
+use tokio::sync::Notify;
+use tokio::sync::watch;
+use tokio::time::sleep_until;
+
+use crate::atomic_value::AtomicValue;
+use crate::balancer::agent_circuit_breaker_configuration::AgentCircuitBreakerConfiguration;
+use crate::balancer::agent_circuit_breaker_state::AgentCircuitBreakerState;
+use crate::balancer::agent_circuit_state::AgentCircuitState;
+
+pub struct AgentCircuitBreaker {
+    agent_id: String,
+    configuration: AgentCircuitBreakerConfiguration,
+    /// When the open circuit lets the next probe through
+    half_open_at_tx: watch::Sender<Option<Instant>>,
+    pub requests_failed: AtomicValue<AtomicUsize>,
+    pub requests_succeeded: AtomicValue<AtomicUsize>,
+    state: Mutex<AgentCircuitBreakerState>,
+    update_notifier: Arc<Notify>,
+}
+
+impl AgentCircuitBreaker {
+    pub fn new(
+        agent_id: String,
+        configuration: AgentCircuitBreakerConfiguration,
+        update_notifier: Arc<Notify>,
+    ) -> Self {
+        let (half_open_at_tx, half_open_at_rx) = watch::channel(None);
+
+        tokio::spawn(Self::notify_when_half_open(
+            half_open_at_rx,
+            update_notifier.clone(),
+        ));
+
+        Self {
+            agent_id,
+            configuration,
+            half_open_at_tx,
+            requests_failed: AtomicValue::<AtomicUsize>::new(0),
+            requests_succeeded: AtomicValue::<AtomicUsize>::new(0),
+            state: Mutex::new(AgentCircuitBreakerState::default()),
+            update_notifier,
+        }
+    }
+
+    pub fn get_circuit_state(&self) -> AgentCircuitState {
+        self.lock_state().circuit_state
+    }
+
+    pub fn get_consecutive_failures(&self) -> usize {
+        self.lock_state().consecutive_failures
+    }
+
+    pub fn get_recent_failure_ratio(&self) -> f64 {
+        self.lock_state().get_recent_failure_ratio()
+    }
+
+    pub fn record_failure(&self) {
+        self.requests_failed.increment_by(1);
+
+        let mut state = self.lock_state();
+
+        state.consecutive_failures += 1;
+        state.record_outcome(true, self.configuration.failure_ratio_window);
+
+        let should_open = match state.circuit_state {
+            AgentCircuitState::Closed => {
+                let has_too_many_consecutive_failures = self.configuration.failure_threshold > 0
+                    && state.consecutive_failures >= self.configuration.failure_threshold;
+                // Catches the agents that fail intermittently, without ever failing
+                // enough times in a row
+                let has_too_high_failure_ratio = self.configuration.failure_ratio > 0.0
+                    && state.recent_outcomes.len()
+                        >= self.configuration.failure_ratio_min_requests.max(1)
+                    && state.get_recent_failure_ratio() >= self.configuration.failure_ratio;
+
+                has_too_many_consecutive_failures || has_too_high_failure_ratio
+            }
+            AgentCircuitState::HalfOpen => true,
+            AgentCircuitState::Open => false,
+        };
+
+        if !should_open {
+            return;
+        }
+
+        warn!(
+            "Opening circuit for agent {:?} after {} consecutive failures ({:.0}% of the recent requests failed)",
+            self.agent_id,
+            state.consecutive_failures,
+            state.get_recent_failure_ratio() * 100.0
+        );
+
+        let opened_at = Instant::now();
+
+        state.circuit_state = AgentCircuitState::Open;
+        state.opened_at = Some(opened_at);
+        state.probe_started_at = None;
+
+        drop(state);
+
+        self.update_notifier.notify_waiters();
+        self.half_open_at_tx
+            .send_replace(Some(opened_at + self.configuration.cooldown));
+    }
+
+    pub fn record_success(&self) {
+        self.requests_succeeded.increment_by(1);
+
+        let mut state = self.lock_state();
+
+        state.consecutive_failures = 0;
+        state.record_outcome(false, self.configuration.failure_ratio_window);
+
+        if state.circuit_state == AgentCircuitState::Closed {
+            return;
+        }
+
+        info!("Closing circuit for agent {:?}", self.agent_id);
+
+        *state = AgentCircuitBreakerState::default();
+
+        drop(state);
+
+        self.update_notifier.notify_waiters();
+    }
+
+    /// Checks if the agent can be handed a request, and if that request is going to be
+    /// the probe, marks the probe as started.
+    pub fn try_acquire(&self) -> bool {
+        let mut state = self.lock_state();
+
+        match state.circuit_state {
+            AgentCircuitState::Closed => true,
+            AgentCircuitState::HalfOpen => {
+                // Probe that never reported back (for example, the client went away)
+                // should not keep the agent out of the rotation forever
+                let is_probe_stale = state.probe_started_at.is_none_or(|probe_started_at| {
+                    probe_started_at.elapsed() >= self.configuration.cooldown
+                });
+
+                if is_probe_stale {
+                    state.probe_started_at = Some(Instant::now());
+                }
+
+                is_probe_stale
+            }
+            AgentCircuitState::Open => {
+                let is_cooldown_over = state
+                    .opened_at
+                    .is_none_or(|opened_at| opened_at.elapsed() >= self.configuration.cooldown);
+
+                if is_cooldown_over {
+                    info!("Sending a probe request to agent {:?}", self.agent_id);
+
+                    state.circuit_state = AgentCircuitState::HalfOpen;
+                    state.probe_started_at = Some(Instant::now());
+                }
+
+                is_cooldown_over
+            }
+        }
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, AgentCircuitBreakerState> {
+        self.state
+            .lock()
+            .expect("Poisoned lock on agent circuit breaker state")
+    }
+
+    /// Buffered requests only wake up on changes, so they are told when the probe can be
+    /// attempted. Ends together with the circuit breaker.
+    async fn notify_when_half_open(
+        mut half_open_at_rx: watch::Receiver<Option<Instant>>,
+        update_notifier: Arc<Notify>,
+    ) {
+        let mut half_open_at: Option<Instant> = None;
+
+        loop {
+            tokio::select! {
+                changed = half_open_at_rx.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+
+                    half_open_at = *half_open_at_rx.borrow_and_update();
+                }
+                _ = sleep_until(half_open_at.unwrap_or_else(Instant::now).into()), if half_open_at.is_some() => {
+                    half_open_at = None;
+                    update_notifier.notify_waiters();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use tokio::time::timeout;
+
+    use super::*;
+
+    fn make_circuit_breaker(cooldown: Duration) -> AgentCircuitBreaker {
+        AgentCircuitBreaker::new(
+            "test_agent".to_string(),
+            AgentCircuitBreakerConfiguration {
+                cooldown,
+                failure_ratio: 0.0,
+                failure_ratio_min_requests: 0,
+                failure_ratio_window: 0,
+                failure_threshold: 2,
+            },
+            Arc::new(Notify::new()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_opens_after_threshold() {
+        let circuit_breaker = make_circuit_breaker(Duration::from_secs(60));
+
+        circuit_breaker.record_failure();
+
+        assert_eq!(
+            circuit_breaker.get_circuit_state(),
+            AgentCircuitState::Closed
+        );
+        assert!(circuit_breaker.try_acquire());
+
+        circuit_breaker.record_failure();
+
+        assert_eq!(circuit_breaker.get_circuit_state(), AgentCircuitState::Open);
+        assert!(!circuit_breaker.try_acquire());
+    }
+
+    #[tokio::test]
+    async fn test_success_resets_consecutive_failures() {
+        let circuit_breaker = make_circuit_breaker(Duration::from_secs(60));
+
+        circuit_breaker.record_failure();
+        circuit_breaker.record_success();
+        circuit_breaker.record_failure();
+
+        assert_eq!(
+            circuit_breaker.get_circuit_state(),
+            AgentCircuitState::Closed
+        );
+        assert_eq!(circuit_breaker.get_consecutive_failures(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_opens_on_interleaved_failures() {
+        let circuit_breaker = AgentCircuitBreaker::new(
+            "test_agent".to_string(),
+            AgentCircuitBreakerConfiguration {
+                cooldown: Duration::from_secs(60),
+                failure_ratio: 0.5,
+                failure_ratio_min_requests: 6,
+                failure_ratio_window: 10,
+                failure_threshold: 2,
+            },
+            Arc::new(Notify::new()),
+        );
+
+        for _ in 0..2 {
+            circuit_breaker.record_failure();
+            circuit_breaker.record_success();
+        }
+
+        circuit_breaker.record_failure();
+
+        // Too few requests to tell yet
+        assert_eq!(
+            circuit_breaker.get_circuit_state(),
+            AgentCircuitState::Closed
+        );
+
+        circuit_breaker.record_success();
+        circuit_breaker.record_failure();
+
+        assert_eq!(circuit_breaker.get_circuit_state(), AgentCircuitState::Open);
+        assert_eq!(circuit_breaker.get_consecutive_failures(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_old_failures_leave_the_window() {
+        let circuit_breaker = AgentCircuitBreaker::new(
+            "test_agent".to_string(),
+            AgentCircuitBreakerConfiguration {
+                cooldown: Duration::from_secs(60),
+                failure_ratio: 0.5,
+                failure_ratio_min_requests: 4,
+                failure_ratio_window: 4,
+                failure_threshold: 0,
+            },
+            Arc::new(Notify::new()),
+        );
+
+        circuit_breaker.record_failure();
+
+        for _ in 0..4 {
+            circuit_breaker.record_success();
+        }
+
+        assert_eq!(circuit_breaker.get_recent_failure_ratio(), 0.0);
+
+        circuit_breaker.record_failure();
+
+        assert_eq!(
+            circuit_breaker.get_circuit_state(),
+            AgentCircuitState::Closed
+        );
+        assert_eq!(circuit_breaker.get_recent_failure_ratio(), 0.25);
+
+        circuit_breaker.record_success();
+        circuit_breaker.record_failure();
+
+        assert_eq!(circuit_breaker.get_circuit_state(), AgentCircuitState::Open);
+    }
+
+    #[tokio::test]
+    async fn test_lets_single_probe_through_after_cooldown() {
+        let circuit_breaker = make_circuit_breaker(Duration::ZERO);
+
+        circuit_breaker.record_failure();
+        circuit_breaker.record_failure();
+
+        assert!(circuit_breaker.try_acquire());
+        assert_eq!(
+            circuit_breaker.get_circuit_state(),
+            AgentCircuitState::HalfOpen
+        );
+
+        circuit_breaker.record_success();
+
+        assert_eq!(
+            circuit_breaker.get_circuit_state(),
+            AgentCircuitState::Closed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_probe_opens_circuit_again() {
+        let circuit_breaker = make_circuit_breaker(Duration::ZERO);
+
+        circuit_breaker.record_failure();
+        circuit_breaker.record_failure();
+
+        assert!(circuit_breaker.try_acquire());
+
+        circuit_breaker.record_failure();
+
+        assert_eq!(circuit_breaker.get_circuit_state(), AgentCircuitState::Open);
+    }
+
+    #[tokio::test]
+    async fn test_notifies_when_probe_can_be_sent() -> Result<()> {
+        let update_notifier = Arc::new(Notify::new());
+        let circuit_breaker = AgentCircuitBreaker::new(
+            "test_agent".to_string(),
+            AgentCircuitBreakerConfiguration {
+                cooldown: Duration::from_millis(100),
+                failure_ratio: 0.0,
+                failure_ratio_min_requests: 0,
+                failure_ratio_window: 0,
+                failure_threshold: 1,
+            },
+            update_notifier.clone(),
+        );
+
+        circuit_breaker.record_failure();
+
+        loop {
+            let update_notified = update_notifier.notified();
+
+            if circuit_breaker.try_acquire() {
+                return Ok(());
+            }
+
+            timeout(Duration::from_secs(5), update_notified).await?;
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use std::time::Duration;
+
+#[derive(Clone)]
+pub struct AgentCircuitBreakerConfiguration {
+    pub cooldown: Duration,
+    /// Share of the failed requests among the recent ones that opens the circuit
+    /// (0 disables it)
+    pub failure_ratio: f64,
+    /// Number of the recent requests needed before the failure ratio is considered
+    pub failure_ratio_min_requests: usize,
+    /// Number of the most recent requests the failure ratio is computed from
+    pub failure_ratio_window: usize,
+    /// Number of consecutive failures that opens the circuit (0 disables it)
+    pub failure_threshold: usize,
+}
@@ -0,0 +1,49 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use crate::balancer::agent_circuit_state::AgentCircuitState;
+
+pub struct AgentCircuitBreakerState {
+    pub circuit_state: AgentCircuitState,
+    pub consecutive_failures: usize,
+    pub opened_at: Option<Instant>,
+    pub probe_started_at: Option<Instant>,
+    /// Outcomes of the most recent requests, `true` for the failed ones
+    pub recent_outcomes: VecDeque<bool>,
+}
+
+impl AgentCircuitBreakerState {
+    pub fn get_recent_failure_ratio(&self) -> f64 {
+        if self.recent_outcomes.is_empty() {
+            return 0.0;
+        }
+
+        let recent_failures = self
+            .recent_outcomes
+            .iter()
+            .filter(|is_failure| **is_failure)
+            .count();
+
+        recent_failures as f64 / self.recent_outcomes.len() as f64
+    }
+
+    pub fn record_outcome(&mut self, is_failure: bool, window: usize) {
+        self.recent_outcomes.push_back(is_failure);
+
+        while self.recent_outcomes.len() > window {
+            self.recent_outcomes.pop_front();
+        }
+    }
+}
+
+impl Default for AgentCircuitBreakerState {
+    fn default() -> Self {
+        Self {
+            circuit_state: AgentCircuitState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            probe_started_at: None,
+            recent_outcomes: VecDeque::new(),
+        }
+    }
+}
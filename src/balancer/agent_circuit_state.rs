@@ -0,0 +1,12 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum AgentCircuitState {
+    /// Agent receives traffic as usual
+    Closed,
+    /// Cooldown has passed and a single probe request is allowed to check if the agent recovered
+    HalfOpen,
+    /// Agent failed too many times in a row and is excluded from selection
+    Open,
+}
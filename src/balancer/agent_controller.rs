@@ -8,11 +8,13 @@ use std::sync::RwLock;
 use anyhow::Result;
 use async_trait::async_trait;
 use log::debug;
+use log::warn;
 use nanoid::nanoid;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc;
 
 use crate::request_params::continue_from_conversation_history_params::tool::tool_params::function_call::parameters_schema::validated_parameters_schema::ValidatedParametersSchema;
+use crate::agent::jsonrpc::notification_params::ReattachRequestParams;
 use crate::agent::jsonrpc::notification_params::SetStateParams;
 use crate::agent::jsonrpc::Message as AgentJsonRpcMessage;
 use crate::agent::jsonrpc::Notification as AgentJsonRpcNotification;
@@ -20,6 +22,7 @@ use crate::agent::jsonrpc::Request as AgentJsonRpcRequest;
 use crate::agent_desired_state::AgentDesiredState;
 use crate::agent_issue::AgentIssue;
 use crate::atomic_value::AtomicValue;
+use crate::balancer::agent_circuit_breaker::AgentCircuitBreaker;
 use crate::balancer::agent_controller_snapshot::AgentControllerSnapshot;
 use crate::balancer::agent_controller_update_result::AgentControllerUpdateResult;
 use crate::balancer::chat_template_override_sender_collection::ChatTemplateOverrideSenderCollection;
@@ -29,31 +32,48 @@ use crate::balancer::handles_agent_streaming_response::HandlesAgentStreamingResp
 use crate::balancer::manages_senders::ManagesSenders;
 use crate::balancer::manages_senders_controller::ManagesSendersController;
 use crate::balancer::model_metadata_sender_collection::ModelMetadataSenderCollection;
+use crate::full_buffer_policy::FullBufferPolicy;
 use crate::jsonrpc::RequestEnvelope;
 use crate::produces_snapshot::ProducesSnapshot;
+use crate::protocol_feature::ProtocolFeature;
 use crate::request_params::ContinueFromConversationHistoryParams;
 use crate::request_params::ContinueFromRawPromptParams;
 use crate::request_params::GenerateEmbeddingBatchParams;
+use crate::response_buffer_configuration::ResponseBufferConfiguration;
 use crate::sends_rpc_message::SendsRpcMessage;
 use crate::sets_desired_state::SetsDesiredState;
 use crate::slot_aggregated_status_snapshot::SlotAggregatedStatusSnapshot;
 
 pub struct AgentController {
     pub agent_message_tx: mpsc::UnboundedSender<AgentJsonRpcMessage>,
+    /// Checked again after the tokens are reloaded
+    pub agent_token: Option<String>,
     pub chat_template_override_sender_collection: Arc<ChatTemplateOverrideSenderCollection>,
+    pub circuit_breaker: AgentCircuitBreaker,
     pub connection_close_rx: broadcast::Receiver<()>,
+    pub connection_close_tx: broadcast::Sender<()>,
     pub desired_slots_total: AtomicValue<AtomicI32>,
     pub download_current: AtomicValue<AtomicUsize>,
     pub download_filename: RwLock<Option<String>>,
     pub download_total: AtomicValue<AtomicUsize>,
     pub embedding_sender_collection: Arc<EmbeddingSenderCollection>,
+    /// Features announced by the agent during the handshake
+    pub features: BTreeSet<ProtocolFeature>,
     pub generate_tokens_sender_collection: Arc<GenerateTokensSenderCollection>,
     pub id: String,
+    pub is_cordoned: AtomicValue<AtomicBool>,
+    pub is_draining: AtomicValue<AtomicBool>,
+    /// Such agents are not checked against the tokens
+    pub is_identified_by_client_certificate: bool,
     pub issues: RwLock<BTreeSet<AgentIssue>>,
     pub model_metadata_sender_collection: Arc<ModelMetadataSenderCollection>,
     pub model_path: RwLock<Option<String>>,
     pub name: Option<String>,
     pub newest_update_version: AtomicValue<AtomicI32>,
+    pub protocol_version: u32,
+    /// Issued to this connection, so the next connection of the same agent can replace it
+    pub resume_secret: String,
+    pub response_buffer_configuration: ResponseBufferConfiguration,
     pub slots_processing: AtomicValue<AtomicI32>,
     pub slots_total: AtomicValue<AtomicI32>,
     pub state_application_status_code: AtomicValue<AtomicI32>,
@@ -61,6 +81,11 @@ pub struct AgentController {
 }
 
 impl AgentController {
+    /// Cordoned or draining agents finish the requests they already have, but do not get new ones
+    pub fn accepts_new_requests(&self) -> bool {
+        !self.is_cordoned.get() && !self.is_draining.get()
+    }
+
     pub async fn get_chat_template_override(
         &self,
     ) -> Result<ManagesSendersController<ChatTemplateOverrideSenderCollection>> {
@@ -71,6 +96,42 @@ impl AgentController {
         .await
     }
 
+    /// Applies the full buffer policy when the client reads the responses slower than
+    /// the agent generates them
+    pub async fn forward_streaming_response<TManagesSenders: ManagesSenders + Sync>(
+        &self,
+        request_id: String,
+        sender_collection: &TManagesSenders,
+        value: TManagesSenders::Value,
+    ) -> Result<()> {
+        if !sender_collection.is_response_buffer_full(&request_id) {
+            return sender_collection.forward_response(request_id, value).await;
+        }
+
+        match self.response_buffer_configuration.full_buffer_policy {
+            FullBufferPolicy::Abort => {
+                warn!("Response buffer of request {request_id:?} is full, aborting it");
+
+                // Waiting request notices that the responses ended before the request was done
+                sender_collection.deregister_sender(request_id.clone())?;
+
+                self.stop_responding_to(request_id).await
+            }
+            FullBufferPolicy::Pause => {
+                // Older agents keep sending the responses, which wait for the room in the buffer
+                if self.supports(ProtocolFeature::ResponseFlowControl)
+                    && sender_collection.mark_paused(&request_id)
+                {
+                    debug!("Response buffer of request {request_id:?} is full, pausing it");
+
+                    self.pause_responding_to(request_id.clone()).await?;
+                }
+
+                sender_collection.forward_response(request_id, value).await
+            }
+        }
+    }
+
     pub fn get_download_filename(&self) -> Option<String> {
         self.download_filename
             .read()
@@ -99,6 +160,53 @@ impl AgentController {
             .clone()
     }
 
+    pub async fn pause_responding_to(&self, request_id: String) -> Result<()> {
+        self.send_rpc_message(AgentJsonRpcMessage::Notification(
+            AgentJsonRpcNotification::PauseRespondingTo(request_id),
+        ))
+        .await
+    }
+
+    /// Asks the reconnected agent to continue the request after the responses that were received
+    pub async fn reattach_request(
+        &self,
+        request_id: String,
+        received_responses: usize,
+    ) -> Result<()> {
+        self.send_rpc_message(AgentJsonRpcMessage::Notification(
+            AgentJsonRpcNotification::ReattachRequest(ReattachRequestParams {
+                received_responses,
+                request_id,
+            }),
+        ))
+        .await
+    }
+
+    /// Resumes the paused request only after the client reads half of its buffered responses,
+    /// so the request is not paused and resumed again on every response
+    pub async fn resume_responding_if_drained<TManagesSenders: ManagesSenders + Sync>(
+        &self,
+        request_id: &str,
+        sender_collection: &TManagesSenders,
+    ) -> Result<()> {
+        if !sender_collection
+            .mark_resumed_if_drained(request_id, self.response_buffer_configuration.capacity / 2)
+        {
+            return Ok(());
+        }
+
+        debug!("Response buffer of request {request_id:?} drained, resuming it");
+
+        self.resume_responding_to(request_id.to_string()).await
+    }
+
+    pub async fn resume_responding_to(&self, request_id: String) -> Result<()> {
+        self.send_rpc_message(AgentJsonRpcMessage::Notification(
+            AgentJsonRpcNotification::ResumeRespondingTo(request_id),
+        ))
+        .await
+    }
+
     pub fn set_download_filename(&self, filename: Option<String>) {
         let mut locked_filename = self
             .download_filename
@@ -132,6 +240,10 @@ impl AgentController {
         Ok(())
     }
 
+    pub fn supports(&self, feature: ProtocolFeature) -> bool {
+        self.features.contains(&feature)
+    }
+
     pub fn update_from_slot_aggregated_status_snapshot(
         &self,
         SlotAggregatedStatusSnapshot {
@@ -213,7 +325,8 @@ impl AgentController {
         }))
         .await?;
 
-        ManagesSendersController::from_request_id(request_id, sender_collection)
+        // Only a single response is expected
+        ManagesSendersController::from_request_id(request_id, 1, sender_collection)
     }
 
     async fn receiver_from_message<TManagesSenders: ManagesSenders>(
@@ -222,7 +335,7 @@ impl AgentController {
         sender_collection: Arc<TManagesSenders>,
         message: AgentJsonRpcMessage,
     ) -> Result<ManagesSendersController<TManagesSenders>> {
-        let (response_tx, response_rx) = mpsc::unbounded_channel();
+        let (response_tx, response_rx) = mpsc::channel(self.response_buffer_configuration.capacity);
 
         sender_collection.register_sender(request_id.clone(), response_tx)?;
 
@@ -306,6 +419,9 @@ impl ProducesSnapshot for AgentController {
 
     fn make_snapshot(&self) -> Result<Self::Snapshot> {
         Ok(AgentControllerSnapshot {
+            circuit_consecutive_failures: self.circuit_breaker.get_consecutive_failures(),
+            circuit_recent_failure_ratio: self.circuit_breaker.get_recent_failure_ratio(),
+            circuit_state: self.circuit_breaker.get_circuit_state(),
             desired_slots_total: self.desired_slots_total.get(),
             download_current: self.download_current.get(),
             download_filename: self
@@ -315,6 +431,8 @@ impl ProducesSnapshot for AgentController {
                 .clone(),
             download_total: self.download_total.get(),
             id: self.id.clone(),
+            is_cordoned: self.is_cordoned.get(),
+            is_draining: self.is_draining.get(),
             issues: self.get_issues(),
             model_path: self
                 .model_path
@@ -322,6 +440,9 @@ impl ProducesSnapshot for AgentController {
                 .expect("Poisoned lock on model path")
                 .clone(),
             name: self.name.clone(),
+            protocol_version: self.protocol_version,
+            requests_failed: self.circuit_breaker.requests_failed.get(),
+            requests_succeeded: self.circuit_breaker.requests_succeeded.get(),
             slots_processing: self.slots_processing.get(),
             slots_total: self.slots_total.get(),
             state_application_status: self.state_application_status_code.get().try_into()?,
@@ -1,31 +1,70 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
+use anyhow::anyhow;
 use async_trait::async_trait;
 use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
+use log::error;
+use log::warn;
 use tokio::sync::Notify;
 
 use super::agent_controller::AgentController;
 use super::agent_controller_pool_total_slots::AgentControllerPoolTotalSlots;
 use crate::agent_desired_state::AgentDesiredState;
+use crate::balancer::agent_circuit_breaker_configuration::AgentCircuitBreakerConfiguration;
 use crate::balancer::agent_controller_pool_snapshot::AgentControllerPoolSnapshot;
 use crate::balancer::agent_controller_snapshot::AgentControllerSnapshot;
+use crate::balancer::agent_token_registry_holder::AgentTokenRegistryHolder;
+use crate::balancer::tokens_match::tokens_match;
+use crate::heartbeat_configuration::HeartbeatConfiguration;
 use crate::produces_snapshot::ProducesSnapshot;
+use crate::response_buffer_configuration::ResponseBufferConfiguration;
 use crate::sets_desired_state::SetsDesiredState;
 
 pub struct AgentControllerPool {
+    pub agent_circuit_breaker_configuration: AgentCircuitBreakerConfiguration,
+    pub agent_heartbeat_configuration: HeartbeatConfiguration,
+    /// How long the requests that were streaming wait for their agent to reconnect
+    pub agent_resume_grace_period: Duration,
     pub agents: DashMap<String, Arc<AgentController>>,
+    pub response_buffer_configuration: ResponseBufferConfiguration,
     pub update_notifier: Arc<Notify>,
 }
 
 impl AgentControllerPool {
+    pub fn new(
+        agent_circuit_breaker_configuration: AgentCircuitBreakerConfiguration,
+        agent_heartbeat_configuration: HeartbeatConfiguration,
+        agent_resume_grace_period: Duration,
+        response_buffer_configuration: ResponseBufferConfiguration,
+    ) -> Self {
+        AgentControllerPool {
+            agent_circuit_breaker_configuration,
+            agent_heartbeat_configuration,
+            agent_resume_grace_period,
+            agents: DashMap::new(),
+            response_buffer_configuration,
+            update_notifier: Arc::new(Notify::new()),
+        }
+    }
+
     pub fn take_least_busy_agent_controller(&self) -> Option<Arc<AgentController>> {
-        let agent_controller: Option<Arc<AgentController>> = self
+        let mut agent_controllers: Vec<Arc<AgentController>> = self
             .agents
             .iter()
             .map(|entry| entry.value().clone())
+            .filter(|agent| agent.accepts_new_requests())
             .filter(|agent| agent.slots_processing.get() < agent.slots_total.get())
-            .min_by_key(|agent| agent.slots_processing.get());
+            .collect();
+
+        agent_controllers.sort_by_key(|agent| agent.slots_processing.get());
+
+        // Agents with an open circuit are skipped, unless they are due for a probe
+        let agent_controller = agent_controllers
+            .into_iter()
+            .find(|agent| agent.circuit_breaker.try_acquire());
 
         if let Some(agent_controller) = agent_controller {
             agent_controller.slots_processing.increment();
@@ -37,26 +76,93 @@ impl AgentControllerPool {
         None
     }
 
+    pub fn set_agent_cordoned(&self, agent_id: &str, is_cordoned: bool) -> bool {
+        match self.get_agent_controller(agent_id) {
+            Some(agent_controller) => {
+                if agent_controller.is_cordoned.set_check(is_cordoned) {
+                    self.update_notifier.notify_waiters();
+                }
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Agents are authenticated only when they register, so the ones whose tokens are no longer
+    /// valid are disconnected after the tokens are reloaded
+    pub fn disconnect_unauthenticated_agents(
+        &self,
+        agent_token_registry_holder: &AgentTokenRegistryHolder,
+    ) {
+        for entry in self.agents.iter() {
+            let agent_controller = entry.value();
+
+            if agent_controller.is_identified_by_client_certificate {
+                continue;
+            }
+
+            if let Err(err) = agent_token_registry_holder.authenticate(
+                agent_controller.name.as_deref(),
+                agent_controller.agent_token.as_deref(),
+            ) {
+                warn!("Disconnecting agent {}: {err}", agent_controller.id);
+
+                if let Err(err) = agent_controller.connection_close_tx.send(()) {
+                    error!("Failed to send connection close signal: {err}");
+                }
+            }
+        }
+    }
+
     pub fn get_agent_controller(&self, agent_id: &str) -> Option<Arc<AgentController>> {
         self.agents.get(agent_id).map(|entry| entry.value().clone())
     }
 
+    /// An agent that reconnects before its previous connection is noticed to be closed
+    /// replaces it if it proves to be the same agent with the resume secret of that connection,
+    /// and the previous connection is closed
     pub fn register_agent_controller(
         &self,
         agent_id: String,
         agent: Arc<AgentController>,
+        resume_secret: Option<&str>,
     ) -> Result<()> {
-        if self.agents.insert(agent_id, agent).is_none() {
-            self.update_notifier.notify_waiters();
-
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("AgentController already registered"))
+        match self.agents.entry(agent_id) {
+            Entry::Occupied(mut entry) => {
+                let is_same_agent = resume_secret.is_some_and(|resume_secret| {
+                    tokens_match(&entry.get().resume_secret, resume_secret)
+                });
+
+                if !is_same_agent {
+                    return Err(anyhow!("Agent {} is already connected", entry.key()));
+                }
+
+                let previous_agent = entry.insert(agent);
+
+                if let Err(err) = previous_agent.connection_close_tx.send(()) {
+                    error!("Failed to close the previous connection of the agent: {err}");
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(agent);
+            }
         }
+
+        self.update_notifier.notify_waiters();
+
+        Ok(())
     }
 
-    pub fn remove_agent_controller(&self, agent_id: &str) -> Result<bool> {
-        if self.agents.remove(agent_id).is_some() {
+    /// Agent is removed only if it has not reconnected in the meantime
+    pub fn remove_agent_controller(&self, agent: &Arc<AgentController>) -> Result<bool> {
+        if self
+            .agents
+            .remove_if(&agent.id, |_, registered_agent| {
+                Arc::ptr_eq(registered_agent, agent)
+            })
+            .is_some()
+        {
             self.update_notifier.notify_waiters();
 
             Ok(true)
@@ -81,13 +187,29 @@ impl AgentControllerPool {
             slots_total,
         }
     }
-}
 
-impl Default for AgentControllerPool {
-    fn default() -> Self {
-        AgentControllerPool {
-            agents: DashMap::new(),
-            update_notifier: Arc::new(Notify::new()),
+    /// Resolves with the controller of the new connection once the agent reconnects
+    pub async fn wait_for_agent_to_reconnect(
+        &self,
+        agent: &Arc<AgentController>,
+    ) -> Arc<AgentController> {
+        loop {
+            let update_notified = self.update_notifier.notified();
+
+            tokio::pin!(update_notified);
+
+            // Register interest before checking the state, so no notification is lost
+            // between the check and the await
+            update_notified.as_mut().enable();
+
+            if let Some(reconnected_agent) = self
+                .get_agent_controller(&agent.id)
+                .filter(|registered_agent| !Arc::ptr_eq(registered_agent, agent))
+            {
+                return reconnected_agent;
+            }
+
+            update_notified.await;
         }
     }
 }
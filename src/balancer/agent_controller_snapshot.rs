@@ -5,18 +5,27 @@ use serde::Serialize;
 
 use crate::agent_issue::AgentIssue;
 use crate::agent_state_application_status::AgentStateApplicationStatus;
+use crate::balancer::agent_circuit_state::AgentCircuitState;
 
 #[derive(Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct AgentControllerSnapshot {
+    pub circuit_consecutive_failures: usize,
+    pub circuit_recent_failure_ratio: f64,
+    pub circuit_state: AgentCircuitState,
     pub desired_slots_total: i32,
     pub download_current: usize,
     pub download_filename: Option<String>,
     pub download_total: usize,
     pub id: String,
+    pub is_cordoned: bool,
+    pub is_draining: bool,
     pub issues: BTreeSet<AgentIssue>,
     pub model_path: Option<String>,
     pub name: Option<String>,
+    pub protocol_version: u32,
+    pub requests_failed: usize,
+    pub requests_succeeded: usize,
     pub slots_processing: i32,
     pub slots_total: i32,
     pub state_application_status: AgentStateApplicationStatus,
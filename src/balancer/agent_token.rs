@@ -0,0 +1,11 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AgentToken {
+    /// Only the agent registering with this name can use the token; any agent can, without it
+    pub agent_name: Option<String>,
+    /// SHA-256 of the token the agents send in the `Authorization` header
+    pub token_hash: String,
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use anyhow::anyhow;
+
+use crate::balancer::tokens_match::tokens_match;
+
+/// Tokens the agents have to present to connect to the management service.
+///
+/// Every non-empty line of the tokens file is either a shared `token` that any agent
+/// can use, or `agent_name:token` that only the agent registering with that name can use.
+/// Lines starting with `#` are comments.
+#[derive(Debug, Default)]
+pub struct AgentTokenRegistry {
+    shared_tokens: Vec<String>,
+    tokens_by_agent_name: HashMap<String, Vec<String>>,
+}
+
+impl AgentTokenRegistry {
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut agent_token_registry = Self::default();
+
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match line.split_once(':') {
+                Some((agent_name, token)) => {
+                    let agent_name = agent_name.trim();
+                    let token = token.trim();
+
+                    if agent_name.is_empty() || token.is_empty() {
+                        return Err(anyhow!(
+                            "Invalid agent token on line {}. Expected 'token' or 'agent_name:token'",
+                            index + 1
+                        ));
+                    }
+
+                    agent_token_registry
+                        .tokens_by_agent_name
+                        .entry(agent_name.to_string())
+                        .or_default()
+                        .push(token.to_string());
+                }
+                None => agent_token_registry.shared_tokens.push(line.to_string()),
+            }
+        }
+
+        Ok(agent_token_registry)
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).context(format!(
+            "Failed to read agent tokens file: {}",
+            path.display()
+        ))?;
+
+        Self::parse(&content).context(format!(
+            "Failed to parse agent tokens file: {}",
+            path.display()
+        ))
+    }
+
+    /// Checks the token before the agent says what its name is.
+    pub fn is_known_token(&self, token: &str) -> bool {
+        self.shared_tokens
+            .iter()
+            .chain(self.tokens_by_agent_name.values().flatten())
+            .any(|known_token| tokens_match(known_token, token))
+    }
+
+    pub fn authenticate(&self, agent_name: Option<&str>, token: Option<&str>) -> Result<()> {
+        let token = token.ok_or_else(|| anyhow!("Agent did not send a token"))?;
+
+        if self
+            .shared_tokens
+            .iter()
+            .any(|shared_token| tokens_match(shared_token, token))
+        {
+            return Ok(());
+        }
+
+        if agent_name
+            .and_then(|agent_name| self.tokens_by_agent_name.get(agent_name))
+            .is_some_and(|agent_tokens| {
+                agent_tokens
+                    .iter()
+                    .any(|agent_token| tokens_match(agent_token, token))
+            })
+        {
+            return Ok(());
+        }
+
+        if self.is_known_token(token) {
+            return Err(anyhow!("Token is assigned to a different agent name"));
+        }
+
+        Err(anyhow!("Token is not valid"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKENS: &str = "
+        # shared by every agent
+        shared-secret
+
+        gpu-1:gpu-1-token
+    ";
+
+    #[test]
+    fn test_shared_token_is_accepted_for_any_agent() -> Result<()> {
+        let agent_token_registry = AgentTokenRegistry::parse(TOKENS)?;
+
+        agent_token_registry.authenticate(None, Some("shared-secret"))?;
+        agent_token_registry.authenticate(Some("gpu-2"), Some("shared-secret"))?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_agent_token_is_bound_to_its_name() -> Result<()> {
+        let agent_token_registry = AgentTokenRegistry::parse(TOKENS)?;
+
+        agent_token_registry.authenticate(Some("gpu-1"), Some("gpu-1-token"))?;
+
+        assert!(agent_token_registry.is_known_token("gpu-1-token"));
+        assert!(
+            agent_token_registry
+                .authenticate(Some("gpu-2"), Some("gpu-1-token"))
+                .is_err()
+        );
+        assert!(
+            agent_token_registry
+                .authenticate(None, Some("gpu-1-token"))
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_or_unknown_token_is_rejected() -> Result<()> {
+        let agent_token_registry = AgentTokenRegistry::parse(TOKENS)?;
+
+        assert!(!agent_token_registry.is_known_token("shared-secre"));
+        assert!(
+            agent_token_registry
+                .authenticate(Some("gpu-1"), None)
+                .is_err()
+        );
+        assert!(
+            agent_token_registry
+                .authenticate(Some("gpu-1"), Some("other"))
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_line_is_rejected() {
+        assert!(AgentTokenRegistry::parse("gpu-1:").is_err());
+        assert!(AgentTokenRegistry::parse(":token").is_err());
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use anyhow::Result;
+use anyhow::anyhow;
+
+use crate::balancer::agent_token::AgentToken;
+use crate::balancer::agent_token_registry::AgentTokenRegistry;
+use crate::balancer::hash_api_key::hash_api_key;
+use crate::balancer::tokens_match::tokens_match;
+
+/// Combines the tokens from the tokens file with the ones stored in the state database.
+///
+/// Agents do not need a token if there are neither.
+pub struct AgentTokenRegistryHolder {
+    agent_token_registry: RwLock<Option<AgentTokenRegistry>>,
+    stored_agent_tokens: RwLock<BTreeMap<String, AgentToken>>,
+}
+
+impl AgentTokenRegistryHolder {
+    pub fn new(agent_token_registry: Option<AgentTokenRegistry>) -> Self {
+        Self {
+            agent_token_registry: RwLock::new(agent_token_registry),
+            stored_agent_tokens: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn authenticate(&self, agent_name: Option<&str>, token: Option<&str>) -> Result<()> {
+        let agent_token_registry = self
+            .agent_token_registry
+            .read()
+            .expect("Failed to get agent token registry lock");
+        let stored_agent_tokens = self
+            .stored_agent_tokens
+            .read()
+            .expect("Failed to get stored agent tokens lock");
+
+        if agent_token_registry.is_none() && stored_agent_tokens.is_empty() {
+            return Ok(());
+        }
+
+        let token = token.ok_or_else(|| anyhow!("Agent did not send a token"))?;
+        let token_hash = hash_api_key(token);
+        let matching_agent_tokens: Vec<&AgentToken> = stored_agent_tokens
+            .values()
+            .filter(|agent_token| tokens_match(&agent_token.token_hash, &token_hash))
+            .collect();
+
+        if matching_agent_tokens.iter().any(|agent_token| {
+            agent_token.agent_name.is_none() || agent_token.agent_name.as_deref() == agent_name
+        }) {
+            return Ok(());
+        }
+
+        let registry_result = match agent_token_registry.as_ref() {
+            Some(agent_token_registry) => {
+                agent_token_registry.authenticate(agent_name, Some(token))
+            }
+            None => Err(anyhow!("Token is not valid")),
+        };
+
+        if registry_result.is_err() && !matching_agent_tokens.is_empty() {
+            return Err(anyhow!("Token is assigned to a different agent name"));
+        }
+
+        registry_result
+    }
+
+    pub fn is_known_token(&self, token: Option<&str>) -> bool {
+        let agent_token_registry = self
+            .agent_token_registry
+            .read()
+            .expect("Failed to get agent token registry lock");
+        let stored_agent_tokens = self
+            .stored_agent_tokens
+            .read()
+            .expect("Failed to get stored agent tokens lock");
+
+        if agent_token_registry.is_none() && stored_agent_tokens.is_empty() {
+            return true;
+        }
+
+        token.is_some_and(|token| {
+            let token_hash = hash_api_key(token);
+
+            agent_token_registry
+                .as_ref()
+                .is_some_and(|agent_token_registry| agent_token_registry.is_known_token(token))
+                || stored_agent_tokens
+                    .values()
+                    .any(|agent_token| tokens_match(&agent_token.token_hash, &token_hash))
+        })
+    }
+
+    pub fn set_agent_token_registry(&self, agent_token_registry: Option<AgentTokenRegistry>) {
+        let mut lock = self
+            .agent_token_registry
+            .write()
+            .expect("Failed to get agent token registry lock");
+
+        *lock = agent_token_registry;
+    }
+
+    pub fn set_stored_agent_tokens(&self, stored_agent_tokens: BTreeMap<String, AgentToken>) {
+        *self
+            .stored_agent_tokens
+            .write()
+            .expect("Failed to get stored agent tokens lock") = stored_agent_tokens;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_stored_agent_tokens() -> BTreeMap<String, AgentToken> {
+        BTreeMap::from([
+            (
+                "gpu-1".to_string(),
+                AgentToken {
+                    agent_name: Some("gpu-1".to_string()),
+                    token_hash: hash_api_key("gpu-1-token"),
+                },
+            ),
+            (
+                "shared".to_string(),
+                AgentToken {
+                    agent_name: None,
+                    token_hash: hash_api_key("shared-secret"),
+                },
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_open_without_tokens() -> Result<()> {
+        let agent_token_registry_holder = AgentTokenRegistryHolder::new(None);
+
+        agent_token_registry_holder.authenticate(None, None)?;
+
+        assert!(agent_token_registry_holder.is_known_token(None));
+
+        agent_token_registry_holder.set_stored_agent_tokens(make_stored_agent_tokens());
+
+        assert!(
+            agent_token_registry_holder
+                .authenticate(None, None)
+                .is_err()
+        );
+        assert!(!agent_token_registry_holder.is_known_token(None));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stored_tokens_are_combined_with_the_tokens_file() -> Result<()> {
+        let agent_token_registry_holder =
+            AgentTokenRegistryHolder::new(Some(AgentTokenRegistry::parse("gpu-2:gpu-2-token")?));
+
+        agent_token_registry_holder.set_stored_agent_tokens(make_stored_agent_tokens());
+        agent_token_registry_holder.authenticate(Some("gpu-1"), Some("gpu-1-token"))?;
+        agent_token_registry_holder.authenticate(Some("gpu-2"), Some("gpu-2-token"))?;
+        agent_token_registry_holder.authenticate(Some("gpu-3"), Some("shared-secret"))?;
+
+        assert!(agent_token_registry_holder.is_known_token(Some("gpu-1-token")));
+        assert!(agent_token_registry_holder.is_known_token(Some("gpu-2-token")));
+        assert!(!agent_token_registry_holder.is_known_token(Some("gpu-1-toke")));
+        assert_eq!(
+            agent_token_registry_holder
+                .authenticate(Some("gpu-2"), Some("gpu-1-token"))
+                .map_err(|err| err.to_string()),
+            Err("Token is assigned to a different agent name".to_string())
+        );
+        assert!(
+            agent_token_registry_holder
+                .authenticate(Some("gpu-1"), Some("unknown-token"))
+                .is_err()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,12 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::balancer::api_key_limits::ApiKeyLimits;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKey {
+    /// SHA-256 of the bearer token the clients send in the `Authorization` header
+    pub key_hash: String,
+    pub limits: ApiKeyLimits,
+}
@@ -0,0 +1,13 @@
+use std::sync::Arc;
+
+use crate::balancer::api_key_limit_exceeded::ApiKeyLimitExceeded;
+use crate::balancer::api_key_usage_guard::ApiKeyUsageGuard;
+
+pub enum ApiKeyAuthorizationResult {
+    /// The usage is not tracked: API keys are not required and the request did not use
+    /// a known one, or the key only assigns a priority class
+    Anonymous,
+    Authorized(Arc<ApiKeyUsageGuard>),
+    LimitExceeded(ApiKeyLimitExceeded),
+    Unauthorized,
+}
@@ -0,0 +1,6 @@
+#[derive(Debug)]
+pub struct ApiKeyLimitExceeded {
+    pub description: String,
+    /// Seconds after which the request is likely to be accepted
+    pub retry_after: u64,
+}
@@ -0,0 +1,12 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Limits that are not set are not enforced.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiKeyLimits {
+    pub max_concurrent_requests: Option<u64>,
+    pub max_requests_per_minute: Option<u64>,
+    /// Checked when the request starts, so the request that crosses the limit can finish
+    pub max_tokens_per_day: Option<u64>,
+}
@@ -0,0 +1,235 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+
+use crate::atomic_value::AtomicValue;
+use crate::balancer::api_key::ApiKey;
+use crate::balancer::api_key_authorization_result::ApiKeyAuthorizationResult;
+use crate::balancer::api_key_usage::ApiKeyUsage;
+use crate::balancer::api_key_usage_snapshot::ApiKeyUsageSnapshot;
+use crate::balancer::buffered_request_priority_class_api_key::BufferedRequestPriorityClassApiKey;
+use crate::balancer::hash_api_key::hash_api_key;
+
+/// Keeps the API keys from the state database in memory, together with their usage.
+/// Keys assigned to the priority classes in the configuration are valid too, but their
+/// usage is not tracked.
+pub struct ApiKeyManager {
+    api_key_usage_by_key_hash: RwLock<HashMap<String, Arc<ApiKeyUsage>>>,
+    is_required: AtomicValue<AtomicBool>,
+    priority_class_api_key_hashes: RwLock<HashSet<String>>,
+    unauthorized_requests: AtomicValue<AtomicUsize>,
+}
+
+impl ApiKeyManager {
+    pub fn new(is_required: bool) -> Self {
+        Self {
+            api_key_usage_by_key_hash: RwLock::new(HashMap::new()),
+            is_required: AtomicValue::<AtomicBool>::new(is_required),
+            priority_class_api_key_hashes: RwLock::new(HashSet::new()),
+            unauthorized_requests: AtomicValue::<AtomicUsize>::new(0),
+        }
+    }
+
+    pub fn authorize(&self, api_key: Option<&str>) -> ApiKeyAuthorizationResult {
+        let api_key_hash = api_key.map(hash_api_key);
+
+        match self.find_api_key_usage(api_key_hash.as_deref()) {
+            Some(api_key_usage) => match api_key_usage.start_request() {
+                Ok(api_key_usage_guard) => {
+                    ApiKeyAuthorizationResult::Authorized(api_key_usage_guard)
+                }
+                Err(limit_exceeded) => ApiKeyAuthorizationResult::LimitExceeded(limit_exceeded),
+            },
+            None if self.is_required.get()
+                && !self.is_priority_class_api_key(api_key_hash.as_deref()) =>
+            {
+                self.unauthorized_requests.increment_by(1);
+
+                ApiKeyAuthorizationResult::Unauthorized
+            }
+            None => ApiKeyAuthorizationResult::Anonymous,
+        }
+    }
+
+    pub fn get_unauthorized_requests(&self) -> usize {
+        self.unauthorized_requests.get()
+    }
+
+    /// Checks the key without counting it as a request, for the long lived connections.
+    pub fn is_allowed_to_connect(&self, api_key: Option<&str>) -> bool {
+        let api_key_hash = api_key.map(hash_api_key);
+
+        !self.is_required.get()
+            || self.find_api_key_usage(api_key_hash.as_deref()).is_some()
+            || self.is_priority_class_api_key(api_key_hash.as_deref())
+    }
+
+    /// Sorted by the key name.
+    pub fn make_usage_snapshots(&self) -> Vec<ApiKeyUsageSnapshot> {
+        let mut usage_snapshots: Vec<ApiKeyUsageSnapshot> = self
+            .api_key_usage_by_key_hash
+            .read()
+            .expect("Failed to get API keys lock")
+            .values()
+            .map(|api_key_usage| api_key_usage.make_snapshot())
+            .collect();
+
+        usage_snapshots.sort_by(|left, right| left.name.cmp(&right.name));
+
+        usage_snapshots
+    }
+
+    /// Usage of the keys that stay under the same name is preserved, so updating
+    /// the limits does not reset the counters.
+    pub fn set_api_keys(&self, api_keys: BTreeMap<String, ApiKey>) {
+        let mut api_key_usage_by_key_hash = self
+            .api_key_usage_by_key_hash
+            .write()
+            .expect("Failed to get API keys lock");
+        let mut api_key_usage_by_name: HashMap<String, Arc<ApiKeyUsage>> =
+            api_key_usage_by_key_hash
+                .drain()
+                .map(|(_, api_key_usage)| (api_key_usage.get_name().to_string(), api_key_usage))
+                .collect();
+
+        for (name, ApiKey { key_hash, limits }) in api_keys {
+            let api_key_usage = match api_key_usage_by_name.remove(&name) {
+                Some(api_key_usage) => {
+                    api_key_usage.set_limits(limits);
+
+                    api_key_usage
+                }
+                None => Arc::new(ApiKeyUsage::new(name, limits)),
+            };
+
+            api_key_usage_by_key_hash.insert(key_hash, api_key_usage);
+        }
+    }
+
+    pub fn set_is_required(&self, is_required: bool) {
+        self.is_required.set(is_required);
+    }
+
+    pub fn set_priority_class_api_keys(
+        &self,
+        priority_class_api_keys: &[BufferedRequestPriorityClassApiKey],
+    ) {
+        *self
+            .priority_class_api_key_hashes
+            .write()
+            .expect("Failed to get priority class API keys lock") = priority_class_api_keys
+            .iter()
+            .map(|priority_class_api_key| hash_api_key(&priority_class_api_key.api_key))
+            .collect();
+    }
+
+    fn find_api_key_usage(&self, api_key_hash: Option<&str>) -> Option<Arc<ApiKeyUsage>> {
+        api_key_hash.and_then(|api_key_hash| {
+            self.api_key_usage_by_key_hash
+                .read()
+                .expect("Failed to get API keys lock")
+                .get(api_key_hash)
+                .cloned()
+        })
+    }
+
+    fn is_priority_class_api_key(&self, api_key_hash: Option<&str>) -> bool {
+        api_key_hash.is_some_and(|api_key_hash| {
+            self.priority_class_api_key_hashes
+                .read()
+                .expect("Failed to get priority class API keys lock")
+                .contains(api_key_hash)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balancer::api_key_limits::ApiKeyLimits;
+
+    fn make_api_keys(limits: ApiKeyLimits) -> BTreeMap<String, ApiKey> {
+        BTreeMap::from([(
+            "frontend".to_string(),
+            ApiKey {
+                key_hash: hash_api_key("secret"),
+                limits,
+            },
+        )])
+    }
+
+    #[test]
+    fn test_anonymous_requests_are_allowed_unless_keys_are_required() {
+        let api_key_manager = ApiKeyManager::new(false);
+
+        api_key_manager.set_api_keys(make_api_keys(ApiKeyLimits::default()));
+
+        assert!(matches!(
+            api_key_manager.authorize(None),
+            ApiKeyAuthorizationResult::Anonymous
+        ));
+        assert!(matches!(
+            api_key_manager.authorize(Some("secret")),
+            ApiKeyAuthorizationResult::Authorized(_)
+        ));
+
+        api_key_manager.set_is_required(true);
+
+        assert!(matches!(
+            api_key_manager.authorize(Some("other")),
+            ApiKeyAuthorizationResult::Unauthorized
+        ));
+        assert!(!api_key_manager.is_allowed_to_connect(None));
+        assert!(api_key_manager.is_allowed_to_connect(Some("secret")));
+        assert_eq!(api_key_manager.get_unauthorized_requests(), 1);
+    }
+
+    #[test]
+    fn test_usage_survives_limit_changes() {
+        let api_key_manager = ApiKeyManager::new(true);
+
+        api_key_manager.set_api_keys(make_api_keys(ApiKeyLimits::default()));
+
+        let _api_key_usage_guard = api_key_manager.authorize(Some("secret"));
+
+        api_key_manager.set_api_keys(make_api_keys(ApiKeyLimits {
+            max_concurrent_requests: Some(1),
+            ..ApiKeyLimits::default()
+        }));
+
+        assert!(matches!(
+            api_key_manager.authorize(Some("secret")),
+            ApiKeyAuthorizationResult::LimitExceeded(_)
+        ));
+
+        let usage_snapshots = api_key_manager.make_usage_snapshots();
+
+        assert_eq!(usage_snapshots.len(), 1);
+        assert_eq!(usage_snapshots[0].total_requests, 1);
+        assert_eq!(usage_snapshots[0].rejected_requests, 1);
+    }
+
+    #[test]
+    fn test_priority_class_api_keys_are_valid_without_limits() {
+        let api_key_manager = ApiKeyManager::new(true);
+
+        api_key_manager.set_priority_class_api_keys(&[BufferedRequestPriorityClassApiKey {
+            api_key: "interactive-secret".to_string(),
+            priority_class: "interactive".to_string(),
+        }]);
+
+        assert!(matches!(
+            api_key_manager.authorize(Some("interactive-secret")),
+            ApiKeyAuthorizationResult::Anonymous
+        ));
+        assert!(api_key_manager.is_allowed_to_connect(Some("interactive-secret")));
+        assert!(matches!(
+            api_key_manager.authorize(Some("other")),
+            ApiKeyAuthorizationResult::Unauthorized
+        ));
+    }
+}
@@ -0,0 +1,227 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::RwLock;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::balancer::api_key_limit_exceeded::ApiKeyLimitExceeded;
+use crate::balancer::api_key_limits::ApiKeyLimits;
+use crate::balancer::api_key_usage_counters::ApiKeyUsageCounters;
+use crate::balancer::api_key_usage_guard::ApiKeyUsageGuard;
+use crate::balancer::api_key_usage_snapshot::ApiKeyUsageSnapshot;
+
+fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+}
+
+pub struct ApiKeyUsage {
+    counters: Mutex<ApiKeyUsageCounters>,
+    limits: RwLock<ApiKeyLimits>,
+    name: String,
+}
+
+impl ApiKeyUsage {
+    pub fn new(name: String, limits: ApiKeyLimits) -> Self {
+        Self {
+            counters: Mutex::new(ApiKeyUsageCounters::default()),
+            limits: RwLock::new(limits),
+            name,
+        }
+    }
+
+    pub fn finish_request(&self) {
+        let mut counters = self.lock_counters();
+
+        counters.concurrent_requests = counters.concurrent_requests.saturating_sub(1);
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn make_snapshot(&self) -> ApiKeyUsageSnapshot {
+        self.make_snapshot_at(current_timestamp())
+    }
+
+    pub fn record_tokens(&self, tokens: u64) {
+        self.record_tokens_at(tokens, current_timestamp());
+    }
+
+    pub fn set_limits(&self, limits: ApiKeyLimits) {
+        *self
+            .limits
+            .write()
+            .expect("Failed to get API key limits lock") = limits;
+    }
+
+    pub fn start_request(self: &Arc<Self>) -> Result<Arc<ApiKeyUsageGuard>, ApiKeyLimitExceeded> {
+        self.start_request_at(current_timestamp())
+    }
+
+    fn lock_counters(&self) -> MutexGuard<'_, ApiKeyUsageCounters> {
+        self.counters
+            .lock()
+            .expect("Failed to get API key usage counters lock")
+    }
+
+    fn make_snapshot_at(&self, now: u64) -> ApiKeyUsageSnapshot {
+        let mut counters = self.lock_counters();
+
+        counters.advance_to(now);
+
+        ApiKeyUsageSnapshot {
+            concurrent_requests: counters.concurrent_requests,
+            limits: self
+                .limits
+                .read()
+                .expect("Failed to get API key limits lock")
+                .clone(),
+            name: self.name.clone(),
+            rejected_requests: counters.rejected_requests,
+            requests_in_current_minute: counters.requests_in_current_minute,
+            tokens_in_current_day: counters.tokens_in_current_day,
+            total_requests: counters.total_requests,
+            total_tokens: counters.total_tokens,
+        }
+    }
+
+    fn record_tokens_at(&self, tokens: u64, now: u64) {
+        let mut counters = self.lock_counters();
+
+        counters.advance_to(now);
+        counters.tokens_in_current_day += tokens;
+        counters.total_tokens += tokens;
+    }
+
+    /// All the limits are checked under the same lock, so concurrent requests cannot
+    /// slip through between the check and the update.
+    fn start_request_at(
+        self: &Arc<Self>,
+        now: u64,
+    ) -> Result<Arc<ApiKeyUsageGuard>, ApiKeyLimitExceeded> {
+        let limits = self
+            .limits
+            .read()
+            .expect("Failed to get API key limits lock")
+            .clone();
+        let mut counters = self.lock_counters();
+
+        counters.advance_to(now);
+
+        let limit_exceeded = if limits
+            .max_tokens_per_day
+            .is_some_and(|max_tokens_per_day| counters.tokens_in_current_day >= max_tokens_per_day)
+        {
+            Some(ApiKeyLimitExceeded {
+                description: "Daily token limit of the API key is exhausted".to_string(),
+                retry_after: 86_400 - now % 86_400,
+            })
+        } else if limits
+            .max_requests_per_minute
+            .is_some_and(|max_requests_per_minute| {
+                counters.requests_in_current_minute >= max_requests_per_minute
+            })
+        {
+            Some(ApiKeyLimitExceeded {
+                description: "Too many requests per minute for the API key".to_string(),
+                retry_after: 60 - now % 60,
+            })
+        } else if limits
+            .max_concurrent_requests
+            .is_some_and(|max_concurrent_requests| {
+                counters.concurrent_requests >= max_concurrent_requests
+            })
+        {
+            Some(ApiKeyLimitExceeded {
+                description: "Too many concurrent requests for the API key".to_string(),
+                retry_after: 1,
+            })
+        } else {
+            None
+        };
+
+        if let Some(limit_exceeded) = limit_exceeded {
+            counters.rejected_requests += 1;
+
+            return Err(limit_exceeded);
+        }
+
+        counters.concurrent_requests += 1;
+        counters.requests_in_current_minute += 1;
+        counters.total_requests += 1;
+
+        Ok(Arc::new(ApiKeyUsageGuard::new(self.clone())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_040;
+
+    fn make_api_key_usage(limits: ApiKeyLimits) -> Arc<ApiKeyUsage> {
+        Arc::new(ApiKeyUsage::new("test".to_string(), limits))
+    }
+
+    #[test]
+    fn test_concurrent_requests_are_released_with_the_guard() {
+        let api_key_usage = make_api_key_usage(ApiKeyLimits {
+            max_concurrent_requests: Some(1),
+            ..ApiKeyLimits::default()
+        });
+
+        let guard = api_key_usage.start_request_at(NOW).unwrap();
+
+        assert!(api_key_usage.start_request_at(NOW).is_err());
+
+        drop(guard);
+
+        assert!(api_key_usage.start_request_at(NOW).is_ok());
+        assert_eq!(api_key_usage.make_snapshot_at(NOW).rejected_requests, 1);
+    }
+
+    #[test]
+    fn test_requests_per_minute_reset_on_the_next_minute() {
+        let api_key_usage = make_api_key_usage(ApiKeyLimits {
+            max_requests_per_minute: Some(2),
+            ..ApiKeyLimits::default()
+        });
+
+        assert!(api_key_usage.start_request_at(NOW).is_ok());
+        assert!(api_key_usage.start_request_at(NOW + 1).is_ok());
+
+        let limit_exceeded = api_key_usage
+            .start_request_at(NOW + 2)
+            .err()
+            .expect("Third request in the same minute should be rejected");
+
+        assert_eq!(limit_exceeded.retry_after, 60 - (NOW + 2) % 60);
+        assert!(api_key_usage.start_request_at(NOW + 60).is_ok());
+    }
+
+    #[test]
+    fn test_tokens_per_day_are_checked_when_request_starts() {
+        let api_key_usage = make_api_key_usage(ApiKeyLimits {
+            max_tokens_per_day: Some(10),
+            ..ApiKeyLimits::default()
+        });
+
+        assert!(api_key_usage.start_request_at(NOW).is_ok());
+
+        api_key_usage.record_tokens_at(10, NOW);
+
+        assert!(api_key_usage.start_request_at(NOW).is_err());
+        assert!(api_key_usage.start_request_at(NOW + 86_400).is_ok());
+
+        let snapshot = api_key_usage.make_snapshot_at(NOW + 86_400);
+
+        assert_eq!(snapshot.tokens_in_current_day, 0);
+        assert_eq!(snapshot.total_tokens, 10);
+        assert_eq!(snapshot.total_requests, 2);
+    }
+}
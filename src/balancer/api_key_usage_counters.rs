@@ -0,0 +1,29 @@
+/// Minutes and days are counted from the Unix epoch, so the windows reset on UTC boundaries.
+#[derive(Default)]
+pub struct ApiKeyUsageCounters {
+    pub concurrent_requests: u64,
+    pub current_day: u64,
+    pub current_minute: u64,
+    pub rejected_requests: u64,
+    pub requests_in_current_minute: u64,
+    pub tokens_in_current_day: u64,
+    pub total_requests: u64,
+    pub total_tokens: u64,
+}
+
+impl ApiKeyUsageCounters {
+    pub fn advance_to(&mut self, now: u64) {
+        let minute = now / 60;
+        let day = now / 86_400;
+
+        if self.current_minute != minute {
+            self.current_minute = minute;
+            self.requests_in_current_minute = 0;
+        }
+
+        if self.current_day != day {
+            self.current_day = day;
+            self.tokens_in_current_day = 0;
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use crate::balancer::api_key_usage::ApiKeyUsage;
+
+/// Holds one of the concurrent request slots of the API key until it is dropped.
+pub struct ApiKeyUsageGuard {
+    api_key_usage: Arc<ApiKeyUsage>,
+}
+
+impl ApiKeyUsageGuard {
+    pub fn new(api_key_usage: Arc<ApiKeyUsage>) -> Self {
+        Self { api_key_usage }
+    }
+
+    /// Generated tokens, together with the prompt and embedding input tokens.
+    pub fn record_tokens(&self, tokens: usize) {
+        self.api_key_usage.record_tokens(tokens as u64);
+    }
+}
+
+impl Drop for ApiKeyUsageGuard {
+    fn drop(&mut self) {
+        self.api_key_usage.finish_request();
+    }
+}
@@ -0,0 +1,18 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::balancer::api_key_limits::ApiKeyLimits;
+
+/// Counters are kept in memory, so they start over when the balancer restarts.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKeyUsageSnapshot {
+    pub concurrent_requests: u64,
+    pub limits: ApiKeyLimits,
+    pub name: String,
+    pub rejected_requests: u64,
+    pub requests_in_current_minute: u64,
+    pub tokens_in_current_day: u64,
+    pub total_requests: u64,
+    pub total_tokens: u64,
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use actix_web::Error;
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use actix_web::error::InternalError;
+use actix_web::http::header;
+
+use crate::balancer::api_key_authorization_result::ApiKeyAuthorizationResult;
+use crate::balancer::api_key_manager::ApiKeyManager;
+use crate::balancer::api_key_usage_guard::ApiKeyUsageGuard;
+use crate::balancer::authorization_bearer_token::authorization_bearer_token;
+
+/// Responds with 401 or 429 if the request cannot proceed. The returned guard has to be
+/// kept until the response is finished.
+pub fn authorize_api_key(
+    api_key_manager: &ApiKeyManager,
+    req: &HttpRequest,
+) -> Result<Option<Arc<ApiKeyUsageGuard>>, Error> {
+    match api_key_manager.authorize(authorization_bearer_token(req).as_deref()) {
+        ApiKeyAuthorizationResult::Anonymous => Ok(None),
+        ApiKeyAuthorizationResult::Authorized(api_key_usage_guard) => Ok(Some(api_key_usage_guard)),
+        ApiKeyAuthorizationResult::LimitExceeded(limit_exceeded) => {
+            Err(InternalError::from_response(
+                limit_exceeded.description.clone(),
+                HttpResponse::TooManyRequests()
+                    .insert_header((header::RETRY_AFTER, limit_exceeded.retry_after))
+                    .body(limit_exceeded.description),
+            )
+            .into())
+        }
+        ApiKeyAuthorizationResult::Unauthorized => Err(InternalError::from_response(
+            "API key is missing or invalid",
+            HttpResponse::Unauthorized()
+                .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+                .body("API key is missing or invalid"),
+        )
+        .into()),
+    }
+}
@@ -0,0 +1,10 @@
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BalancerDesiredStateChange {
+    pub current: Option<Value>,
+    /// Dotted path to the changed field, for example `inference_parameters.temperature`
+    pub field: String,
+    pub proposed: Option<Value>,
+}
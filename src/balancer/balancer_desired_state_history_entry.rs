@@ -0,0 +1,15 @@
+use serde::Serialize;
+
+use crate::balancer_desired_state::BalancerDesiredState;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BalancerDesiredStateHistoryEntry {
+    pub balancer_desired_state: BalancerDesiredState,
+    /// Unix timestamp (in seconds)
+    pub created_at: i64,
+    /// Set if this version was created by activating a profile
+    pub profile: Option<String>,
+    /// Set if this version was created by rolling back to an earlier one
+    pub restored_from_version: Option<i64>,
+    pub version: i64,
+}
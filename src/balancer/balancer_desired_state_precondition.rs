@@ -0,0 +1,9 @@
+use crate::balancer_desired_state::BalancerDesiredState;
+
+pub enum BalancerDesiredStatePrecondition {
+    /// The state was changed since the client has read it
+    Failed,
+    Met(BalancerDesiredState),
+    /// The balancer requires `If-Match`, but the client did not send it
+    Required,
+}
@@ -0,0 +1,13 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::balancer_desired_state::BalancerDesiredState;
+
+/// Used to move the profiles between balancers
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BalancerDesiredStateProfileBundle {
+    pub profiles: BTreeMap<String, BalancerDesiredState>,
+}
@@ -0,0 +1,5 @@
+pub enum BalancerDesiredStateProfileDeleteResult {
+    Deleted,
+    NotFound,
+    UsedBySchedule,
+}
@@ -0,0 +1,7 @@
+use crate::balancer_desired_state::BalancerDesiredState;
+
+pub enum BalancerDesiredStateRollbackResult {
+    NotSupported,
+    RolledBack(BalancerDesiredState),
+    VersionNotFound,
+}
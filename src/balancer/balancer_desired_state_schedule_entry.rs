@@ -0,0 +1,12 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::balancer::cron_schedule::CronSchedule;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BalancerDesiredStateScheduleEntry {
+    pub cron: CronSchedule,
+    /// Name of the stored profile that becomes the desired state
+    pub profile: String,
+}
@@ -0,0 +1,4 @@
+pub enum BalancerDesiredStateScheduleStoreResult {
+    ProfileNotFound(String),
+    Stored,
+}
@@ -0,0 +1,119 @@
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use crate::balancer::balancer_desired_state_schedule_entry::BalancerDesiredStateScheduleEntry;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BalancerDesiredStateTransition {
+    /// Unix timestamp (in seconds)
+    pub at: i64,
+    pub profile: String,
+}
+
+impl BalancerDesiredStateTransition {
+    /// If more than one entry is due at the same minute, the one listed last wins,
+    /// so only that one is listed.
+    pub fn list_upcoming(
+        schedule: &[BalancerDesiredStateScheduleEntry],
+        after: OffsetDateTime,
+        limit: usize,
+    ) -> Vec<Self> {
+        let mut transitions: Vec<(OffsetDateTime, usize)> = Vec::new();
+
+        for (index, entry) in schedule.iter().enumerate() {
+            let mut occurrence = after;
+
+            for _ in 0..limit {
+                match entry.cron.next_after(occurrence) {
+                    Some(next_occurrence) => {
+                        transitions.push((next_occurrence, index));
+                        occurrence = next_occurrence;
+                    }
+                    None => break,
+                }
+            }
+        }
+
+        transitions.sort();
+        transitions.reverse();
+        transitions.dedup_by_key(|(at, _)| *at);
+        transitions.reverse();
+        transitions.truncate(limit);
+
+        transitions
+            .into_iter()
+            .map(|(at, index)| Self {
+                at: at.unix_timestamp(),
+                profile: schedule[index].profile.clone(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn make_entry(cron: &str, profile: &str) -> Result<BalancerDesiredStateScheduleEntry> {
+        Ok(BalancerDesiredStateScheduleEntry {
+            cron: cron.parse()?,
+            profile: profile.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_lists_transitions_in_order() -> Result<()> {
+        let schedule = vec![
+            make_entry("0 8 * * *", "large")?,
+            make_entry("0 20 * * *", "small")?,
+        ];
+
+        let transitions = BalancerDesiredStateTransition::list_upcoming(
+            &schedule,
+            datetime!(2025-01-01 12:00 UTC),
+            3,
+        );
+
+        assert_eq!(
+            transitions,
+            vec![
+                BalancerDesiredStateTransition {
+                    at: datetime!(2025-01-01 20:00 UTC).unix_timestamp(),
+                    profile: "small".to_string(),
+                },
+                BalancerDesiredStateTransition {
+                    at: datetime!(2025-01-02 08:00 UTC).unix_timestamp(),
+                    profile: "large".to_string(),
+                },
+                BalancerDesiredStateTransition {
+                    at: datetime!(2025-01-02 20:00 UTC).unix_timestamp(),
+                    profile: "small".to_string(),
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_last_entry_wins_at_the_same_time() -> Result<()> {
+        let schedule = vec![
+            make_entry("0 8 * * *", "everyday")?,
+            make_entry("0 8 * * 6", "saturday")?,
+        ];
+
+        // 2025-01-04 is a Saturday
+        let transitions = BalancerDesiredStateTransition::list_upcoming(
+            &schedule,
+            datetime!(2025-01-04 00:00 UTC),
+            1,
+        );
+
+        assert_eq!(transitions[0].profile, "saturday");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,15 @@
+use actix_web::http::header::EntityTag;
+
+use crate::balancer_desired_state::BalancerDesiredState;
+
+pub enum BalancerDesiredStateUpdateResult {
+    /// The state was changed since the client has read it
+    PreconditionFailed,
+    /// The balancer requires `If-Match`, but the client did not send it
+    PreconditionRequired,
+    Rejected(String),
+    Updated {
+        balancer_desired_state: BalancerDesiredState,
+        etag: EntityTag,
+    },
+}
@@ -0,0 +1,8 @@
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BalancerDesiredStateValidationIssue {
+    /// Dotted path to the offending field, for example `inference_parameters.top_p`
+    pub field: String,
+    pub message: String,
+}
@@ -0,0 +1,13 @@
+use serde::Serialize;
+
+use crate::balancer::balancer_desired_state_change::BalancerDesiredStateChange;
+use crate::balancer::balancer_desired_state_validation_issue::BalancerDesiredStateValidationIssue;
+
+#[derive(Serialize)]
+pub struct BalancerDesiredStateValidationReport {
+    /// Agents that would have to apply the new state (and reload the model)
+    pub agents_to_reload: Vec<String>,
+    pub changes: Vec<BalancerDesiredStateChange>,
+    pub is_valid: bool,
+    pub issues: Vec<BalancerDesiredStateValidationIssue>,
+}
@@ -7,5 +7,6 @@ use crate::balancer::agent_controller::AgentController;
 pub enum BufferedRequestAgentWaitResult {
     BufferOverflow,
     Found(Arc<AgentController>),
+    ShuttingDown,
     Timeout(Error),
 }
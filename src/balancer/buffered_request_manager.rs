@@ -1,29 +1,41 @@
 use std::sync::Arc;
-use std::time::Duration;
+use std::sync::RwLock;
+use std::sync::atomic::AtomicBool;
 
 use anyhow::Result;
 use tokio::sync::Notify;
 use tokio::time::timeout;
 
+use crate::atomic_value::AtomicValue;
+use crate::balancer::agent_controller::AgentController;
 use crate::balancer::agent_controller_pool::AgentControllerPool;
 use crate::balancer::buffered_request_agent_wait_result::BufferedRequestAgentWaitResult;
 use crate::balancer::buffered_request_counter::BufferedRequestCounter;
 use crate::balancer::buffered_request_manager_snapshot::BufferedRequestManagerSnapshot;
+use crate::balancer::buffered_request_priority_class::BufferedRequestPriorityClass;
+use crate::balancer::buffered_request_priority_class_registry::BufferedRequestPriorityClassRegistry;
+use crate::balancer::buffered_request_queue::BufferedRequestQueue;
+use crate::balancer::buffered_request_queue_entry_guard::BufferedRequestQueueEntryGuard;
+use crate::balancer::request_retry_counter::RequestRetryCounter;
+use crate::drain_status::DrainStatus;
 use crate::produces_snapshot::ProducesSnapshot;
 
 pub struct BufferedRequestManager {
-    agent_controller_pool: Arc<AgentControllerPool>,
+    pub agent_controller_pool: Arc<AgentControllerPool>,
     pub buffered_request_counter: Arc<BufferedRequestCounter>,
-    buffered_request_timeout: Duration,
-    max_buffered_requests: i32,
+    buffered_request_queue: Arc<BufferedRequestQueue>,
+    drain_status: Arc<DrainStatus>,
+    is_rejecting_buffered_requests: AtomicValue<AtomicBool>,
+    priority_class_registry: RwLock<Arc<BufferedRequestPriorityClassRegistry>>,
+    pub request_retry_counter: Arc<RequestRetryCounter>,
     pub update_notifier: Arc<Notify>,
 }
 
 impl BufferedRequestManager {
     pub fn new(
         agent_controller_pool: Arc<AgentControllerPool>,
-        buffered_request_timeout: Duration,
-        max_buffered_requests: i32,
+        drain_status: Arc<DrainStatus>,
+        priority_class_registry: BufferedRequestPriorityClassRegistry,
     ) -> Self {
         let update_notifier = Arc::new(Notify::new());
 
@@ -32,37 +44,109 @@ impl BufferedRequestManager {
             buffered_request_counter: Arc::new(BufferedRequestCounter::new(
                 update_notifier.clone(),
             )),
-            buffered_request_timeout,
-            max_buffered_requests,
+            buffered_request_queue: Arc::new(BufferedRequestQueue::new(update_notifier.clone())),
+            drain_status,
+            is_rejecting_buffered_requests: AtomicValue::<AtomicBool>::new(false),
+            priority_class_registry: RwLock::new(Arc::new(priority_class_registry)),
+            request_retry_counter: Arc::new(RequestRetryCounter::default()),
             update_notifier,
         }
     }
 
-    pub async fn wait_for_available_agent(&self) -> Result<BufferedRequestAgentWaitResult> {
-        if self.buffered_request_counter.get() >= self.max_buffered_requests {
-            return Ok(BufferedRequestAgentWaitResult::BufferOverflow);
+    /// Taken once per request, so a retried request keeps its place in the queue
+    pub fn next_queue_sequence(&self) -> u64 {
+        self.buffered_request_queue.next_sequence()
+    }
+
+    /// Requests that are still waiting for a slot give up, so the clients can retry them
+    /// against a different balancer.
+    pub fn reject_buffered_requests(&self) {
+        self.is_rejecting_buffered_requests.set(true);
+        self.update_notifier.notify_waiters();
+    }
+
+    pub fn select_priority_class(
+        &self,
+        requested_priority_class: Option<&str>,
+        api_key: Option<&str>,
+    ) -> Result<Arc<BufferedRequestPriorityClass>> {
+        self.priority_class_registry
+            .read()
+            .expect("Failed to get priority class registry lock")
+            .select_priority_class(requested_priority_class, api_key)
+    }
+
+    /// Requests that are already buffered keep the priority class they were admitted with.
+    pub fn set_priority_class_registry(
+        &self,
+        priority_class_registry: BufferedRequestPriorityClassRegistry,
+    ) {
+        let mut lock = self
+            .priority_class_registry
+            .write()
+            .expect("Failed to get priority class registry lock");
+
+        *lock = Arc::new(priority_class_registry);
+    }
+
+    pub async fn wait_for_available_agent(
+        &self,
+        priority_class: &BufferedRequestPriorityClass,
+        queue_sequence: u64,
+        request_id: String,
+    ) -> Result<BufferedRequestAgentWaitResult> {
+        if self.drain_status.is_draining() {
+            return Ok(BufferedRequestAgentWaitResult::ShuttingDown);
         }
 
+        let buffered_request_queue_entry_guard =
+            match self
+                .buffered_request_queue
+                .enqueue(priority_class, queue_sequence, request_id)
+            {
+                Some(buffered_request_queue_entry_guard) => buffered_request_queue_entry_guard,
+                None => return Ok(BufferedRequestAgentWaitResult::BufferOverflow),
+            };
+
         // Do a quick check before getting into the coroutines
-        if let Some(agent_controller) = self
-            .agent_controller_pool
-            .take_least_busy_agent_controller()
+        if let Some(agent_controller) =
+            self.take_agent_controller_if_first(&buffered_request_queue_entry_guard)
         {
             return Ok(BufferedRequestAgentWaitResult::Found(agent_controller));
         }
 
         let _buffered_request_count_guard = self.buffered_request_counter.increment_with_guard();
-        let agent_controller_pool = self.agent_controller_pool.clone();
 
-        match timeout(self.buffered_request_timeout, async {
+        match timeout(priority_class.buffered_request_timeout, async {
             loop {
-                match agent_controller_pool.take_least_busy_agent_controller() {
-                    Some(agent_controller) => {
-                        return Ok::<_, anyhow::Error>(BufferedRequestAgentWaitResult::Found(
-                            agent_controller,
-                        ));
-                    }
-                    None => agent_controller_pool.update_notifier.notified().await,
+                let agent_controller_pool_notified =
+                    self.agent_controller_pool.update_notifier.notified();
+                let buffered_request_queue_notified =
+                    self.buffered_request_queue.update_notifier.notified();
+
+                tokio::pin!(agent_controller_pool_notified);
+                tokio::pin!(buffered_request_queue_notified);
+
+                // Register interest before checking the state, so no notification is lost
+                // between the check and the await
+                agent_controller_pool_notified.as_mut().enable();
+                buffered_request_queue_notified.as_mut().enable();
+
+                if self.is_rejecting_buffered_requests.get() {
+                    return Ok(BufferedRequestAgentWaitResult::ShuttingDown);
+                }
+
+                if let Some(agent_controller) =
+                    self.take_agent_controller_if_first(&buffered_request_queue_entry_guard)
+                {
+                    return Ok::<_, anyhow::Error>(BufferedRequestAgentWaitResult::Found(
+                        agent_controller,
+                    ));
+                }
+
+                tokio::select! {
+                    _ = agent_controller_pool_notified => {}
+                    _ = buffered_request_queue_notified => {}
                 }
             }
         })
@@ -72,6 +156,46 @@ impl BufferedRequestManager {
             Err(timeout_err) => Ok(BufferedRequestAgentWaitResult::Timeout(timeout_err.into())),
         }
     }
+
+    /// Resolves once there are no buffered requests and no agent is processing anything.
+    pub async fn wait_for_requests_to_finish(&self) {
+        loop {
+            let agent_controller_pool_notified =
+                self.agent_controller_pool.update_notifier.notified();
+            let buffered_request_queue_notified =
+                self.buffered_request_queue.update_notifier.notified();
+
+            tokio::pin!(agent_controller_pool_notified);
+            tokio::pin!(buffered_request_queue_notified);
+
+            agent_controller_pool_notified.as_mut().enable();
+            buffered_request_queue_notified.as_mut().enable();
+
+            if self.buffered_request_queue.is_empty()
+                && self.agent_controller_pool.total_slots().slots_processing < 1
+            {
+                return;
+            }
+
+            tokio::select! {
+                _ = agent_controller_pool_notified => {}
+                _ = buffered_request_queue_notified => {}
+            }
+        }
+    }
+
+    /// Only the request at the front of the queue is allowed to take a slot
+    fn take_agent_controller_if_first(
+        &self,
+        buffered_request_queue_entry_guard: &BufferedRequestQueueEntryGuard,
+    ) -> Option<Arc<AgentController>> {
+        if !buffered_request_queue_entry_guard.is_first() {
+            return None;
+        }
+
+        self.agent_controller_pool
+            .take_least_busy_agent_controller()
+    }
 }
 
 impl ProducesSnapshot for BufferedRequestManager {
@@ -80,6 +204,141 @@ impl ProducesSnapshot for BufferedRequestManager {
     fn make_snapshot(&self) -> Result<Self::Snapshot> {
         Ok(BufferedRequestManagerSnapshot {
             buffered_requests_current: self.buffered_request_counter.get(),
+            queue: self.buffered_request_queue.make_snapshot()?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::balancer::agent_circuit_breaker_configuration::AgentCircuitBreakerConfiguration;
+    use crate::full_buffer_policy::FullBufferPolicy;
+    use crate::heartbeat_configuration::HeartbeatConfiguration;
+    use crate::response_buffer_configuration::ResponseBufferConfiguration;
+
+    fn make_buffered_request_manager(
+        drain_status: Arc<DrainStatus>,
+    ) -> Result<BufferedRequestManager> {
+        Ok(BufferedRequestManager::new(
+            Arc::new(AgentControllerPool::new(
+                AgentCircuitBreakerConfiguration {
+                    cooldown: Duration::from_secs(10),
+                    failure_ratio: 0.0,
+                    failure_ratio_min_requests: 0,
+                    failure_ratio_window: 0,
+                    failure_threshold: 0,
+                },
+                HeartbeatConfiguration {
+                    interval: Duration::from_secs(5),
+                    missed_beats_threshold: 0,
+                },
+                Duration::ZERO,
+                ResponseBufferConfiguration {
+                    capacity: 16,
+                    full_buffer_policy: FullBufferPolicy::Pause,
+                },
+            )),
+            drain_status,
+            BufferedRequestPriorityClassRegistry::new(
+                Duration::from_secs(10),
+                10,
+                Vec::new(),
+                Vec::new(),
+            )?,
+        ))
+    }
+
+    async fn wait_for_available_agent(
+        buffered_request_manager: &BufferedRequestManager,
+    ) -> Result<BufferedRequestAgentWaitResult> {
+        let priority_class = buffered_request_manager.select_priority_class(None, None)?;
+
+        buffered_request_manager
+            .wait_for_available_agent(
+                &priority_class,
+                buffered_request_manager.next_queue_sequence(),
+                "request-1".to_string(),
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_draining_turns_away_new_requests() -> Result<()> {
+        let drain_status = Arc::new(DrainStatus::default());
+        let buffered_request_manager = make_buffered_request_manager(drain_status.clone())?;
+
+        drain_status.start_draining();
+
+        assert!(matches!(
+            wait_for_available_agent(&buffered_request_manager).await?,
+            BufferedRequestAgentWaitResult::ShuttingDown
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rejecting_buffered_requests_releases_the_waiting_ones() -> Result<()> {
+        let buffered_request_manager = Arc::new(make_buffered_request_manager(Arc::new(
+            DrainStatus::default(),
+        ))?);
+        let waiting_request = tokio::spawn({
+            let buffered_request_manager = buffered_request_manager.clone();
+
+            async move { wait_for_available_agent(&buffered_request_manager).await }
+        });
+
+        // Lets the request get into the queue before the drain ends
+        while buffered_request_manager.buffered_request_counter.get() < 1 {
+            tokio::task::yield_now().await;
+        }
+
+        buffered_request_manager.reject_buffered_requests();
+
+        assert!(matches!(
+            waiting_request.await??,
+            BufferedRequestAgentWaitResult::ShuttingDown
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_waiting_for_requests_to_finish_ends_once_the_queue_is_empty() -> Result<()> {
+        let buffered_request_manager = Arc::new(make_buffered_request_manager(Arc::new(
+            DrainStatus::default(),
+        ))?);
+        let waiting_request = tokio::spawn({
+            let buffered_request_manager = buffered_request_manager.clone();
+
+            async move { wait_for_available_agent(&buffered_request_manager).await }
+        });
+
+        while buffered_request_manager.buffered_request_counter.get() < 1 {
+            tokio::task::yield_now().await;
+        }
+
+        assert!(
+            timeout(
+                Duration::from_millis(50),
+                buffered_request_manager.wait_for_requests_to_finish()
+            )
+            .await
+            .is_err()
+        );
+
+        buffered_request_manager.reject_buffered_requests();
+        waiting_request.await??;
+
+        timeout(
+            Duration::from_secs(5),
+            buffered_request_manager.wait_for_requests_to_finish(),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,6 +1,9 @@
 use serde::Serialize;
 
+use crate::balancer::buffered_request_queue_entry_snapshot::BufferedRequestQueueEntrySnapshot;
+
 #[derive(Serialize)]
 pub struct BufferedRequestManagerSnapshot {
     pub buffered_requests_current: i32,
+    pub queue: Vec<BufferedRequestQueueEntrySnapshot>,
 }
@@ -0,0 +1,57 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Request as the clients send it to the balancer. The priority class only decides the place
+/// in the balancer queue, so it is never forwarded to the agents.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BufferedRequestParams<TParams> {
+    #[serde(flatten)]
+    pub params: TParams,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority_class: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::json;
+
+    use super::*;
+    use crate::request_params::ContinueFromRawPromptParams;
+
+    #[test]
+    fn test_priority_class_is_not_forwarded_to_agents() -> Result<()> {
+        let BufferedRequestParams {
+            params,
+            priority_class,
+        }: BufferedRequestParams<ContinueFromRawPromptParams> = serde_json::from_value(json!({
+            "max_tokens": 10,
+            "priority_class": "interactive",
+            "raw_prompt": "Hello",
+        }))?;
+
+        assert_eq!(priority_class.as_deref(), Some("interactive"));
+        assert_eq!(
+            serde_json::to_value(params)?,
+            json!({
+                "max_tokens": 10,
+                "raw_prompt": "Hello",
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_unknown_fields() {
+        assert!(
+            serde_json::from_value::<BufferedRequestParams<ContinueFromRawPromptParams>>(json!({
+                "max_tokens": 10,
+                "raw_prompt": "Hello",
+                "temperature": 0.5,
+            }))
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::Error;
+use anyhow::Result;
+use anyhow::anyhow;
+
+pub const DEFAULT_PRIORITY_CLASS_NAME: &str = "default";
+
+/// Requests from a class with a higher priority are always handed a free slot before the
+/// requests from a class with a lower priority. Within the same class, requests are served
+/// in the order of arrival.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BufferedRequestPriorityClass {
+    pub buffered_request_timeout: Duration,
+    pub max_buffered_requests: i32,
+    pub name: String,
+    pub priority: i32,
+}
+
+impl FromStr for BufferedRequestPriorityClass {
+    type Err = Error;
+
+    /// Format: `name:priority:max_buffered_requests:buffered_request_timeout_millis`
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = input.split(':').map(str::trim).collect();
+
+        if parts.len() != 4 {
+            return Err(anyhow!(
+                "Invalid priority class '{input}'. Expected 'name:priority:max_buffered_requests:buffered_request_timeout_millis'"
+            ));
+        }
+
+        let name = parts[0];
+
+        if name.is_empty() {
+            return Err(anyhow!("Priority class name cannot be empty"));
+        }
+
+        let max_buffered_requests: i32 = parts[2].parse()?;
+
+        if max_buffered_requests < 0 {
+            return Err(anyhow!(
+                "Priority class '{name}' cannot have a negative buffer size"
+            ));
+        }
+
+        Ok(BufferedRequestPriorityClass {
+            buffered_request_timeout: Duration::from_millis(parts[3].parse()?),
+            max_buffered_requests,
+            name: name.to_string(),
+            priority: parts[1].parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_priority_class() {
+        let result = BufferedRequestPriorityClass::from_str("interactive:10:50:2000").unwrap();
+
+        assert_eq!(result.name, "interactive");
+        assert_eq!(result.priority, 10);
+        assert_eq!(result.max_buffered_requests, 50);
+        assert_eq!(result.buffered_request_timeout, Duration::from_millis(2000));
+    }
+
+    #[test]
+    fn test_parse_negative_priority() {
+        let result = BufferedRequestPriorityClass::from_str("batch:-5:100:60000").unwrap();
+
+        assert_eq!(result.priority, -5);
+    }
+
+    #[test]
+    fn test_missing_parts_fail() {
+        assert!(BufferedRequestPriorityClass::from_str("interactive:10").is_err());
+    }
+
+    #[test]
+    fn test_empty_name_fails() {
+        assert!(BufferedRequestPriorityClass::from_str(":10:50:2000").is_err());
+    }
+
+    #[test]
+    fn test_negative_buffer_size_fails() {
+        assert!(BufferedRequestPriorityClass::from_str("batch:0:-1:2000").is_err());
+    }
+}
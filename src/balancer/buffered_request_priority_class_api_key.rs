@@ -0,0 +1,33 @@
+use std::str::FromStr;
+
+use anyhow::Error;
+use anyhow::Result;
+use anyhow::anyhow;
+
+/// Assigns every request that is authorized with the given API key to a priority class.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BufferedRequestPriorityClassApiKey {
+    pub api_key: String,
+    pub priority_class: String,
+}
+
+impl FromStr for BufferedRequestPriorityClassApiKey {
+    type Err = Error;
+
+    /// Format: `priority_class:api_key`
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.split_once(':') {
+            Some((priority_class, api_key))
+                if !priority_class.trim().is_empty() && !api_key.is_empty() =>
+            {
+                Ok(BufferedRequestPriorityClassApiKey {
+                    api_key: api_key.to_string(),
+                    priority_class: priority_class.trim().to_string(),
+                })
+            }
+            _ => Err(anyhow!(
+                "Invalid priority class API key assignment. Expected 'priority_class:api_key'"
+            )),
+        }
+    }
+}
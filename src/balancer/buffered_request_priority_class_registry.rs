@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use anyhow::anyhow;
+
+use crate::balancer::buffered_request_priority_class::BufferedRequestPriorityClass;
+use crate::balancer::buffered_request_priority_class::DEFAULT_PRIORITY_CLASS_NAME;
+use crate::balancer::buffered_request_priority_class_api_key::BufferedRequestPriorityClassApiKey;
+
+pub struct BufferedRequestPriorityClassRegistry {
+    default_priority_class: Arc<BufferedRequestPriorityClass>,
+    priority_class_by_api_key: HashMap<String, Arc<BufferedRequestPriorityClass>>,
+    priority_classes: HashMap<String, Arc<BufferedRequestPriorityClass>>,
+}
+
+impl BufferedRequestPriorityClassRegistry {
+    pub fn new(
+        buffered_request_timeout: Duration,
+        max_buffered_requests: i32,
+        priority_classes: Vec<BufferedRequestPriorityClass>,
+        priority_class_api_keys: Vec<BufferedRequestPriorityClassApiKey>,
+    ) -> Result<Self> {
+        let mut priority_classes_by_name: HashMap<String, Arc<BufferedRequestPriorityClass>> =
+            HashMap::new();
+
+        priority_classes_by_name.insert(
+            DEFAULT_PRIORITY_CLASS_NAME.to_string(),
+            Arc::new(BufferedRequestPriorityClass {
+                buffered_request_timeout,
+                max_buffered_requests,
+                name: DEFAULT_PRIORITY_CLASS_NAME.to_string(),
+                priority: 0,
+            }),
+        );
+
+        // Explicitly configured classes can also override the default one
+        for priority_class in priority_classes {
+            priority_classes_by_name.insert(priority_class.name.clone(), Arc::new(priority_class));
+        }
+
+        let mut priority_class_by_api_key = HashMap::new();
+
+        for BufferedRequestPriorityClassApiKey {
+            api_key,
+            priority_class,
+        } in priority_class_api_keys
+        {
+            match priority_classes_by_name.get(&priority_class) {
+                Some(priority_class) => {
+                    priority_class_by_api_key.insert(api_key, priority_class.clone());
+                }
+                None => {
+                    return Err(anyhow!(
+                        "API key is assigned to an undefined priority class '{priority_class}'"
+                    ));
+                }
+            }
+        }
+
+        let default_priority_class = priority_classes_by_name
+            .get(DEFAULT_PRIORITY_CLASS_NAME)
+            .cloned()
+            .ok_or_else(|| anyhow!("Default priority class is not defined"))?;
+
+        Ok(Self {
+            default_priority_class,
+            priority_class_by_api_key,
+            priority_classes: priority_classes_by_name,
+        })
+    }
+
+    /// Priority class assigned to the API key takes precedence over the one requested
+    /// by the client, so clients cannot elevate their own priority.
+    pub fn select_priority_class(
+        &self,
+        requested_priority_class: Option<&str>,
+        api_key: Option<&str>,
+    ) -> Result<Arc<BufferedRequestPriorityClass>> {
+        if let Some(priority_class) =
+            api_key.and_then(|api_key| self.priority_class_by_api_key.get(api_key))
+        {
+            return Ok(priority_class.clone());
+        }
+
+        match requested_priority_class {
+            Some(name) => self
+                .priority_classes
+                .get(name)
+                .cloned()
+                .ok_or_else(|| anyhow!("Unknown priority class: '{name}'")),
+            None => Ok(self.default_priority_class.clone()),
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+
+use anyhow::Result;
+use tokio::sync::Notify;
+
+use crate::balancer::buffered_request_priority_class::BufferedRequestPriorityClass;
+use crate::balancer::buffered_request_queue_entry::BufferedRequestQueueEntry;
+use crate::balancer::buffered_request_queue_entry_guard::BufferedRequestQueueEntryGuard;
+use crate::balancer::buffered_request_queue_entry_snapshot::BufferedRequestQueueEntrySnapshot;
+use crate::balancer::buffered_request_queue_position::BufferedRequestQueuePosition;
+use crate::produces_snapshot::ProducesSnapshot;
+
+pub struct BufferedRequestQueue {
+    entries: Mutex<BTreeMap<BufferedRequestQueuePosition, BufferedRequestQueueEntry>>,
+    next_sequence: AtomicU64,
+    pub update_notifier: Arc<Notify>,
+}
+
+impl BufferedRequestQueue {
+    pub fn new(update_notifier: Arc<Notify>) -> Self {
+        Self {
+            entries: Mutex::new(BTreeMap::new()),
+            next_sequence: AtomicU64::new(0),
+            update_notifier,
+        }
+    }
+
+    /// Returns `None` if the buffer of the given priority class is already full.
+    pub fn enqueue(
+        self: &Arc<Self>,
+        priority_class: &BufferedRequestPriorityClass,
+        sequence: u64,
+        request_id: String,
+    ) -> Option<BufferedRequestQueueEntryGuard> {
+        let position = {
+            let mut entries = self
+                .entries
+                .lock()
+                .expect("Poisoned lock on buffered request queue");
+
+            let buffered_in_class = entries
+                .values()
+                .filter(|entry| entry.priority_class == priority_class.name)
+                .count();
+
+            if buffered_in_class >= priority_class.max_buffered_requests as usize {
+                return None;
+            }
+
+            let position = BufferedRequestQueuePosition {
+                priority: Reverse(priority_class.priority),
+                sequence,
+            };
+
+            entries.insert(
+                position,
+                BufferedRequestQueueEntry {
+                    enqueued_at: Instant::now(),
+                    priority_class: priority_class.name.clone(),
+                    request_id,
+                },
+            );
+
+            position
+        };
+
+        self.update_notifier.notify_waiters();
+
+        Some(BufferedRequestQueueEntryGuard {
+            buffered_request_queue: self.clone(),
+            position,
+        })
+    }
+
+    pub fn is_first(&self, position: &BufferedRequestQueuePosition) -> bool {
+        self.entries
+            .lock()
+            .expect("Poisoned lock on buffered request queue")
+            .keys()
+            .next()
+            .is_some_and(|first_position| first_position == position)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .expect("Poisoned lock on buffered request queue")
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn next_sequence(&self) -> u64 {
+        self.next_sequence.fetch_add(1, Ordering::SeqCst)
+    }
+
+    pub fn remove(&self, position: &BufferedRequestQueuePosition) {
+        let removed = self
+            .entries
+            .lock()
+            .expect("Poisoned lock on buffered request queue")
+            .remove(position)
+            .is_some();
+
+        if removed {
+            // Wake up the remaining requests, so the next one in line can take the slot
+            self.update_notifier.notify_waiters();
+        }
+    }
+}
+
+impl ProducesSnapshot for BufferedRequestQueue {
+    type Snapshot = Vec<BufferedRequestQueueEntrySnapshot>;
+
+    fn make_snapshot(&self) -> Result<Self::Snapshot> {
+        Ok(self
+            .entries
+            .lock()
+            .expect("Poisoned lock on buffered request queue")
+            .values()
+            .map(|entry| BufferedRequestQueueEntrySnapshot {
+                priority_class: entry.priority_class.clone(),
+                request_id: entry.request_id.clone(),
+                waiting_millis: entry.enqueued_at.elapsed().as_millis() as u64,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn priority_class(name: &str, priority: i32) -> BufferedRequestPriorityClass {
+        BufferedRequestPriorityClass {
+            buffered_request_timeout: Duration::from_secs(10),
+            max_buffered_requests: 2,
+            name: name.to_string(),
+            priority,
+        }
+    }
+
+    fn enqueue(
+        buffered_request_queue: &Arc<BufferedRequestQueue>,
+        priority_class: &BufferedRequestPriorityClass,
+        request_id: &str,
+    ) -> Option<BufferedRequestQueueEntryGuard> {
+        buffered_request_queue.enqueue(
+            priority_class,
+            buffered_request_queue.next_sequence(),
+            request_id.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_serves_requests_in_order_of_arrival_within_class() {
+        let buffered_request_queue = Arc::new(BufferedRequestQueue::new(Arc::new(Notify::new())));
+        let default_class = priority_class("default", 0);
+        let first_request = enqueue(&buffered_request_queue, &default_class, "first").unwrap();
+        let second_request = enqueue(&buffered_request_queue, &default_class, "second").unwrap();
+
+        assert!(first_request.is_first());
+        assert!(!second_request.is_first());
+
+        drop(first_request);
+
+        assert!(second_request.is_first());
+    }
+
+    #[test]
+    fn test_serves_higher_priority_class_first() {
+        let buffered_request_queue = Arc::new(BufferedRequestQueue::new(Arc::new(Notify::new())));
+        let batch_request = enqueue(
+            &buffered_request_queue,
+            &priority_class("batch", -5),
+            "batch",
+        )
+        .unwrap();
+        let interactive_request = enqueue(
+            &buffered_request_queue,
+            &priority_class("interactive", 10),
+            "interactive",
+        )
+        .unwrap();
+
+        assert!(interactive_request.is_first());
+        assert!(!batch_request.is_first());
+    }
+
+    #[test]
+    fn test_limits_buffered_requests_per_class() {
+        let buffered_request_queue = Arc::new(BufferedRequestQueue::new(Arc::new(Notify::new())));
+        let batch_class = priority_class("batch", -5);
+        let _first_request = enqueue(&buffered_request_queue, &batch_class, "first").unwrap();
+        let _second_request = enqueue(&buffered_request_queue, &batch_class, "second").unwrap();
+
+        assert!(enqueue(&buffered_request_queue, &batch_class, "third").is_none());
+        assert!(
+            enqueue(
+                &buffered_request_queue,
+                &priority_class("interactive", 10),
+                "interactive"
+            )
+            .is_some()
+        );
+        assert_eq!(buffered_request_queue.len(), 2);
+    }
+
+    #[test]
+    fn test_retried_request_keeps_its_place() {
+        let buffered_request_queue = Arc::new(BufferedRequestQueue::new(Arc::new(Notify::new())));
+        let default_class = priority_class("default", 0);
+        let retried_sequence = buffered_request_queue.next_sequence();
+        let later_request = enqueue(&buffered_request_queue, &default_class, "later").unwrap();
+        let retried_request = buffered_request_queue
+            .enqueue(&default_class, retried_sequence, "retried".to_string())
+            .unwrap();
+
+        assert!(retried_request.is_first());
+        assert!(!later_request.is_first());
+    }
+}
@@ -0,0 +1,7 @@
+use std::time::Instant;
+
+pub struct BufferedRequestQueueEntry {
+    pub enqueued_at: Instant,
+    pub priority_class: String,
+    pub request_id: String,
+}
@@ -0,0 +1,21 @@
+use std::sync::Arc;
+
+use crate::balancer::buffered_request_queue::BufferedRequestQueue;
+use crate::balancer::buffered_request_queue_position::BufferedRequestQueuePosition;
+
+pub struct BufferedRequestQueueEntryGuard {
+    pub buffered_request_queue: Arc<BufferedRequestQueue>,
+    pub position: BufferedRequestQueuePosition,
+}
+
+impl BufferedRequestQueueEntryGuard {
+    pub fn is_first(&self) -> bool {
+        self.buffered_request_queue.is_first(&self.position)
+    }
+}
+
+impl Drop for BufferedRequestQueueEntryGuard {
+    fn drop(&mut self) {
+        self.buffered_request_queue.remove(&self.position);
+    }
+}
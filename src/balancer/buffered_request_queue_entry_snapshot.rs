@@ -0,0 +1,10 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BufferedRequestQueueEntrySnapshot {
+    pub priority_class: String,
+    pub request_id: String,
+    pub waiting_millis: u64,
+}
@@ -0,0 +1,9 @@
+use std::cmp::Reverse;
+
+/// Orders the queue so that the higher priority goes first, and then the oldest request goes
+/// first within the same priority.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct BufferedRequestQueuePosition {
+    pub priority: Reverse<i32>,
+    pub sequence: u64,
+}
@@ -1,12 +1,12 @@
 use async_trait::async_trait;
 use dashmap::DashMap;
-use tokio::sync::mpsc;
 
 use crate::balancer::manages_senders::ManagesSenders;
+use crate::balancer::response_sender::ResponseSender;
 use crate::chat_template::ChatTemplate;
 
 pub struct ChatTemplateOverrideSenderCollection {
-    senders: DashMap<String, mpsc::UnboundedSender<Option<ChatTemplate>>>,
+    senders: DashMap<String, ResponseSender<Option<ChatTemplate>>>,
 }
 
 impl Default for ChatTemplateOverrideSenderCollection {
@@ -21,7 +21,7 @@ impl Default for ChatTemplateOverrideSenderCollection {
 impl ManagesSenders for ChatTemplateOverrideSenderCollection {
     type Value = Option<ChatTemplate>;
 
-    fn get_sender_collection(&self) -> &DashMap<String, mpsc::UnboundedSender<Self::Value>> {
+    fn get_sender_collection(&self) -> &DashMap<String, ResponseSender<Self::Value>> {
         &self.senders
     }
 }
@@ -8,12 +8,16 @@ use self::transforms_outgoing_message::TransformsOutgoingMessage;
 use crate::balancer::inference_client::Message as OutgoingMessage;
 use crate::controls_session::ControlsSession;
 
+/// Chunks wait here for the HTTP client. When it is full, the responses of the request
+/// pile up in its response buffer instead.
+pub const CHUNK_BUFFER_CAPACITY: usize = 16;
+
 #[derive(Clone)]
 pub struct ChunkForwardingSessionController<TTransformsOutgoingMessage>
 where
     TTransformsOutgoingMessage: Clone + TransformsOutgoingMessage + Send + Sync,
 {
-    chunk_tx: mpsc::UnboundedSender<String>,
+    chunk_tx: mpsc::Sender<String>,
     transformer: TTransformsOutgoingMessage,
 }
 
@@ -21,10 +25,7 @@ impl<TTransformsOutgoingMessage> ChunkForwardingSessionController<TTransformsOut
 where
     TTransformsOutgoingMessage: Clone + TransformsOutgoingMessage + Send + Sync,
 {
-    pub fn new(
-        chunk_tx: mpsc::UnboundedSender<String>,
-        transformer: TTransformsOutgoingMessage,
-    ) -> Self {
+    pub fn new(chunk_tx: mpsc::Sender<String>, transformer: TTransformsOutgoingMessage) -> Self {
         Self {
             chunk_tx,
             transformer,
@@ -42,7 +43,7 @@ where
         let transformed_message = self.transformer.transform(message).await?;
         let stringified_message = self.transformer.stringify(&transformed_message)?;
 
-        self.chunk_tx.send(stringified_message)?;
+        self.chunk_tx.send(stringified_message).await?;
 
         Ok(())
     }
@@ -0,0 +1,83 @@
+use std::any::Any;
+
+use actix_tls::accept::rustls_0_23::TlsStream;
+use actix_web::dev::Extensions;
+use actix_web::rt::net::TcpStream;
+use anyhow::Result;
+use anyhow::anyhow;
+use rustls_pki_types::CertificateDer;
+use rustls_pki_types::ServerName;
+use webpki::EndEntityCert;
+
+/// Certificate the client presented during the TLS handshake. It is already verified
+/// against the client CA by then, so it proves the identity of the client.
+#[derive(Clone)]
+pub struct ClientCertificate {
+    pub certificate: CertificateDer<'static>,
+}
+
+impl ClientCertificate {
+    /// Agents prove their name by presenting a certificate issued for it.
+    pub fn authenticate_agent(&self, agent_name: Option<&str>) -> Result<()> {
+        let agent_name = agent_name
+            .ok_or_else(|| anyhow!("Agents authenticating with a certificate need a name"))?;
+        let subject_name = ServerName::try_from(agent_name)
+            .map_err(|err| anyhow!("Agent name {agent_name:?} is not a valid DNS name: {err}"))?;
+
+        EndEntityCert::try_from(&self.certificate)
+            .map_err(|err| anyhow!("Invalid client certificate: {err}"))?
+            .verify_is_valid_for_subject_name(&subject_name)
+            .map_err(|err| {
+                anyhow!("Client certificate is not issued for agent {agent_name:?}: {err}")
+            })
+    }
+
+    /// Meant to be used with `HttpServer::on_connect`, so the handlers can get the certificate
+    /// with `HttpRequest::conn_data`.
+    pub fn store_in_connection_data(connection: &dyn Any, extensions: &mut Extensions) {
+        let certificate = connection
+            .downcast_ref::<TlsStream<TcpStream>>()
+            .and_then(|tls_stream| tls_stream.get_ref().1.peer_certificates())
+            .and_then(|certificates| certificates.first());
+
+        if let Some(certificate) = certificate {
+            extensions.insert(ClientCertificate {
+                certificate: certificate.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rcgen::generate_simple_self_signed;
+
+    use super::*;
+
+    fn make_client_certificate(agent_name: &str) -> ClientCertificate {
+        ClientCertificate {
+            certificate: generate_simple_self_signed(vec![agent_name.to_string()])
+                .unwrap()
+                .cert
+                .der()
+                .clone(),
+        }
+    }
+
+    #[test]
+    fn test_agent_name_has_to_match_the_certificate() {
+        let client_certificate = make_client_certificate("agent-1");
+
+        assert!(
+            client_certificate
+                .authenticate_agent(Some("agent-1"))
+                .is_ok()
+        );
+        assert!(
+            client_certificate
+                .authenticate_agent(Some("agent-2"))
+                .is_err()
+        );
+        assert!(client_certificate.authenticate_agent(None).is_err());
+    }
+}
@@ -1,9 +1,11 @@
 use std::sync::Arc;
 
+use crate::balancer::api_key_manager::ApiKeyManager;
 use crate::balancer::buffered_request_manager::BufferedRequestManager;
-use crate::balancer::inference_service::configuration::Configuration;
+use crate::balancer::inference_service::configuration_holder::ConfigurationHolder;
 
 pub struct AppData {
+    pub api_key_manager: Arc<ApiKeyManager>,
     pub buffered_request_manager: Arc<BufferedRequestManager>,
-    pub inference_service_configuration: Configuration,
+    pub inference_service_configuration_holder: Arc<ConfigurationHolder>,
 }
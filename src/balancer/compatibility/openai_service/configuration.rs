@@ -1,6 +1,6 @@
-use std::net::SocketAddr;
+use crate::server_addr::ServerAddr;
 
 #[derive(Clone)]
 pub struct Configuration {
-    pub addr: SocketAddr,
+    pub addr: ServerAddr,
 }
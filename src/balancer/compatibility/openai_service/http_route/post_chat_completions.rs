@@ -2,7 +2,9 @@ use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 
 use actix_web::Error;
+use actix_web::HttpRequest;
 use actix_web::HttpResponse;
+use actix_web::error::ErrorBadRequest;
 use actix_web::post;
 use actix_web::web;
 use anyhow::anyhow;
@@ -12,12 +14,14 @@ use serde::Deserialize;
 use serde_json::json;
 use tokio_stream::StreamExt as _;
 
+use crate::balancer::authorization_bearer_token::authorization_bearer_token;
+use crate::balancer::authorize_api_key::authorize_api_key;
 use crate::balancer::chunk_forwarding_session_controller::transforms_outgoing_message::TransformsOutgoingMessage;
 use crate::balancer::compatibility::openai_service::app_data::AppData;
 use crate::balancer::http_stream_from_agent::http_stream_from_agent;
 use crate::balancer::inference_client::Message as OutgoingMessage;
 use crate::balancer::inference_client::Response as OutgoingResponse;
-use crate::balancer::unbounded_stream_from_agent::unbounded_stream_from_agent;
+use crate::balancer::stream_from_agent::stream_from_agent;
 use crate::conversation_message::ConversationMessage;
 use crate::generated_token_result::GeneratedTokenResult;
 use crate::jsonrpc::ResponseEnvelope;
@@ -153,7 +157,14 @@ impl TransformsOutgoingMessage for OpenAICombinedResponseTransformer {
 async fn respond(
     app_data: web::Data<AppData>,
     openai_params: web::Json<OpenAICompletionRequestParams>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, Error> {
+    let api_key_usage_guard = authorize_api_key(&app_data.api_key_manager, &req)?;
+    let priority_class = app_data
+        .buffered_request_manager
+        .select_priority_class(None, authorization_bearer_token(&req).as_deref())
+        .map_err(ErrorBadRequest)?;
+
     let paddler_params = ContinueFromConversationHistoryParams {
         add_generation_prompt: true,
         conversation_history: openai_params
@@ -168,19 +179,27 @@ async fn respond(
 
     if openai_params.stream {
         http_stream_from_agent(
+            api_key_usage_guard,
             app_data.buffered_request_manager.clone(),
-            app_data.inference_service_configuration.clone(),
+            app_data
+                .inference_service_configuration_holder
+                .get_configuration(),
             paddler_params,
+            priority_class,
             OpenAIStreamingResponseTransformer {
                 model: openai_params.model.clone(),
                 system_fingerprint: nanoid!(),
             },
         )
     } else {
-        let combined_response = unbounded_stream_from_agent(
+        let combined_response = stream_from_agent(
+            api_key_usage_guard,
             app_data.buffered_request_manager.clone(),
-            app_data.inference_service_configuration.clone(),
+            app_data
+                .inference_service_configuration_holder
+                .get_configuration(),
             paddler_params,
+            priority_class,
             OpenAICombinedResponseTransformer {},
         )?
         .collect::<Vec<String>>()
@@ -6,24 +6,38 @@ use std::sync::Arc;
 
 use actix_web::App;
 use actix_web::HttpServer;
+use actix_web::rt;
 use actix_web::web::Data;
 use anyhow::Result;
 use async_trait::async_trait;
 use log::error;
+use rustls::ServerConfig;
 use tokio::sync::broadcast;
 
+use crate::balancer::api_key_manager::ApiKeyManager;
 use crate::balancer::buffered_request_manager::BufferedRequestManager;
 use crate::balancer::compatibility::openai_service::app_data::AppData;
 use crate::balancer::compatibility::openai_service::configuration::Configuration as OpenAIServiceConfiguration;
+use crate::balancer::create_unix_listener::create_unix_listener;
 use crate::balancer::http_route as common_http_route;
-use crate::balancer::inference_service::configuration::Configuration as InferenceServiceConfiguration;
+use crate::balancer::inference_service::configuration_holder::ConfigurationHolder as InferenceServiceConfigurationHolder;
+use crate::balancer::pause_server_when_draining::pause_server_when_draining;
+use crate::balancer::unix_socket_mode::UnixSocketMode;
+use crate::cors_allowed_hosts::CorsAllowedHosts;
 use crate::create_cors_middleware::create_cors_middleware;
+use crate::drain_status::DrainStatus;
+use crate::server_addr::ServerAddr;
 use crate::service::Service;
 
 pub struct OpenAIService {
+    pub api_key_manager: Arc<ApiKeyManager>,
     pub buffered_request_manager: Arc<BufferedRequestManager>,
-    pub inference_service_configuration: InferenceServiceConfiguration,
+    pub cors_allowed_hosts: Arc<CorsAllowedHosts>,
+    pub drain_status: Arc<DrainStatus>,
+    pub inference_service_configuration_holder: Arc<InferenceServiceConfigurationHolder>,
     pub openai_service_configuration: OpenAIServiceConfiguration,
+    pub tls_server_config: Option<ServerConfig>,
+    pub unix_socket_mode: Option<UnixSocketMode>,
 }
 
 #[async_trait]
@@ -33,21 +47,22 @@ impl Service for OpenAIService {
     }
 
     async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
-        let cors_allowed_hosts = self
-            .inference_service_configuration
-            .cors_allowed_hosts
-            .clone();
-        let cors_allowed_hosts_arc = Arc::new(cors_allowed_hosts);
-
         let app_data = Data::new(AppData {
+            api_key_manager: self.api_key_manager.clone(),
             buffered_request_manager: self.buffered_request_manager.clone(),
-            inference_service_configuration: self.inference_service_configuration.clone(),
+            inference_service_configuration_holder: self
+                .inference_service_configuration_holder
+                .clone(),
         });
 
-        HttpServer::new(move || {
+        let cors_allowed_hosts = self.cors_allowed_hosts.clone();
+        let drain_status = Data::from(self.drain_status.clone());
+
+        let http_server = HttpServer::new(move || {
             App::new()
-                .wrap(create_cors_middleware(cors_allowed_hosts_arc.clone()))
+                .wrap(create_cors_middleware(cors_allowed_hosts.clone()))
                 .app_data(app_data.clone())
+                .app_data(drain_status.clone())
                 .configure(common_http_route::get_health::register)
                 .configure(http_route::post_chat_completions::register)
         })
@@ -55,12 +70,28 @@ impl Service for OpenAIService {
             if let Err(err) = shutdown.recv().await {
                 error!("Failed to receive shutdown signal: {err}");
             }
-        })
-        .bind(self.openai_service_configuration.addr)
+        });
+
+        let server = match self.openai_service_configuration.addr.clone() {
+            ServerAddr::Tcp(socket_addr) => match self.tls_server_config.clone() {
+                Some(tls_server_config) => {
+                    http_server.bind_rustls_0_23(socket_addr, tls_server_config)
+                }
+                None => http_server.bind(socket_addr),
+            },
+            ServerAddr::Unix(path) => create_unix_listener(&path, self.unix_socket_mode)
+                .and_then(|unix_listener| http_server.listen_uds(unix_listener)),
+        }
         .expect("Unable to bind server to address")
-        .run()
-        .await?;
+        .run();
+        let pause_server_handle = rt::spawn(pause_server_when_draining(
+            self.drain_status.clone(),
+            server.handle(),
+        ));
+        let server_result = server.await;
+
+        pause_server_handle.abort();
 
-        Ok(())
+        Ok(server_result?)
     }
 }
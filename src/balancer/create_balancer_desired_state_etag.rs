@@ -0,0 +1,16 @@
+use actix_web::http::header::EntityTag;
+use anyhow::Result;
+use sha2::Digest as _;
+use sha2::Sha256;
+
+use crate::balancer_desired_state::BalancerDesiredState;
+
+/// Derived from the contents of the state, so it works the same with every state
+/// database, and also changes when the state is edited outside of the API.
+pub fn create_balancer_desired_state_etag(
+    balancer_desired_state: &BalancerDesiredState,
+) -> Result<EntityTag> {
+    let digest = Sha256::digest(serde_json::to_vec(balancer_desired_state)?);
+
+    Ok(EntityTag::new_strong(format!("{digest:x}")))
+}
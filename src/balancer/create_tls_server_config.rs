@@ -0,0 +1,230 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use rustls::ServerConfig;
+use rustls::crypto::ring::default_provider;
+use rustls::server::WebPkiClientVerifier;
+
+use crate::balancer::reloadable_tls_certificate::ReloadableTlsCertificate;
+use crate::read_root_cert_store::read_root_cert_store;
+
+/// With a client CA, the clients can present a certificate signed by it to prove who they are.
+/// Clients without a certificate are still let in, so they can authenticate with a token instead.
+pub fn create_tls_server_config(
+    client_ca_file: Option<&Path>,
+    reloadable_tls_certificate: Arc<ReloadableTlsCertificate>,
+) -> Result<ServerConfig> {
+    let provider = Arc::new(default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .context("Unable to configure TLS protocol versions")?;
+
+    let server_config = match client_ca_file {
+        Some(client_ca_file) => builder
+            .with_client_cert_verifier(
+                WebPkiClientVerifier::builder_with_provider(
+                    Arc::new(read_root_cert_store(client_ca_file)?),
+                    provider,
+                )
+                .allow_unauthenticated()
+                .build()
+                .context("Unable to create the client certificate verifier")?,
+            )
+            .with_cert_resolver(reloadable_tls_certificate),
+        None => builder
+            .with_no_client_auth()
+            .with_cert_resolver(reloadable_tls_certificate),
+    };
+
+    Ok(server_config)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use rcgen::BasicConstraints;
+    use rcgen::Certificate;
+    use rcgen::CertificateParams;
+    use rcgen::ExtendedKeyUsagePurpose;
+    use rcgen::IsCa;
+    use rcgen::KeyPair;
+    use rustls_pki_types::ServerName;
+    use tempfile::TempDir;
+    use tokio::io::duplex;
+    use tokio_rustls::TlsAcceptor;
+    use tokio_rustls::TlsConnector;
+
+    use super::*;
+    use crate::agent::create_tls_client_config::create_tls_client_config;
+    use crate::balancer::client_certificate::ClientCertificate;
+
+    struct CertificateAuthority {
+        certificate: Certificate,
+        key_pair: KeyPair,
+    }
+
+    impl CertificateAuthority {
+        fn generate() -> Result<Self> {
+            let mut params = CertificateParams::new(Vec::<String>::new())?;
+
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+
+            let key_pair = KeyPair::generate()?;
+
+            Ok(Self {
+                certificate: params.self_signed(&key_pair)?,
+                key_pair,
+            })
+        }
+
+        /// Writes the certificate and its key to the directory and returns their paths.
+        fn issue(
+            &self,
+            directory: &TempDir,
+            name: &str,
+            extended_key_usage: ExtendedKeyUsagePurpose,
+        ) -> Result<(PathBuf, PathBuf)> {
+            let mut params = CertificateParams::new(vec![name.to_string()])?;
+
+            params.extended_key_usages = vec![extended_key_usage];
+
+            let key_pair = KeyPair::generate()?;
+            let certificate = params.signed_by(&key_pair, &self.certificate, &self.key_pair)?;
+            let cert_file = directory.path().join(format!("{name}.crt"));
+            let key_file = directory.path().join(format!("{name}.key"));
+
+            fs::write(&cert_file, certificate.pem())?;
+            fs::write(&key_file, key_pair.serialize_pem())?;
+
+            Ok((cert_file, key_file))
+        }
+
+        fn write(&self, directory: &TempDir, name: &str) -> Result<PathBuf> {
+            let ca_file = directory.path().join(format!("{name}.crt"));
+
+            fs::write(&ca_file, self.certificate.pem())?;
+
+            Ok(ca_file)
+        }
+    }
+
+    /// Returns the client certificate the server saw, if the handshake succeeded.
+    async fn handshake(
+        server_config: ServerConfig,
+        client_config: rustls::ClientConfig,
+    ) -> Result<Option<ClientCertificate>> {
+        let (client_stream, server_stream) = duplex(64 * 1024);
+        let (server_result, client_result) = tokio::join!(
+            TlsAcceptor::from(Arc::new(server_config)).accept(server_stream),
+            TlsConnector::from(Arc::new(client_config))
+                .connect(ServerName::try_from("localhost")?, client_stream),
+        );
+        let server_stream = server_result?;
+
+        client_result?;
+
+        Ok(server_stream
+            .get_ref()
+            .1
+            .peer_certificates()
+            .and_then(|certificates| certificates.first())
+            .map(|certificate| ClientCertificate {
+                certificate: certificate.clone(),
+            }))
+    }
+
+    fn make_server_config(
+        directory: &TempDir,
+        certificate_authority: &CertificateAuthority,
+    ) -> Result<ServerConfig> {
+        let (cert_file, key_file) = certificate_authority.issue(
+            directory,
+            "localhost",
+            ExtendedKeyUsagePurpose::ServerAuth,
+        )?;
+        let client_ca_file = certificate_authority.write(directory, "ca")?;
+
+        create_tls_server_config(
+            Some(&client_ca_file),
+            Arc::new(ReloadableTlsCertificate::new(
+                ReloadableTlsCertificate::read_certified_key(&cert_file, &key_file)?,
+            )),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_agent_is_identified_by_its_certificate() -> Result<()> {
+        let directory = TempDir::new()?;
+        let certificate_authority = CertificateAuthority::generate()?;
+        let server_config = make_server_config(&directory, &certificate_authority)?;
+        let (agent_cert_file, agent_key_file) = certificate_authority.issue(
+            &directory,
+            "agent-1",
+            ExtendedKeyUsagePurpose::ClientAuth,
+        )?;
+        let client_config = create_tls_client_config(
+            Some(&certificate_authority.write(&directory, "ca")?),
+            Some(&agent_cert_file),
+            Some(&agent_key_file),
+        )?;
+
+        let client_certificate = handshake(server_config, client_config)
+            .await?
+            .expect("Client certificate is missing");
+
+        assert!(
+            client_certificate
+                .authenticate_agent(Some("agent-1"))
+                .is_ok()
+        );
+        assert!(
+            client_certificate
+                .authenticate_agent(Some("agent-2"))
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_certificate_is_optional() -> Result<()> {
+        let directory = TempDir::new()?;
+        let certificate_authority = CertificateAuthority::generate()?;
+        let server_config = make_server_config(&directory, &certificate_authority)?;
+        let client_config = create_tls_client_config(
+            Some(&certificate_authority.write(&directory, "ca")?),
+            None,
+            None,
+        )?;
+
+        assert!(handshake(server_config, client_config).await?.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_untrusted_client_certificate_is_rejected() -> Result<()> {
+        let directory = TempDir::new()?;
+        let certificate_authority = CertificateAuthority::generate()?;
+        let untrusted_certificate_authority = CertificateAuthority::generate()?;
+        let server_config = make_server_config(&directory, &certificate_authority)?;
+        let (agent_cert_file, agent_key_file) = untrusted_certificate_authority.issue(
+            &directory,
+            "agent-1",
+            ExtendedKeyUsagePurpose::ClientAuth,
+        )?;
+        let client_config = create_tls_client_config(
+            Some(&certificate_authority.write(&directory, "ca")?),
+            Some(&agent_cert_file),
+            Some(&agent_key_file),
+        )?;
+
+        assert!(handshake(server_config, client_config).await.is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use std::fs;
+use std::fs::DirBuilder;
+use std::fs::Permissions;
+use std::io;
+use std::os::unix::fs::DirBuilderExt as _;
+use std::os::unix::fs::FileTypeExt as _;
+use std::os::unix::fs::PermissionsExt as _;
+use std::os::unix::net::UnixListener;
+use std::path::Path;
+
+use nanoid::nanoid;
+
+use crate::balancer::unix_socket_mode::UnixSocketMode;
+
+/// Replaces the socket file left over by a previous run, but never any other kind of file.
+/// With a mode, the socket is bound in a private directory and moved into place only after
+/// its permissions are set, so nobody can connect to it in the meantime.
+pub fn create_unix_listener(
+    path: &Path,
+    unix_socket_mode: Option<UnixSocketMode>,
+) -> io::Result<UnixListener> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path.display()),
+            ));
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+
+    let UnixSocketMode(mode) = match unix_socket_mode {
+        Some(unix_socket_mode) => unix_socket_mode,
+        None => return UnixListener::bind(path),
+    };
+
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a file path", path.display()),
+        )
+    })?;
+    let private_directory = path.with_file_name(format!(".{}", nanoid!(10)));
+
+    DirBuilder::new().mode(0o700).create(&private_directory)?;
+
+    let private_path = private_directory.join(file_name);
+    let unix_listener = UnixListener::bind(&private_path).and_then(|unix_listener| {
+        fs::set_permissions(&private_path, Permissions::from_mode(mode))?;
+        fs::rename(&private_path, path)?;
+
+        Ok(unix_listener)
+    });
+
+    fs::remove_dir_all(&private_directory)?;
+
+    unix_listener
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_socket_mode_is_applied() -> Result<()> {
+        let directory = TempDir::new()?;
+        let path = directory.path().join("paddler.sock");
+
+        create_unix_listener(&path, Some(UnixSocketMode(0o600)))?;
+
+        assert_eq!(fs::metadata(&path)?.permissions().mode() & 0o777, 0o600);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_private_directory_is_removed() -> Result<()> {
+        let directory = TempDir::new()?;
+        let path = directory.path().join("paddler.sock");
+
+        create_unix_listener(&path, Some(UnixSocketMode(0o600)))?;
+
+        assert_eq!(fs::read_dir(directory.path())?.count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stale_socket_is_replaced() -> Result<()> {
+        let directory = TempDir::new()?;
+        let path = directory.path().join("paddler.sock");
+
+        drop(create_unix_listener(&path, None)?);
+
+        assert!(create_unix_listener(&path, None).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_regular_file_is_kept() -> Result<()> {
+        let directory = TempDir::new()?;
+        let path = directory.path().join("paddler.sock");
+
+        fs::write(&path, "data")?;
+
+        assert!(create_unix_listener(&path, None).is_err());
+        assert_eq!(fs::read_to_string(&path)?, "data");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,304 @@
+use std::str::FromStr;
+
+use anyhow::Error;
+use anyhow::Result;
+use anyhow::anyhow;
+use serde::Deserialize;
+use serde::Serialize;
+use time::Date;
+use time::Duration;
+use time::OffsetDateTime;
+use time::Time;
+
+/// How far ahead (or back) to look for the next (or last) occurrence; covers schedules
+/// that run only on leap days
+const MAX_LOOKAHEAD_YEARS: i32 = 5;
+
+fn parse_value(value: &str, field_name: &str, min: u8, max: u8) -> Result<u8> {
+    let parsed: u8 = value
+        .parse()
+        .map_err(|_| anyhow!("Invalid {field_name} value: '{value}'"))?;
+
+    if parsed < min || parsed > max {
+        return Err(anyhow!(
+            "{field_name} value {parsed} is out of range ({min}-{max})"
+        ));
+    }
+
+    Ok(parsed)
+}
+
+/// Supports `*`, single values, ranges (`1-5`), lists (`1,15`) and steps (`*/15`, `8-18/2`).
+fn parse_field(field: &str, field_name: &str, min: u8, max: u8) -> Result<u64> {
+    let mut mask = 0u64;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(parse_value(step, field_name, 1, max)?)),
+            None => (part, None),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else {
+            match range.split_once('-') {
+                Some((start, end)) => (
+                    parse_value(start, field_name, min, max)?,
+                    parse_value(end, field_name, min, max)?,
+                ),
+                None => {
+                    let value = parse_value(range, field_name, min, max)?;
+
+                    match step {
+                        Some(_) => (value, max),
+                        None => (value, value),
+                    }
+                }
+            }
+        };
+
+        if start > end {
+            return Err(anyhow!("Invalid {field_name} range: '{range}'"));
+        }
+
+        for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            mask |= 1 << value;
+        }
+    }
+
+    Ok(mask)
+}
+
+fn start_of_day(date: Date) -> OffsetDateTime {
+    date.with_time(Time::MIDNIGHT).assume_utc()
+}
+
+/// Standard five-field cron expression (`minute hour day-of-month month day-of-week`),
+/// evaluated in UTC.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct CronSchedule {
+    days_of_month: u64,
+    days_of_week: u64,
+    expression: String,
+    hours: u64,
+    is_day_of_month_restricted: bool,
+    is_day_of_week_restricted: bool,
+    minutes: u64,
+    months: u64,
+}
+
+impl CronSchedule {
+    /// Last minute at or before the given moment that matches the schedule.
+    pub fn last_at_or_before(&self, at: OffsetDateTime) -> Option<OffsetDateTime> {
+        let at = at.to_offset(time::UtcOffset::UTC);
+        let mut candidate = at.replace_time(Time::from_hms(at.hour(), at.minute(), 0).ok()?);
+        let lookback_limit = at.year() - MAX_LOOKAHEAD_YEARS;
+
+        while candidate.year() >= lookback_limit {
+            if self.months & (1 << candidate.month() as u8) == 0 {
+                candidate =
+                    start_of_day(candidate.date().replace_day(1).ok()?) - Duration::minutes(1);
+            } else if !self.matches_day(candidate.date()) {
+                candidate = start_of_day(candidate.date()) - Duration::minutes(1);
+            } else if self.hours & (1 << candidate.hour()) == 0 {
+                candidate = candidate.replace_minute(0).ok()? - Duration::minutes(1);
+            } else if self.minutes & (1 << candidate.minute()) == 0 {
+                candidate -= Duration::minutes(1);
+            } else {
+                return Some(candidate);
+            }
+        }
+
+        None
+    }
+
+    pub fn matches(&self, date_time: OffsetDateTime) -> bool {
+        let date_time = date_time.to_offset(time::UtcOffset::UTC);
+
+        self.minutes & (1 << date_time.minute()) != 0
+            && self.hours & (1 << date_time.hour()) != 0
+            && self.months & (1 << date_time.month() as u8) != 0
+            && self.matches_day(date_time.date())
+    }
+
+    /// First minute strictly after the given moment that matches the schedule.
+    pub fn next_after(&self, after: OffsetDateTime) -> Option<OffsetDateTime> {
+        let after = after.to_offset(time::UtcOffset::UTC);
+        let mut candidate = after
+            .replace_time(Time::from_hms(after.hour(), after.minute(), 0).ok()?)
+            + Duration::minutes(1);
+        let lookahead_limit = after.year() + MAX_LOOKAHEAD_YEARS;
+
+        while candidate.year() <= lookahead_limit {
+            if self.months & (1 << candidate.month() as u8) == 0 {
+                let next_month = candidate.month().next();
+                let year = if next_month == time::Month::January {
+                    candidate.year() + 1
+                } else {
+                    candidate.year()
+                };
+
+                candidate = start_of_day(Date::from_calendar_date(year, next_month, 1).ok()?);
+            } else if !self.matches_day(candidate.date()) {
+                candidate = start_of_day(candidate.date().next_day()?);
+            } else if self.hours & (1 << candidate.hour()) == 0 {
+                candidate = candidate.replace_minute(0).ok()? + Duration::hours(1);
+            } else if self.minutes & (1 << candidate.minute()) == 0 {
+                candidate += Duration::minutes(1);
+            } else {
+                return Some(candidate);
+            }
+        }
+
+        None
+    }
+
+    /// Like in the classic cron, if both the day of the month and the day of the week
+    /// are restricted, matching either of them is enough.
+    fn matches_day(&self, date: Date) -> bool {
+        let matches_day_of_month = self.days_of_month & (1 << date.day()) != 0;
+        let matches_day_of_week =
+            self.days_of_week & (1 << date.weekday().number_days_from_sunday()) != 0;
+
+        if self.is_day_of_month_restricted && self.is_day_of_week_restricted {
+            matches_day_of_month || matches_day_of_week
+        } else {
+            matches_day_of_month && matches_day_of_week
+        }
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = input.split_whitespace().collect();
+
+        if fields.len() != 5 {
+            return Err(anyhow!(
+                "Invalid cron expression '{input}'. Expected 'minute hour day-of-month month day-of-week'"
+            ));
+        }
+
+        let mut days_of_week = parse_field(fields[4], "day of week", 0, 7)?;
+
+        // Both 0 and 7 mean Sunday
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week & !(1 << 7)) | 1;
+        }
+
+        Ok(CronSchedule {
+            days_of_month: parse_field(fields[2], "day of month", 1, 31)?,
+            days_of_week,
+            expression: fields.join(" "),
+            hours: parse_field(fields[1], "hour", 0, 23)?,
+            is_day_of_month_restricted: fields[2] != "*",
+            is_day_of_week_restricted: fields[4] != "*",
+            minutes: parse_field(fields[0], "minute", 0, 59)?,
+            months: parse_field(fields[3], "month", 1, 12)?,
+        })
+    }
+}
+
+impl TryFrom<String> for CronSchedule {
+    type Error = Error;
+
+    fn try_from(expression: String) -> Result<Self, Self::Error> {
+        expression.parse()
+    }
+}
+
+impl From<CronSchedule> for String {
+    fn from(cron_schedule: CronSchedule) -> Self {
+        cron_schedule.expression
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_next_weekday_business_hours() -> Result<()> {
+        let schedule: CronSchedule = "0 9 * * 1-5".parse()?;
+
+        // 2025-01-03 is a Friday
+        assert_eq!(
+            schedule.next_after(datetime!(2025-01-03 09:00 UTC)),
+            Some(datetime!(2025-01-06 09:00 UTC))
+        );
+        assert_eq!(
+            schedule.next_after(datetime!(2025-01-06 08:59:30 UTC)),
+            Some(datetime!(2025-01-06 09:00 UTC))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_steps_and_lists() -> Result<()> {
+        let schedule: CronSchedule = "*/15 8,20 * * *".parse()?;
+
+        assert!(schedule.matches(datetime!(2025-03-10 08:45 UTC)));
+        assert!(!schedule.matches(datetime!(2025-03-10 08:50 UTC)));
+        assert_eq!(
+            schedule.next_after(datetime!(2025-03-10 08:45 UTC)),
+            Some(datetime!(2025-03-10 20:00 UTC))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_last_at_or_before() -> Result<()> {
+        let schedule: CronSchedule = "0 9 * * 1-5".parse()?;
+
+        // 2025-01-06 is a Monday
+        assert_eq!(
+            schedule.last_at_or_before(datetime!(2025-01-06 08:59 UTC)),
+            Some(datetime!(2025-01-03 09:00 UTC))
+        );
+        assert_eq!(
+            schedule.last_at_or_before(datetime!(2025-01-06 09:00:30 UTC)),
+            Some(datetime!(2025-01-06 09:00 UTC))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_leap_day() -> Result<()> {
+        let schedule: CronSchedule = "30 0 29 2 *".parse()?;
+
+        assert_eq!(
+            schedule.next_after(datetime!(2025-01-01 00:00 UTC)),
+            Some(datetime!(2028-02-29 00:30 UTC))
+        );
+        assert_eq!(
+            schedule.last_at_or_before(datetime!(2025-01-01 00:00 UTC)),
+            Some(datetime!(2024-02-29 00:30 UTC))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sunday_as_seven() -> Result<()> {
+        let schedule: CronSchedule = "0 0 * * 7".parse()?;
+
+        // 2025-01-05 is a Sunday
+        assert!(schedule.matches(datetime!(2025-01-05 00:00 UTC)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_expressions_fail() {
+        assert!("0 9 * *".parse::<CronSchedule>().is_err());
+        assert!("60 9 * * *".parse::<CronSchedule>().is_err());
+        assert!("0 9 * * 1-".parse::<CronSchedule>().is_err());
+        assert!("*/0 * * * *".parse::<CronSchedule>().is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::balancer::balancer_desired_state_change::BalancerDesiredStateChange;
+use crate::balancer_desired_state::BalancerDesiredState;
+
+fn join_field(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{parent}.{key}")
+    }
+}
+
+fn collect_changes(
+    field: &str,
+    current: Option<&Value>,
+    proposed: Option<&Value>,
+    changes: &mut Vec<BalancerDesiredStateChange>,
+) {
+    match (current, proposed) {
+        (Some(Value::Object(current_object)), Some(Value::Object(proposed_object))) => {
+            let mut keys: Vec<&String> = current_object
+                .keys()
+                .chain(proposed_object.keys())
+                .collect();
+
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                collect_changes(
+                    &join_field(field, key),
+                    current_object.get(key),
+                    proposed_object.get(key),
+                    changes,
+                );
+            }
+        }
+        (current, proposed) if current != proposed => {
+            changes.push(BalancerDesiredStateChange {
+                current: current.cloned(),
+                field: field.to_string(),
+                proposed: proposed.cloned(),
+            });
+        }
+        _ => {}
+    }
+}
+
+/// Lists the changed leaf fields, so a model change does not show up as a change
+/// of the whole state.
+pub fn diff_balancer_desired_states(
+    current: &BalancerDesiredState,
+    proposed: &BalancerDesiredState,
+) -> Result<Vec<BalancerDesiredStateChange>> {
+    let mut changes = Vec::new();
+
+    collect_changes(
+        "",
+        Some(&serde_json::to_value(current)?),
+        Some(&serde_json::to_value(proposed)?),
+        &mut changes,
+    );
+
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::agent_desired_model::AgentDesiredModel;
+    use crate::huggingface_model_reference::HuggingFaceModelReference;
+
+    #[test]
+    fn test_lists_changed_fields() -> Result<()> {
+        let current = BalancerDesiredState {
+            model: AgentDesiredModel::LocalToAgent("model.gguf".to_string()),
+            ..BalancerDesiredState::default()
+        };
+        let mut proposed = BalancerDesiredState {
+            model: AgentDesiredModel::HuggingFace(HuggingFaceModelReference {
+                filename: "model.gguf".to_string(),
+                repo_id: "org/repo".to_string(),
+                revision: "main".to_string(),
+            }),
+            ..BalancerDesiredState::default()
+        };
+
+        proposed.inference_parameters.top_k = 20;
+
+        let changes = diff_balancer_desired_states(&current, &proposed)?;
+        let fields: Vec<&str> = changes.iter().map(|change| change.field.as_str()).collect();
+
+        assert_eq!(
+            fields,
+            vec![
+                "inference_parameters.top_k",
+                "model.HuggingFace",
+                "model.LocalToAgent",
+            ]
+        );
+        assert_eq!(changes[0].current, Some(json!(40)));
+        assert_eq!(changes[0].proposed, Some(json!(20)));
+        assert_eq!(changes[2].proposed, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_identical_states_have_no_changes() -> Result<()> {
+        let state = BalancerDesiredState::default();
+
+        assert!(diff_balancer_desired_states(&state, &state)?.is_empty());
+
+        Ok(())
+    }
+}
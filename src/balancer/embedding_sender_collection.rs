@@ -1,12 +1,12 @@
 use async_trait::async_trait;
 use dashmap::DashMap;
-use tokio::sync::mpsc;
 
 use crate::balancer::manages_senders::ManagesSenders;
+use crate::balancer::response_sender::ResponseSender;
 use crate::embedding_result::EmbeddingResult;
 
 pub struct EmbeddingSenderCollection {
-    senders: DashMap<String, mpsc::UnboundedSender<EmbeddingResult>>,
+    senders: DashMap<String, ResponseSender<EmbeddingResult>>,
 }
 
 impl Default for EmbeddingSenderCollection {
@@ -21,7 +21,7 @@ impl Default for EmbeddingSenderCollection {
 impl ManagesSenders for EmbeddingSenderCollection {
     type Value = EmbeddingResult;
 
-    fn get_sender_collection(&self) -> &DashMap<String, mpsc::UnboundedSender<Self::Value>> {
+    fn get_sender_collection(&self) -> &DashMap<String, ResponseSender<Self::Value>> {
         &self.senders
     }
 }
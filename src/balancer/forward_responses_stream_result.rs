@@ -0,0 +1,9 @@
+pub enum ForwardResponsesStreamResult {
+    /// Agent went away before anything was sent to the client, so the request can be
+    /// safely handed to a different agent.
+    AgentDisconnectedBeforeFirstChunk,
+    /// Agent answered with an error before anything was sent to the client, and there are
+    /// attempts left to hand the request to a different agent.
+    AgentFailedBeforeFirstChunk,
+    Finished,
+}
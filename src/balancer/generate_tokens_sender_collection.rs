@@ -1,12 +1,12 @@
 use async_trait::async_trait;
 use dashmap::DashMap;
-use tokio::sync::mpsc;
 
 use crate::balancer::manages_senders::ManagesSenders;
+use crate::balancer::response_sender::ResponseSender;
 use crate::generated_token_result::GeneratedTokenResult;
 
 pub struct GenerateTokensSenderCollection {
-    senders: DashMap<String, mpsc::UnboundedSender<GeneratedTokenResult>>,
+    senders: DashMap<String, ResponseSender<GeneratedTokenResult>>,
 }
 
 impl Default for GenerateTokensSenderCollection {
@@ -21,7 +21,7 @@ impl Default for GenerateTokensSenderCollection {
 impl ManagesSenders for GenerateTokensSenderCollection {
     type Value = GeneratedTokenResult;
 
-    fn get_sender_collection(&self) -> &DashMap<String, mpsc::UnboundedSender<Self::Value>> {
+    fn get_sender_collection(&self) -> &DashMap<String, ResponseSender<Self::Value>> {
         &self.senders
     }
 }
@@ -0,0 +1,21 @@
+use sha2::Digest as _;
+use sha2::Sha256;
+
+/// Only the hashes of the API keys and management tokens are stored, so a leaked state
+/// database does not expose the secrets themselves.
+pub fn hash_api_key(api_key: &str) -> String {
+    format!("{:x}", Sha256::digest(api_key.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_is_hex_encoded_sha256() {
+        assert_eq!(
+            hash_api_key("secret"),
+            "2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b"
+        );
+    }
+}
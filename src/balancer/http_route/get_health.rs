@@ -1,14 +1,26 @@
 use std::error::Error;
 
+use actix_web::HttpResponse;
 use actix_web::Responder;
 use actix_web::get;
+use actix_web::web::Data;
 use actix_web::web::ServiceConfig;
 
+use crate::drain_status::DrainStatus;
+
 pub fn register(cfg: &mut ServiceConfig) {
     cfg.service(respond);
 }
 
+/// Services that are drained before shutdown register `DrainStatus`, so load balancers
+/// in front of them can stop routing new traffic.
 #[get("/health")]
-async fn respond() -> Result<impl Responder, Box<dyn Error>> {
-    Ok("OK")
+async fn respond(
+    drain_status: Option<Data<DrainStatus>>,
+) -> Result<impl Responder, Box<dyn Error>> {
+    if drain_status.is_some_and(|drain_status| drain_status.is_draining()) {
+        return Ok(HttpResponse::ServiceUnavailable().body("Shutting down"));
+    }
+
+    Ok(HttpResponse::Ok().body("OK"))
 }
@@ -9,31 +9,37 @@ use futures::stream::StreamExt;
 
 use crate::agent::jsonrpc::Request as AgentJsonRpcRequest;
 use crate::balancer::agent_controller::AgentController;
+use crate::balancer::api_key_usage_guard::ApiKeyUsageGuard;
 use crate::balancer::buffered_request_manager::BufferedRequestManager;
+use crate::balancer::buffered_request_priority_class::BufferedRequestPriorityClass;
 use crate::balancer::chunk_forwarding_session_controller::transforms_outgoing_message::TransformsOutgoingMessage;
 use crate::balancer::handles_agent_streaming_response::HandlesAgentStreamingResponse;
 use crate::balancer::inference_client::Response as OutgoingResponse;
 use crate::balancer::inference_service::configuration::Configuration as InferenceServiceConfiguration;
 use crate::balancer::manages_senders::ManagesSenders;
-use crate::balancer::unbounded_stream_from_agent::unbounded_stream_from_agent;
+use crate::balancer::stream_from_agent::stream_from_agent;
 use crate::streamable_result::StreamableResult;
 
 pub fn http_stream_from_agent<TParams, TTransformsOutgoingMessage>(
+    api_key_usage_guard: Option<Arc<ApiKeyUsageGuard>>,
     buffered_request_manager: Arc<BufferedRequestManager>,
     inference_service_configuration: InferenceServiceConfiguration,
     params: TParams,
+    priority_class: Arc<BufferedRequestPriorityClass>,
     transformer: TTransformsOutgoingMessage,
 ) -> Result<HttpResponse, Error>
 where
-    TParams: Debug + Into<AgentJsonRpcRequest> + Send + 'static,
+    TParams: Clone + Debug + Into<AgentJsonRpcRequest> + Send + 'static,
     AgentController: HandlesAgentStreamingResponse<TParams>,
     <<AgentController as HandlesAgentStreamingResponse<TParams>>::SenderCollection as ManagesSenders>::Value: Debug + Into<OutgoingResponse> + StreamableResult,
     TTransformsOutgoingMessage: Clone + TransformsOutgoingMessage + Send + Sync + 'static,
 {
-    let stream = unbounded_stream_from_agent(
+    let stream = stream_from_agent(
+        api_key_usage_guard,
         buffered_request_manager,
         inference_service_configuration,
         params,
+        priority_class,
         transformer,
     )?
     .map(|chunk: String| Ok::<_, Error>(Bytes::from(format!("{chunk}\n"))));
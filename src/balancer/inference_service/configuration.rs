@@ -1,9 +1,10 @@
-use std::net::SocketAddr;
 use std::time::Duration;
 
+use crate::server_addr::ServerAddr;
+
 #[derive(Clone)]
 pub struct Configuration {
-    pub addr: SocketAddr,
-    pub cors_allowed_hosts: Vec<String>,
+    pub addr: ServerAddr,
     pub inference_item_timeout: Duration,
+    pub max_request_attempts: usize,
 }
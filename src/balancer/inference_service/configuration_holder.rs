@@ -0,0 +1,33 @@
+use std::sync::RwLock;
+
+use crate::balancer::inference_service::configuration::Configuration;
+
+/// Requests read the configuration when they start, so a reload affects only the requests
+/// that arrive after it.
+pub struct ConfigurationHolder {
+    configuration: RwLock<Configuration>,
+}
+
+impl ConfigurationHolder {
+    pub fn new(configuration: Configuration) -> Self {
+        Self {
+            configuration: RwLock::new(configuration),
+        }
+    }
+
+    pub fn get_configuration(&self) -> Configuration {
+        self.configuration
+            .read()
+            .expect("Failed to get inference service configuration lock")
+            .clone()
+    }
+
+    pub fn set_configuration(&self, configuration: Configuration) {
+        let mut lock = self
+            .configuration
+            .write()
+            .expect("Failed to get inference service configuration lock");
+
+        *lock = configuration;
+    }
+}
@@ -2,6 +2,7 @@ use actix_web::post;
 use actix_web::error::ErrorBadRequest;
 use actix_web::web;
 use actix_web::Error;
+use actix_web::HttpRequest;
 use actix_web::Responder;
 
 use crate::validates::Validates as _;
@@ -10,6 +11,9 @@ use crate::request_params::ContinueFromConversationHistoryParams;
 use crate::request_params::continue_from_conversation_history_params::tool::tool_params::function_call::parameters_schema::raw_parameters_schema::RawParametersSchema;
 use crate::balancer::http_stream_from_agent::http_stream_from_agent;
 use crate::balancer::chunk_forwarding_session_controller::identity_transformer::IdentityTransformer;
+use crate::balancer::authorization_bearer_token::authorization_bearer_token;
+use crate::balancer::authorize_api_key::authorize_api_key;
+use crate::balancer::buffered_request_params::BufferedRequestParams;
 
 pub fn register(cfg: &mut web::ServiceConfig) {
     cfg.service(respond);
@@ -18,19 +22,40 @@ pub fn register(cfg: &mut web::ServiceConfig) {
 #[post("/api/v1/continue_from_conversation_history")]
 async fn respond(
     app_data: web::Data<AppData>,
-    params: web::Json<ContinueFromConversationHistoryParams<RawParametersSchema>>,
+    params: web::Json<
+        BufferedRequestParams<ContinueFromConversationHistoryParams<RawParametersSchema>>,
+    >,
+    req: HttpRequest,
 ) -> Result<impl Responder, Error> {
+    let api_key_usage_guard = authorize_api_key(&app_data.api_key_manager, &req)?;
+    let BufferedRequestParams {
+        params,
+        priority_class: requested_priority_class,
+    } = params.into_inner();
+    let validated_params = match params.validate() {
+        Ok(validated_params) => validated_params,
+        Err(validation_error) => {
+            return Err(ErrorBadRequest(format!(
+                "Invalid request parameters: {validation_error}"
+            )));
+        }
+    };
+    let priority_class = app_data
+        .buffered_request_manager
+        .select_priority_class(
+            requested_priority_class.as_deref(),
+            authorization_bearer_token(&req).as_deref(),
+        )
+        .map_err(ErrorBadRequest)?;
+
     http_stream_from_agent(
+        api_key_usage_guard,
         app_data.buffered_request_manager.clone(),
-        app_data.inference_service_configuration.clone(),
-        match params.into_inner().validate() {
-            Ok(validated_params) => validated_params,
-            Err(validation_error) => {
-                return Err(ErrorBadRequest(format!(
-                    "Invalid request parameters: {validation_error}"
-                )));
-            }
-        },
+        app_data
+            .inference_service_configuration_holder
+            .get_configuration(),
+        validated_params,
+        priority_class,
         IdentityTransformer::new(),
     )
 }
@@ -1,8 +1,13 @@
 use actix_web::Error;
+use actix_web::HttpRequest;
 use actix_web::Responder;
+use actix_web::error::ErrorBadRequest;
 use actix_web::post;
 use actix_web::web;
 
+use crate::balancer::authorization_bearer_token::authorization_bearer_token;
+use crate::balancer::authorize_api_key::authorize_api_key;
+use crate::balancer::buffered_request_params::BufferedRequestParams;
 use crate::balancer::chunk_forwarding_session_controller::identity_transformer::IdentityTransformer;
 use crate::balancer::http_stream_from_agent::http_stream_from_agent;
 use crate::balancer::inference_service::app_data::AppData;
@@ -15,12 +20,121 @@ pub fn register(cfg: &mut web::ServiceConfig) {
 #[post("/api/v1/continue_from_raw_prompt")]
 async fn respond(
     app_data: web::Data<AppData>,
-    params: web::Json<ContinueFromRawPromptParams>,
+    params: web::Json<BufferedRequestParams<ContinueFromRawPromptParams>>,
+    req: HttpRequest,
 ) -> Result<impl Responder, Error> {
+    let api_key_usage_guard = authorize_api_key(&app_data.api_key_manager, &req)?;
+    let BufferedRequestParams {
+        params,
+        priority_class: requested_priority_class,
+    } = params.into_inner();
+    let priority_class = app_data
+        .buffered_request_manager
+        .select_priority_class(
+            requested_priority_class.as_deref(),
+            authorization_bearer_token(&req).as_deref(),
+        )
+        .map_err(ErrorBadRequest)?;
+
     http_stream_from_agent(
+        api_key_usage_guard,
         app_data.buffered_request_manager.clone(),
-        app_data.inference_service_configuration.clone(),
-        params.into_inner(),
+        app_data
+            .inference_service_configuration_holder
+            .get_configuration(),
+        params,
+        priority_class,
         IdentityTransformer::new(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use actix_web::App;
+    use actix_web::http::StatusCode;
+    use actix_web::http::header;
+    use actix_web::test;
+    use anyhow::Result;
+    use serde_json::json;
+
+    use super::*;
+    use crate::balancer::api_key::ApiKey;
+    use crate::balancer::api_key_limits::ApiKeyLimits;
+    use crate::balancer::api_key_manager::ApiKeyManager;
+    use crate::balancer::hash_api_key::hash_api_key;
+    use crate::balancer::inference_service::configuration::Configuration;
+    use crate::balancer::inference_service::configuration_holder::ConfigurationHolder;
+    use crate::balancer::management_service::make_test_app_data::make_test_app_data;
+
+    #[actix_web::test]
+    async fn test_api_key_is_checked_before_the_request_is_buffered() -> Result<()> {
+        let (management_app_data, _balancer_desired_state_rx) = make_test_app_data().await?;
+        let api_key_manager = Arc::new(ApiKeyManager::new(true));
+
+        api_key_manager.set_api_keys(BTreeMap::from([(
+            "frontend".to_string(),
+            ApiKey {
+                key_hash: hash_api_key("secret"),
+                limits: ApiKeyLimits {
+                    max_requests_per_minute: Some(0),
+                    ..ApiKeyLimits::default()
+                },
+            },
+        )]));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppData {
+                    api_key_manager,
+                    balancer_applicable_state_holder: management_app_data
+                        .balancer_applicable_state_holder
+                        .clone(),
+                    buffered_request_manager: management_app_data.buffered_request_manager.clone(),
+                    inference_service_configuration_holder: Arc::new(ConfigurationHolder::new(
+                        Configuration {
+                            addr: "127.0.0.1:0".parse()?,
+                            inference_item_timeout: Duration::from_secs(1),
+                            max_request_attempts: 1,
+                        },
+                    )),
+                }))
+                .configure(register),
+        )
+        .await;
+        let params = json!({
+            "max_tokens": 10,
+            "raw_prompt": "Hello",
+        });
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/v1/continue_from_raw_prompt")
+                .set_json(&params)
+                .to_request(),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(response.headers().contains_key(header::WWW_AUTHENTICATE));
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/v1/continue_from_raw_prompt")
+                .insert_header((header::AUTHORIZATION, "Bearer secret"))
+                .set_json(&params)
+                .to_request(),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key(header::RETRY_AFTER));
+
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 use actix_web::Error;
+use actix_web::HttpRequest;
 use actix_web::HttpResponse;
 use actix_web::Responder;
+use actix_web::error::ErrorBadRequest;
 use actix_web::error::ErrorNotImplemented;
 use actix_web::error::ErrorServiceUnavailable;
 use actix_web::http::header;
@@ -13,8 +15,12 @@ use log::error;
 use nanoid::nanoid;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc;
-use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::wrappers::ReceiverStream;
 
+use crate::balancer::authorization_bearer_token::authorization_bearer_token;
+use crate::balancer::authorize_api_key::authorize_api_key;
+use crate::balancer::buffered_request_params::BufferedRequestParams;
+use crate::balancer::chunk_forwarding_session_controller::CHUNK_BUFFER_CAPACITY;
 use crate::balancer::chunk_forwarding_session_controller::ChunkForwardingSessionController;
 use crate::balancer::chunk_forwarding_session_controller::identity_transformer::IdentityTransformer;
 use crate::balancer::inference_client::Message as OutgoingMessage;
@@ -34,8 +40,10 @@ pub fn register(cfg: &mut web::ServiceConfig) {
 #[post("/api/v1/generate_embedding_batch")]
 async fn respond(
     app_data: web::Data<AppData>,
-    params: web::Json<GenerateEmbeddingBatchParams>,
+    params: web::Json<BufferedRequestParams<GenerateEmbeddingBatchParams>>,
+    req: HttpRequest,
 ) -> Result<impl Responder, Error> {
+    let api_key_usage_guard = authorize_api_key(&app_data.api_key_manager, &req)?;
     let balancer_applicable_state_holder = app_data.balancer_applicable_state_holder.clone();
     let agent_desired_state = match balancer_applicable_state_holder.get_agent_desired_state() {
         Some(agent_desired_state) => agent_desired_state,
@@ -52,19 +60,34 @@ async fn respond(
         ));
     }
 
+    let BufferedRequestParams {
+        params,
+        priority_class: requested_priority_class,
+    } = params.into_inner();
+    let priority_class = app_data
+        .buffered_request_manager
+        .select_priority_class(
+            requested_priority_class.as_deref(),
+            authorization_bearer_token(&req).as_deref(),
+        )
+        .map_err(ErrorBadRequest)?;
+
     let (connection_close_tx, _connection_close_rx) = broadcast::channel::<()>(1);
-    let (chunk_tx, chunk_rx) = mpsc::unbounded_channel();
+    let (chunk_tx, chunk_rx) = mpsc::channel(CHUNK_BUFFER_CAPACITY);
 
     // Distribute the embeddings evenly across the available agents
     for batch in params.chunk_by_input_size(
         agent_desired_state.inference_parameters.batch_n_tokens
             * CHARACTERS_PER_TOKEN_APPROXIMATELY,
     ) {
+        let api_key_usage_guard_clone = api_key_usage_guard.clone();
         let buffered_request_manager_clone = app_data.buffered_request_manager.clone();
         let chunk_tx_clone = chunk_tx.clone();
         let connection_close_tx_clone = connection_close_tx.clone();
-        let inference_service_configuration_clone =
-            app_data.inference_service_configuration.clone();
+        let inference_service_configuration_clone = app_data
+            .inference_service_configuration_holder
+            .get_configuration();
+        let priority_class_clone = priority_class.clone();
 
         rt::spawn(async move {
             let request_id: String = nanoid!();
@@ -72,10 +95,12 @@ async fn respond(
                 ChunkForwardingSessionController::new(chunk_tx_clone, IdentityTransformer::new());
 
             if let Err(err) = request_from_agent(
+                api_key_usage_guard_clone,
                 buffered_request_manager_clone,
                 connection_close_tx_clone,
                 inference_service_configuration_clone,
                 batch,
+                priority_class_clone,
                 request_id.clone(),
                 session_controller.clone(),
             )
@@ -95,7 +120,7 @@ async fn respond(
         });
     }
 
-    let stream = UnboundedReceiverStream::new(chunk_rx)
+    let stream = ReceiverStream::new(chunk_rx)
         .map(|chunk: String| Ok::<_, Error>(Bytes::from(format!("{chunk}\n"))));
 
     Ok(HttpResponse::Ok()
@@ -1,9 +1,12 @@
 use std::sync::Arc;
 
+use crate::balancer::api_key_manager::ApiKeyManager;
 use crate::balancer::buffered_request_manager::BufferedRequestManager;
 use crate::balancer::inference_service::configuration::Configuration as InferenceServiceConfiguration;
 
 pub struct InferenceSocketControllerContext {
+    pub api_key: Option<String>,
+    pub api_key_manager: Arc<ApiKeyManager>,
     pub buffered_request_manager: Arc<BufferedRequestManager>,
     pub inference_service_configuration: InferenceServiceConfiguration,
 }
@@ -1,6 +1,7 @@
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::balancer::buffered_request_params::BufferedRequestParams;
 use crate::request_params::ContinueFromConversationHistoryParams;
 use crate::request_params::ContinueFromRawPromptParams;
 use crate::request_params::continue_from_conversation_history_params::tool::tool_params::function_call::parameters_schema::raw_parameters_schema::RawParametersSchema;
@@ -8,6 +9,8 @@ use crate::request_params::continue_from_conversation_history_params::tool::tool
 #[derive(Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub enum Request {
-    ContinueFromConversationHistory(ContinueFromConversationHistoryParams<RawParametersSchema>),
-    ContinueFromRawPrompt(ContinueFromRawPromptParams),
+    ContinueFromConversationHistory(
+        BufferedRequestParams<ContinueFromConversationHistoryParams<RawParametersSchema>>,
+    ),
+    ContinueFromRawPrompt(BufferedRequestParams<ContinueFromRawPromptParams>),
 }
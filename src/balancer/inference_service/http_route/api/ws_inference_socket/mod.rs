@@ -7,6 +7,7 @@ use actix_web::Error;
 use actix_web::HttpRequest;
 use actix_web::HttpResponse;
 use actix_web::get;
+use actix_web::http::header;
 use actix_web::web::Data;
 use actix_web::web::Payload;
 use actix_web::web::ServiceConfig;
@@ -18,11 +19,18 @@ use tokio::sync::broadcast;
 use self::inference_socket_controller_context::InferenceSocketControllerContext;
 use self::jsonrpc::Message as InferenceJsonRpcMessage;
 use self::jsonrpc::Request as InferenceJsonRpcRequest;
+use crate::balancer::api_key_authorization_result::ApiKeyAuthorizationResult;
+use crate::balancer::api_key_manager::ApiKeyManager;
+use crate::balancer::api_key_usage_guard::ApiKeyUsageGuard;
+use crate::balancer::authorization_bearer_token::authorization_bearer_token;
 use crate::balancer::buffered_request_manager::BufferedRequestManager;
+use crate::balancer::buffered_request_params::BufferedRequestParams;
+use crate::balancer::buffered_request_priority_class::BufferedRequestPriorityClass;
 use crate::balancer::inference_client::Message as OutgoingMessage;
 use crate::balancer::inference_service::app_data::AppData;
 use crate::balancer::inference_service::configuration::Configuration as InferenceServiceConfiguration;
 use crate::balancer::request_from_agent::request_from_agent;
+use crate::controls_session::ControlsSession as _;
 use crate::controls_websocket_endpoint::ContinuationDecision;
 use crate::controls_websocket_endpoint::ControlsWebSocketEndpoint;
 use crate::jsonrpc::Error as JsonRpcError;
@@ -36,6 +44,8 @@ pub fn register(cfg: &mut ServiceConfig) {
 }
 
 struct InferenceSocketController {
+    api_key: Option<String>,
+    api_key_manager: Arc<ApiKeyManager>,
     buffered_request_manager: Arc<BufferedRequestManager>,
     inference_service_configuration: InferenceServiceConfiguration,
 }
@@ -48,6 +58,8 @@ impl ControlsWebSocketEndpoint for InferenceSocketController {
 
     fn create_context(&self) -> Self::Context {
         InferenceSocketControllerContext {
+            api_key: self.api_key.clone(),
+            api_key_manager: self.api_key_manager.clone(),
             buffered_request_manager: self.buffered_request_manager.clone(),
             inference_service_configuration: self.inference_service_configuration.clone(),
         }
@@ -57,7 +69,7 @@ impl ControlsWebSocketEndpoint for InferenceSocketController {
         connection_close_tx: broadcast::Sender<()>,
         context: Arc<Self::Context>,
         deserialized_message: Self::IncomingMessage,
-        websocket_session_controller: WebSocketSessionController<Self::OutgoingMessage>,
+        mut websocket_session_controller: WebSocketSessionController<Self::OutgoingMessage>,
     ) -> Result<ContinuationDecision> {
         match deserialized_message {
             InferenceJsonRpcMessage::Error(ErrorEnvelope {
@@ -72,13 +84,44 @@ impl ControlsWebSocketEndpoint for InferenceSocketController {
             }
             InferenceJsonRpcMessage::Request(RequestEnvelope {
                 id,
-                request: InferenceJsonRpcRequest::ContinueFromConversationHistory(params),
+                request:
+                    InferenceJsonRpcRequest::ContinueFromConversationHistory(BufferedRequestParams {
+                        params,
+                        priority_class: requested_priority_class,
+                    }),
             }) => {
+                let params = params.validate()?;
+
+                let api_key_usage_guard = match authorize_api_key(
+                    &context,
+                    id.clone(),
+                    &mut websocket_session_controller,
+                )
+                .await
+                {
+                    Some(api_key_usage_guard) => api_key_usage_guard,
+                    None => return Ok(ContinuationDecision::Continue),
+                };
+
+                let priority_class = match select_priority_class(
+                    &context,
+                    requested_priority_class.as_deref(),
+                    id.clone(),
+                    &mut websocket_session_controller,
+                )
+                .await
+                {
+                    Some(priority_class) => priority_class,
+                    None => return Ok(ContinuationDecision::Continue),
+                };
+
                 request_from_agent(
+                    api_key_usage_guard,
                     context.buffered_request_manager.clone(),
                     connection_close_tx,
                     context.inference_service_configuration.clone(),
-                    params.validate()?,
+                    params,
+                    priority_class,
                     id,
                     websocket_session_controller,
                 )
@@ -88,13 +131,42 @@ impl ControlsWebSocketEndpoint for InferenceSocketController {
             }
             InferenceJsonRpcMessage::Request(RequestEnvelope {
                 id,
-                request: InferenceJsonRpcRequest::ContinueFromRawPrompt(params),
+                request:
+                    InferenceJsonRpcRequest::ContinueFromRawPrompt(BufferedRequestParams {
+                        params,
+                        priority_class: requested_priority_class,
+                    }),
             }) => {
+                let api_key_usage_guard = match authorize_api_key(
+                    &context,
+                    id.clone(),
+                    &mut websocket_session_controller,
+                )
+                .await
+                {
+                    Some(api_key_usage_guard) => api_key_usage_guard,
+                    None => return Ok(ContinuationDecision::Continue),
+                };
+
+                let priority_class = match select_priority_class(
+                    &context,
+                    requested_priority_class.as_deref(),
+                    id.clone(),
+                    &mut websocket_session_controller,
+                )
+                .await
+                {
+                    Some(priority_class) => priority_class,
+                    None => return Ok(ContinuationDecision::Continue),
+                };
+
                 request_from_agent(
+                    api_key_usage_guard,
                     context.buffered_request_manager.clone(),
                     connection_close_tx,
                     context.inference_service_configuration.clone(),
                     params,
+                    priority_class,
                     id,
                     websocket_session_controller,
                 )
@@ -106,15 +178,89 @@ impl ControlsWebSocketEndpoint for InferenceSocketController {
     }
 }
 
+/// Returns None if the error was already sent to the client. Every request is checked
+/// separately, so the limits apply to the socket the same way as to the HTTP endpoints.
+async fn authorize_api_key(
+    context: &InferenceSocketControllerContext,
+    request_id: String,
+    websocket_session_controller: &mut WebSocketSessionController<OutgoingMessage>,
+) -> Option<Option<Arc<ApiKeyUsageGuard>>> {
+    let error = match context
+        .api_key_manager
+        .authorize(context.api_key.as_deref())
+    {
+        ApiKeyAuthorizationResult::Anonymous => return Some(None),
+        ApiKeyAuthorizationResult::Authorized(api_key_usage_guard) => {
+            return Some(Some(api_key_usage_guard));
+        }
+        ApiKeyAuthorizationResult::LimitExceeded(limit_exceeded) => JsonRpcError {
+            code: 429,
+            description: limit_exceeded.description,
+        },
+        ApiKeyAuthorizationResult::Unauthorized => JsonRpcError {
+            code: 401,
+            description: "API key is missing or invalid".to_string(),
+        },
+    };
+
+    websocket_session_controller
+        .send_response_safe(OutgoingMessage::Error(ErrorEnvelope { request_id, error }))
+        .await;
+
+    None
+}
+
+async fn select_priority_class(
+    context: &InferenceSocketControllerContext,
+    requested_priority_class: Option<&str>,
+    request_id: String,
+    websocket_session_controller: &mut WebSocketSessionController<OutgoingMessage>,
+) -> Option<Arc<BufferedRequestPriorityClass>> {
+    match context
+        .buffered_request_manager
+        .select_priority_class(requested_priority_class, context.api_key.as_deref())
+    {
+        Ok(priority_class) => Some(priority_class),
+        Err(err) => {
+            websocket_session_controller
+                .send_response_safe(OutgoingMessage::Error(ErrorEnvelope {
+                    request_id,
+                    error: JsonRpcError {
+                        code: 400,
+                        description: err.to_string(),
+                    },
+                }))
+                .await;
+
+            None
+        }
+    }
+}
+
 #[get("/api/v1/inference_socket")]
 async fn respond(
     app_data: Data<AppData>,
     payload: Payload,
     req: HttpRequest,
 ) -> Result<HttpResponse, Error> {
+    let api_key = authorization_bearer_token(&req);
+
+    if !app_data
+        .api_key_manager
+        .is_allowed_to_connect(api_key.as_deref())
+    {
+        return Ok(HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+            .body("API key is missing or invalid"));
+    }
+
     let inference_socket_controller = InferenceSocketController {
+        api_key,
+        api_key_manager: app_data.api_key_manager.clone(),
         buffered_request_manager: app_data.buffered_request_manager.clone(),
-        inference_service_configuration: app_data.inference_service_configuration.clone(),
+        inference_service_configuration: app_data
+            .inference_service_configuration_holder
+            .get_configuration(),
     };
 
     inference_socket_controller.respond(payload, req)
@@ -0,0 +1,7 @@
+use anyhow::Result;
+
+use crate::balancer::reloadable_configuration::ReloadableConfiguration;
+
+pub trait LoadsReloadableConfiguration: Send + Sync {
+    fn load_reloadable_configuration(&self) -> Result<ReloadableConfiguration>;
+}
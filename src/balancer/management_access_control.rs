@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use crate::balancer::hash_api_key::hash_api_key;
+use crate::balancer::management_principal::ManagementPrincipal;
+use crate::balancer::management_role::ManagementRole;
+use crate::balancer::management_token::ManagementToken;
+use crate::balancer::management_token_registry::ManagementTokenRegistry;
+use crate::balancer::tokens_match::tokens_match;
+
+/// Combines the tokens from the tokens file with the ones stored in the state database.
+///
+/// Without any tokens the management API is closed, unless anonymous access is allowed;
+/// then everyone can use it as an admin until the first token is created.
+pub struct ManagementAccessControl {
+    allows_anonymous_access: bool,
+    file_tokens: RwLock<BTreeMap<String, ManagementToken>>,
+    stored_tokens: RwLock<BTreeMap<String, ManagementToken>>,
+}
+
+impl ManagementAccessControl {
+    pub fn new(
+        allows_anonymous_access: bool,
+        management_token_registry: Option<ManagementTokenRegistry>,
+    ) -> Self {
+        Self {
+            allows_anonymous_access,
+            file_tokens: RwLock::new(
+                management_token_registry
+                    .map(|management_token_registry| management_token_registry.tokens_by_name)
+                    .unwrap_or_default(),
+            ),
+            stored_tokens: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns None if the token is missing or not valid.
+    pub fn authenticate(&self, token: Option<&str>) -> Option<ManagementPrincipal> {
+        let file_tokens = self
+            .file_tokens
+            .read()
+            .expect("Failed to get management tokens lock");
+        let stored_tokens = self
+            .stored_tokens
+            .read()
+            .expect("Failed to get management tokens lock");
+
+        if self.allows_anonymous_access && file_tokens.is_empty() && stored_tokens.is_empty() {
+            return Some(ManagementPrincipal {
+                name: "anonymous".to_string(),
+                role: ManagementRole::Admin,
+            });
+        }
+
+        let token_hash = hash_api_key(token?);
+
+        file_tokens
+            .iter()
+            .chain(stored_tokens.iter())
+            .find(|(_, management_token)| tokens_match(&management_token.token_hash, &token_hash))
+            .map(|(name, management_token)| ManagementPrincipal {
+                name: name.clone(),
+                role: management_token.role,
+            })
+    }
+
+    pub fn set_management_token_registry(
+        &self,
+        management_token_registry: Option<ManagementTokenRegistry>,
+    ) {
+        *self
+            .file_tokens
+            .write()
+            .expect("Failed to get management tokens lock") = management_token_registry
+            .map(|management_token_registry| management_token_registry.tokens_by_name)
+            .unwrap_or_default();
+    }
+
+    pub fn set_stored_tokens(&self, stored_tokens: BTreeMap<String, ManagementToken>) {
+        *self
+            .stored_tokens
+            .write()
+            .expect("Failed to get management tokens lock") = stored_tokens;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closed_without_tokens() {
+        let management_access_control = ManagementAccessControl::new(false, None);
+
+        assert_eq!(management_access_control.authenticate(None), None);
+    }
+
+    #[test]
+    fn test_everyone_is_admin_without_tokens_if_allowed() {
+        let management_access_control = ManagementAccessControl::new(true, None);
+
+        assert_eq!(
+            management_access_control
+                .authenticate(None)
+                .map(|management_principal| management_principal.role),
+            Some(ManagementRole::Admin)
+        );
+
+        management_access_control.set_stored_tokens(BTreeMap::from([(
+            "alice".to_string(),
+            ManagementToken {
+                role: ManagementRole::Admin,
+                token_hash: hash_api_key("alice-token"),
+            },
+        )]));
+
+        assert_eq!(management_access_control.authenticate(None), None);
+    }
+
+    #[test]
+    fn test_file_and_stored_tokens_are_accepted() -> anyhow::Result<()> {
+        let management_access_control = ManagementAccessControl::new(
+            false,
+            Some(ManagementTokenRegistry::parse("alice:admin:alice-token")?),
+        );
+
+        management_access_control.set_stored_tokens(BTreeMap::from([(
+            "grafana".to_string(),
+            ManagementToken {
+                role: ManagementRole::ReadOnly,
+                token_hash: hash_api_key("grafana-token"),
+            },
+        )]));
+
+        assert_eq!(
+            management_access_control.authenticate(Some("alice-token")),
+            Some(ManagementPrincipal {
+                name: "alice".to_string(),
+                role: ManagementRole::Admin,
+            })
+        );
+        assert_eq!(
+            management_access_control.authenticate(Some("grafana-token")),
+            Some(ManagementPrincipal {
+                name: "grafana".to_string(),
+                role: ManagementRole::ReadOnly,
+            })
+        );
+        assert_eq!(management_access_control.authenticate(None), None);
+        assert_eq!(management_access_control.authenticate(Some("other")), None);
+
+        Ok(())
+    }
+}
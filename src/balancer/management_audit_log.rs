@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use log::info;
+use tokio::fs::File;
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt as _;
+use tokio::sync::Mutex;
+
+use crate::balancer::management_audit_log_entry::ManagementAuditLogEntry;
+
+/// Appends the entries to a file as JSON lines, or writes them to the log
+/// if there is no audit log file.
+pub struct ManagementAuditLog {
+    file: Mutex<Option<File>>,
+}
+
+impl ManagementAuditLog {
+    pub async fn open(path: Option<&Path>) -> Result<Self> {
+        let file = match path {
+            Some(path) => Some(
+                OpenOptions::new()
+                    .append(true)
+                    .create(true)
+                    .open(path)
+                    .await
+                    .context(format!(
+                        "Failed to open management audit log file: {}",
+                        path.display()
+                    ))?,
+            ),
+            None => None,
+        };
+
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    pub async fn record(&self, entry: &ManagementAuditLogEntry) -> Result<()> {
+        let mut serialized_entry = serde_json::to_string(entry)?;
+
+        match self.file.lock().await.as_mut() {
+            Some(file) => {
+                serialized_entry.push('\n');
+
+                file.write_all(serialized_entry.as_bytes()).await?;
+                file.flush().await?;
+            }
+            None => info!(target: "paddler::audit", "{serialized_entry}"),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::balancer::management_role::ManagementRole;
+
+    fn make_entry(path: &str) -> ManagementAuditLogEntry {
+        ManagementAuditLogEntry {
+            body: Some(serde_json::json!({"name": "overnight"})),
+            created_at: 1_700_000_000,
+            method: "PUT".to_string(),
+            path: path.to_string(),
+            principal: "alice".to_string(),
+            role: ManagementRole::Admin,
+            status: 204,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entries_are_appended_as_json_lines() -> Result<()> {
+        let directory = TempDir::new()?;
+        let path = directory.path().join("audit.log");
+
+        ManagementAuditLog::open(Some(&path))
+            .await?
+            .record(&make_entry("/api/v1/first"))
+            .await?;
+
+        // Reopening must not truncate the earlier entries
+        ManagementAuditLog::open(Some(&path))
+            .await?
+            .record(&make_entry("/api/v1/second"))
+            .await?;
+
+        let entries = tokio::fs::read_to_string(&path)
+            .await?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<ManagementAuditLogEntry>, _>>()?;
+
+        assert_eq!(
+            entries,
+            vec![make_entry("/api/v1/first"), make_entry("/api/v1/second")]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,18 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::balancer::management_role::ManagementRole;
+
+/// One state-changing call to the management API.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManagementAuditLogEntry {
+    /// Request body, parsed as JSON if possible
+    pub body: Option<serde_json::Value>,
+    pub created_at: i64,
+    pub method: String,
+    pub path: String,
+    pub principal: String,
+    pub role: ManagementRole,
+    pub status: u16,
+}
@@ -0,0 +1,8 @@
+use crate::balancer::management_role::ManagementRole;
+
+/// Who made the management request; recorded in the audit log.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ManagementPrincipal {
+    pub name: String,
+    pub role: ManagementRole,
+}
@@ -0,0 +1,48 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::Error;
+use anyhow::Result;
+use anyhow::anyhow;
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ManagementRole {
+    Admin,
+    ReadOnly,
+}
+
+impl ManagementRole {
+    /// Admins can do everything the read-only role can.
+    pub fn allows(&self, required_role: ManagementRole) -> bool {
+        matches!(
+            (self, required_role),
+            (ManagementRole::Admin, _) | (ManagementRole::ReadOnly, ManagementRole::ReadOnly)
+        )
+    }
+}
+
+impl fmt::Display for ManagementRole {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManagementRole::Admin => write!(formatter, "admin"),
+            ManagementRole::ReadOnly => write!(formatter, "read_only"),
+        }
+    }
+}
+
+impl FromStr for ManagementRole {
+    type Err = Error;
+
+    fn from_str(role: &str) -> Result<Self> {
+        match role {
+            "admin" => Ok(ManagementRole::Admin),
+            "read_only" => Ok(ManagementRole::ReadOnly),
+            _ => Err(anyhow!(
+                "Unknown management role: '{role}'. Expected 'admin' or 'read_only'"
+            )),
+        }
+    }
+}
@@ -1,22 +1,43 @@
 use std::sync::Arc;
 
+use tokio::sync::Mutex;
+
 use crate::balancer::agent_controller_pool::AgentControllerPool;
+use crate::balancer::agent_token_registry_holder::AgentTokenRegistryHolder;
+use crate::balancer::api_key_manager::ApiKeyManager;
 use crate::balancer::buffered_request_manager::BufferedRequestManager;
 use crate::balancer::chat_template_override_sender_collection::ChatTemplateOverrideSenderCollection;
 use crate::balancer::embedding_sender_collection::EmbeddingSenderCollection;
 use crate::balancer::generate_tokens_sender_collection::GenerateTokensSenderCollection;
+use crate::balancer::management_access_control::ManagementAccessControl;
+use crate::balancer::management_audit_log::ManagementAuditLog;
 use crate::balancer::model_metadata_sender_collection::ModelMetadataSenderCollection;
 use crate::balancer::state_database::StateDatabase;
 use crate::balancer_applicable_state_holder::BalancerApplicableStateHolder;
 
 pub struct AppData {
     pub agent_controller_pool: Arc<AgentControllerPool>,
+    pub agent_token_registry_holder: Arc<AgentTokenRegistryHolder>,
+    /// Serializes the agent token updates, so two tokens cannot be created with the same name
+    pub agent_tokens_write_lock: Mutex<()>,
+    pub api_key_manager: Arc<ApiKeyManager>,
+    /// Serializes the API key updates, so two keys cannot be created with the same name
+    pub api_keys_write_lock: Mutex<()>,
     pub balancer_applicable_state_holder: Arc<BalancerApplicableStateHolder>,
+    /// Serializes the desired state updates, so `If-Match` checks cannot race. Shared with
+    /// the other services that change the desired state
+    pub balancer_desired_state_write_lock: Arc<Mutex<()>>,
     pub buffered_request_manager: Arc<BufferedRequestManager>,
     pub chat_template_override_sender_collection: Arc<ChatTemplateOverrideSenderCollection>,
     pub embedding_sender_collection: Arc<EmbeddingSenderCollection>,
     pub generate_tokens_sender_collection: Arc<GenerateTokensSenderCollection>,
+    pub management_access_control: Arc<ManagementAccessControl>,
+    pub management_audit_log: Arc<ManagementAuditLog>,
+    /// Serializes the management token updates, so two tokens cannot be created with the same name
+    pub management_tokens_write_lock: Mutex<()>,
     pub model_metadata_sender_collection: Arc<ModelMetadataSenderCollection>,
+    /// Desired state updates without `If-Match` are rejected
+    pub requires_if_match: bool,
     pub state_database: Arc<dyn StateDatabase>,
     pub statsd_prefix: String,
 }
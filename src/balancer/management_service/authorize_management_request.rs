@@ -0,0 +1,335 @@
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::body::BoxBody;
+use actix_web::body::MessageBody;
+use actix_web::dev::Payload;
+use actix_web::dev::ServiceRequest;
+use actix_web::dev::ServiceResponse;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::error::ErrorPayloadTooLarge;
+use actix_web::http::Method;
+use actix_web::http::header;
+use actix_web::middleware::Next;
+use actix_web::web::Bytes;
+use actix_web::web::BytesMut;
+use actix_web::web::Data;
+use futures_util::StreamExt as _;
+use log::error;
+use url::form_urlencoded;
+
+use crate::balancer::authorization_bearer_token::authorization_bearer_token;
+use crate::balancer::management_audit_log_entry::ManagementAuditLogEntry;
+use crate::balancer::management_role::ManagementRole;
+use crate::balancer::management_service::app_data::AppData;
+
+/// Same as the default JSON payload limit of the routes.
+const MAX_AUDITED_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// Routes that authenticate on their own, or have to stay reachable for the health checks.
+fn is_public_path(path: &str) -> bool {
+    path == "/health" || path.starts_with("/api/v1/agent_socket/")
+}
+
+/// Body fields that are replaced in the audit log, so it does not leak any credentials.
+const SECRET_FIELD_NAMES: [&str; 4] = ["access_token", "api_key", "key", "token"];
+
+/// Routes that are consumed with event sources.
+fn is_event_stream_path(path: &str) -> bool {
+    path.starts_with("/api/v1/") && path.ends_with("/stream")
+}
+
+/// Event sources cannot send headers, so the event stream routes also accept
+/// the token in the query string.
+fn access_token(req: &ServiceRequest) -> Option<String> {
+    authorization_bearer_token(req.request()).or_else(|| {
+        if !is_event_stream_path(req.path()) {
+            return None;
+        }
+
+        form_urlencoded::parse(req.query_string().as_bytes())
+            .find(|(name, _)| name == "access_token")
+            .map(|(_, token)| token.into_owned())
+    })
+}
+
+fn redact_secrets(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        serde_json::Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if SECRET_FIELD_NAMES.contains(&name.as_str()) {
+                    *field = serde_json::Value::String("[redacted]".to_string());
+                } else {
+                    redact_secrets(field);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Bodies that are not JSON are not logged at all, only their size.
+fn audited_body(body: &[u8]) -> Option<serde_json::Value> {
+    if body.is_empty() {
+        return None;
+    }
+
+    Some(match serde_json::from_slice(body) {
+        Ok(mut value) => {
+            redact_secrets(&mut value);
+
+            value
+        }
+        Err(_) => serde_json::Value::String(format!("[{} bytes]", body.len())),
+    })
+}
+
+async fn take_body(req: &mut ServiceRequest) -> Result<Bytes, Error> {
+    let mut payload = req.take_payload();
+    let mut body = BytesMut::new();
+
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+
+        if body.len() + chunk.len() > MAX_AUDITED_BODY_SIZE {
+            return Err(ErrorPayloadTooLarge("Request body is too large"));
+        }
+
+        body.extend_from_slice(&chunk);
+    }
+
+    let body = body.freeze();
+
+    req.set_payload(Payload::from(body.clone()));
+
+    Ok(body)
+}
+
+/// Reading requires the read-only role, everything else requires the admin role.
+/// Every request that can change the state is written to the audit log, together
+/// with its body stripped of the credentials.
+pub async fn authorize_management_request(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    if req.method() == Method::OPTIONS || is_public_path(req.path()) {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+
+    let app_data = req
+        .app_data::<Data<AppData>>()
+        .cloned()
+        .ok_or_else(|| ErrorInternalServerError("Management service data is missing"))?;
+    let management_principal = match app_data
+        .management_access_control
+        .authenticate(access_token(&req).as_deref())
+    {
+        Some(management_principal) => management_principal,
+        None => {
+            return Ok(req.into_response(
+                HttpResponse::Unauthorized()
+                    .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+                    .body("Management token is missing or invalid"),
+            ));
+        }
+    };
+    let is_state_changing = !matches!(*req.method(), Method::GET | Method::HEAD);
+    let required_role = if is_state_changing {
+        ManagementRole::Admin
+    } else {
+        ManagementRole::ReadOnly
+    };
+
+    if !management_principal.role.allows(required_role) {
+        return Ok(req.into_response(
+            HttpResponse::Forbidden()
+                .body(format!("This request requires the '{required_role}' role")),
+        ));
+    }
+
+    if !is_state_changing {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+
+    let body = take_body(&mut req).await?;
+    let method = req.method().to_string();
+    let path = req.path().to_string();
+
+    let response = next.call(req).await?.map_into_boxed_body();
+    let entry = ManagementAuditLogEntry {
+        body: audited_body(&body),
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or_default(),
+        method,
+        path,
+        principal: management_principal.name,
+        role: management_principal.role,
+        status: response.status().as_u16(),
+    };
+
+    if let Err(err) = app_data.management_audit_log.record(&entry).await {
+        error!("Failed to write the management audit log entry: {err}");
+    }
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::App;
+    use actix_web::http::StatusCode;
+    use actix_web::middleware::from_fn;
+    use actix_web::test;
+    use actix_web::test::TestRequest;
+    use anyhow::Result;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::balancer::hash_api_key::hash_api_key;
+    use crate::balancer::management_access_control::ManagementAccessControl;
+    use crate::balancer::management_audit_log::ManagementAuditLog;
+    use crate::balancer::management_service::http_route;
+    use crate::balancer::management_service::make_test_app_data::make_test_app_data;
+    use crate::balancer::management_service::reload_management_tokens::reload_management_tokens;
+    use crate::balancer::management_token::ManagementToken;
+
+    #[test]
+    fn test_access_token_is_read_from_header_or_query() {
+        let req = TestRequest::get()
+            .uri("/api/v1/agents")
+            .insert_header((header::AUTHORIZATION, "Bearer from-header"))
+            .to_srv_request();
+
+        assert_eq!(access_token(&req), Some("from-header".to_string()));
+
+        let req = TestRequest::get()
+            .uri("/api/v1/agents/stream?access_token=from%20query")
+            .to_srv_request();
+
+        assert_eq!(access_token(&req), Some("from query".to_string()));
+
+        let req = TestRequest::get()
+            .uri("/api/v1/agents?access_token=from%20query")
+            .to_srv_request();
+
+        assert_eq!(access_token(&req), None);
+    }
+
+    #[test]
+    fn test_secrets_are_redacted() {
+        assert_eq!(
+            audited_body(br#"{"name":"ci","limits":{"max_requests_per_minute":10},"keys":[{"key":"secret"}]}"#),
+            Some(serde_json::json!({
+                "keys": [{"key": "[redacted]"}],
+                "limits": {"max_requests_per_minute": 10},
+                "name": "ci",
+            }))
+        );
+        assert_eq!(
+            audited_body(b"token=secret"),
+            Some(serde_json::Value::String("[12 bytes]".to_string()))
+        );
+        assert_eq!(audited_body(b""), None);
+    }
+
+    #[test]
+    fn test_public_paths() {
+        assert!(is_public_path("/health"));
+        assert!(is_public_path("/api/v1/agent_socket/agent-1"));
+        assert!(!is_public_path("/api/v1/agents"));
+    }
+
+    #[actix_web::test]
+    async fn test_roles_are_enforced_by_the_middleware() -> Result<()> {
+        let directory = TempDir::new()?;
+        let audit_log_path = directory.path().join("audit.log");
+        let (mut app_data, _balancer_desired_state_rx) = make_test_app_data().await?;
+
+        app_data.management_access_control = Arc::new(ManagementAccessControl::new(false, None));
+        app_data.management_audit_log =
+            Arc::new(ManagementAuditLog::open(Some(&audit_log_path)).await?);
+
+        for (name, role) in [
+            ("alice", ManagementRole::Admin),
+            ("grafana", ManagementRole::ReadOnly),
+        ] {
+            app_data
+                .state_database
+                .store_management_token(
+                    name,
+                    &ManagementToken {
+                        role,
+                        token_hash: hash_api_key(&format!("{name}-token")),
+                    },
+                )
+                .await?;
+        }
+
+        reload_management_tokens(&app_data).await?;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(app_data))
+                .wrap(from_fn(authorize_management_request))
+                .configure(http_route::api::get_management_tokens::register)
+                .configure(http_route::api::post_management_token::register),
+        )
+        .await;
+        let requests = [
+            (
+                TestRequest::get().uri("/api/v1/management_tokens"),
+                StatusCode::UNAUTHORIZED,
+            ),
+            (
+                TestRequest::get().uri("/api/v1/management_tokens?access_token=grafana-token"),
+                StatusCode::UNAUTHORIZED,
+            ),
+            (
+                TestRequest::get()
+                    .uri("/api/v1/management_tokens")
+                    .insert_header((header::AUTHORIZATION, "Bearer grafana-token")),
+                StatusCode::OK,
+            ),
+            (
+                TestRequest::post()
+                    .uri("/api/v1/management_tokens")
+                    .insert_header((header::AUTHORIZATION, "Bearer grafana-token"))
+                    .set_json(serde_json::json!({"name": "ci", "role": "read_only"})),
+                StatusCode::FORBIDDEN,
+            ),
+            (
+                TestRequest::post()
+                    .uri("/api/v1/management_tokens")
+                    .insert_header((header::AUTHORIZATION, "Bearer alice-token"))
+                    .set_json(serde_json::json!({"name": "ci", "role": "read_only"})),
+                StatusCode::CREATED,
+            ),
+        ];
+
+        for (req, expected_status) in requests {
+            assert_eq!(
+                test::call_service(&app, req.to_request()).await.status(),
+                expected_status
+            );
+        }
+
+        let audit_log = std::fs::read_to_string(&audit_log_path)?;
+        let entries = audit_log
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<serde_json::Result<Vec<ManagementAuditLogEntry>>>()?;
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].principal, "alice");
+        assert_eq!(entries[0].status, 201);
+
+        Ok(())
+    }
+}
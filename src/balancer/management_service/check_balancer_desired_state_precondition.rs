@@ -0,0 +1,42 @@
+use actix_web::http::header::IfMatch;
+use anyhow::Result;
+
+use crate::balancer::balancer_desired_state_precondition::BalancerDesiredStatePrecondition;
+use crate::balancer::create_balancer_desired_state_etag::create_balancer_desired_state_etag;
+use crate::balancer::management_service::app_data::AppData;
+
+/// Compares the `If-Match` precondition with the current state. It has to be called while
+/// holding the desired state write lock, so the state cannot change before it is replaced.
+/// Without `If-Match` the precondition is met, unless the balancer requires it.
+pub async fn check_balancer_desired_state_precondition(
+    app_data: &AppData,
+    if_match: Option<IfMatch>,
+) -> Result<BalancerDesiredStatePrecondition> {
+    let if_match = match if_match {
+        Some(if_match) => if_match,
+        None if app_data.requires_if_match => {
+            return Ok(BalancerDesiredStatePrecondition::Required);
+        }
+        None => IfMatch::Any,
+    };
+
+    let current_balancer_desired_state = app_data
+        .state_database
+        .read_balancer_desired_state()
+        .await?;
+
+    if let IfMatch::Items(entity_tags) = &if_match {
+        let current_etag = create_balancer_desired_state_etag(&current_balancer_desired_state)?;
+
+        if !entity_tags
+            .iter()
+            .any(|entity_tag| entity_tag.strong_eq(&current_etag))
+        {
+            return Ok(BalancerDesiredStatePrecondition::Failed);
+        }
+    }
+
+    Ok(BalancerDesiredStatePrecondition::Met(
+        current_balancer_desired_state,
+    ))
+}
@@ -1,7 +1,6 @@
-use std::net::SocketAddr;
+use crate::server_addr::ServerAddr;
 
 #[derive(Clone)]
 pub struct Configuration {
-    pub addr: SocketAddr,
-    pub cors_allowed_hosts: Vec<String>,
+    pub addr: ServerAddr,
 }
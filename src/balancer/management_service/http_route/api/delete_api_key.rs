@@ -0,0 +1,43 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::delete;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::web;
+use serde::Deserialize;
+
+use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::reload_api_keys::reload_api_keys;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathParams {
+    name: String,
+}
+
+/// Requests that are already running with the key are allowed to finish.
+#[delete("/api/v1/api_keys/{name}")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    params: web::Path<PathParams>,
+) -> Result<HttpResponse, Error> {
+    let _lock = app_data.api_keys_write_lock.lock().await;
+
+    if !app_data
+        .state_database
+        .delete_api_key(&params.name)
+        .await
+        .map_err(ErrorInternalServerError)?
+    {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    reload_api_keys(&app_data)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,38 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::delete;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::web;
+use serde::Deserialize;
+
+use crate::balancer::balancer_desired_state_profile_delete_result::BalancerDesiredStateProfileDeleteResult;
+use crate::balancer::management_service::app_data::AppData;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathParams {
+    name: String,
+}
+
+/// Profiles that are still used by the schedule cannot be deleted.
+#[delete("/api/v1/balancer_desired_state_profiles/{name}")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    params: web::Path<PathParams>,
+) -> Result<HttpResponse, Error> {
+    match app_data
+        .state_database
+        .delete_balancer_desired_state_profile(&params.name)
+        .await
+        .map_err(ErrorInternalServerError)?
+    {
+        BalancerDesiredStateProfileDeleteResult::Deleted => Ok(HttpResponse::NoContent().finish()),
+        BalancerDesiredStateProfileDeleteResult::NotFound => Ok(HttpResponse::NotFound().finish()),
+        BalancerDesiredStateProfileDeleteResult::UsedBySchedule => Ok(HttpResponse::Conflict()
+            .body(format!("Profile '{}' is used by the schedule", params.name))),
+    }
+}
@@ -0,0 +1,42 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::delete;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::web;
+use serde::Deserialize;
+
+use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::reload_management_tokens::reload_management_tokens;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathParams {
+    name: String,
+}
+
+#[delete("/api/v1/management_tokens/{name}")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    params: web::Path<PathParams>,
+) -> Result<HttpResponse, Error> {
+    let _lock = app_data.management_tokens_write_lock.lock().await;
+
+    if !app_data
+        .state_database
+        .delete_management_token(&params.name)
+        .await
+        .map_err(ErrorInternalServerError)?
+    {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    reload_management_tokens(&app_data)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,40 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::get;
+use actix_web::web;
+use serde::Serialize;
+
+use crate::balancer::management_service::app_data::AppData;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Serialize)]
+struct AgentTokenSummary {
+    agent_name: Option<String>,
+    name: String,
+}
+
+/// Lists only the tokens stored in the state database; the ones from the tokens file
+/// are managed in that file.
+#[get("/api/v1/agent_tokens")]
+async fn respond(app_data: web::Data<AppData>) -> Result<impl Responder, Error> {
+    let agent_tokens = app_data
+        .state_database
+        .read_agent_tokens()
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok().json(
+        agent_tokens
+            .into_iter()
+            .map(|(name, agent_token)| AgentTokenSummary {
+                agent_name: agent_token.agent_name,
+                name,
+            })
+            .collect::<Vec<AgentTokenSummary>>(),
+    ))
+}
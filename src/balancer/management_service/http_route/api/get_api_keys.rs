@@ -0,0 +1,40 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::get;
+use actix_web::web;
+use serde::Serialize;
+
+use crate::balancer::api_key_limits::ApiKeyLimits;
+use crate::balancer::management_service::app_data::AppData;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Serialize)]
+struct ApiKeySummary {
+    limits: ApiKeyLimits,
+    name: String,
+}
+
+/// The keys themselves are shown only once, when they are created.
+#[get("/api/v1/api_keys")]
+async fn respond(app_data: web::Data<AppData>) -> Result<impl Responder, Error> {
+    let api_keys = app_data
+        .state_database
+        .read_api_keys()
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok().json(
+        api_keys
+            .into_iter()
+            .map(|(name, api_key)| ApiKeySummary {
+                limits: api_key.limits,
+                name,
+            })
+            .collect::<Vec<ApiKeySummary>>(),
+    ))
+}
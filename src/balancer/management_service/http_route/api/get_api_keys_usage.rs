@@ -0,0 +1,16 @@
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::get;
+use actix_web::web;
+
+use crate::balancer::management_service::app_data::AppData;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+/// Counters are kept in memory, so they start from zero when the balancer restarts.
+#[get("/api/v1/api_keys/usage")]
+async fn respond(app_data: web::Data<AppData>) -> impl Responder {
+    HttpResponse::Ok().json(app_data.api_key_manager.make_usage_snapshots())
+}
@@ -3,8 +3,10 @@ use actix_web::HttpResponse;
 use actix_web::Responder;
 use actix_web::error::ErrorInternalServerError;
 use actix_web::get;
+use actix_web::http::header::ETag;
 use actix_web::web;
 
+use crate::balancer::create_balancer_desired_state_etag::create_balancer_desired_state_etag;
 use crate::balancer::management_service::app_data::AppData;
 
 pub fn register(cfg: &mut web::ServiceConfig) {
@@ -18,6 +20,10 @@ async fn respond(app_data: web::Data<AppData>) -> Result<impl Responder, Error>
         .read_balancer_desired_state()
         .await
         .map_err(ErrorInternalServerError)?;
+    let etag =
+        create_balancer_desired_state_etag(&desired_state).map_err(ErrorInternalServerError)?;
 
-    Ok(HttpResponse::Ok().json(desired_state))
+    Ok(HttpResponse::Ok()
+        .insert_header(ETag(etag))
+        .json(desired_state))
 }
@@ -0,0 +1,26 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::get;
+use actix_web::web;
+
+use crate::balancer::management_service::app_data::AppData;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[get("/api/v1/balancer_desired_state/history")]
+async fn respond(app_data: web::Data<AppData>) -> Result<impl Responder, Error> {
+    match app_data
+        .state_database
+        .read_balancer_desired_state_history()
+        .await
+        .map_err(ErrorInternalServerError)?
+    {
+        Some(history) => Ok(HttpResponse::Ok().json(history)),
+        None => Ok(HttpResponse::NotImplemented()
+            .body("This state database does not keep the history of the desired states")),
+    }
+}
@@ -0,0 +1,34 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::get;
+use actix_web::web;
+use serde::Deserialize;
+
+use crate::balancer::management_service::app_data::AppData;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathParams {
+    name: String,
+}
+
+#[get("/api/v1/balancer_desired_state_profiles/{name}")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    params: web::Path<PathParams>,
+) -> Result<HttpResponse, Error> {
+    match app_data
+        .state_database
+        .read_balancer_desired_state_profile(&params.name)
+        .await
+        .map_err(ErrorInternalServerError)?
+    {
+        Some(balancer_desired_state) => Ok(HttpResponse::Ok().json(balancer_desired_state)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
@@ -0,0 +1,38 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::get;
+use actix_web::web;
+use serde::Serialize;
+
+use crate::balancer::management_service::app_data::AppData;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Serialize)]
+struct ProfilesResponse {
+    active_profile: Option<String>,
+    profiles: Vec<String>,
+}
+
+#[get("/api/v1/balancer_desired_state_profiles")]
+async fn respond(app_data: web::Data<AppData>) -> Result<impl Responder, Error> {
+    let active_profile = app_data
+        .state_database
+        .read_active_balancer_desired_state_profile()
+        .await
+        .map_err(ErrorInternalServerError)?;
+    let profiles = app_data
+        .state_database
+        .read_balancer_desired_state_profiles()
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok().json(ProfilesResponse {
+        active_profile,
+        profiles: profiles.into_keys().collect(),
+    }))
+}
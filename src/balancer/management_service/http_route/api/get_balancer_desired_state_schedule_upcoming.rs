@@ -0,0 +1,44 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::get;
+use actix_web::web;
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use crate::balancer::balancer_desired_state_transition::BalancerDesiredStateTransition;
+use crate::balancer::management_service::app_data::AppData;
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 100;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QueryParams {
+    limit: Option<usize>,
+}
+
+#[get("/api/v1/balancer_desired_state/schedule/upcoming")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    query: web::Query<QueryParams>,
+) -> Result<impl Responder, Error> {
+    let schedule = app_data
+        .state_database
+        .read_balancer_desired_state_schedule()
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(
+        HttpResponse::Ok().json(BalancerDesiredStateTransition::list_upcoming(
+            &schedule,
+            OffsetDateTime::now_utc(),
+            query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT),
+        )),
+    )
+}
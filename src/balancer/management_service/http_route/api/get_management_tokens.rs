@@ -0,0 +1,41 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::get;
+use actix_web::web;
+use serde::Serialize;
+
+use crate::balancer::management_role::ManagementRole;
+use crate::balancer::management_service::app_data::AppData;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Serialize)]
+struct ManagementTokenSummary {
+    name: String,
+    role: ManagementRole,
+}
+
+/// Lists only the tokens stored in the state database; the ones from the tokens file
+/// are managed in that file.
+#[get("/api/v1/management_tokens")]
+async fn respond(app_data: web::Data<AppData>) -> Result<impl Responder, Error> {
+    let management_tokens = app_data
+        .state_database
+        .read_management_tokens()
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok().json(
+        management_tokens
+            .into_iter()
+            .map(|(name, management_token)| ManagementTokenSummary {
+                name,
+                role: management_token.role,
+            })
+            .collect::<Vec<ManagementTokenSummary>>(),
+    ))
+}
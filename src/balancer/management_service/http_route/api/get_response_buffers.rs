@@ -0,0 +1,25 @@
+use actix_web::HttpResponse;
+use actix_web::Responder;
+use actix_web::get;
+use actix_web::web;
+
+use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::manages_senders::ManagesSenders as _;
+use crate::balancer::response_buffers_snapshot::ResponseBuffersSnapshot;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+/// Shows how many responses of each in-flight request wait for its client to receive them.
+#[get("/api/v1/response_buffers")]
+async fn respond(app_data: web::Data<AppData>) -> impl Responder {
+    HttpResponse::Ok().json(ResponseBuffersSnapshot {
+        embedding_batches: app_data
+            .embedding_sender_collection
+            .get_response_buffer_snapshots(),
+        generated_tokens: app_data
+            .generate_tokens_sender_collection
+            .get_response_buffer_snapshots(),
+    })
+}
@@ -1,10 +1,39 @@
+pub mod delete_agent_token;
+pub mod delete_api_key;
+pub mod delete_balancer_desired_state_profile;
+pub mod delete_management_token;
+pub mod get_agent_tokens;
 pub mod get_agents;
 pub mod get_agents_stream;
+pub mod get_api_keys;
+pub mod get_api_keys_usage;
 pub mod get_balancer_desired_state;
+pub mod get_balancer_desired_state_history;
+pub mod get_balancer_desired_state_profile;
+pub mod get_balancer_desired_state_profile_bundle;
+pub mod get_balancer_desired_state_profiles;
+pub mod get_balancer_desired_state_schedule;
+pub mod get_balancer_desired_state_schedule_upcoming;
 pub mod get_buffered_requests;
 pub mod get_buffered_requests_stream;
 pub mod get_chat_template_override;
+pub mod get_management_tokens;
 pub mod get_model_metadata;
+pub mod get_response_buffers;
 pub mod grammar;
+pub mod patch_balancer_desired_state;
+pub mod post_agent_cordon;
+pub mod post_agent_disconnect;
+pub mod post_agent_token;
+pub mod post_agent_uncordon;
+pub mod post_api_key;
+pub mod post_balancer_desired_state_profile_activate;
+pub mod post_balancer_desired_state_profile_bundle;
+pub mod post_balancer_desired_state_rollback;
+pub mod post_balancer_desired_state_validate;
+pub mod post_management_token;
+pub mod put_api_key_limits;
 pub mod put_balancer_desired_state;
+pub mod put_balancer_desired_state_profile;
+pub mod put_balancer_desired_state_schedule;
 pub mod ws_agent_socket;
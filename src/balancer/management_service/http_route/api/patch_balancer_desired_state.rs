@@ -0,0 +1,64 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::error::ErrorBadRequest;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::http::header::ETag;
+use actix_web::http::header::IfMatch;
+use actix_web::patch;
+use actix_web::web;
+use serde_json::Value;
+
+use crate::apply_json_merge_patch::apply_json_merge_patch;
+use crate::balancer::balancer_desired_state_update_result::BalancerDesiredStateUpdateResult;
+use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::update_balancer_desired_state::update_balancer_desired_state;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+/// Accepts a JSON merge patch (`application/merge-patch+json`), so clients can change
+/// a single parameter without sending the whole state.
+#[patch("/api/v1/balancer_desired_state")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    body: web::Bytes,
+    if_match: Option<web::Header<IfMatch>>,
+) -> Result<HttpResponse, Error> {
+    let patch: Value = serde_json::from_slice(&body).map_err(ErrorBadRequest)?;
+
+    match update_balancer_desired_state(
+        &app_data,
+        if_match.map(|if_match| if_match.into_inner()),
+        |current_balancer_desired_state| {
+            let mut balancer_desired_state = serde_json::to_value(current_balancer_desired_state)
+                .map_err(|err| err.to_string())?;
+
+            apply_json_merge_patch(&mut balancer_desired_state, &patch);
+
+            serde_json::from_value(balancer_desired_state)
+                .map_err(|err| format!("Patched desired state is not valid: {err}"))
+        },
+    )
+    .await
+    .map_err(ErrorInternalServerError)?
+    {
+        BalancerDesiredStateUpdateResult::PreconditionFailed => {
+            Ok(HttpResponse::PreconditionFailed()
+                .body("Desired state was changed since it was read; fetch it again"))
+        }
+        BalancerDesiredStateUpdateResult::PreconditionRequired => {
+            Ok(HttpResponse::PreconditionRequired()
+                .body("If-Match header with the desired state ETag is required"))
+        }
+        BalancerDesiredStateUpdateResult::Rejected(message) => {
+            Ok(HttpResponse::BadRequest().body(message))
+        }
+        BalancerDesiredStateUpdateResult::Updated {
+            balancer_desired_state,
+            etag,
+        } => Ok(HttpResponse::Ok()
+            .insert_header(ETag(etag))
+            .json(balancer_desired_state)),
+    }
+}
@@ -0,0 +1,42 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::post;
+use actix_web::web;
+use serde::Deserialize;
+
+use crate::balancer::management_service::app_data::AppData;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathParams {
+    agent_id: String,
+}
+
+/// Closes the agent connection right away. The agent process is not stopped, so it reconnects
+/// unless it is shut down. The requests it is still processing are cut off, unless it reconnects
+/// within the resume grace period.
+#[post("/api/v1/agent/{agent_id}/disconnect")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    params: web::Path<PathParams>,
+) -> Result<HttpResponse, Error> {
+    match app_data
+        .agent_controller_pool
+        .get_agent_controller(&params.agent_id)
+    {
+        Some(agent_controller) => {
+            agent_controller
+                .connection_close_tx
+                .send(())
+                .map_err(ErrorInternalServerError)?;
+
+            Ok(HttpResponse::NoContent().finish())
+        }
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
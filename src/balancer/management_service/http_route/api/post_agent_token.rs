@@ -0,0 +1,126 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::post;
+use actix_web::web;
+use nanoid::nanoid;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::balancer::agent_token::AgentToken;
+use crate::balancer::hash_api_key::hash_api_key;
+use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::reload_agent_tokens::reload_agent_tokens;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CreateAgentTokenParams {
+    agent_name: Option<String>,
+    name: String,
+}
+
+#[derive(Serialize)]
+struct CreatedAgentToken {
+    agent_name: Option<String>,
+    name: String,
+    token: String,
+}
+
+/// Only the hash of the token is stored, so the token is shown just this once.
+/// Creating the first token disconnects the agents that connected without one.
+#[post("/api/v1/agent_tokens")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    params: web::Json<CreateAgentTokenParams>,
+) -> Result<HttpResponse, Error> {
+    let CreateAgentTokenParams { agent_name, name } = params.into_inner();
+
+    if name.trim().is_empty() {
+        return Ok(HttpResponse::BadRequest().body("Agent token name cannot be empty"));
+    }
+
+    let _lock = app_data.agent_tokens_write_lock.lock().await;
+
+    if app_data
+        .state_database
+        .read_agent_tokens()
+        .await
+        .map_err(ErrorInternalServerError)?
+        .contains_key(&name)
+    {
+        return Ok(HttpResponse::Conflict().body(format!("Agent token '{name}' already exists")));
+    }
+
+    let token = nanoid!(32);
+
+    app_data
+        .state_database
+        .store_agent_token(
+            &name,
+            &AgentToken {
+                agent_name: agent_name.clone(),
+                token_hash: hash_api_key(&token),
+            },
+        )
+        .await
+        .map_err(ErrorInternalServerError)?;
+    reload_agent_tokens(&app_data)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Created().json(CreatedAgentToken {
+        agent_name,
+        name,
+        token,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::App;
+    use actix_web::test;
+    use anyhow::Result;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::balancer::management_service::make_test_app_data::make_test_app_data;
+
+    #[actix_web::test]
+    async fn test_only_the_token_hash_is_stored() -> Result<()> {
+        let (app_data, _balancer_desired_state_rx) = make_test_app_data().await?;
+        let app_data = web::Data::new(app_data);
+        let app =
+            test::init_service(App::new().app_data(app_data.clone()).configure(register)).await;
+        let created_agent_token: Value = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/v1/agent_tokens")
+                .set_json(serde_json::json!({"agent_name": "gpu-1", "name": "gpu-1"}))
+                .to_request(),
+        )
+        .await;
+        let token = created_agent_token["token"].as_str().unwrap();
+
+        assert_eq!(
+            app_data.state_database.read_agent_tokens().await?["gpu-1"].token_hash,
+            hash_api_key(token)
+        );
+
+        app_data
+            .agent_token_registry_holder
+            .authenticate(Some("gpu-1"), Some(token))?;
+
+        assert!(
+            app_data
+                .agent_token_registry_holder
+                .authenticate(Some("gpu-2"), Some(token))
+                .is_err()
+        );
+
+        Ok(())
+    }
+}
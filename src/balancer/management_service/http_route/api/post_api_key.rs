@@ -0,0 +1,102 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::post;
+use actix_web::web;
+use nanoid::nanoid;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::balancer::api_key::ApiKey;
+use crate::balancer::api_key_limits::ApiKeyLimits;
+use crate::balancer::hash_api_key::hash_api_key;
+use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::reload_api_keys::reload_api_keys;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+/// Names are used in the metric names, so they are limited to the characters that
+/// are safe there.
+fn is_valid_api_key_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(|character| {
+            character.is_ascii_alphanumeric() || character == '-' || character == '_'
+        })
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CreateApiKeyParams {
+    #[serde(default)]
+    limits: ApiKeyLimits,
+    name: String,
+}
+
+#[derive(Serialize)]
+struct CreatedApiKey {
+    key: String,
+    limits: ApiKeyLimits,
+    name: String,
+}
+
+#[post("/api/v1/api_keys")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    params: web::Json<CreateApiKeyParams>,
+) -> Result<HttpResponse, Error> {
+    let CreateApiKeyParams { limits, name } = params.into_inner();
+
+    if !is_valid_api_key_name(&name) {
+        return Ok(HttpResponse::BadRequest()
+            .body("API key name can only contain letters, digits, dashes and underscores"));
+    }
+
+    let _lock = app_data.api_keys_write_lock.lock().await;
+
+    if app_data
+        .state_database
+        .read_api_keys()
+        .await
+        .map_err(ErrorInternalServerError)?
+        .contains_key(&name)
+    {
+        return Ok(HttpResponse::Conflict().body(format!("API key '{name}' already exists")));
+    }
+
+    let key = nanoid!(32);
+    let api_key = ApiKey {
+        key_hash: hash_api_key(&key),
+        limits,
+    };
+
+    app_data
+        .state_database
+        .store_api_key(&name, &api_key)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    reload_api_keys(&app_data)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    // The key cannot be recovered from its hash, so this is the only time it is shown
+    Ok(HttpResponse::Created().json(CreatedApiKey {
+        key,
+        limits: api_key.limits,
+        name,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_key_name_validation() {
+        assert!(is_valid_api_key_name("frontend_app-2"));
+        assert!(!is_valid_api_key_name(""));
+        assert!(!is_valid_api_key_name("front end"));
+        assert!(!is_valid_api_key_name("frontend.app"));
+    }
+}
@@ -0,0 +1,185 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::http::header::ETag;
+use actix_web::http::header::IfMatch;
+use actix_web::post;
+use actix_web::web;
+use serde::Deserialize;
+
+use crate::balancer::balancer_desired_state_precondition::BalancerDesiredStatePrecondition;
+use crate::balancer::create_balancer_desired_state_etag::create_balancer_desired_state_etag;
+use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::check_balancer_desired_state_precondition::check_balancer_desired_state_precondition;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathParams {
+    name: String,
+}
+
+#[post("/api/v1/balancer_desired_state_profiles/{name}/activate")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    if_match: Option<web::Header<IfMatch>>,
+    params: web::Path<PathParams>,
+) -> Result<HttpResponse, Error> {
+    let _lock = app_data.balancer_desired_state_write_lock.lock().await;
+
+    match check_balancer_desired_state_precondition(
+        &app_data,
+        if_match.map(|if_match| if_match.into_inner()),
+    )
+    .await
+    .map_err(ErrorInternalServerError)?
+    {
+        BalancerDesiredStatePrecondition::Failed => {
+            return Ok(HttpResponse::PreconditionFailed()
+                .body("Desired state was changed since it was read; fetch it again"));
+        }
+        BalancerDesiredStatePrecondition::Met(_) => {}
+        BalancerDesiredStatePrecondition::Required => {
+            return Ok(HttpResponse::PreconditionRequired()
+                .body("If-Match header with the desired state ETag is required"));
+        }
+    }
+
+    match app_data
+        .state_database
+        .activate_balancer_desired_state_profile(&params.name)
+        .await
+        .map_err(ErrorInternalServerError)?
+    {
+        Some(balancer_desired_state) => Ok(HttpResponse::Ok()
+            .insert_header(ETag(
+                create_balancer_desired_state_etag(&balancer_desired_state)
+                    .map_err(ErrorInternalServerError)?,
+            ))
+            .json(balancer_desired_state)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::App;
+    use actix_web::http::StatusCode;
+    use actix_web::http::header;
+    use actix_web::test;
+    use anyhow::Result;
+    use tokio::sync::broadcast;
+
+    use super::*;
+    use crate::agent_desired_model::AgentDesiredModel;
+    use crate::balancer::management_service::make_test_app_data::make_test_app_data;
+    use crate::balancer_desired_state::BalancerDesiredState;
+
+    fn make_profile() -> BalancerDesiredState {
+        BalancerDesiredState {
+            model: AgentDesiredModel::LocalToAgent("model.gguf".to_string()),
+            ..BalancerDesiredState::default()
+        }
+    }
+
+    async fn make_app_data(
+        requires_if_match: bool,
+    ) -> Result<(
+        web::Data<AppData>,
+        broadcast::Receiver<BalancerDesiredState>,
+    )> {
+        let (app_data, balancer_desired_state_rx) = make_test_app_data().await?;
+        let app_data = AppData {
+            requires_if_match,
+            ..app_data
+        };
+
+        app_data
+            .state_database
+            .store_balancer_desired_state_profile("overnight", &make_profile())
+            .await?;
+
+        Ok((web::Data::new(app_data), balancer_desired_state_rx))
+    }
+
+    async fn activate_profile(
+        app_data: web::Data<AppData>,
+        if_match: Option<String>,
+    ) -> Result<StatusCode> {
+        let app = test::init_service(App::new().app_data(app_data).configure(register)).await;
+        let mut req = test::TestRequest::post()
+            .uri("/api/v1/balancer_desired_state_profiles/overnight/activate");
+
+        if let Some(if_match) = if_match {
+            req = req.insert_header((header::IF_MATCH, if_match));
+        }
+
+        Ok(test::call_service(&app, req.to_request()).await.status())
+    }
+
+    #[actix_web::test]
+    async fn test_activate_with_matching_etag() -> Result<()> {
+        let (app_data, _balancer_desired_state_rx) = make_app_data(true).await?;
+        let current_etag = create_balancer_desired_state_etag(
+            &app_data
+                .state_database
+                .read_balancer_desired_state()
+                .await?,
+        )?;
+
+        assert_eq!(
+            activate_profile(app_data.clone(), Some(current_etag.to_string())).await?,
+            StatusCode::OK
+        );
+        assert_eq!(
+            app_data
+                .state_database
+                .read_balancer_desired_state()
+                .await?,
+            make_profile()
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_activate_with_stale_etag() -> Result<()> {
+        let (app_data, _balancer_desired_state_rx) = make_app_data(false).await?;
+
+        assert_eq!(
+            activate_profile(app_data.clone(), Some("\"stale\"".to_string())).await?,
+            StatusCode::PRECONDITION_FAILED
+        );
+        assert_eq!(
+            app_data
+                .state_database
+                .read_balancer_desired_state()
+                .await?,
+            BalancerDesiredState::default()
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_activate_without_required_if_match() -> Result<()> {
+        let (app_data, _balancer_desired_state_rx) = make_app_data(true).await?;
+
+        assert_eq!(
+            activate_profile(app_data.clone(), None).await?,
+            StatusCode::PRECONDITION_REQUIRED
+        );
+        assert_eq!(
+            app_data
+                .state_database
+                .read_balancer_desired_state()
+                .await?,
+            BalancerDesiredState::default()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::post;
+use actix_web::web;
+
+use crate::balancer::balancer_desired_state_profile_bundle::BalancerDesiredStateProfileBundle;
+use crate::balancer::management_service::app_data::AppData;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+/// Imports all the profiles at once. Profiles with the same names are replaced; the other
+/// existing profiles are kept.
+#[post("/api/v1/balancer_desired_state_profile_bundle")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    bundle: web::Json<BalancerDesiredStateProfileBundle>,
+) -> Result<HttpResponse, Error> {
+    if bundle.profiles.keys().any(|name| name.trim().is_empty()) {
+        return Ok(HttpResponse::BadRequest().body("Profile names cannot be empty"));
+    }
+
+    app_data
+        .state_database
+        .store_balancer_desired_state_profiles(&bundle.profiles)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
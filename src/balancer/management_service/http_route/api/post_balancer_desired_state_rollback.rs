@@ -0,0 +1,218 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::http::header::ETag;
+use actix_web::http::header::IfMatch;
+use actix_web::post;
+use actix_web::web;
+use serde::Deserialize;
+
+use crate::balancer::balancer_desired_state_precondition::BalancerDesiredStatePrecondition;
+use crate::balancer::balancer_desired_state_rollback_result::BalancerDesiredStateRollbackResult;
+use crate::balancer::create_balancer_desired_state_etag::create_balancer_desired_state_etag;
+use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::check_balancer_desired_state_precondition::check_balancer_desired_state_precondition;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathParams {
+    version: i64,
+}
+
+#[post("/api/v1/balancer_desired_state/history/{version}/rollback")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    if_match: Option<web::Header<IfMatch>>,
+    params: web::Path<PathParams>,
+) -> Result<HttpResponse, Error> {
+    let _lock = app_data.balancer_desired_state_write_lock.lock().await;
+
+    match check_balancer_desired_state_precondition(
+        &app_data,
+        if_match.map(|if_match| if_match.into_inner()),
+    )
+    .await
+    .map_err(ErrorInternalServerError)?
+    {
+        BalancerDesiredStatePrecondition::Failed => {
+            return Ok(HttpResponse::PreconditionFailed()
+                .body("Desired state was changed since it was read; fetch it again"));
+        }
+        BalancerDesiredStatePrecondition::Met(_) => {}
+        BalancerDesiredStatePrecondition::Required => {
+            return Ok(HttpResponse::PreconditionRequired()
+                .body("If-Match header with the desired state ETag is required"));
+        }
+    }
+
+    match app_data
+        .state_database
+        .rollback_balancer_desired_state(params.version)
+        .await
+        .map_err(ErrorInternalServerError)?
+    {
+        BalancerDesiredStateRollbackResult::NotSupported => Ok(HttpResponse::NotImplemented()
+            .body("This state database does not keep the history of the desired states")),
+        BalancerDesiredStateRollbackResult::RolledBack(balancer_desired_state) => {
+            Ok(HttpResponse::Ok()
+                .insert_header(ETag(
+                    create_balancer_desired_state_etag(&balancer_desired_state)
+                        .map_err(ErrorInternalServerError)?,
+                ))
+                .json(balancer_desired_state))
+        }
+        BalancerDesiredStateRollbackResult::VersionNotFound => {
+            Ok(HttpResponse::NotFound().finish())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::App;
+    use actix_web::http::StatusCode;
+    use actix_web::http::header;
+    use actix_web::test;
+    use anyhow::Result;
+    use tempfile::NamedTempFile;
+    use tokio::sync::broadcast;
+
+    use super::*;
+    use crate::agent_desired_model::AgentDesiredModel;
+    use crate::balancer::management_service::make_test_app_data::make_test_app_data;
+    use crate::balancer::state_database::Sqlite;
+    use crate::balancer_desired_state::BalancerDesiredState;
+
+    struct RollbackFixture {
+        app_data: web::Data<AppData>,
+        initial_version: i64,
+        _balancer_desired_state_rx: broadcast::Receiver<BalancerDesiredState>,
+        _tempfile: NamedTempFile,
+    }
+
+    fn make_desired_state() -> BalancerDesiredState {
+        BalancerDesiredState {
+            model: AgentDesiredModel::LocalToAgent("model.gguf".to_string()),
+            ..BalancerDesiredState::default()
+        }
+    }
+
+    async fn make_fixture(requires_if_match: bool) -> Result<RollbackFixture> {
+        let (app_data, _) = make_test_app_data().await?;
+        let (balancer_desired_state_tx, balancer_desired_state_rx) = broadcast::channel(100);
+        let tempfile = NamedTempFile::new()?;
+        let app_data = AppData {
+            requires_if_match,
+            state_database: Arc::new(Sqlite::new(
+                balancer_desired_state_tx,
+                BalancerDesiredState::default(),
+                tempfile.path().to_path_buf(),
+            )?),
+            ..app_data
+        };
+
+        app_data
+            .state_database
+            .store_balancer_desired_state(&make_desired_state())
+            .await?;
+
+        let history = app_data
+            .state_database
+            .read_balancer_desired_state_history()
+            .await?
+            .expect("Sqlite keeps the history");
+
+        Ok(RollbackFixture {
+            app_data: web::Data::new(app_data),
+            initial_version: history[1].version,
+            _balancer_desired_state_rx: balancer_desired_state_rx,
+            _tempfile: tempfile,
+        })
+    }
+
+    async fn rollback(fixture: &RollbackFixture, if_match: Option<String>) -> Result<StatusCode> {
+        let app = test::init_service(
+            App::new()
+                .app_data(fixture.app_data.clone())
+                .configure(register),
+        )
+        .await;
+        let mut req = test::TestRequest::post().uri(&format!(
+            "/api/v1/balancer_desired_state/history/{}/rollback",
+            fixture.initial_version
+        ));
+
+        if let Some(if_match) = if_match {
+            req = req.insert_header((header::IF_MATCH, if_match));
+        }
+
+        Ok(test::call_service(&app, req.to_request()).await.status())
+    }
+
+    #[actix_web::test]
+    async fn test_rollback_with_matching_etag() -> Result<()> {
+        let fixture = make_fixture(true).await?;
+        let current_etag = create_balancer_desired_state_etag(&make_desired_state())?;
+
+        assert_eq!(
+            rollback(&fixture, Some(current_etag.to_string())).await?,
+            StatusCode::OK
+        );
+        assert_eq!(
+            fixture
+                .app_data
+                .state_database
+                .read_balancer_desired_state()
+                .await?,
+            BalancerDesiredState::default()
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_rollback_with_stale_etag() -> Result<()> {
+        let fixture = make_fixture(false).await?;
+
+        assert_eq!(
+            rollback(&fixture, Some("\"stale\"".to_string())).await?,
+            StatusCode::PRECONDITION_FAILED
+        );
+        assert_eq!(
+            fixture
+                .app_data
+                .state_database
+                .read_balancer_desired_state()
+                .await?,
+            make_desired_state()
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_rollback_without_required_if_match() -> Result<()> {
+        let fixture = make_fixture(true).await?;
+
+        assert_eq!(
+            rollback(&fixture, None).await?,
+            StatusCode::PRECONDITION_REQUIRED
+        );
+        assert_eq!(
+            fixture
+                .app_data
+                .state_database
+                .read_balancer_desired_state()
+                .await?,
+            make_desired_state()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::post;
+use actix_web::web;
+use serde::Deserialize;
+
+use crate::balancer::balancer_desired_state_validation_report::BalancerDesiredStateValidationReport;
+use crate::balancer::diff_balancer_desired_states::diff_balancer_desired_states;
+use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::validate_balancer_desired_state::validate_balancer_desired_state;
+use crate::balancer_desired_state::BalancerDesiredState;
+use crate::converts_to_applicable_state::ConvertsToApplicableState as _;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QueryParams {
+    /// Checks that the Hugging Face model exists (makes a request to Hugging Face)
+    #[serde(default)]
+    check_huggingface: bool,
+}
+
+/// Checks the desired state without applying it.
+#[post("/api/v1/balancer_desired_state/validate")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    balancer_desired_state: web::Json<BalancerDesiredState>,
+    query: web::Query<QueryParams>,
+) -> Result<HttpResponse, Error> {
+    let proposed_balancer_desired_state = balancer_desired_state.into_inner();
+    let current_balancer_desired_state = app_data
+        .state_database
+        .read_balancer_desired_state()
+        .await
+        .map_err(ErrorInternalServerError)?;
+    let issues =
+        validate_balancer_desired_state(&proposed_balancer_desired_state, query.check_huggingface)
+            .await;
+    let proposed_agent_desired_state = proposed_balancer_desired_state
+        .to_applicable_state(())
+        .await
+        .map_err(ErrorInternalServerError)?
+        .map(|balancer_applicable_state| balancer_applicable_state.agent_desired_state);
+    let agents_to_reload = if proposed_agent_desired_state
+        == app_data
+            .balancer_applicable_state_holder
+            .get_agent_desired_state()
+    {
+        Vec::new()
+    } else {
+        let mut agent_ids: Vec<String> = app_data
+            .agent_controller_pool
+            .agents
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect();
+
+        agent_ids.sort();
+
+        agent_ids
+    };
+
+    Ok(
+        HttpResponse::Ok().json(BalancerDesiredStateValidationReport {
+            agents_to_reload,
+            changes: diff_balancer_desired_states(
+                &current_balancer_desired_state,
+                &proposed_balancer_desired_state,
+            )
+            .map_err(ErrorInternalServerError)?,
+            is_valid: issues.is_empty(),
+            issues,
+        }),
+    )
+}
@@ -0,0 +1,121 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::post;
+use actix_web::web;
+use nanoid::nanoid;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::balancer::hash_api_key::hash_api_key;
+use crate::balancer::management_role::ManagementRole;
+use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::reload_management_tokens::reload_management_tokens;
+use crate::balancer::management_token::ManagementToken;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CreateManagementTokenParams {
+    name: String,
+    role: ManagementRole,
+}
+
+#[derive(Serialize)]
+struct CreatedManagementToken {
+    name: String,
+    role: ManagementRole,
+    token: String,
+}
+
+/// Only the hash of the token is stored, so the token is shown just this once.
+/// Creating the first token ends the anonymous access to the management API, if it was allowed.
+#[post("/api/v1/management_tokens")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    params: web::Json<CreateManagementTokenParams>,
+) -> Result<HttpResponse, Error> {
+    let CreateManagementTokenParams { name, role } = params.into_inner();
+
+    if name.trim().is_empty() {
+        return Ok(HttpResponse::BadRequest().body("Management token name cannot be empty"));
+    }
+
+    let _lock = app_data.management_tokens_write_lock.lock().await;
+
+    if app_data
+        .state_database
+        .read_management_tokens()
+        .await
+        .map_err(ErrorInternalServerError)?
+        .contains_key(&name)
+    {
+        return Ok(
+            HttpResponse::Conflict().body(format!("Management token '{name}' already exists"))
+        );
+    }
+
+    let token = nanoid!(32);
+
+    app_data
+        .state_database
+        .store_management_token(
+            &name,
+            &ManagementToken {
+                role,
+                token_hash: hash_api_key(&token),
+            },
+        )
+        .await
+        .map_err(ErrorInternalServerError)?;
+    reload_management_tokens(&app_data)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Created().json(CreatedManagementToken { name, role, token }))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::App;
+    use actix_web::test;
+    use anyhow::Result;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::balancer::management_service::make_test_app_data::make_test_app_data;
+
+    #[actix_web::test]
+    async fn test_only_the_token_hash_is_stored() -> Result<()> {
+        let (app_data, _balancer_desired_state_rx) = make_test_app_data().await?;
+        let app_data = web::Data::new(app_data);
+        let app =
+            test::init_service(App::new().app_data(app_data.clone()).configure(register)).await;
+        let created_management_token: Value = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/v1/management_tokens")
+                .set_json(serde_json::json!({"name": "ci", "role": "read_only"}))
+                .to_request(),
+        )
+        .await;
+        let token = created_management_token["token"].as_str().unwrap();
+
+        assert_eq!(
+            app_data.state_database.read_management_tokens().await?["ci"].token_hash,
+            hash_api_key(token)
+        );
+        assert_eq!(
+            app_data
+                .management_access_control
+                .authenticate(Some(token))
+                .map(|management_principal| management_principal.role),
+            Some(ManagementRole::ReadOnly)
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::put;
+use actix_web::web;
+use serde::Deserialize;
+
+use crate::balancer::api_key::ApiKey;
+use crate::balancer::api_key_limits::ApiKeyLimits;
+use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::reload_api_keys::reload_api_keys;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathParams {
+    name: String,
+}
+
+/// Usage counters of the key are kept, so lowering a limit applies immediately.
+#[put("/api/v1/api_keys/{name}/limits")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    limits: web::Json<ApiKeyLimits>,
+    params: web::Path<PathParams>,
+) -> Result<HttpResponse, Error> {
+    let _lock = app_data.api_keys_write_lock.lock().await;
+    let api_key = match app_data
+        .state_database
+        .read_api_keys()
+        .await
+        .map_err(ErrorInternalServerError)?
+        .remove(&params.name)
+    {
+        Some(api_key) => api_key,
+        None => return Ok(HttpResponse::NotFound().finish()),
+    };
+
+    app_data
+        .state_database
+        .store_api_key(
+            &params.name,
+            &ApiKey {
+                limits: limits.into_inner(),
+                ..api_key
+            },
+        )
+        .await
+        .map_err(ErrorInternalServerError)?;
+    reload_api_keys(&app_data)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
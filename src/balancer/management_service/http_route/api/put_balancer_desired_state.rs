@@ -1,11 +1,14 @@
 use actix_web::Error;
 use actix_web::HttpResponse;
-use actix_web::Responder;
 use actix_web::error::ErrorInternalServerError;
+use actix_web::http::header::ETag;
+use actix_web::http::header::IfMatch;
 use actix_web::put;
 use actix_web::web;
 
+use crate::balancer::balancer_desired_state_update_result::BalancerDesiredStateUpdateResult;
 use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::update_balancer_desired_state::update_balancer_desired_state;
 use crate::balancer_desired_state::BalancerDesiredState;
 
 pub fn register(cfg: &mut web::ServiceConfig) {
@@ -16,14 +19,142 @@ pub fn register(cfg: &mut web::ServiceConfig) {
 async fn respond(
     app_data: web::Data<AppData>,
     balancer_desired_state: web::Json<BalancerDesiredState>,
-) -> Result<impl Responder, Error> {
+    if_match: Option<web::Header<IfMatch>>,
+) -> Result<HttpResponse, Error> {
     let balancer_desired_state_inner = balancer_desired_state.into_inner();
 
-    app_data
-        .state_database
-        .store_balancer_desired_state(&balancer_desired_state_inner)
-        .await
-        .map_err(ErrorInternalServerError)?;
+    match update_balancer_desired_state(
+        &app_data,
+        if_match.map(|if_match| if_match.into_inner()),
+        |_| Ok(balancer_desired_state_inner),
+    )
+    .await
+    .map_err(ErrorInternalServerError)?
+    {
+        BalancerDesiredStateUpdateResult::PreconditionFailed => {
+            Ok(HttpResponse::PreconditionFailed()
+                .body("Desired state was changed since it was read; fetch it again"))
+        }
+        BalancerDesiredStateUpdateResult::PreconditionRequired => {
+            Ok(HttpResponse::PreconditionRequired()
+                .body("If-Match header with the desired state ETag is required"))
+        }
+        BalancerDesiredStateUpdateResult::Rejected(message) => {
+            Ok(HttpResponse::BadRequest().body(message))
+        }
+        BalancerDesiredStateUpdateResult::Updated { etag, .. } => {
+            Ok(HttpResponse::NoContent().insert_header(ETag(etag)).finish())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::App;
+    use actix_web::http::StatusCode;
+    use actix_web::http::header;
+    use actix_web::test;
+    use anyhow::Result;
+
+    use super::*;
+    use crate::agent_desired_model::AgentDesiredModel;
+    use crate::balancer::create_balancer_desired_state_etag::create_balancer_desired_state_etag;
+    use crate::balancer::management_service::make_test_app_data::make_test_app_data;
+
+    fn make_desired_state() -> BalancerDesiredState {
+        BalancerDesiredState {
+            model: AgentDesiredModel::LocalToAgent("model.gguf".to_string()),
+            ..BalancerDesiredState::default()
+        }
+    }
+
+    async fn put_desired_state(
+        app_data: web::Data<AppData>,
+        if_match: Option<String>,
+    ) -> Result<StatusCode> {
+        let app = test::init_service(App::new().app_data(app_data).configure(register)).await;
+        let mut req = test::TestRequest::put()
+            .uri("/api/v1/balancer_desired_state")
+            .set_json(make_desired_state());
+
+        if let Some(if_match) = if_match {
+            req = req.insert_header((header::IF_MATCH, if_match));
+        }
+
+        Ok(test::call_service(&app, req.to_request()).await.status())
+    }
+
+    #[actix_web::test]
+    async fn test_put_with_matching_etag() -> Result<()> {
+        let (app_data, _balancer_desired_state_rx) = make_test_app_data().await?;
+        let app_data = web::Data::new(app_data);
+        let current_etag = create_balancer_desired_state_etag(
+            &app_data
+                .state_database
+                .read_balancer_desired_state()
+                .await?,
+        )?;
+
+        assert_eq!(
+            put_desired_state(app_data.clone(), Some(current_etag.to_string())).await?,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            app_data
+                .state_database
+                .read_balancer_desired_state()
+                .await?,
+            make_desired_state()
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_put_with_stale_etag() -> Result<()> {
+        let (app_data, _balancer_desired_state_rx) = make_test_app_data().await?;
+        let app_data = web::Data::new(app_data);
+
+        assert_eq!(
+            put_desired_state(app_data.clone(), Some("\"stale\"".to_string())).await?,
+            StatusCode::PRECONDITION_FAILED
+        );
+        assert_eq!(
+            app_data
+                .state_database
+                .read_balancer_desired_state()
+                .await?,
+            BalancerDesiredState::default()
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_put_without_if_match() -> Result<()> {
+        let (app_data, _balancer_desired_state_rx) = make_test_app_data().await?;
+
+        assert_eq!(
+            put_desired_state(web::Data::new(app_data), None).await?,
+            StatusCode::NO_CONTENT
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_put_without_required_if_match() -> Result<()> {
+        let (app_data, _balancer_desired_state_rx) = make_test_app_data().await?;
+        let app_data = AppData {
+            requires_if_match: true,
+            ..app_data
+        };
+
+        assert_eq!(
+            put_desired_state(web::Data::new(app_data), None).await?,
+            StatusCode::PRECONDITION_REQUIRED
+        );
 
-    Ok(HttpResponse::NoContent().finish())
+        Ok(())
+    }
 }
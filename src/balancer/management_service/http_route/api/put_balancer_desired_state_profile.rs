@@ -0,0 +1,38 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::put;
+use actix_web::web;
+use serde::Deserialize;
+
+use crate::balancer::management_service::app_data::AppData;
+use crate::balancer_desired_state::BalancerDesiredState;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathParams {
+    name: String,
+}
+
+#[put("/api/v1/balancer_desired_state_profiles/{name}")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    balancer_desired_state: web::Json<BalancerDesiredState>,
+    params: web::Path<PathParams>,
+) -> Result<HttpResponse, Error> {
+    if params.name.trim().is_empty() {
+        return Ok(HttpResponse::BadRequest().body("Profile names cannot be empty"));
+    }
+
+    app_data
+        .state_database
+        .store_balancer_desired_state_profile(&params.name, &balancer_desired_state)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,32 @@
+use actix_web::Error;
+use actix_web::HttpResponse;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::put;
+use actix_web::web;
+
+use crate::balancer::balancer_desired_state_schedule_entry::BalancerDesiredStateScheduleEntry;
+use crate::balancer::balancer_desired_state_schedule_store_result::BalancerDesiredStateScheduleStoreResult;
+use crate::balancer::management_service::app_data::AppData;
+
+pub fn register(cfg: &mut web::ServiceConfig) {
+    cfg.service(respond);
+}
+
+/// Replaces the whole schedule. Every entry has to point to an existing profile.
+#[put("/api/v1/balancer_desired_state/schedule")]
+async fn respond(
+    app_data: web::Data<AppData>,
+    schedule: web::Json<Vec<BalancerDesiredStateScheduleEntry>>,
+) -> Result<HttpResponse, Error> {
+    match app_data
+        .state_database
+        .store_balancer_desired_state_schedule(&schedule)
+        .await
+        .map_err(ErrorInternalServerError)?
+    {
+        BalancerDesiredStateScheduleStoreResult::ProfileNotFound(profile) => {
+            Ok(HttpResponse::BadRequest().body(format!("Profile '{profile}' does not exist")))
+        }
+        BalancerDesiredStateScheduleStoreResult::Stored => Ok(HttpResponse::NoContent().finish()),
+    }
+}
@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use log::error;
+use log::warn;
+use tokio::sync::mpsc;
+
+use crate::agent::jsonrpc::Response as AgentJsonRpcResponse;
+use crate::balancer::agent_controller_pool::AgentControllerPool;
+use crate::balancer::chat_template_override_sender_collection::ChatTemplateOverrideSenderCollection;
+use crate::balancer::embedding_sender_collection::EmbeddingSenderCollection;
+use crate::balancer::generate_tokens_sender_collection::GenerateTokensSenderCollection;
+use crate::balancer::manages_senders::ManagesSenders as _;
+use crate::balancer::model_metadata_sender_collection::ModelMetadataSenderCollection;
+use crate::jsonrpc::ResponseEnvelope;
+
+/// Forwards the responses of a single agent connection one at a time, in the order the agent
+/// sent them. While a response waits for room in the buffer of its request, the responses
+/// to the other requests wait too, and the queue fills up until the agent socket is not read.
+pub struct AgentResponseForwarder {
+    pub agent_controller_pool: Arc<AgentControllerPool>,
+    pub agent_id: String,
+    pub chat_template_override_sender_collection: Arc<ChatTemplateOverrideSenderCollection>,
+    pub embedding_sender_collection: Arc<EmbeddingSenderCollection>,
+    pub generate_tokens_sender_collection: Arc<GenerateTokensSenderCollection>,
+    pub model_metadata_sender_collection: Arc<ModelMetadataSenderCollection>,
+}
+
+impl AgentResponseForwarder {
+    /// Finishes after the connection closes and the queued responses are forwarded
+    pub async fn run(
+        self,
+        mut response_rx: mpsc::Receiver<ResponseEnvelope<AgentJsonRpcResponse>>,
+    ) {
+        while let Some(response_envelope) = response_rx.recv().await {
+            self.forward_response(response_envelope).await;
+        }
+    }
+
+    async fn forward_response(
+        &self,
+        ResponseEnvelope {
+            request_id,
+            response,
+        }: ResponseEnvelope<AgentJsonRpcResponse>,
+    ) {
+        match response {
+            AgentJsonRpcResponse::ChatTemplateOverride(chat_template_override) => {
+                self.chat_template_override_sender_collection
+                    .forward_response_safe(request_id, chat_template_override)
+                    .await;
+            }
+            AgentJsonRpcResponse::Embedding(embedding_result) => {
+                match self
+                    .agent_controller_pool
+                    .get_agent_controller(&self.agent_id)
+                {
+                    Some(agent_controller) => agent_controller
+                        .forward_streaming_response(
+                            request_id,
+                            self.embedding_sender_collection.as_ref(),
+                            embedding_result,
+                        )
+                        .await
+                        .unwrap_or_else(|err| {
+                            warn!("Error forwarding response: {err}");
+                        }),
+                    None => error!("Agent controller not found for agent: {}", self.agent_id),
+                }
+            }
+            AgentJsonRpcResponse::GeneratedToken(generated_token_result) => {
+                match self
+                    .agent_controller_pool
+                    .get_agent_controller(&self.agent_id)
+                {
+                    Some(agent_controller) => agent_controller
+                        .forward_streaming_response(
+                            request_id,
+                            self.generate_tokens_sender_collection.as_ref(),
+                            generated_token_result,
+                        )
+                        .await
+                        .unwrap_or_else(|err| {
+                            warn!("Error forwarding response: {err}");
+                        }),
+                    None => error!("Agent controller not found for agent: {}", self.agent_id),
+                }
+            }
+            AgentJsonRpcResponse::ModelMetadata(model_metadata) => {
+                self.model_metadata_sender_collection
+                    .forward_response_safe(request_id, model_metadata)
+                    .await;
+            }
+        }
+    }
+}
@@ -1,34 +1,32 @@
 use std::sync::Arc;
 
-use log::error;
-use log::info;
+use tokio::sync::mpsc;
 
+use crate::agent::jsonrpc::Response as AgentJsonRpcResponse;
 use crate::balancer::agent_controller_pool::AgentControllerPool;
+use crate::balancer::agent_token_registry_holder::AgentTokenRegistryHolder;
 use crate::balancer::chat_template_override_sender_collection::ChatTemplateOverrideSenderCollection;
+use crate::balancer::client_certificate::ClientCertificate;
 use crate::balancer::embedding_sender_collection::EmbeddingSenderCollection;
 use crate::balancer::generate_tokens_sender_collection::GenerateTokensSenderCollection;
 use crate::balancer::model_metadata_sender_collection::ModelMetadataSenderCollection;
 use crate::balancer_applicable_state_holder::BalancerApplicableStateHolder;
+use crate::heartbeat_monitor::HeartbeatMonitor;
+use crate::jsonrpc::ResponseEnvelope;
 
 pub struct AgentSocketControllerContext {
+    /// Older agents reject the handshake fields they do not know
+    pub agent_announces_protocol_version: bool,
     pub agent_controller_pool: Arc<AgentControllerPool>,
     pub agent_id: String,
+    pub agent_response_tx: mpsc::Sender<ResponseEnvelope<AgentJsonRpcResponse>>,
+    pub agent_token: Option<String>,
+    pub agent_token_registry_holder: Arc<AgentTokenRegistryHolder>,
     pub balancer_applicable_state_holder: Arc<BalancerApplicableStateHolder>,
     pub chat_template_override_sender_collection: Arc<ChatTemplateOverrideSenderCollection>,
+    pub client_certificate: Option<ClientCertificate>,
     pub embedding_sender_collection: Arc<EmbeddingSenderCollection>,
     pub generate_tokens_sender_collection: Arc<GenerateTokensSenderCollection>,
+    pub heartbeat_monitor: HeartbeatMonitor,
     pub model_metadata_sender_collection: Arc<ModelMetadataSenderCollection>,
 }
-
-impl Drop for AgentSocketControllerContext {
-    fn drop(&mut self) {
-        if let Err(err) = self
-            .agent_controller_pool
-            .remove_agent_controller(&self.agent_id)
-        {
-            error!("Failed to remove agent: {err}");
-        }
-
-        info!("Removed agent: {}", self.agent_id);
-    }
-}
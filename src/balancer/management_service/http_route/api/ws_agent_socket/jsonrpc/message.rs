@@ -17,3 +17,126 @@ pub enum Message {
 }
 
 impl RpcMessage for Message {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::collections::BTreeSet;
+
+    use anyhow::Result;
+
+    use super::*;
+    use crate::agent_issue::AgentIssue;
+    use crate::agent_state_application_status::AgentStateApplicationStatus;
+    use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::notification_params::RegisterAgentParams;
+    use crate::balancer::management_service::http_route::api::ws_agent_socket::jsonrpc::notification_params::UpdateAgentStatusParams;
+    use crate::chat_template::ChatTemplate;
+    use crate::embedding::Embedding;
+    use crate::embedding_normalization_method::EmbeddingNormalizationMethod;
+    use crate::embedding_result::EmbeddingResult;
+    use crate::generated_token_result::GeneratedTokenResult;
+    use crate::model_metadata::ModelMetadata;
+    use crate::pooling_type::PoolingType;
+    use crate::protocol_feature::ProtocolFeature;
+    use crate::protocol_version_range::ProtocolVersionRange;
+    use crate::slot_aggregated_status_snapshot::SlotAggregatedStatusSnapshot;
+
+    /// Messages are compared through their JSON form, which is what the other peers still use
+    fn assert_survives_message_pack(message: Message) -> Result<()> {
+        let decoded_message: Message = rmp_serde::from_slice(&rmp_serde::to_vec_named(&message)?)?;
+
+        assert_eq!(
+            serde_json::to_value(decoded_message)?,
+            serde_json::to_value(message)?
+        );
+
+        Ok(())
+    }
+
+    fn slot_aggregated_status_snapshot() -> SlotAggregatedStatusSnapshot {
+        SlotAggregatedStatusSnapshot {
+            desired_slots_total: 4,
+            download_current: 10,
+            download_filename: Some("model.gguf".to_string()),
+            download_total: 100,
+            issues: BTreeSet::from([AgentIssue::ModelFileDoesNotExist(
+                "/models/model.gguf".to_string(),
+            )]),
+            model_path: None,
+            slots_processing: 1,
+            slots_total: 4,
+            state_application_status: AgentStateApplicationStatus::AttemptedAndRetrying,
+            uses_chat_template_override: false,
+            version: 3,
+        }
+    }
+
+    #[test]
+    fn test_error_survives_message_pack() -> Result<()> {
+        assert_survives_message_pack(Message::Error(ErrorEnvelope {
+            request_id: "request-1".to_string(),
+            error: Error {
+                code: 503,
+                description: "Agent is draining and does not accept request request-1".to_string(),
+            },
+        }))
+    }
+
+    #[test]
+    fn test_notifications_survive_message_pack() -> Result<()> {
+        for notification in [
+            Notification::DeregisterAgent,
+            Notification::Heartbeat,
+            Notification::RegisterAgent(RegisterAgentParams {
+                features: BTreeSet::from(ProtocolFeature::SUPPORTED),
+                name: Some("agent-1".to_string()),
+                protocol_version_range: Some(ProtocolVersionRange::SUPPORTED),
+                resume_secret: Some("resume-secret".to_string()),
+                slot_aggregated_status_snapshot: slot_aggregated_status_snapshot(),
+            }),
+            Notification::UpdateAgentStatus(UpdateAgentStatusParams {
+                slot_aggregated_status_snapshot: slot_aggregated_status_snapshot(),
+            }),
+        ] {
+            assert_survives_message_pack(Message::Notification(notification))?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_responses_survive_message_pack() -> Result<()> {
+        for response in [
+            Response::ChatTemplateOverride(None),
+            Response::ChatTemplateOverride(Some(ChatTemplate {
+                content: "{{ messages }}".to_string(),
+            })),
+            Response::Embedding(EmbeddingResult::Done),
+            Response::Embedding(EmbeddingResult::Embedding(Embedding {
+                embedding: vec![0.25, -1.5, 3.0],
+                normalization_method: EmbeddingNormalizationMethod::L2,
+                pooling_type: PoolingType::Mean,
+                source_document_id: "document-1".to_string(),
+            })),
+            Response::Embedding(EmbeddingResult::Error("Unable to embed".to_string())),
+            Response::Embedding(EmbeddingResult::InputTokens(12)),
+            Response::GeneratedToken(GeneratedTokenResult::ChatTemplateError(
+                "Unable to render".to_string(),
+            )),
+            Response::GeneratedToken(GeneratedTokenResult::Done),
+            Response::GeneratedToken(GeneratedTokenResult::PromptTokens(12)),
+            Response::GeneratedToken(GeneratedTokenResult::Token("Hello".to_string())),
+            Response::ModelMetadata(None),
+            Response::ModelMetadata(Some(ModelMetadata {
+                metadata: BTreeMap::from([("general.name".to_string(), "Model".to_string())]),
+            })),
+        ] {
+            assert_survives_message_pack(Message::Response(ResponseEnvelope {
+                request_id: "request-1".to_string(),
+                response,
+            }))?;
+        }
+
+        Ok(())
+    }
+}
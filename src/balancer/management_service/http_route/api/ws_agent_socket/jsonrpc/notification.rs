@@ -8,6 +8,7 @@ use super::notification_params::UpdateAgentStatusParams;
 #[serde(deny_unknown_fields)]
 pub enum Notification {
     DeregisterAgent,
+    Heartbeat,
     RegisterAgent(RegisterAgentParams),
     UpdateAgentStatus(UpdateAgentStatusParams),
 }
@@ -1,11 +1,83 @@
+use std::collections::BTreeSet;
+
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::protocol_feature::ProtocolFeature;
+use crate::protocol_version_range::ProtocolVersionRange;
 use crate::slot_aggregated_status_snapshot::SlotAggregatedStatusSnapshot;
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct RegisterAgentParams {
+    /// Sent only to the peers that announce their protocol version range
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub features: BTreeSet<ProtocolFeature>,
     pub name: Option<String>,
+    /// Peers from before the negotiation was introduced neither send nor accept it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol_version_range: Option<ProtocolVersionRange>,
+    /// Issued to the previous connection of the agent. Sent only to the management servers
+    /// that support the request resumption
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resume_secret: Option<String>,
     pub slot_aggregated_status_snapshot: SlotAggregatedStatusSnapshot,
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::json;
+
+    use super::*;
+    use crate::agent_state_application_status::AgentStateApplicationStatus;
+
+    fn slot_aggregated_status_snapshot() -> SlotAggregatedStatusSnapshot {
+        SlotAggregatedStatusSnapshot {
+            desired_slots_total: 1,
+            download_current: 0,
+            download_filename: None,
+            download_total: 0,
+            issues: BTreeSet::new(),
+            model_path: None,
+            slots_processing: 0,
+            slots_total: 1,
+            state_application_status: AgentStateApplicationStatus::Applied,
+            uses_chat_template_override: false,
+            version: 0,
+        }
+    }
+
+    #[test]
+    fn test_accepts_registration_from_older_agent() -> Result<()> {
+        let register_agent_params: RegisterAgentParams = serde_json::from_value(json!({
+            "name": "agent-1",
+            "slot_aggregated_status_snapshot": slot_aggregated_status_snapshot(),
+        }))?;
+
+        assert!(register_agent_params.features.is_empty());
+        assert_eq!(register_agent_params.protocol_version_range, None);
+        assert_eq!(register_agent_params.resume_secret, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sends_older_balancers_only_the_original_fields() -> Result<()> {
+        assert_eq!(
+            serde_json::to_value(RegisterAgentParams {
+                features: BTreeSet::new(),
+                name: Some("agent-1".to_string()),
+                protocol_version_range: None,
+                resume_secret: None,
+                slot_aggregated_status_snapshot: slot_aggregated_status_snapshot(),
+            })?,
+            json!({
+                "name": "agent-1",
+                "slot_aggregated_status_snapshot": slot_aggregated_status_snapshot(),
+            })
+        );
+
+        Ok(())
+    }
+}
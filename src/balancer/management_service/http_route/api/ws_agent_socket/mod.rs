@@ -1,6 +1,8 @@
+mod agent_response_forwarder;
 mod agent_socket_controller_context;
 pub mod jsonrpc;
 
+use std::collections::BTreeSet;
 use std::sync::Arc;
 use std::sync::RwLock;
 use std::sync::atomic::AtomicBool;
@@ -19,13 +21,19 @@ use actix_web::web::ServiceConfig;
 use actix_ws::Session;
 use anyhow::Context;
 use anyhow::Result;
+use anyhow::anyhow;
 use async_trait::async_trait;
 use log::error;
 use log::info;
+use log::warn;
+use nanoid::nanoid;
 use serde::Deserialize;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc;
+use tokio::time::MissedTickBehavior;
+use tokio::time::interval;
 
+use self::agent_response_forwarder::AgentResponseForwarder;
 use self::agent_socket_controller_context::AgentSocketControllerContext;
 use self::jsonrpc::Message as ManagementJsonRpcMessage;
 use self::jsonrpc::Notification as ManagementJsonRpcNotification;
@@ -33,13 +41,18 @@ use self::jsonrpc::notification_params::RegisterAgentParams;
 use self::jsonrpc::notification_params::UpdateAgentStatusParams;
 use crate::agent::jsonrpc::Message as AgentJsonRpcMessage;
 use crate::agent::jsonrpc::Notification as AgentJsonRpcNotification;
-use crate::agent::jsonrpc::Response as AgentJsonRpcResponse;
+use crate::agent::jsonrpc::notification_params::AgentRegisteredParams;
+use crate::agent::jsonrpc::notification_params::RegistrationRejectedParams;
 use crate::agent::jsonrpc::notification_params::VersionParams;
 use crate::atomic_value::AtomicValue;
+use crate::balancer::agent_circuit_breaker::AgentCircuitBreaker;
 use crate::balancer::agent_controller::AgentController;
 use crate::balancer::agent_controller_pool::AgentControllerPool;
 use crate::balancer::agent_controller_update_result::AgentControllerUpdateResult;
+use crate::balancer::agent_token_registry_holder::AgentTokenRegistryHolder;
+use crate::balancer::authorization_bearer_token::authorization_bearer_token;
 use crate::balancer::chat_template_override_sender_collection::ChatTemplateOverrideSenderCollection;
+use crate::balancer::client_certificate::ClientCertificate;
 use crate::balancer::embedding_sender_collection::EmbeddingSenderCollection;
 use crate::balancer::generate_tokens_sender_collection::GenerateTokensSenderCollection;
 use crate::balancer::management_service::app_data::AppData;
@@ -49,20 +62,31 @@ use crate::balancer_applicable_state_holder::BalancerApplicableStateHolder;
 use crate::controls_session::ControlsSession as _;
 use crate::controls_websocket_endpoint::ContinuationDecision;
 use crate::controls_websocket_endpoint::ControlsWebSocketEndpoint;
-use crate::jsonrpc::ResponseEnvelope;
+use crate::heartbeat_monitor::HeartbeatMonitor;
+use crate::jsonrpc::ErrorEnvelope;
+use crate::message_framing::MessageFraming;
+use crate::protocol_feature::ProtocolFeature;
+use crate::protocol_version_range::ProtocolVersionRange;
 use crate::sets_desired_state::SetsDesiredState as _;
 use crate::slot_aggregated_status_snapshot::SlotAggregatedStatusSnapshot;
 use crate::websocket_session_controller::WebSocketSessionController;
 
+/// Responses of a single agent connection that wait to be forwarded to the requests
+const AGENT_RESPONSE_QUEUE_CAPACITY: usize = 64;
+
 pub fn register(cfg: &mut ServiceConfig) {
     cfg.service(respond);
 }
 
 struct AgentSocketController {
+    agent_announces_protocol_version: bool,
     agent_controller_pool: Arc<AgentControllerPool>,
     agent_id: String,
+    agent_token: Option<String>,
+    agent_token_registry_holder: Arc<AgentTokenRegistryHolder>,
     balancer_applicable_state_holder: Arc<BalancerApplicableStateHolder>,
     chat_template_override_sender_collection: Arc<ChatTemplateOverrideSenderCollection>,
+    client_certificate: Option<ClientCertificate>,
     embedding_sender_collection: Arc<EmbeddingSenderCollection>,
     generate_tokens_sender_collection: Arc<GenerateTokensSenderCollection>,
     model_metadata_sender_collection: Arc<ModelMetadataSenderCollection>,
@@ -75,41 +99,155 @@ impl ControlsWebSocketEndpoint for AgentSocketController {
     type OutgoingMessage = AgentJsonRpcMessage;
 
     fn create_context(&self) -> Self::Context {
+        let (agent_response_tx, agent_response_rx) = mpsc::channel(AGENT_RESPONSE_QUEUE_CAPACITY);
+
+        rt::spawn(
+            AgentResponseForwarder {
+                agent_controller_pool: self.agent_controller_pool.clone(),
+                agent_id: self.agent_id.clone(),
+                chat_template_override_sender_collection: self
+                    .chat_template_override_sender_collection
+                    .clone(),
+                embedding_sender_collection: self.embedding_sender_collection.clone(),
+                generate_tokens_sender_collection: self.generate_tokens_sender_collection.clone(),
+                model_metadata_sender_collection: self.model_metadata_sender_collection.clone(),
+            }
+            .run(agent_response_rx),
+        );
+
         AgentSocketControllerContext {
+            agent_announces_protocol_version: self.agent_announces_protocol_version,
             agent_controller_pool: self.agent_controller_pool.clone(),
             agent_id: self.agent_id.clone(),
+            agent_response_tx,
+            agent_token: self.agent_token.clone(),
+            agent_token_registry_holder: self.agent_token_registry_holder.clone(),
             balancer_applicable_state_holder: self.balancer_applicable_state_holder.clone(),
             chat_template_override_sender_collection: self
                 .chat_template_override_sender_collection
                 .clone(),
+            client_certificate: self.client_certificate.clone(),
             embedding_sender_collection: self.embedding_sender_collection.clone(),
             generate_tokens_sender_collection: self.generate_tokens_sender_collection.clone(),
+            heartbeat_monitor: HeartbeatMonitor::new(
+                self.agent_controller_pool
+                    .agent_heartbeat_configuration
+                    .clone(),
+            ),
             model_metadata_sender_collection: self.model_metadata_sender_collection.clone(),
         }
     }
 
+    /// Responses are queued in the order they arrive, the rest is handled concurrently
+    async fn dispatch_deserialized_message(
+        connection_close_tx: broadcast::Sender<()>,
+        context: Arc<Self::Context>,
+        deserialized_message: Self::IncomingMessage,
+        websocket_session_controller: WebSocketSessionController<Self::OutgoingMessage>,
+    ) -> Result<ContinuationDecision> {
+        match deserialized_message {
+            ManagementJsonRpcMessage::Response(_) => {
+                Self::handle_deserialized_message(
+                    connection_close_tx,
+                    context,
+                    deserialized_message,
+                    websocket_session_controller,
+                )
+                .await
+            }
+            _ => {
+                Self::spawn_deserialized_message_handler(
+                    connection_close_tx,
+                    context,
+                    deserialized_message,
+                    websocket_session_controller,
+                );
+
+                Ok(ContinuationDecision::Continue)
+            }
+        }
+    }
+
+    async fn handle_binary_message(
+        connection_close_tx: broadcast::Sender<()>,
+        context: Arc<Self::Context>,
+        bytes: &[u8],
+        websocket_session_controller: WebSocketSessionController<Self::OutgoingMessage>,
+    ) -> Result<ContinuationDecision> {
+        match rmp_serde::from_slice::<Self::IncomingMessage>(bytes) {
+            Ok(deserialized_message) => {
+                Self::dispatch_deserialized_message(
+                    connection_close_tx,
+                    context,
+                    deserialized_message,
+                    websocket_session_controller,
+                )
+                .await
+            }
+            Err(err) => {
+                error!("Paddler-RPC MessagePack decoding error: {err}");
+
+                Ok(ContinuationDecision::Continue)
+            }
+        }
+    }
+
     async fn handle_deserialized_message(
         connection_close_tx: broadcast::Sender<()>,
         context: Arc<Self::Context>,
         deserialized_message: Self::IncomingMessage,
         mut websocket_session_controller: WebSocketSessionController<Self::OutgoingMessage>,
     ) -> Result<ContinuationDecision> {
+        context.heartbeat_monitor.record_beat();
+
         match deserialized_message {
-            ManagementJsonRpcMessage::Error(err) => {
-                error!("Received error message: {err:?}");
+            ManagementJsonRpcMessage::Error(ErrorEnvelope { request_id, error }) => {
+                error!("Agent failed to respond to request {request_id:?}: {error:?}");
+
+                // Request that waits for the responses fails when they end before it is done
+                if context
+                    .embedding_sender_collection
+                    .deregister_sender(request_id.clone())
+                    .is_err()
+                    && context
+                        .generate_tokens_sender_collection
+                        .deregister_sender(request_id.clone())
+                        .is_err()
+                {
+                    warn!("No request is waiting for the responses to {request_id:?}");
+                }
 
                 Ok(ContinuationDecision::Continue)
             }
             ManagementJsonRpcMessage::Notification(
                 ManagementJsonRpcNotification::DeregisterAgent,
             ) => {
-                connection_close_tx.send(())?;
+                // Agent is going to close the connection by itself after it finishes
+                // the requests it is still processing
+                if let Some(agent_controller) = context
+                    .agent_controller_pool
+                    .get_agent_controller(&context.agent_id)
+                {
+                    info!("Agent is draining: {}", context.agent_id);
+
+                    agent_controller.is_draining.set(true);
+                    context
+                        .agent_controller_pool
+                        .update_notifier
+                        .notify_waiters();
+                }
 
-                return Ok(ContinuationDecision::Stop);
+                Ok(ContinuationDecision::Continue)
+            }
+            ManagementJsonRpcMessage::Notification(ManagementJsonRpcNotification::Heartbeat) => {
+                Ok(ContinuationDecision::Continue)
             }
             ManagementJsonRpcMessage::Notification(
                 ManagementJsonRpcNotification::RegisterAgent(RegisterAgentParams {
+                    features,
                     name,
+                    protocol_version_range,
+                    resume_secret,
                     slot_aggregated_status_snapshot:
                         SlotAggregatedStatusSnapshot {
                             desired_slots_total,
@@ -126,19 +264,79 @@ impl ControlsWebSocketEndpoint for AgentSocketController {
                         },
                 }),
             ) => {
+                // A verified client certificate identifies the agent instead of the token
+                let authentication_result = match &context.client_certificate {
+                    Some(client_certificate) => {
+                        client_certificate.authenticate_agent(name.as_deref())
+                    }
+                    None => context
+                        .agent_token_registry_holder
+                        .authenticate(name.as_deref(), context.agent_token.as_deref()),
+                };
+
+                let protocol_version_result = authentication_result.and_then(|()| {
+                    let protocol_version_range =
+                        protocol_version_range.unwrap_or_else(ProtocolVersionRange::legacy);
+
+                    ProtocolVersionRange::SUPPORTED
+                        .negotiate(&protocol_version_range)
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "Agent supports protocol versions {protocol_version_range}, but the balancer supports {}",
+                                ProtocolVersionRange::SUPPORTED
+                            )
+                        })
+                });
+
+                let protocol_version = match protocol_version_result {
+                    Ok(protocol_version) => protocol_version,
+                    Err(err) => {
+                        warn!(
+                            "Rejected agent {} (name: {name:?}): {err}",
+                            context.agent_id
+                        );
+
+                        websocket_session_controller
+                            .send_response(AgentJsonRpcMessage::Notification(
+                                AgentJsonRpcNotification::RegistrationRejected(
+                                    RegistrationRejectedParams {
+                                        reason: err.to_string(),
+                                    },
+                                ),
+                            ))
+                            .await
+                            .unwrap_or_else(|err| {
+                                error!("Failed to send registration rejection: {err}");
+                            });
+
+                        return Ok(ContinuationDecision::Stop);
+                    }
+                };
+
                 let (agent_message_tx, mut agent_message_rx) =
                     mpsc::unbounded_channel::<AgentJsonRpcMessage>();
                 let agent_controller = Arc::new(AgentController {
                     agent_message_tx,
+                    agent_token: context.agent_token.clone(),
                     chat_template_override_sender_collection: context
                         .chat_template_override_sender_collection
                         .clone(),
+                    circuit_breaker: AgentCircuitBreaker::new(
+                        context.agent_id.clone(),
+                        context
+                            .agent_controller_pool
+                            .agent_circuit_breaker_configuration
+                            .clone(),
+                        context.agent_controller_pool.update_notifier.clone(),
+                    ),
                     connection_close_rx: connection_close_tx.subscribe(),
+                    connection_close_tx: connection_close_tx.clone(),
                     desired_slots_total: AtomicValue::<AtomicI32>::new(desired_slots_total),
                     download_current: AtomicValue::<AtomicUsize>::new(download_current),
                     download_filename: RwLock::new(download_filename),
                     download_total: AtomicValue::<AtomicUsize>::new(download_total),
                     embedding_sender_collection: context.embedding_sender_collection.clone(),
+                    features,
                     generate_tokens_sender_collection: context
                         .generate_tokens_sender_collection
                         .clone(),
@@ -146,10 +344,19 @@ impl ControlsWebSocketEndpoint for AgentSocketController {
                         .model_metadata_sender_collection
                         .clone(),
                     id: context.agent_id.clone(),
+                    is_identified_by_client_certificate: context.client_certificate.is_some(),
+                    is_cordoned: AtomicValue::<AtomicBool>::new(false),
+                    is_draining: AtomicValue::<AtomicBool>::new(false),
                     issues: RwLock::new(issues),
                     model_path: RwLock::new(model_path),
                     name,
                     newest_update_version: AtomicValue::<AtomicI32>::new(version),
+                    protocol_version,
+                    resume_secret: nanoid!(32),
+                    response_buffer_configuration: context
+                        .agent_controller_pool
+                        .response_buffer_configuration
+                        .clone(),
                     slots_processing: AtomicValue::<AtomicI32>::new(slots_processing),
                     slots_total: AtomicValue::<AtomicI32>::new(slots_total),
                     state_application_status_code: AtomicValue::<AtomicI32>::new(
@@ -160,24 +367,64 @@ impl ControlsWebSocketEndpoint for AgentSocketController {
                     ),
                 });
 
-                context
-                    .agent_controller_pool
-                    .register_agent_controller(context.agent_id.clone(), agent_controller.clone())
-                    .context("Unable to register agent controller")?;
+                if let Err(err) = context.agent_controller_pool.register_agent_controller(
+                    context.agent_id.clone(),
+                    agent_controller.clone(),
+                    resume_secret.as_deref(),
+                ) {
+                    warn!("Rejected agent {}: {err}", context.agent_id);
 
-                if let Some(desired_state) = context
-                    .balancer_applicable_state_holder
-                    .get_agent_desired_state()
-                {
-                    agent_controller
-                        .set_desired_state(desired_state)
+                    websocket_session_controller
+                        .send_response(AgentJsonRpcMessage::Notification(
+                            AgentJsonRpcNotification::RegistrationRejected(
+                                RegistrationRejectedParams {
+                                    reason: err.to_string(),
+                                },
+                            ),
+                        ))
                         .await
-                        .context("Unable to set desired state")?;
+                        .unwrap_or_else(|err| {
+                            error!("Failed to send registration rejection: {err}");
+                        });
+
+                    return Ok(ContinuationDecision::Stop);
                 }
 
-                info!("Registered agent: {}", context.agent_id);
+                info!(
+                    "Registered agent: {} (protocol version {protocol_version})",
+                    context.agent_id
+                );
 
+                let desired_state = context
+                    .balancer_applicable_state_holder
+                    .get_agent_desired_state();
+                let mut heartbeat_ticker = interval(
+                    context
+                        .agent_controller_pool
+                        .agent_heartbeat_configuration
+                        .interval,
+                );
                 let mut shutdown_tx_resubscribed = connection_close_tx.subscribe();
+                let registered_agent_controller = agent_controller.clone();
+                let sends_heartbeats = agent_controller.supports(ProtocolFeature::Heartbeat);
+
+                websocket_session_controller
+                    .set_message_framing(MessageFraming::from_features(&agent_controller.features));
+
+                heartbeat_ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+                if agent_controller.supports(ProtocolFeature::RequestResumption) {
+                    websocket_session_controller
+                        .send_response(AgentJsonRpcMessage::Notification(
+                            AgentJsonRpcNotification::AgentRegistered(AgentRegisteredParams {
+                                resume_secret: agent_controller.resume_secret.clone(),
+                            }),
+                        ))
+                        .await
+                        .unwrap_or_else(|err| {
+                            error!("Failed to send the resume secret: {err}");
+                        });
+                }
 
                 rt::spawn(async move {
                     loop {
@@ -185,6 +432,31 @@ impl ControlsWebSocketEndpoint for AgentSocketController {
                             _ = shutdown_tx_resubscribed.recv() => {
                                 break;
                             }
+                            _ = heartbeat_ticker.tick() => {
+                                // Closing the connection removes the agent. Requests that are waiting
+                                // for it fail, unless it reconnects in time to resume them. Agents
+                                // without the heartbeats can stay silent for a long time
+                                if sends_heartbeats && context.heartbeat_monitor.has_missed_beats() {
+                                    warn!("Agent stopped sending heartbeats, evicting it: {}", context.agent_id);
+
+                                    if let Err(err) = connection_close_tx.send(()) {
+                                        error!("Failed to send connection close signal: {err}");
+                                    }
+
+                                    break;
+                                }
+
+                                if sends_heartbeats {
+                                    websocket_session_controller
+                                        .send_response(AgentJsonRpcMessage::Notification(
+                                            AgentJsonRpcNotification::Heartbeat,
+                                        ))
+                                        .await
+                                        .unwrap_or_else(|err| {
+                                            error!("Error sending heartbeat: {err}");
+                                        });
+                                }
+                            }
                             result = agent_message_rx.recv() => {
                                 match result {
                                     Some(message) => {
@@ -203,8 +475,26 @@ impl ControlsWebSocketEndpoint for AgentSocketController {
                             }
                         }
                     }
+
+                    // Agent that already reconnected keeps its new connection
+                    match context
+                        .agent_controller_pool
+                        .remove_agent_controller(&registered_agent_controller)
+                    {
+                        Ok(true) => info!("Removed agent: {}", context.agent_id),
+                        Ok(false) => {}
+                        Err(err) => error!("Failed to remove agent: {err}"),
+                    }
                 });
 
+                // The connection closes if this fails, which removes the agent again
+                if let Some(desired_state) = desired_state {
+                    agent_controller
+                        .set_desired_state(desired_state)
+                        .await
+                        .context("Unable to set desired state")?;
+                }
+
                 Ok(ContinuationDecision::Continue)
             }
             ManagementJsonRpcMessage::Notification(
@@ -233,47 +523,12 @@ impl ControlsWebSocketEndpoint for AgentSocketController {
 
                 Ok(ContinuationDecision::Continue)
             }
-            ManagementJsonRpcMessage::Response(ResponseEnvelope {
-                request_id,
-                response: AgentJsonRpcResponse::ChatTemplateOverride(chat_template_override),
-            }) => {
-                context
-                    .chat_template_override_sender_collection
-                    .forward_response_safe(request_id, chat_template_override)
-                    .await;
-
-                Ok(ContinuationDecision::Continue)
-            }
-            ManagementJsonRpcMessage::Response(ResponseEnvelope {
-                request_id,
-                response: AgentJsonRpcResponse::Embedding(embedding_result),
-            }) => {
+            ManagementJsonRpcMessage::Response(response_envelope) => {
                 context
-                    .embedding_sender_collection
-                    .forward_response_safe(request_id, embedding_result)
-                    .await;
-
-                Ok(ContinuationDecision::Continue)
-            }
-            ManagementJsonRpcMessage::Response(ResponseEnvelope {
-                request_id,
-                response: AgentJsonRpcResponse::GeneratedToken(generated_token_envelope),
-            }) => {
-                context
-                    .generate_tokens_sender_collection
-                    .forward_response_safe(request_id, generated_token_envelope)
-                    .await;
-
-                Ok(ContinuationDecision::Continue)
-            }
-            ManagementJsonRpcMessage::Response(ResponseEnvelope {
-                request_id,
-                response: AgentJsonRpcResponse::ModelMetadata(model_metadata),
-            }) => {
-                context
-                    .model_metadata_sender_collection
-                    .forward_response_safe(request_id, model_metadata)
-                    .await;
+                    .agent_response_tx
+                    .send(response_envelope)
+                    .await
+                    .context("Agent response forwarder stopped")?;
 
                 Ok(ContinuationDecision::Continue)
             }
@@ -281,14 +536,26 @@ impl ControlsWebSocketEndpoint for AgentSocketController {
     }
 
     async fn on_connection_start(
-        _context: Arc<Self::Context>,
+        context: Arc<Self::Context>,
         session: &mut Session,
     ) -> Result<ContinuationDecision> {
+        let version_params = if context.agent_announces_protocol_version {
+            VersionParams {
+                features: BTreeSet::from(ProtocolFeature::SUPPORTED),
+                protocol_version_range: Some(ProtocolVersionRange::SUPPORTED),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            }
+        } else {
+            VersionParams {
+                features: BTreeSet::new(),
+                protocol_version_range: None,
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            }
+        };
+
         if let Err(err) = session
             .text(serde_json::to_string(&AgentJsonRpcMessage::Notification(
-                AgentJsonRpcNotification::Version(VersionParams {
-                    version: env!("CARGO_PKG_VERSION").to_string(),
-                }),
+                AgentJsonRpcNotification::Version(version_params),
             ))?)
             .await
         {
@@ -314,13 +581,36 @@ async fn respond(
     payload: Payload,
     req: HttpRequest,
 ) -> Result<HttpResponse, Error> {
+    let agent_token = authorization_bearer_token(&req);
+    let client_certificate = req.conn_data::<ClientCertificate>().cloned();
+
+    // Agents that do not know any valid token are turned away before the upgrade
+    if client_certificate.is_none()
+        && !app_data
+            .agent_token_registry_holder
+            .is_known_token(agent_token.as_deref())
+    {
+        warn!(
+            "Rejected agent {} connecting without a valid token",
+            path_params.agent_id
+        );
+
+        return Ok(HttpResponse::Unauthorized().body("Agent token is missing or invalid"));
+    }
+
     let agent_socket_controller = AgentSocketController {
+        agent_announces_protocol_version: req
+            .headers()
+            .contains_key(ProtocolVersionRange::HEADER_NAME),
         agent_controller_pool: app_data.agent_controller_pool.clone(),
         agent_id: path_params.agent_id.clone(),
+        agent_token,
+        agent_token_registry_holder: app_data.agent_token_registry_holder.clone(),
         balancer_applicable_state_holder: app_data.balancer_applicable_state_holder.clone(),
         chat_template_override_sender_collection: app_data
             .chat_template_override_sender_collection
             .clone(),
+        client_certificate,
         embedding_sender_collection: app_data.embedding_sender_collection.clone(),
         generate_tokens_sender_collection: app_data.generate_tokens_sender_collection.clone(),
         model_metadata_sender_collection: app_data.model_metadata_sender_collection.clone(),
@@ -328,3 +618,693 @@ async fn respond(
 
     agent_socket_controller.respond(payload, req)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    use actix_web::App;
+    use actix_web::HttpServer;
+    use actix_web::dev::ServerHandle;
+    use futures_util::SinkExt as _;
+    use futures_util::StreamExt as _;
+    use reqwest::StatusCode;
+    use serde_json::json;
+    use tokio::net::TcpStream;
+    use tokio::time::timeout;
+    use tokio_tungstenite::MaybeTlsStream;
+    use tokio_tungstenite::WebSocketStream;
+    use tokio_tungstenite::connect_async;
+    use tokio_tungstenite::tungstenite::protocol::Message;
+
+    use super::*;
+    use crate::agent::jsonrpc::Request as AgentJsonRpcRequest;
+    use crate::agent::jsonrpc::Response as AgentJsonRpcResponse;
+    use crate::agent::jsonrpc::notification_params::ReattachRequestParams;
+    use crate::agent_state_application_status::AgentStateApplicationStatus;
+    use crate::balancer::agent_circuit_breaker_configuration::AgentCircuitBreakerConfiguration;
+    use crate::balancer::agent_token_registry::AgentTokenRegistry;
+    use crate::balancer::buffered_request_manager::BufferedRequestManager;
+    use crate::balancer::buffered_request_priority_class_registry::BufferedRequestPriorityClassRegistry;
+    use crate::balancer::inference_client::Message as OutgoingMessage;
+    use crate::balancer::inference_service::configuration::Configuration as InferenceServiceConfiguration;
+    use crate::balancer::management_service::http_route::api::post_agent_cordon;
+    use crate::balancer::management_service::http_route::api::post_agent_disconnect;
+    use crate::balancer::management_service::http_route::api::post_agent_uncordon;
+    use crate::balancer::management_service::make_test_app_data::make_test_app_data;
+    use crate::balancer::request_from_agent::request_from_agent;
+    use crate::controls_session::ControlsSession;
+    use crate::drain_status::DrainStatus;
+    use crate::full_buffer_policy::FullBufferPolicy;
+    use crate::generated_token_result::GeneratedTokenResult;
+    use crate::heartbeat_configuration::HeartbeatConfiguration;
+    use crate::jsonrpc::RequestEnvelope;
+    use crate::jsonrpc::ResponseEnvelope;
+    use crate::request_params::ContinueFromRawPromptParams;
+    use crate::response_buffer_configuration::ResponseBufferConfiguration;
+    use crate::server_addr::ServerAddr;
+
+    type AgentConnection = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+    const MESSAGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+    struct ChannelSessionController {
+        message_tx: mpsc::UnboundedSender<OutgoingMessage>,
+    }
+
+    #[async_trait]
+    impl ControlsSession<OutgoingMessage> for ChannelSessionController {
+        async fn send_response(&mut self, message: OutgoingMessage) -> Result<()> {
+            Ok(self.message_tx.send(message)?)
+        }
+    }
+
+    fn generated_token_message(generated_token_result: GeneratedTokenResult) -> serde_json::Value {
+        json!({
+            "Response": {
+                "request_id": "request-1",
+                "response": {"GeneratedToken": generated_token_result},
+            }
+        })
+    }
+
+    /// Calls one of the agent management endpoints, e.g. `cordon`
+    async fn post_agent_action(
+        addr: SocketAddr,
+        agent_id: &str,
+        action: &str,
+    ) -> Result<StatusCode> {
+        Ok(reqwest::Client::new()
+            .post(format!("http://{addr}/api/v1/agent/{agent_id}/{action}"))
+            .send()
+            .await?
+            .status())
+    }
+
+    async fn receive_client_message(
+        client_message_rx: &mut mpsc::UnboundedReceiver<OutgoingMessage>,
+    ) -> Result<serde_json::Value> {
+        let message = timeout(MESSAGE_TIMEOUT, client_message_rx.recv())
+            .await?
+            .ok_or_else(|| anyhow!("Client session was closed"))?;
+
+        Ok(serde_json::to_value(message)?)
+    }
+
+    /// Returns None once the balancer closes the connection
+    async fn receive_message(
+        agent_connection: &mut AgentConnection,
+    ) -> Result<Option<AgentJsonRpcMessage>> {
+        loop {
+            match timeout(MESSAGE_TIMEOUT, agent_connection.next()).await? {
+                Some(Ok(Message::Text(text))) => return Ok(Some(serde_json::from_str(&text)?)),
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Ok(None),
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+
+    async fn receive_request(agent_connection: &mut AgentConnection) -> Result<()> {
+        match receive_message(agent_connection).await? {
+            Some(AgentJsonRpcMessage::Request(RequestEnvelope {
+                id,
+                request: AgentJsonRpcRequest::ContinueFromRawPrompt(_),
+            })) => {
+                assert_eq!(id, "request-1");
+
+                Ok(())
+            }
+            _ => Err(anyhow!("Agent did not receive the request")),
+        }
+    }
+
+    /// Connects as the agent, and returns the notification that answers the registration
+    async fn register_agent(
+        addr: SocketAddr,
+        agent_id: &str,
+        resume_secret: Option<String>,
+    ) -> Result<(AgentConnection, AgentJsonRpcNotification)> {
+        register_agent_with_features(
+            addr,
+            agent_id,
+            BTreeSet::from([ProtocolFeature::RequestResumption]),
+            resume_secret,
+        )
+        .await
+    }
+
+    async fn register_agent_with_features(
+        addr: SocketAddr,
+        agent_id: &str,
+        features: BTreeSet<ProtocolFeature>,
+        resume_secret: Option<String>,
+    ) -> Result<(AgentConnection, AgentJsonRpcNotification)> {
+        let (mut agent_connection, _) =
+            connect_async(format!("ws://{addr}/api/v1/agent_socket/{agent_id}")).await?;
+
+        match receive_message(&mut agent_connection).await? {
+            Some(AgentJsonRpcMessage::Notification(AgentJsonRpcNotification::Version(_))) => {}
+            _ => return Err(anyhow!("Balancer did not send its version")),
+        }
+
+        send_message(
+            &mut agent_connection,
+            ManagementJsonRpcMessage::Notification(ManagementJsonRpcNotification::RegisterAgent(
+                RegisterAgentParams {
+                    features,
+                    name: None,
+                    protocol_version_range: Some(ProtocolVersionRange::SUPPORTED),
+                    resume_secret,
+                    slot_aggregated_status_snapshot: SlotAggregatedStatusSnapshot {
+                        desired_slots_total: 1,
+                        download_current: 0,
+                        download_filename: None,
+                        download_total: 0,
+                        issues: BTreeSet::new(),
+                        model_path: None,
+                        slots_processing: 0,
+                        slots_total: 1,
+                        state_application_status: AgentStateApplicationStatus::Applied,
+                        uses_chat_template_override: false,
+                        version: 0,
+                    },
+                },
+            )),
+        )
+        .await?;
+
+        match receive_message(&mut agent_connection).await? {
+            Some(AgentJsonRpcMessage::Notification(notification)) => {
+                Ok((agent_connection, notification))
+            }
+            _ => Err(anyhow!("Balancer did not answer the registration")),
+        }
+    }
+
+    async fn send_message(
+        agent_connection: &mut AgentConnection,
+        message: ManagementJsonRpcMessage,
+    ) -> Result<()> {
+        agent_connection
+            .send(Message::Text(serde_json::to_string(&message)?.into()))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn send_generated_token(
+        agent_connection: &mut AgentConnection,
+        generated_token_result: GeneratedTokenResult,
+    ) -> Result<()> {
+        send_message(
+            agent_connection,
+            ManagementJsonRpcMessage::Response(ResponseEnvelope {
+                request_id: "request-1".to_string(),
+                response: AgentJsonRpcResponse::GeneratedToken(generated_token_result),
+            }),
+        )
+        .await
+    }
+
+    async fn start_balancer(
+        agent_resume_grace_period: Duration,
+    ) -> Result<(SocketAddr, ServerHandle, Arc<BufferedRequestManager>)> {
+        start_balancer_with_heartbeat(
+            HeartbeatConfiguration {
+                interval: Duration::from_secs(5),
+                missed_beats_threshold: 0,
+            },
+            agent_resume_grace_period,
+        )
+        .await
+    }
+
+    async fn start_balancer_with_heartbeat(
+        agent_heartbeat_configuration: HeartbeatConfiguration,
+        agent_resume_grace_period: Duration,
+    ) -> Result<(SocketAddr, ServerHandle, Arc<BufferedRequestManager>)> {
+        let (mut app_data, _balancer_desired_state_rx) = make_test_app_data().await?;
+        let agent_controller_pool = Arc::new(AgentControllerPool::new(
+            AgentCircuitBreakerConfiguration {
+                cooldown: Duration::from_secs(10),
+                failure_ratio: 0.0,
+                failure_ratio_min_requests: 0,
+                failure_ratio_window: 0,
+                failure_threshold: 0,
+            },
+            agent_heartbeat_configuration,
+            agent_resume_grace_period,
+            ResponseBufferConfiguration {
+                capacity: 16,
+                full_buffer_policy: FullBufferPolicy::Pause,
+            },
+        ));
+        let buffered_request_manager = Arc::new(BufferedRequestManager::new(
+            agent_controller_pool.clone(),
+            Arc::new(DrainStatus::default()),
+            BufferedRequestPriorityClassRegistry::new(
+                Duration::from_secs(10),
+                10,
+                Vec::new(),
+                Vec::new(),
+            )?,
+        ));
+
+        app_data.agent_controller_pool = agent_controller_pool;
+        app_data.buffered_request_manager = buffered_request_manager.clone();
+
+        let app_data = Data::new(app_data);
+        let http_server = HttpServer::new(move || {
+            App::new()
+                .app_data(app_data.clone())
+                .configure(post_agent_cordon::register)
+                .configure(post_agent_disconnect::register)
+                .configure(post_agent_uncordon::register)
+                .configure(register)
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))?;
+        let addr = http_server.addrs()[0];
+        let server = http_server.run();
+        let server_handle = server.handle();
+
+        rt::spawn(server);
+
+        Ok((addr, server_handle, buffered_request_manager))
+    }
+
+    fn start_request(
+        addr: SocketAddr,
+        buffered_request_manager: Arc<BufferedRequestManager>,
+        max_request_attempts: usize,
+    ) -> Result<mpsc::UnboundedReceiver<OutgoingMessage>> {
+        let priority_class = buffered_request_manager.select_priority_class(None, None)?;
+        let (client_connection_close_tx, _) = broadcast::channel(1);
+        let (client_message_tx, client_message_rx) = mpsc::unbounded_channel();
+
+        rt::spawn(request_from_agent(
+            None,
+            buffered_request_manager,
+            client_connection_close_tx,
+            InferenceServiceConfiguration {
+                addr: ServerAddr::Tcp(addr),
+                inference_item_timeout: MESSAGE_TIMEOUT,
+                max_request_attempts,
+            },
+            ContinueFromRawPromptParams {
+                max_tokens: 10,
+                raw_prompt: "Hello".to_string(),
+            },
+            priority_class,
+            "request-1".to_string(),
+            ChannelSessionController {
+                message_tx: client_message_tx,
+            },
+        ));
+
+        Ok(client_message_rx)
+    }
+
+    #[actix_web::test]
+    async fn test_reconnected_agent_takes_over_and_resumes_the_request() -> Result<()> {
+        let (addr, server_handle, buffered_request_manager) =
+            start_balancer(Duration::from_secs(10)).await?;
+
+        let (mut first_connection, registration) = register_agent(addr, "agent-1", None).await?;
+        let resume_secret = match registration {
+            AgentJsonRpcNotification::AgentRegistered(AgentRegisteredParams { resume_secret }) => {
+                resume_secret
+            }
+            _ => return Err(anyhow!("Agent was not registered")),
+        };
+
+        // Client that only knows the agent id cannot take over the registration
+        let (_, registration) =
+            register_agent(addr, "agent-1", Some("guessed".to_string())).await?;
+
+        assert!(matches!(
+            registration,
+            AgentJsonRpcNotification::RegistrationRejected(_)
+        ));
+
+        let mut client_message_rx = start_request(addr, buffered_request_manager, 1)?;
+
+        receive_request(&mut first_connection).await?;
+
+        for token in ["a", "b"] {
+            send_generated_token(
+                &mut first_connection,
+                GeneratedTokenResult::Token(token.to_string()),
+            )
+            .await?;
+
+            assert_eq!(
+                receive_client_message(&mut client_message_rx).await?,
+                generated_token_message(GeneratedTokenResult::Token(token.to_string()))
+            );
+        }
+
+        let (mut second_connection, registration) =
+            register_agent(addr, "agent-1", Some(resume_secret)).await?;
+
+        assert!(matches!(
+            registration,
+            AgentJsonRpcNotification::AgentRegistered(_)
+        ));
+        assert!(receive_message(&mut first_connection).await?.is_none());
+
+        match receive_message(&mut second_connection).await? {
+            Some(AgentJsonRpcMessage::Notification(AgentJsonRpcNotification::ReattachRequest(
+                ReattachRequestParams {
+                    received_responses,
+                    request_id,
+                },
+            ))) => {
+                assert_eq!(received_responses, 2);
+                assert_eq!(request_id, "request-1");
+            }
+            _ => return Err(anyhow!("Agent was not asked to resume the request")),
+        }
+
+        // Responses continue on the new connection
+        for generated_token_result in [
+            GeneratedTokenResult::Token("c".to_string()),
+            GeneratedTokenResult::Done,
+        ] {
+            send_generated_token(&mut second_connection, generated_token_result.clone()).await?;
+
+            assert_eq!(
+                receive_client_message(&mut client_message_rx).await?,
+                generated_token_message(generated_token_result)
+            );
+        }
+
+        server_handle.stop(false).await;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_retries_request_when_agent_disconnects_before_first_chunk() -> Result<()> {
+        let (addr, server_handle, buffered_request_manager) =
+            start_balancer(Duration::ZERO).await?;
+        let (mut first_connection, _) = register_agent(addr, "agent-1", None).await?;
+        let mut client_message_rx = start_request(addr, buffered_request_manager.clone(), 2)?;
+
+        receive_request(&mut first_connection).await?;
+
+        let (mut second_connection, _) = register_agent(addr, "agent-2", None).await?;
+
+        first_connection.close(None).await?;
+
+        receive_request(&mut second_connection).await?;
+        send_generated_token(
+            &mut second_connection,
+            GeneratedTokenResult::Token("a".to_string()),
+        )
+        .await?;
+
+        assert_eq!(
+            receive_client_message(&mut client_message_rx).await?,
+            generated_token_message(GeneratedTokenResult::Token("a".to_string()))
+        );
+        assert_eq!(
+            buffered_request_manager.request_retry_counter.get_retries(),
+            1
+        );
+
+        server_handle.stop(false).await;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_retries_request_when_agent_fails_before_first_chunk() -> Result<()> {
+        let (addr, server_handle, buffered_request_manager) =
+            start_balancer(Duration::ZERO).await?;
+        let (mut first_connection, _) = register_agent(addr, "agent-1", None).await?;
+        let mut client_message_rx = start_request(addr, buffered_request_manager.clone(), 2)?;
+
+        receive_request(&mut first_connection).await?;
+
+        let (mut second_connection, _) = register_agent(addr, "agent-2", None).await?;
+
+        send_generated_token(
+            &mut first_connection,
+            GeneratedTokenResult::ChatTemplateError("broken template".to_string()),
+        )
+        .await?;
+
+        receive_request(&mut second_connection).await?;
+        send_generated_token(&mut second_connection, GeneratedTokenResult::Done).await?;
+
+        assert_eq!(
+            receive_client_message(&mut client_message_rx).await?,
+            generated_token_message(GeneratedTokenResult::Done)
+        );
+        assert_eq!(
+            buffered_request_manager.request_retry_counter.get_retries(),
+            1
+        );
+
+        server_handle.stop(false).await;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_does_not_retry_request_after_first_chunk() -> Result<()> {
+        let (addr, server_handle, buffered_request_manager) =
+            start_balancer(Duration::ZERO).await?;
+        let (mut first_connection, _) = register_agent(addr, "agent-1", None).await?;
+        let mut client_message_rx = start_request(addr, buffered_request_manager.clone(), 2)?;
+
+        receive_request(&mut first_connection).await?;
+        send_generated_token(
+            &mut first_connection,
+            GeneratedTokenResult::Token("a".to_string()),
+        )
+        .await?;
+        receive_client_message(&mut client_message_rx).await?;
+
+        let (_second_connection, _) = register_agent(addr, "agent-2", None).await?;
+
+        first_connection.close(None).await?;
+
+        assert_eq!(
+            receive_client_message(&mut client_message_rx).await?["Error"]["error"]["code"],
+            json!(502)
+        );
+        assert_eq!(
+            buffered_request_manager.request_retry_counter.get_retries(),
+            0
+        );
+
+        server_handle.stop(false).await;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_forwards_agent_error_when_no_attempts_are_left() -> Result<()> {
+        let (addr, server_handle, buffered_request_manager) =
+            start_balancer(Duration::ZERO).await?;
+        let (mut first_connection, _) = register_agent(addr, "agent-1", None).await?;
+        let mut client_message_rx = start_request(addr, buffered_request_manager.clone(), 1)?;
+
+        receive_request(&mut first_connection).await?;
+
+        let (_second_connection, _) = register_agent(addr, "agent-2", None).await?;
+        let chat_template_error =
+            GeneratedTokenResult::ChatTemplateError("broken template".to_string());
+
+        send_generated_token(&mut first_connection, chat_template_error.clone()).await?;
+
+        assert_eq!(
+            receive_client_message(&mut client_message_rx).await?,
+            generated_token_message(chat_template_error)
+        );
+        assert_eq!(
+            buffered_request_manager.request_retry_counter.get_retries(),
+            0
+        );
+
+        server_handle.stop(false).await;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_cordoned_agent_finishes_its_request_but_gets_no_new_ones() -> Result<()> {
+        let (addr, server_handle, buffered_request_manager) =
+            start_balancer(Duration::ZERO).await?;
+        let (mut first_connection, _) = register_agent(addr, "agent-1", None).await?;
+        let mut client_message_rx = start_request(addr, buffered_request_manager.clone(), 1)?;
+
+        receive_request(&mut first_connection).await?;
+
+        assert_eq!(
+            post_agent_action(addr, "agent-1", "cordon").await?,
+            StatusCode::NO_CONTENT
+        );
+
+        send_generated_token(&mut first_connection, GeneratedTokenResult::Done).await?;
+
+        assert_eq!(
+            receive_client_message(&mut client_message_rx).await?,
+            generated_token_message(GeneratedTokenResult::Done)
+        );
+
+        // The only other agent connects after the request is queued, so the request waits for it
+        let _client_message_rx = start_request(addr, buffered_request_manager, 1)?;
+        let (mut second_connection, _) = register_agent(addr, "agent-2", None).await?;
+
+        receive_request(&mut second_connection).await?;
+
+        server_handle.stop(false).await;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_uncordoned_agent_gets_new_requests() -> Result<()> {
+        let (addr, server_handle, buffered_request_manager) =
+            start_balancer(Duration::ZERO).await?;
+        let (mut agent_connection, _) = register_agent(addr, "agent-1", None).await?;
+
+        assert_eq!(
+            post_agent_action(addr, "agent-1", "cordon").await?,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            post_agent_action(addr, "agent-1", "uncordon").await?,
+            StatusCode::NO_CONTENT
+        );
+
+        let _client_message_rx = start_request(addr, buffered_request_manager, 1)?;
+
+        receive_request(&mut agent_connection).await?;
+
+        server_handle.stop(false).await;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_disconnect_closes_the_agent_connection() -> Result<()> {
+        let (addr, server_handle, _) = start_balancer(Duration::ZERO).await?;
+        let (mut agent_connection, _) = register_agent(addr, "agent-1", None).await?;
+
+        assert_eq!(
+            post_agent_action(addr, "agent-1", "disconnect").await?,
+            StatusCode::NO_CONTENT
+        );
+        assert!(receive_message(&mut agent_connection).await?.is_none());
+
+        server_handle.stop(false).await;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_disconnects_agent_whose_token_was_revoked() -> Result<()> {
+        let (addr, server_handle, buffered_request_manager) =
+            start_balancer(Duration::ZERO).await?;
+        let (mut agent_connection, _) = register_agent(addr, "agent-1", None).await?;
+        let agent_controller_pool = &buffered_request_manager.agent_controller_pool;
+
+        agent_controller_pool
+            .disconnect_unauthenticated_agents(&AgentTokenRegistryHolder::new(None));
+
+        assert!(
+            timeout(Duration::from_millis(200), agent_connection.next())
+                .await
+                .is_err()
+        );
+
+        agent_controller_pool.disconnect_unauthenticated_agents(&AgentTokenRegistryHolder::new(
+            Some(AgentTokenRegistry::parse("other-token")?),
+        ));
+
+        assert!(receive_message(&mut agent_connection).await?.is_none());
+
+        server_handle.stop(false).await;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_agent_actions_report_unknown_agents() -> Result<()> {
+        let (addr, server_handle, _) = start_balancer(Duration::ZERO).await?;
+
+        for action in ["cordon", "disconnect", "uncordon"] {
+            assert_eq!(
+                post_agent_action(addr, "agent-1", action).await?,
+                StatusCode::NOT_FOUND
+            );
+        }
+
+        server_handle.stop(false).await;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_evicts_silent_agent_that_sends_heartbeats() -> Result<()> {
+        let (addr, server_handle, _) = start_balancer_with_heartbeat(
+            HeartbeatConfiguration {
+                interval: Duration::from_millis(50),
+                missed_beats_threshold: 2,
+            },
+            Duration::ZERO,
+        )
+        .await?;
+        let (mut agent_connection, _) = register_agent_with_features(
+            addr,
+            "agent-1",
+            BTreeSet::from([
+                ProtocolFeature::Heartbeat,
+                ProtocolFeature::RequestResumption,
+            ]),
+            None,
+        )
+        .await?;
+
+        // Heartbeats from the balancer arrive until the agent is evicted for staying silent
+        while let Some(message) = receive_message(&mut agent_connection).await? {
+            assert!(matches!(
+                message,
+                AgentJsonRpcMessage::Notification(AgentJsonRpcNotification::Heartbeat)
+            ));
+        }
+
+        server_handle.stop(false).await;
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_keeps_silent_agent_without_heartbeats() -> Result<()> {
+        let (addr, server_handle, _) = start_balancer_with_heartbeat(
+            HeartbeatConfiguration {
+                interval: Duration::from_millis(50),
+                missed_beats_threshold: 2,
+            },
+            Duration::ZERO,
+        )
+        .await?;
+        let (mut agent_connection, _) = register_agent(addr, "agent-1", None).await?;
+
+        // Neither a heartbeat it does not understand, nor the closed connection
+        assert!(
+            timeout(Duration::from_millis(500), agent_connection.next())
+                .await
+                .is_err()
+        );
+
+        server_handle.stop(false).await;
+
+        Ok(())
+    }
+}
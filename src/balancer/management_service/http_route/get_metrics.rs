@@ -1,4 +1,6 @@
 use std::error::Error;
+use std::fmt;
+use std::fmt::Write as _;
 
 use actix_web::HttpResponse;
 use actix_web::Responder;
@@ -8,8 +10,44 @@ use actix_web::web::ServiceConfig;
 use indoc::formatdoc;
 
 use crate::balancer::agent_controller_pool_total_slots::AgentControllerPoolTotalSlots;
+use crate::balancer::api_key_usage_snapshot::ApiKeyUsageSnapshot;
 use crate::balancer::management_service::app_data::AppData;
 
+/// Writes one sample per API key, labeled with the key name.
+fn write_api_key_metric<TGetValue>(
+    metrics_response: &mut String,
+    statsd_prefix: &str,
+    metric_name: &str,
+    metric_type: &str,
+    help: &str,
+    usage_snapshots: &[ApiKeyUsageSnapshot],
+    get_value: TGetValue,
+) -> fmt::Result
+where
+    TGetValue: Fn(&ApiKeyUsageSnapshot) -> u64,
+{
+    writeln!(metrics_response)?;
+    writeln!(
+        metrics_response,
+        "# HELP {statsd_prefix}{metric_name} {help}"
+    )?;
+    writeln!(
+        metrics_response,
+        "# TYPE {statsd_prefix}{metric_name} {metric_type}"
+    )?;
+
+    for usage_snapshot in usage_snapshots {
+        writeln!(
+            metrics_response,
+            "{statsd_prefix}{metric_name}{{api_key=\"{}\"}} {}",
+            usage_snapshot.name,
+            get_value(usage_snapshot)
+        )?;
+    }
+
+    Ok(())
+}
+
 pub fn register(cfg: &mut ServiceConfig) {
     cfg.service(respond);
 }
@@ -25,8 +63,10 @@ async fn respond(app_data: Data<AppData>) -> Result<impl Responder, Box<dyn Erro
         .buffered_request_counter
         .get();
     let statsd_prefix = app_data.statsd_prefix.clone();
+    let unauthorized_requests = app_data.api_key_manager.get_unauthorized_requests();
+    let usage_snapshots = app_data.api_key_manager.make_usage_snapshots();
 
-    let metrics_response = formatdoc! {"
+    let mut metrics_response = formatdoc! {"
         # HELP {statsd_prefix}slots_processing Number of processing slots
         # TYPE {statsd_prefix}slots_processing gauge
         {statsd_prefix}slots_processing {slots_processing}
@@ -38,8 +78,49 @@ async fn respond(app_data: Data<AppData>) -> Result<impl Responder, Box<dyn Erro
         # HELP {statsd_prefix}requests_buffered Number of buffered requests
         # TYPE {statsd_prefix}requests_buffered gauge
         {statsd_prefix}requests_buffered {buffered_requests_count}
+
+        # HELP {statsd_prefix}api_key_unauthorized_requests Number of requests rejected because of a missing or invalid API key
+        # TYPE {statsd_prefix}api_key_unauthorized_requests counter
+        {statsd_prefix}api_key_unauthorized_requests {unauthorized_requests}
     "};
 
+    write_api_key_metric(
+        &mut metrics_response,
+        &statsd_prefix,
+        "api_key_concurrent_requests",
+        "gauge",
+        "Number of requests in progress per API key",
+        &usage_snapshots,
+        |usage_snapshot| usage_snapshot.concurrent_requests,
+    )?;
+    write_api_key_metric(
+        &mut metrics_response,
+        &statsd_prefix,
+        "api_key_rejected_requests",
+        "counter",
+        "Number of requests rejected because of the API key limits",
+        &usage_snapshots,
+        |usage_snapshot| usage_snapshot.rejected_requests,
+    )?;
+    write_api_key_metric(
+        &mut metrics_response,
+        &statsd_prefix,
+        "api_key_requests_total",
+        "counter",
+        "Number of requests per API key",
+        &usage_snapshots,
+        |usage_snapshot| usage_snapshot.total_requests,
+    )?;
+    write_api_key_metric(
+        &mut metrics_response,
+        &statsd_prefix,
+        "api_key_tokens_total",
+        "counter",
+        "Number of tokens generated per API key",
+        &usage_snapshots,
+        |usage_snapshot| usage_snapshot.total_tokens,
+    )?;
+
     Ok(HttpResponse::Ok()
         .content_type("text/plain; version=0.0.4; charset=utf-8; escaping=values")
         .body(metrics_response))
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::sync::Mutex;
+use tokio::sync::broadcast;
+
+use crate::balancer::agent_circuit_breaker_configuration::AgentCircuitBreakerConfiguration;
+use crate::balancer::agent_controller_pool::AgentControllerPool;
+use crate::balancer::agent_token_registry_holder::AgentTokenRegistryHolder;
+use crate::balancer::api_key_manager::ApiKeyManager;
+use crate::balancer::buffered_request_manager::BufferedRequestManager;
+use crate::balancer::buffered_request_priority_class_registry::BufferedRequestPriorityClassRegistry;
+use crate::balancer::management_access_control::ManagementAccessControl;
+use crate::balancer::management_audit_log::ManagementAuditLog;
+use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::state_database::Memory;
+use crate::balancer_applicable_state_holder::BalancerApplicableStateHolder;
+use crate::balancer_desired_state::BalancerDesiredState;
+use crate::drain_status::DrainStatus;
+use crate::full_buffer_policy::FullBufferPolicy;
+use crate::heartbeat_configuration::HeartbeatConfiguration;
+use crate::response_buffer_configuration::ResponseBufferConfiguration;
+
+/// Management service data backed by the memory state database, without any agents.
+/// The state database fails to store the desired state once the receiver is dropped.
+pub async fn make_test_app_data() -> Result<(AppData, broadcast::Receiver<BalancerDesiredState>)> {
+    let (balancer_desired_state_tx, balancer_desired_state_rx) = broadcast::channel(100);
+    let agent_controller_pool = Arc::new(AgentControllerPool::new(
+        AgentCircuitBreakerConfiguration {
+            cooldown: Duration::from_secs(10),
+            failure_ratio: 0.0,
+            failure_ratio_min_requests: 0,
+            failure_ratio_window: 0,
+            failure_threshold: 0,
+        },
+        HeartbeatConfiguration {
+            interval: Duration::from_secs(5),
+            missed_beats_threshold: 0,
+        },
+        Duration::ZERO,
+        ResponseBufferConfiguration {
+            capacity: 16,
+            full_buffer_policy: FullBufferPolicy::Pause,
+        },
+    ));
+
+    let app_data = AppData {
+        agent_controller_pool: agent_controller_pool.clone(),
+        agent_token_registry_holder: Arc::new(AgentTokenRegistryHolder::new(None)),
+        agent_tokens_write_lock: Mutex::new(()),
+        api_key_manager: Arc::new(ApiKeyManager::new(false)),
+        api_keys_write_lock: Mutex::new(()),
+        balancer_applicable_state_holder: Arc::new(BalancerApplicableStateHolder::default()),
+        balancer_desired_state_write_lock: Arc::new(Mutex::new(())),
+        buffered_request_manager: Arc::new(BufferedRequestManager::new(
+            agent_controller_pool,
+            Arc::new(DrainStatus::default()),
+            BufferedRequestPriorityClassRegistry::new(
+                Duration::from_secs(10),
+                10,
+                Vec::new(),
+                Vec::new(),
+            )?,
+        )),
+        chat_template_override_sender_collection: Default::default(),
+        embedding_sender_collection: Default::default(),
+        generate_tokens_sender_collection: Default::default(),
+        management_access_control: Arc::new(ManagementAccessControl::new(true, None)),
+        management_audit_log: Arc::new(ManagementAuditLog::open(None).await?),
+        management_tokens_write_lock: Mutex::new(()),
+        model_metadata_sender_collection: Default::default(),
+        requires_if_match: false,
+        state_database: Arc::new(Memory::new(
+            balancer_desired_state_tx,
+            BalancerDesiredState::default(),
+        )),
+        statsd_prefix: "paddler_".to_string(),
+    };
+
+    Ok((app_data, balancer_desired_state_rx))
+}
@@ -1,46 +1,72 @@
 pub mod app_data;
+pub mod authorize_management_request;
+pub mod check_balancer_desired_state_precondition;
 pub mod configuration;
 pub mod http_route;
+#[cfg(test)]
+pub mod make_test_app_data;
+pub mod reload_agent_tokens;
+pub mod reload_api_keys;
+pub mod reload_management_tokens;
+pub mod update_balancer_desired_state;
 
 use std::sync::Arc;
 
 use actix_web::App;
 use actix_web::HttpServer;
+use actix_web::middleware::from_fn;
 use actix_web::web::Data;
 use anyhow::Result;
 use async_trait::async_trait;
 use log::error;
+use rustls::ServerConfig;
+use tokio::sync::Mutex;
 use tokio::sync::broadcast;
 
 use crate::balancer::agent_controller_pool::AgentControllerPool;
+use crate::balancer::agent_token_registry_holder::AgentTokenRegistryHolder;
+use crate::balancer::api_key_manager::ApiKeyManager;
 use crate::balancer::buffered_request_manager::BufferedRequestManager;
 use crate::balancer::chat_template_override_sender_collection::ChatTemplateOverrideSenderCollection;
+use crate::balancer::client_certificate::ClientCertificate;
+use crate::balancer::create_unix_listener::create_unix_listener;
 use crate::balancer::embedding_sender_collection::EmbeddingSenderCollection;
 use crate::balancer::generate_tokens_sender_collection::GenerateTokensSenderCollection;
 use crate::balancer::http_route as common_http_route;
+use crate::balancer::management_access_control::ManagementAccessControl;
+use crate::balancer::management_audit_log::ManagementAuditLog;
 use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::authorize_management_request::authorize_management_request;
 use crate::balancer::management_service::configuration::Configuration as ManagementServiceConfiguration;
 use crate::balancer::model_metadata_sender_collection::ModelMetadataSenderCollection;
 use crate::balancer::state_database::StateDatabase;
-#[cfg(feature = "web_admin_panel")]
-use crate::balancer::web_admin_panel_service::configuration::Configuration as WebAdminPanelServiceConfiguration;
+use crate::balancer::unix_socket_mode::UnixSocketMode;
 use crate::balancer_applicable_state_holder::BalancerApplicableStateHolder;
+use crate::cors_allowed_hosts::CorsAllowedHosts;
 use crate::create_cors_middleware::create_cors_middleware;
+use crate::server_addr::ServerAddr;
 use crate::service::Service;
 
 pub struct ManagementService {
     pub agent_controller_pool: Arc<AgentControllerPool>,
+    pub agent_token_registry_holder: Arc<AgentTokenRegistryHolder>,
+    pub api_key_manager: Arc<ApiKeyManager>,
     pub balancer_applicable_state_holder: Arc<BalancerApplicableStateHolder>,
+    pub balancer_desired_state_write_lock: Arc<Mutex<()>>,
     pub buffered_request_manager: Arc<BufferedRequestManager>,
     pub chat_template_override_sender_collection: Arc<ChatTemplateOverrideSenderCollection>,
     pub configuration: ManagementServiceConfiguration,
+    pub cors_allowed_hosts: Arc<CorsAllowedHosts>,
     pub embedding_sender_collection: Arc<EmbeddingSenderCollection>,
     pub generate_tokens_sender_collection: Arc<GenerateTokensSenderCollection>,
+    pub management_access_control: Arc<ManagementAccessControl>,
+    pub management_audit_log: Arc<ManagementAuditLog>,
     pub model_metadata_sender_collection: Arc<ModelMetadataSenderCollection>,
+    pub requires_if_match: bool,
     pub state_database: Arc<dyn StateDatabase>,
     pub statsd_prefix: String,
-    #[cfg(feature = "web_admin_panel")]
-    pub web_admin_panel_service_configuration: Option<WebAdminPanelServiceConfiguration>,
+    pub tls_server_config: Option<ServerConfig>,
+    pub unix_socket_mode: Option<UnixSocketMode>,
 }
 
 #[async_trait]
@@ -50,56 +76,99 @@ impl Service for ManagementService {
     }
 
     async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
-        #[allow(unused_mut)]
-        let mut cors_allowed_hosts = self.configuration.cors_allowed_hosts.clone();
-
-        #[cfg(feature = "web_admin_panel")]
-        if let Some(web_admin_panel_config) = &self.web_admin_panel_service_configuration {
-            cors_allowed_hosts.push(format!("http://{}", web_admin_panel_config.addr));
-        }
-
-        let cors_allowed_hosts_arc = Arc::new(cors_allowed_hosts);
-
         let app_data = Data::new(AppData {
             agent_controller_pool: self.agent_controller_pool.clone(),
+            agent_token_registry_holder: self.agent_token_registry_holder.clone(),
+            agent_tokens_write_lock: Mutex::new(()),
+            api_key_manager: self.api_key_manager.clone(),
+            api_keys_write_lock: Mutex::new(()),
             balancer_applicable_state_holder: self.balancer_applicable_state_holder.clone(),
+            balancer_desired_state_write_lock: self.balancer_desired_state_write_lock.clone(),
             buffered_request_manager: self.buffered_request_manager.clone(),
             chat_template_override_sender_collection: self
                 .chat_template_override_sender_collection
                 .clone(),
             embedding_sender_collection: self.embedding_sender_collection.clone(),
             generate_tokens_sender_collection: self.generate_tokens_sender_collection.clone(),
+            management_access_control: self.management_access_control.clone(),
+            management_audit_log: self.management_audit_log.clone(),
+            management_tokens_write_lock: Mutex::new(()),
             model_metadata_sender_collection: self.model_metadata_sender_collection.clone(),
+            requires_if_match: self.requires_if_match,
             state_database: self.state_database.clone(),
             statsd_prefix: self.statsd_prefix.clone(),
         });
 
-        HttpServer::new(move || {
+        let cors_allowed_hosts = self.cors_allowed_hosts.clone();
+
+        let http_server = HttpServer::new(move || {
             App::new()
-                .wrap(create_cors_middleware(cors_allowed_hosts_arc.clone()))
+                // Registered first, so the CORS headers are added to its responses too
+                .wrap(from_fn(authorize_management_request))
+                .wrap(create_cors_middleware(cors_allowed_hosts.clone()))
                 .app_data(app_data.clone())
                 .configure(common_http_route::get_health::register)
+                .configure(http_route::api::delete_agent_token::register)
+                .configure(http_route::api::delete_api_key::register)
+                .configure(http_route::api::delete_balancer_desired_state_profile::register)
+                .configure(http_route::api::delete_management_token::register)
+                .configure(http_route::api::get_agent_tokens::register)
                 .configure(http_route::api::get_agents::register)
                 .configure(http_route::api::get_agents_stream::register)
+                .configure(http_route::api::get_api_keys::register)
+                .configure(http_route::api::get_api_keys_usage::register)
                 .configure(http_route::api::get_balancer_desired_state::register)
+                .configure(http_route::api::get_balancer_desired_state_history::register)
+                .configure(http_route::api::get_balancer_desired_state_profile::register)
+                .configure(http_route::api::get_balancer_desired_state_profile_bundle::register)
+                .configure(http_route::api::get_balancer_desired_state_profiles::register)
+                .configure(http_route::api::get_balancer_desired_state_schedule::register)
+                .configure(http_route::api::get_balancer_desired_state_schedule_upcoming::register)
                 .configure(http_route::api::get_buffered_requests::register)
                 .configure(http_route::api::get_buffered_requests_stream::register)
                 .configure(http_route::api::get_chat_template_override::register)
+                .configure(http_route::api::get_management_tokens::register)
                 .configure(http_route::api::get_model_metadata::register)
+                .configure(http_route::api::get_response_buffers::register)
                 .configure(http_route::api::grammar::generate::register)
                 .configure(http_route::api::grammar::list::register)
                 .configure(http_route::api::grammar::load::register)
                 .configure(http_route::api::grammar::parse::register)
+                .configure(http_route::api::patch_balancer_desired_state::register)
+                .configure(http_route::api::post_agent_cordon::register)
+                .configure(http_route::api::post_agent_disconnect::register)
+                .configure(http_route::api::post_agent_token::register)
+                .configure(http_route::api::post_agent_uncordon::register)
+                .configure(http_route::api::post_api_key::register)
+                .configure(http_route::api::post_balancer_desired_state_profile_activate::register)
+                .configure(http_route::api::post_balancer_desired_state_profile_bundle::register)
+                .configure(http_route::api::post_balancer_desired_state_rollback::register)
+                .configure(http_route::api::post_balancer_desired_state_validate::register)
+                .configure(http_route::api::post_management_token::register)
+                .configure(http_route::api::put_api_key_limits::register)
                 .configure(http_route::api::put_balancer_desired_state::register)
+                .configure(http_route::api::put_balancer_desired_state_profile::register)
+                .configure(http_route::api::put_balancer_desired_state_schedule::register)
                 .configure(http_route::api::ws_agent_socket::register)
                 .configure(http_route::get_metrics::register)
         })
+        .on_connect(ClientCertificate::store_in_connection_data)
         .shutdown_signal(async move {
             if let Err(err) = shutdown.recv().await {
                 error!("Failed to receive shutdown signal: {err}");
             }
-        })
-        .bind(self.configuration.addr)
+        });
+
+        match self.configuration.addr.clone() {
+            ServerAddr::Tcp(socket_addr) => match self.tls_server_config.clone() {
+                Some(tls_server_config) => {
+                    http_server.bind_rustls_0_23(socket_addr, tls_server_config)
+                }
+                None => http_server.bind(socket_addr),
+            },
+            ServerAddr::Unix(path) => create_unix_listener(&path, self.unix_socket_mode)
+                .and_then(|unix_listener| http_server.listen_uds(unix_listener)),
+        }
         .expect("Unable to bind server to address")
         .run()
         .await?;
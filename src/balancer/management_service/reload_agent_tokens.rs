@@ -0,0 +1,16 @@
+use anyhow::Result;
+
+use crate::balancer::management_service::app_data::AppData;
+
+/// Applies the agent tokens stored in the state database, and disconnects the agents whose
+/// tokens are no longer valid.
+pub async fn reload_agent_tokens(app_data: &AppData) -> Result<()> {
+    app_data
+        .agent_token_registry_holder
+        .set_stored_agent_tokens(app_data.state_database.read_agent_tokens().await?);
+    app_data
+        .agent_controller_pool
+        .disconnect_unauthenticated_agents(&app_data.agent_token_registry_holder);
+
+    Ok(())
+}
@@ -0,0 +1,12 @@
+use anyhow::Result;
+
+use crate::balancer::management_service::app_data::AppData;
+
+/// Applies the API keys stored in the state database to the inference services.
+pub async fn reload_api_keys(app_data: &AppData) -> Result<()> {
+    app_data
+        .api_key_manager
+        .set_api_keys(app_data.state_database.read_api_keys().await?);
+
+    Ok(())
+}
@@ -0,0 +1,12 @@
+use anyhow::Result;
+
+use crate::balancer::management_service::app_data::AppData;
+
+/// Applies the management tokens stored in the state database to the access control.
+pub async fn reload_management_tokens(app_data: &AppData) -> Result<()> {
+    app_data
+        .management_access_control
+        .set_stored_tokens(app_data.state_database.read_management_tokens().await?);
+
+    Ok(())
+}
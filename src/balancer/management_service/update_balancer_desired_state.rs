@@ -0,0 +1,49 @@
+use actix_web::http::header::IfMatch;
+use anyhow::Result;
+
+use crate::balancer::balancer_desired_state_precondition::BalancerDesiredStatePrecondition;
+use crate::balancer::balancer_desired_state_update_result::BalancerDesiredStateUpdateResult;
+use crate::balancer::create_balancer_desired_state_etag::create_balancer_desired_state_etag;
+use crate::balancer::management_service::app_data::AppData;
+use crate::balancer::management_service::check_balancer_desired_state_precondition::check_balancer_desired_state_precondition;
+use crate::balancer_desired_state::BalancerDesiredState;
+
+/// Checks the `If-Match` precondition and stores the modified state while holding the write
+/// lock, so concurrent writers cannot undo each other's changes.
+pub async fn update_balancer_desired_state<TModifier>(
+    app_data: &AppData,
+    if_match: Option<IfMatch>,
+    modifier: TModifier,
+) -> Result<BalancerDesiredStateUpdateResult>
+where
+    TModifier: FnOnce(BalancerDesiredState) -> Result<BalancerDesiredState, String>,
+{
+    let _lock = app_data.balancer_desired_state_write_lock.lock().await;
+    let current_balancer_desired_state =
+        match check_balancer_desired_state_precondition(app_data, if_match).await? {
+            BalancerDesiredStatePrecondition::Failed => {
+                return Ok(BalancerDesiredStateUpdateResult::PreconditionFailed);
+            }
+            BalancerDesiredStatePrecondition::Met(current_balancer_desired_state) => {
+                current_balancer_desired_state
+            }
+            BalancerDesiredStatePrecondition::Required => {
+                return Ok(BalancerDesiredStateUpdateResult::PreconditionRequired);
+            }
+        };
+
+    let balancer_desired_state = match modifier(current_balancer_desired_state) {
+        Ok(balancer_desired_state) => balancer_desired_state,
+        Err(message) => return Ok(BalancerDesiredStateUpdateResult::Rejected(message)),
+    };
+
+    app_data
+        .state_database
+        .store_balancer_desired_state(&balancer_desired_state)
+        .await?;
+
+    Ok(BalancerDesiredStateUpdateResult::Updated {
+        etag: create_balancer_desired_state_etag(&balancer_desired_state)?,
+        balancer_desired_state,
+    })
+}
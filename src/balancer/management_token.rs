@@ -0,0 +1,12 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::balancer::management_role::ManagementRole;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManagementToken {
+    pub role: ManagementRole,
+    /// SHA-256 of the bearer token the callers send in the `Authorization` header
+    pub token_hash: String,
+}
@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use anyhow::anyhow;
+
+use crate::balancer::hash_api_key::hash_api_key;
+use crate::balancer::management_token::ManagementToken;
+
+/// Tokens that grant access to the management API, read from a file.
+///
+/// Every non-empty line of the tokens file is `name:role:token`, where the role is
+/// either `admin` or `read_only`. The name identifies the caller in the audit log.
+/// Lines starting with `#` are comments.
+#[derive(Debug, Default)]
+pub struct ManagementTokenRegistry {
+    pub tokens_by_name: BTreeMap<String, ManagementToken>,
+}
+
+impl ManagementTokenRegistry {
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut management_token_registry = Self::default();
+
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid_line_error = || {
+                anyhow!(
+                    "Invalid management token on line {}. Expected 'name:role:token'",
+                    index + 1
+                )
+            };
+            let mut parts = line.splitn(3, ':').map(str::trim);
+            let name = parts.next().ok_or_else(invalid_line_error)?;
+            let role = parts.next().ok_or_else(invalid_line_error)?;
+            let token = parts.next().ok_or_else(invalid_line_error)?;
+
+            if name.is_empty() || token.is_empty() {
+                return Err(invalid_line_error());
+            }
+
+            if management_token_registry
+                .tokens_by_name
+                .insert(
+                    name.to_string(),
+                    ManagementToken {
+                        role: role
+                            .parse()
+                            .context(format!("Invalid role on line {}", index + 1))?,
+                        token_hash: hash_api_key(token),
+                    },
+                )
+                .is_some()
+            {
+                return Err(anyhow!(
+                    "Duplicate management token name on line {}: '{name}'",
+                    index + 1
+                ));
+            }
+        }
+
+        Ok(management_token_registry)
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).context(format!(
+            "Failed to read management tokens file: {}",
+            path.display()
+        ))?;
+
+        Self::parse(&content).context(format!(
+            "Failed to parse management tokens file: {}",
+            path.display()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balancer::management_role::ManagementRole;
+
+    #[test]
+    fn test_parse_tokens() -> Result<()> {
+        let management_token_registry = ManagementTokenRegistry::parse(
+            "
+            # operators
+            alice:admin:alice-token
+            grafana:read_only:grafana:token
+            ",
+        )?;
+
+        assert_eq!(
+            management_token_registry.tokens_by_name.get("alice"),
+            Some(&ManagementToken {
+                role: ManagementRole::Admin,
+                token_hash: hash_api_key("alice-token"),
+            })
+        );
+        assert_eq!(
+            management_token_registry.tokens_by_name.get("grafana"),
+            Some(&ManagementToken {
+                role: ManagementRole::ReadOnly,
+                token_hash: hash_api_key("grafana:token"),
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_lines_are_rejected() {
+        assert!(ManagementTokenRegistry::parse("alice:admin").is_err());
+        assert!(ManagementTokenRegistry::parse("alice:owner:token").is_err());
+        assert!(ManagementTokenRegistry::parse(":admin:token").is_err());
+        assert!(ManagementTokenRegistry::parse("alice:admin:one\nalice:admin:two").is_err());
+    }
+}
@@ -6,11 +6,14 @@ use log::warn;
 use serde::Serialize;
 use tokio::sync::mpsc;
 
+use crate::balancer::response_buffer_snapshot::ResponseBufferSnapshot;
+use crate::balancer::response_sender::ResponseSender;
+
 #[async_trait]
 pub trait ManagesSenders {
     type Value: Send + Serialize + Sync + 'static;
 
-    fn get_sender_collection(&self) -> &DashMap<String, mpsc::UnboundedSender<Self::Value>>;
+    fn get_sender_collection(&self) -> &DashMap<String, ResponseSender<Self::Value>>;
 
     fn deregister_sender(&self, request_id: String) -> Result<()> {
         let senders = self.get_sender_collection();
@@ -24,16 +27,20 @@ pub trait ManagesSenders {
         }
     }
 
+    /// Waits until there is room in the response buffer of the request
     async fn forward_response(&self, request_id: String, value: Self::Value) -> Result<()> {
-        let senders = self.get_sender_collection();
+        // Cloned, so the collection is not locked while waiting
+        let response_tx = match self.get_sender_collection().get(&request_id) {
+            Some(response_sender) => {
+                response_sender.forwarded_responses.increment_by(1);
+                response_sender.response_tx.clone()
+            }
+            None => return Err(anyhow!("No sender found for request_id {request_id}")),
+        };
 
-        if let Some(sender) = senders.get(&request_id) {
-            sender.send(value)?;
+        response_tx.send(value).await?;
 
-            Ok(())
-        } else {
-            Err(anyhow!("No sender found for request_id {request_id}"))
-        }
+        Ok(())
     }
 
     async fn forward_response_safe(&self, request_id: String, value: Self::Value) {
@@ -43,18 +50,94 @@ pub trait ManagesSenders {
         }
     }
 
-    fn register_sender(
-        &self,
-        request_id: String,
-        sender: mpsc::UnboundedSender<Self::Value>,
-    ) -> Result<()> {
+    fn get_forwarded_responses(&self, request_id: &str) -> Result<usize> {
+        match self.get_sender_collection().get(request_id) {
+            Some(response_sender) => Ok(response_sender.forwarded_responses.get()),
+            None => Err(anyhow!("No sender found for request_id {request_id}")),
+        }
+    }
+
+    fn get_response_buffer_snapshots(&self) -> Vec<ResponseBufferSnapshot> {
+        self.get_sender_collection()
+            .iter()
+            .map(|entry| ResponseBufferSnapshot {
+                buffer_capacity: entry.value().response_tx.max_capacity(),
+                buffer_depth: entry.value().response_tx.max_capacity()
+                    - entry.value().response_tx.capacity(),
+                request_id: entry.key().clone(),
+            })
+            .collect()
+    }
+
+    fn is_response_buffer_full(&self, request_id: &str) -> bool {
+        self.get_sender_collection()
+            .get(request_id)
+            .is_some_and(|response_sender| response_sender.response_tx.capacity() == 0)
+    }
+
+    /// Returns true only if the request was not paused already
+    fn mark_paused(&self, request_id: &str) -> bool {
+        self.get_sender_collection()
+            .get(request_id)
+            .is_some_and(|response_sender| response_sender.is_paused.set_check(true))
+    }
+
+    /// Returns true if the request was paused, and its buffer drained to the low water mark
+    fn mark_resumed_if_drained(&self, request_id: &str, low_water_mark: usize) -> bool {
+        self.get_sender_collection()
+            .get(request_id)
+            .is_some_and(|response_sender| {
+                let buffer_depth = response_sender.response_tx.max_capacity()
+                    - response_sender.response_tx.capacity();
+
+                buffer_depth <= low_water_mark && response_sender.is_paused.set_check(false)
+            })
+    }
+
+    fn register_sender(&self, request_id: String, sender: mpsc::Sender<Self::Value>) -> Result<()> {
         let senders = self.get_sender_collection();
 
         if senders.contains_key(&request_id) {
             return Err(anyhow!("Sender for request_id {request_id} already exists"));
         }
 
-        senders.insert(request_id, sender);
+        senders.insert(request_id, ResponseSender::new(sender));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balancer::generate_tokens_sender_collection::GenerateTokensSenderCollection;
+    use crate::generated_token_result::GeneratedTokenResult;
+
+    #[tokio::test]
+    async fn test_paused_request_resumes_at_the_low_water_mark() -> Result<()> {
+        let sender_collection = GenerateTokensSenderCollection::default();
+        let (response_tx, mut response_rx) = mpsc::channel(4);
+
+        sender_collection.register_sender("request-1".to_string(), response_tx)?;
+
+        for _ in 0..4 {
+            sender_collection
+                .forward_response("request-1".to_string(), GeneratedTokenResult::Done)
+                .await?;
+        }
+
+        assert!(sender_collection.is_response_buffer_full("request-1"));
+        assert!(sender_collection.mark_paused("request-1"));
+        assert!(!sender_collection.mark_paused("request-1"));
+
+        response_rx.recv().await;
+
+        assert!(!sender_collection.mark_resumed_if_drained("request-1", 2));
+
+        response_rx.recv().await;
+
+        assert!(sender_collection.mark_resumed_if_drained("request-1", 2));
+        assert!(!sender_collection.mark_resumed_if_drained("request-1", 2));
 
         Ok(())
     }
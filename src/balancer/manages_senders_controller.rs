@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use anyhow::Result;
-use log::error;
+use log::debug;
 use tokio::sync::mpsc;
 
 use crate::balancer::manages_senders::ManagesSenders;
@@ -11,7 +11,7 @@ where
     TSenderCollection: ManagesSenders,
 {
     pub request_id: String,
-    pub response_rx: mpsc::UnboundedReceiver<TSenderCollection::Value>,
+    pub response_rx: mpsc::Receiver<TSenderCollection::Value>,
     pub response_sender_collection: Arc<TSenderCollection>,
 }
 
@@ -21,9 +21,10 @@ where
 {
     pub fn from_request_id(
         request_id: String,
+        response_buffer_capacity: usize,
         response_sender_collection: Arc<TSenderCollection>,
     ) -> Result<Self> {
-        let (response_tx, response_rx) = mpsc::unbounded_channel();
+        let (response_tx, response_rx) = mpsc::channel(response_buffer_capacity);
 
         response_sender_collection.register_sender(request_id.clone(), response_tx)?;
 
@@ -40,10 +41,11 @@ where
     TSenderCollection: ManagesSenders,
 {
     fn drop(&mut self) {
+        // The sender is already gone if the request was aborted
         self.response_sender_collection
             .deregister_sender(self.request_id.clone())
             .unwrap_or_else(|err| {
-                error!(
+                debug!(
                     "Failed to deregister sender for request_id {}: {err}",
                     self.request_id
                 );
@@ -1,36 +1,103 @@
+mod agent_circuit_breaker;
+pub mod agent_circuit_breaker_configuration;
+mod agent_circuit_breaker_state;
+mod agent_circuit_state;
 mod agent_controller;
 pub mod agent_controller_pool;
 mod agent_controller_pool_snapshot;
 mod agent_controller_pool_total_slots;
 mod agent_controller_snapshot;
 mod agent_controller_update_result;
+pub mod agent_token;
+pub mod agent_token_registry;
+pub mod agent_token_registry_holder;
+pub mod api_key;
+mod api_key_authorization_result;
+mod api_key_limit_exceeded;
+pub mod api_key_limits;
+pub mod api_key_manager;
+mod api_key_usage;
+mod api_key_usage_counters;
+mod api_key_usage_guard;
+pub mod api_key_usage_snapshot;
+mod authorization_bearer_token;
+mod authorize_api_key;
+pub mod balancer_desired_state_change;
+pub mod balancer_desired_state_history_entry;
+pub mod balancer_desired_state_precondition;
+pub mod balancer_desired_state_profile_bundle;
+pub mod balancer_desired_state_profile_delete_result;
+pub mod balancer_desired_state_rollback_result;
+pub mod balancer_desired_state_schedule_entry;
+pub mod balancer_desired_state_schedule_store_result;
+pub mod balancer_desired_state_transition;
+pub mod balancer_desired_state_update_result;
+pub mod balancer_desired_state_validation_issue;
+pub mod balancer_desired_state_validation_report;
 mod buffered_request_agent_wait_result;
 mod buffered_request_count_guard;
 mod buffered_request_counter;
 pub mod buffered_request_manager;
 mod buffered_request_manager_snapshot;
+pub mod buffered_request_params;
+pub mod buffered_request_priority_class;
+pub mod buffered_request_priority_class_api_key;
+pub mod buffered_request_priority_class_registry;
+mod buffered_request_queue;
+mod buffered_request_queue_entry;
+mod buffered_request_queue_entry_guard;
+mod buffered_request_queue_entry_snapshot;
+mod buffered_request_queue_position;
 pub mod chat_template_override_sender_collection;
 mod chunk_forwarding_session_controller;
+pub mod client_certificate;
 pub mod compatibility;
 mod controls_manages_senders_endpoint;
+pub mod create_balancer_desired_state_etag;
+pub mod create_tls_server_config;
+mod create_unix_listener;
+pub mod cron_schedule;
+pub mod diff_balancer_desired_states;
 pub mod embedding_sender_collection;
+mod forward_responses_stream_result;
 pub mod generate_tokens_sender_collection;
 mod handles_agent_streaming_response;
+mod hash_api_key;
 mod http_route;
 mod http_stream_from_agent;
 mod inference_client;
 pub mod inference_service;
+pub mod loads_reloadable_configuration;
+pub mod management_access_control;
+pub mod management_audit_log;
+mod management_audit_log_entry;
+mod management_principal;
+pub mod management_role;
 pub mod management_service;
+pub mod management_token;
+pub mod management_token_registry;
 mod manages_senders;
 mod manages_senders_controller;
 pub mod model_metadata_sender_collection;
+mod pause_server_when_draining;
 pub mod reconciliation_service;
+pub mod reload_service;
+pub mod reloadable_configuration;
+pub mod reloadable_tls_certificate;
 mod request_from_agent;
+mod request_retry_counter;
 #[cfg(feature = "web_admin_panel")]
 mod response;
+pub mod response_buffer_snapshot;
+pub mod response_buffers_snapshot;
+pub mod response_sender;
 pub mod state_database;
+pub mod state_database_file_watch_service;
 pub mod state_database_type;
 pub mod statsd_service;
-mod unbounded_stream_from_agent;
+mod stream_from_agent;
+mod tokens_match;
+pub mod unix_socket_mode;
+pub mod validate_balancer_desired_state;
 #[cfg(feature = "web_admin_panel")]
 pub mod web_admin_panel_service;
@@ -1,12 +1,12 @@
 use async_trait::async_trait;
 use dashmap::DashMap;
-use tokio::sync::mpsc;
 
 use crate::balancer::manages_senders::ManagesSenders;
+use crate::balancer::response_sender::ResponseSender;
 use crate::model_metadata::ModelMetadata;
 
 pub struct ModelMetadataSenderCollection {
-    senders: DashMap<String, mpsc::UnboundedSender<Option<ModelMetadata>>>,
+    senders: DashMap<String, ResponseSender<Option<ModelMetadata>>>,
 }
 
 impl Default for ModelMetadataSenderCollection {
@@ -21,7 +21,7 @@ impl Default for ModelMetadataSenderCollection {
 impl ManagesSenders for ModelMetadataSenderCollection {
     type Value = Option<ModelMetadata>;
 
-    fn get_sender_collection(&self) -> &DashMap<String, mpsc::UnboundedSender<Self::Value>> {
+    fn get_sender_collection(&self) -> &DashMap<String, ResponseSender<Self::Value>> {
         &self.senders
     }
 }
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use actix_web::dev::ServerHandle;
+use log::info;
+
+use crate::drain_status::DrainStatus;
+
+/// Stops accepting new connections once the drain starts. The open connections are kept, so
+/// the in-flight requests can finish and the clients that keep them alive can see the failing
+/// health check.
+pub async fn pause_server_when_draining(
+    drain_status: Arc<DrainStatus>,
+    server_handle: ServerHandle,
+) {
+    drain_status.wait_for_draining().await;
+
+    info!("Draining, no longer accepting new connections");
+
+    server_handle.pause().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use actix_web::App;
+    use actix_web::HttpServer;
+    use actix_web::rt;
+    use anyhow::Result;
+    use reqwest::StatusCode;
+
+    use super::*;
+    use crate::balancer::http_route::get_health;
+
+    #[actix_web::test]
+    async fn test_stops_accepting_connections_when_draining() -> Result<()> {
+        let drain_status = Arc::new(DrainStatus::default());
+        let http_server = HttpServer::new(|| App::new().configure(get_health::register))
+            .workers(1)
+            .bind(("127.0.0.1", 0))?;
+        let health_url = format!("http://{}/health", http_server.addrs()[0]);
+        let server = http_server.run();
+        let pause_server_handle = rt::spawn(pause_server_when_draining(
+            drain_status.clone(),
+            server.handle(),
+        ));
+        let server_handle = server.handle();
+
+        rt::spawn(server);
+
+        // Each request opens its own connection
+        let client = reqwest::Client::builder()
+            .pool_max_idle_per_host(0)
+            .timeout(Duration::from_millis(500))
+            .build()?;
+
+        assert_eq!(
+            client.get(&health_url).send().await?.status(),
+            StatusCode::OK
+        );
+
+        drain_status.start_draining();
+        pause_server_handle.await?;
+
+        assert!(client.get(&health_url).send().await.is_err());
+
+        server_handle.stop(false).await;
+
+        Ok(())
+    }
+}
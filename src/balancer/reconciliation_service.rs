@@ -1,14 +1,19 @@
 use std::sync::Arc;
 
 use anyhow::Result;
+use anyhow::anyhow;
 use async_trait::async_trait;
 use log::error;
+use log::info;
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
 use tokio::sync::broadcast;
 use tokio::time::Duration;
 use tokio::time::MissedTickBehavior;
 use tokio::time::interval;
 
 use crate::balancer::agent_controller_pool::AgentControllerPool;
+use crate::balancer::state_database::StateDatabase;
 use crate::balancer_applicable_state_holder::BalancerApplicableStateHolder;
 use crate::balancer_desired_state::BalancerDesiredState;
 use crate::converts_to_applicable_state::ConvertsToApplicableState as _;
@@ -20,7 +25,10 @@ pub struct ReconciliationService {
     pub balancer_applicable_state_holder: Arc<BalancerApplicableStateHolder>,
     pub balancer_desired_state: BalancerDesiredState,
     pub balancer_desired_state_rx: broadcast::Receiver<BalancerDesiredState>,
+    pub balancer_desired_state_write_lock: Arc<Mutex<()>>,
     pub is_converted_to_applicable_state: bool,
+    pub last_scheduled_minute: Option<OffsetDateTime>,
+    pub state_database: Arc<dyn StateDatabase>,
 }
 
 impl ReconciliationService {
@@ -40,6 +48,58 @@ impl ReconciliationService {
         Ok(())
     }
 
+    /// Checks the schedule once per minute, and applies the most recent entry that became
+    /// due since the previous check. The first check after the start applies the most recent
+    /// entry that is already due, so the transitions missed while the balancer was down
+    /// are not lost. If more than one entry is due at the same minute, the one listed last wins.
+    pub async fn apply_scheduled_state(&mut self, now: OffsetDateTime) -> Result<()> {
+        let minute = now.replace_second(0)?.replace_nanosecond(0)?;
+
+        if self.last_scheduled_minute == Some(minute) {
+            return Ok(());
+        }
+
+        let last_scheduled_minute = self.last_scheduled_minute.replace(minute);
+        let schedule = self
+            .state_database
+            .read_balancer_desired_state_schedule()
+            .await?;
+
+        // Picks the last of the equally recent entries
+        if let Some((_, entry)) = schedule
+            .iter()
+            .filter_map(|entry| {
+                entry
+                    .cron
+                    .last_at_or_before(minute)
+                    .map(|due_at| (due_at, entry))
+            })
+            .filter(|(due_at, _)| {
+                last_scheduled_minute
+                    .is_none_or(|last_scheduled_minute| *due_at > last_scheduled_minute)
+            })
+            .max_by_key(|(due_at, _)| *due_at)
+        {
+            info!("Activating scheduled profile '{}'", entry.profile);
+
+            let _lock = self.balancer_desired_state_write_lock.lock().await;
+
+            if self
+                .state_database
+                .activate_balancer_desired_state_profile(&entry.profile)
+                .await?
+                .is_none()
+            {
+                return Err(anyhow!(
+                    "Scheduled profile '{}' does not exist",
+                    entry.profile
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
     pub async fn try_convert_to_applicable_state(&mut self) {
         if let Err(err) = self.convert_to_applicable_state().await {
             error!("Failed to convert to applicable state: {err}");
@@ -65,13 +125,164 @@ impl Service for ReconciliationService {
                     if !self.is_converted_to_applicable_state {
                         self.try_convert_to_applicable_state().await;
                     }
+
+                    if let Err(err) = self.apply_scheduled_state(OffsetDateTime::now_utc()).await {
+                        error!("Failed to apply the scheduled state: {err}");
+                    }
                 },
                 balancer_desired_state = self.balancer_desired_state_rx.recv() => {
+                    let balancer_desired_state = balancer_desired_state?;
+
+                    // Reloading an unchanged state would needlessly disturb the agents
+                    if self.is_converted_to_applicable_state
+                        && balancer_desired_state == self.balancer_desired_state
+                    {
+                        continue;
+                    }
+
                     self.is_converted_to_applicable_state = false;
-                    self.balancer_desired_state = balancer_desired_state?;
+                    self.balancer_desired_state = balancer_desired_state;
                     self.try_convert_to_applicable_state().await;
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::agent_desired_model::AgentDesiredModel;
+    use crate::balancer::agent_circuit_breaker_configuration::AgentCircuitBreakerConfiguration;
+    use crate::balancer::balancer_desired_state_schedule_entry::BalancerDesiredStateScheduleEntry;
+    use crate::balancer::state_database::Memory;
+    use crate::full_buffer_policy::FullBufferPolicy;
+    use crate::heartbeat_configuration::HeartbeatConfiguration;
+    use crate::response_buffer_configuration::ResponseBufferConfiguration;
+
+    fn make_profile(model: &str) -> BalancerDesiredState {
+        BalancerDesiredState {
+            model: AgentDesiredModel::LocalToAgent(model.to_string()),
+            ..BalancerDesiredState::default()
+        }
+    }
+
+    async fn make_reconciliation_service() -> Result<ReconciliationService> {
+        let (balancer_desired_state_tx, balancer_desired_state_rx) = broadcast::channel(100);
+        let state_database = Arc::new(Memory::new(
+            balancer_desired_state_tx,
+            BalancerDesiredState::default(),
+        ));
+
+        state_database
+            .store_balancer_desired_state_profiles(&BTreeMap::from([
+                ("daytime".to_string(), make_profile("large.gguf")),
+                ("overnight".to_string(), make_profile("small.gguf")),
+            ]))
+            .await?;
+        state_database
+            .store_balancer_desired_state_schedule(&[
+                BalancerDesiredStateScheduleEntry {
+                    cron: "0 8 * * *".parse()?,
+                    profile: "daytime".to_string(),
+                },
+                BalancerDesiredStateScheduleEntry {
+                    cron: "0 20 * * *".parse()?,
+                    profile: "overnight".to_string(),
+                },
+            ])
+            .await?;
+
+        Ok(ReconciliationService {
+            agent_controller_pool: Arc::new(AgentControllerPool::new(
+                AgentCircuitBreakerConfiguration {
+                    cooldown: Duration::from_secs(10),
+                    failure_ratio: 0.0,
+                    failure_ratio_min_requests: 0,
+                    failure_ratio_window: 0,
+                    failure_threshold: 0,
+                },
+                HeartbeatConfiguration {
+                    interval: Duration::from_secs(5),
+                    missed_beats_threshold: 0,
+                },
+                Duration::ZERO,
+                ResponseBufferConfiguration {
+                    capacity: 16,
+                    full_buffer_policy: FullBufferPolicy::Pause,
+                },
+            )),
+            balancer_applicable_state_holder: Arc::new(BalancerApplicableStateHolder::default()),
+            balancer_desired_state: BalancerDesiredState::default(),
+            balancer_desired_state_rx,
+            balancer_desired_state_write_lock: Arc::new(Mutex::new(())),
+            is_converted_to_applicable_state: false,
+            last_scheduled_minute: None,
+            state_database,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_applies_missed_transition_on_start() -> Result<()> {
+        let mut reconciliation_service = make_reconciliation_service().await?;
+
+        reconciliation_service
+            .apply_scheduled_state(datetime!(2025-01-01 12:30 UTC))
+            .await?;
+
+        assert_eq!(
+            reconciliation_service
+                .state_database
+                .read_active_balancer_desired_state_profile()
+                .await?,
+            Some("daytime".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_applies_each_transition_once() -> Result<()> {
+        let mut reconciliation_service = make_reconciliation_service().await?;
+
+        reconciliation_service
+            .apply_scheduled_state(datetime!(2025-01-01 12:30 UTC))
+            .await?;
+
+        // Manual changes stay in place until the next transition
+        reconciliation_service
+            .state_database
+            .store_balancer_desired_state(&BalancerDesiredState::default())
+            .await?;
+        reconciliation_service
+            .apply_scheduled_state(datetime!(2025-01-01 12:31 UTC))
+            .await?;
+
+        assert_eq!(
+            reconciliation_service
+                .state_database
+                .read_active_balancer_desired_state_profile()
+                .await?,
+            None
+        );
+
+        // Transitions that fall between two checks are not skipped
+        reconciliation_service
+            .apply_scheduled_state(datetime!(2025-01-01 20:02 UTC))
+            .await?;
+
+        assert_eq!(
+            reconciliation_service
+                .state_database
+                .read_balancer_desired_state()
+                .await?,
+            make_profile("small.gguf")
+        );
+
+        Ok(())
+    }
+}
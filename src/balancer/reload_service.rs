@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use log::error;
+use log::info;
+use log::warn;
+use tokio::sync::Mutex;
+use tokio::sync::broadcast;
+
+use crate::balancer::agent_token_registry_holder::AgentTokenRegistryHolder;
+use crate::balancer::api_key_manager::ApiKeyManager;
+use crate::balancer::buffered_request_manager::BufferedRequestManager;
+use crate::balancer::inference_service::configuration_holder::ConfigurationHolder as InferenceServiceConfigurationHolder;
+use crate::balancer::loads_reloadable_configuration::LoadsReloadableConfiguration;
+use crate::balancer::management_access_control::ManagementAccessControl;
+use crate::balancer::reloadable_configuration::ReloadableConfiguration;
+use crate::balancer::reloadable_tls_certificate::ReloadableTlsCertificate;
+use crate::balancer::state_database::StateDatabase;
+use crate::balancer::statsd_service::configuration::Configuration as StatsdServiceConfiguration;
+use crate::balancer_desired_state::BalancerDesiredState;
+use crate::cors_allowed_hosts::CorsAllowedHosts;
+use crate::service::Service;
+
+pub struct ReloadService {
+    pub agent_token_registry_holder: Arc<AgentTokenRegistryHolder>,
+    pub api_key_manager: Arc<ApiKeyManager>,
+    pub balancer_desired_state_tx: broadcast::Sender<BalancerDesiredState>,
+    pub balancer_desired_state_write_lock: Arc<Mutex<()>>,
+    pub buffered_request_manager: Arc<BufferedRequestManager>,
+    pub configuration: ReloadableConfiguration,
+    pub configuration_loader: Arc<dyn LoadsReloadableConfiguration>,
+    pub inference_cors_allowed_hosts: Arc<CorsAllowedHosts>,
+    pub inference_service_configuration_holder: Arc<InferenceServiceConfigurationHolder>,
+    pub management_access_control: Arc<ManagementAccessControl>,
+    pub management_cors_allowed_hosts: Arc<CorsAllowedHosts>,
+    pub reload_rx: broadcast::Receiver<()>,
+    pub reloadable_tls_certificate: Option<Arc<ReloadableTlsCertificate>>,
+    pub state_database: Arc<dyn StateDatabase>,
+    pub statsd_service_configuration_tx: broadcast::Sender<StatsdServiceConfiguration>,
+}
+
+impl ReloadService {
+    pub async fn reload(&mut self) -> Result<()> {
+        let mut configuration = self.configuration_loader.load_reloadable_configuration()?;
+
+        for setting in configuration.retain_non_reloadable_settings(&self.configuration) {
+            warn!("Changing '{setting}' requires a restart; keeping the current value");
+        }
+
+        // Validate everything before applying anything, so a broken configuration
+        // does not get applied halfway
+        let agent_token_registry = configuration.get_agent_token_registry()?;
+        let agent_tokens = self.state_database.read_agent_tokens().await?;
+        let api_keys = self.state_database.read_api_keys().await?;
+        let management_token_registry = configuration.get_management_token_registry()?;
+        let management_tokens = self.state_database.read_management_tokens().await?;
+        let priority_class_registry = configuration.get_priority_class_registry()?;
+        // Held until the state is sent, so a state read before a concurrent update
+        // is never sent after it
+        let _balancer_desired_state_lock = self.balancer_desired_state_write_lock.lock().await;
+        let balancer_desired_state = self.state_database.read_balancer_desired_state().await?;
+        let tls_certified_key = configuration.get_tls_certified_key()?;
+
+        self.agent_token_registry_holder
+            .set_agent_token_registry(agent_token_registry);
+        self.agent_token_registry_holder
+            .set_stored_agent_tokens(agent_tokens);
+        self.buffered_request_manager
+            .agent_controller_pool
+            .disconnect_unauthenticated_agents(&self.agent_token_registry_holder);
+        self.api_key_manager.set_api_keys(api_keys);
+        self.api_key_manager
+            .set_is_required(configuration.require_api_key);
+        self.api_key_manager
+            .set_priority_class_api_keys(&configuration.priority_class_api_keys);
+        self.buffered_request_manager
+            .set_priority_class_registry(priority_class_registry);
+        self.inference_cors_allowed_hosts
+            .set_hosts(configuration.get_inference_cors_allowed_hosts());
+        self.inference_service_configuration_holder
+            .set_configuration(configuration.get_inference_service_configuration());
+        self.management_access_control
+            .set_management_token_registry(management_token_registry);
+        self.management_access_control
+            .set_stored_tokens(management_tokens);
+        self.management_cors_allowed_hosts
+            .set_hosts(configuration.get_management_cors_allowed_hosts());
+
+        if let (Some(reloadable_tls_certificate), Some(tls_certified_key)) =
+            (&self.reloadable_tls_certificate, tls_certified_key)
+        {
+            reloadable_tls_certificate.set_certified_key(tls_certified_key);
+        }
+
+        match (
+            self.configuration.get_statsd_service_configuration(),
+            configuration.get_statsd_service_configuration(),
+        ) {
+            (Some(current), Some(reloaded)) if current != reloaded => {
+                self.statsd_service_configuration_tx.send(reloaded)?;
+            }
+            _ => {}
+        }
+
+        self.balancer_desired_state_tx
+            .send(balancer_desired_state)?;
+        self.configuration = configuration;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Service for ReloadService {
+    fn name(&self) -> &'static str {
+        "balancer::reload_service"
+    }
+
+    async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
+        loop {
+            tokio::select! {
+                _ = shutdown.recv() => break Ok(()),
+                reload = self.reload_rx.recv() => {
+                    // Signals that arrived while reloading are coalesced into a single reload
+                    if let Err(broadcast::error::RecvError::Closed) = reload {
+                        break Ok(());
+                    }
+
+                    match self.reload().await {
+                        Ok(()) => info!("Configuration reloaded"),
+                        Err(err) => error!("Failed to reload configuration: {err}"),
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Result;
+use anyhow::anyhow;
+use rustls::sign::CertifiedKey;
+
+use crate::balancer::agent_token_registry::AgentTokenRegistry;
+use crate::balancer::buffered_request_priority_class::BufferedRequestPriorityClass;
+use crate::balancer::buffered_request_priority_class_api_key::BufferedRequestPriorityClassApiKey;
+use crate::balancer::buffered_request_priority_class_registry::BufferedRequestPriorityClassRegistry;
+use crate::balancer::inference_service::configuration::Configuration as InferenceServiceConfiguration;
+use crate::balancer::management_token_registry::ManagementTokenRegistry;
+use crate::balancer::reloadable_tls_certificate::ReloadableTlsCertificate;
+use crate::balancer::statsd_service::configuration::Configuration as StatsdServiceConfiguration;
+use crate::server_addr::ServerAddr;
+
+/// Settings that are applied again when the balancer receives SIGHUP.
+/// Addresses are here only to report that changing them requires a restart.
+#[derive(Clone)]
+pub struct ReloadableConfiguration {
+    pub agent_tokens_file: Option<PathBuf>,
+    pub buffered_request_timeout: Duration,
+    pub compat_openai_addr: Option<ServerAddr>,
+    pub inference_addr: ServerAddr,
+    pub inference_cors_allowed_hosts: Vec<String>,
+    pub inference_item_timeout: Duration,
+    pub management_addr: ServerAddr,
+    pub management_cors_allowed_hosts: Vec<String>,
+    pub management_tokens_file: Option<PathBuf>,
+    pub max_buffered_requests: i32,
+    pub max_request_attempts: usize,
+    pub priority_class_api_keys: Vec<BufferedRequestPriorityClassApiKey>,
+    pub priority_classes: Vec<BufferedRequestPriorityClass>,
+    pub require_api_key: bool,
+    pub statsd_addr: Option<SocketAddr>,
+    pub statsd_prefix: String,
+    pub statsd_reporting_interval: Duration,
+    pub tls_cert_file: Option<PathBuf>,
+    pub tls_client_ca_file: Option<PathBuf>,
+    pub tls_key_file: Option<PathBuf>,
+    pub web_admin_panel_addr: Option<SocketAddr>,
+}
+
+impl ReloadableConfiguration {
+    pub fn get_agent_token_registry(&self) -> Result<Option<AgentTokenRegistry>> {
+        self.agent_tokens_file
+            .as_deref()
+            .map(AgentTokenRegistry::read)
+            .transpose()
+    }
+
+    pub fn get_inference_cors_allowed_hosts(&self) -> Vec<String> {
+        self.with_web_admin_panel_origin(self.inference_cors_allowed_hosts.clone())
+    }
+
+    pub fn get_inference_service_configuration(&self) -> InferenceServiceConfiguration {
+        InferenceServiceConfiguration {
+            addr: self.inference_addr.clone(),
+            inference_item_timeout: self.inference_item_timeout,
+            max_request_attempts: self.max_request_attempts,
+        }
+    }
+
+    pub fn get_management_cors_allowed_hosts(&self) -> Vec<String> {
+        self.with_web_admin_panel_origin(self.management_cors_allowed_hosts.clone())
+    }
+
+    pub fn get_management_token_registry(&self) -> Result<Option<ManagementTokenRegistry>> {
+        self.management_tokens_file
+            .as_deref()
+            .map(ManagementTokenRegistry::read)
+            .transpose()
+    }
+
+    pub fn get_priority_class_registry(&self) -> Result<BufferedRequestPriorityClassRegistry> {
+        BufferedRequestPriorityClassRegistry::new(
+            self.buffered_request_timeout,
+            self.max_buffered_requests,
+            self.priority_classes.clone(),
+            self.priority_class_api_keys.clone(),
+        )
+    }
+
+    pub fn get_statsd_service_configuration(&self) -> Option<StatsdServiceConfiguration> {
+        self.statsd_addr
+            .map(|statsd_addr| StatsdServiceConfiguration {
+                statsd_addr,
+                statsd_prefix: self.statsd_prefix.clone(),
+                statsd_reporting_interval: self.statsd_reporting_interval,
+            })
+    }
+
+    /// Reads the files again, so a renewed certificate is picked up on reload.
+    pub fn get_tls_certified_key(&self) -> Result<Option<CertifiedKey>> {
+        match (&self.tls_cert_file, &self.tls_key_file) {
+            (Some(tls_cert_file), Some(tls_key_file)) => Ok(Some(
+                ReloadableTlsCertificate::read_certified_key(tls_cert_file, tls_key_file)?,
+            )),
+            (None, None) if self.tls_client_ca_file.is_some() => Err(anyhow!(
+                "Verifying client certificates requires the TLS certificate and private key files"
+            )),
+            (None, None) => Ok(None),
+            _ => Err(anyhow!(
+                "TLS requires both the certificate and the private key file"
+            )),
+        }
+    }
+
+    /// Restores the settings that cannot change without a restart and returns the names
+    /// of the ones that were different.
+    pub fn retain_non_reloadable_settings(&mut self, current: &Self) -> Vec<&'static str> {
+        let mut changed_settings = Vec::new();
+
+        if self.compat_openai_addr != current.compat_openai_addr {
+            changed_settings.push("compat_openai_addr");
+            self.compat_openai_addr = current.compat_openai_addr.clone();
+        }
+
+        if self.inference_addr != current.inference_addr {
+            changed_settings.push("inference_addr");
+            self.inference_addr = current.inference_addr.clone();
+        }
+
+        if self.management_addr != current.management_addr {
+            changed_settings.push("management_addr");
+            self.management_addr = current.management_addr.clone();
+        }
+
+        // The statsd service can be reconfigured, but it cannot be started or stopped
+        if self.statsd_addr.is_some() != current.statsd_addr.is_some() {
+            changed_settings.push("statsd_addr");
+            self.statsd_addr = current.statsd_addr;
+        }
+
+        // The certificate can be replaced, but TLS cannot be turned on or off
+        if self.tls_cert_file.is_some() != current.tls_cert_file.is_some() {
+            changed_settings.push("tls_cert_file");
+            self.tls_cert_file = current.tls_cert_file.clone();
+        }
+
+        if self.tls_client_ca_file != current.tls_client_ca_file {
+            changed_settings.push("tls_client_ca_file");
+            self.tls_client_ca_file = current.tls_client_ca_file.clone();
+        }
+
+        if self.tls_key_file.is_some() != current.tls_key_file.is_some() {
+            changed_settings.push("tls_key_file");
+            self.tls_key_file = current.tls_key_file.clone();
+        }
+
+        if self.web_admin_panel_addr != current.web_admin_panel_addr {
+            changed_settings.push("web_admin_panel_addr");
+            self.web_admin_panel_addr = current.web_admin_panel_addr;
+        }
+
+        changed_settings
+    }
+
+    #[cfg_attr(not(feature = "web_admin_panel"), allow(unused_mut))]
+    fn with_web_admin_panel_origin(&self, mut cors_allowed_hosts: Vec<String>) -> Vec<String> {
+        #[cfg(feature = "web_admin_panel")]
+        if let Some(web_admin_panel_addr) = self.web_admin_panel_addr {
+            let scheme = if self.tls_cert_file.is_some() {
+                "https"
+            } else {
+                "http"
+            };
+
+            cors_allowed_hosts.push(format!("{scheme}://{web_admin_panel_addr}"));
+        }
+
+        cors_allowed_hosts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_configuration() -> ReloadableConfiguration {
+        ReloadableConfiguration {
+            agent_tokens_file: None,
+            buffered_request_timeout: Duration::from_secs(10),
+            compat_openai_addr: None,
+            inference_addr: "127.0.0.1:8061".parse().unwrap(),
+            inference_cors_allowed_hosts: vec![],
+            inference_item_timeout: Duration::from_secs(5),
+            management_addr: "127.0.0.1:8060".parse().unwrap(),
+            management_cors_allowed_hosts: vec![],
+            management_tokens_file: None,
+            max_buffered_requests: 30,
+            max_request_attempts: 3,
+            priority_class_api_keys: vec![],
+            priority_classes: vec![],
+            require_api_key: false,
+            statsd_addr: None,
+            statsd_prefix: "paddler_".to_string(),
+            statsd_reporting_interval: Duration::from_secs(10),
+            tls_cert_file: None,
+            tls_client_ca_file: None,
+            tls_key_file: None,
+            web_admin_panel_addr: None,
+        }
+    }
+
+    #[test]
+    fn test_addresses_are_retained() {
+        let current = make_configuration();
+        let mut reloaded = make_configuration();
+
+        reloaded.inference_addr = "127.0.0.1:9061".parse().unwrap();
+        reloaded.max_buffered_requests = 100;
+
+        let changed_settings = reloaded.retain_non_reloadable_settings(&current);
+
+        assert_eq!(changed_settings, vec!["inference_addr"]);
+        assert_eq!(reloaded.inference_addr, current.inference_addr);
+        assert_eq!(reloaded.max_buffered_requests, 100);
+    }
+
+    #[test]
+    fn test_statsd_cannot_be_enabled() {
+        let current = make_configuration();
+        let mut reloaded = make_configuration();
+
+        reloaded.statsd_addr = Some("127.0.0.1:8125".parse().unwrap());
+
+        let changed_settings = reloaded.retain_non_reloadable_settings(&current);
+
+        assert_eq!(changed_settings, vec!["statsd_addr"]);
+        assert!(reloaded.get_statsd_service_configuration().is_none());
+    }
+
+    #[test]
+    fn test_tls_cannot_be_enabled() -> Result<()> {
+        let current = make_configuration();
+        let mut reloaded = make_configuration();
+
+        reloaded.tls_cert_file = Some(PathBuf::from("/etc/paddler/tls.crt"));
+        reloaded.tls_key_file = Some(PathBuf::from("/etc/paddler/tls.key"));
+
+        let changed_settings = reloaded.retain_non_reloadable_settings(&current);
+
+        assert_eq!(changed_settings, vec!["tls_cert_file", "tls_key_file"]);
+        assert!(reloaded.get_tls_certified_key()?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tls_requires_both_files() {
+        let mut configuration = make_configuration();
+
+        configuration.tls_cert_file = Some(PathBuf::from("/etc/paddler/tls.crt"));
+
+        assert!(configuration.get_tls_certified_key().is_err());
+    }
+}
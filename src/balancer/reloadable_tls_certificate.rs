@@ -0,0 +1,131 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use rustls::crypto::ring::default_provider;
+use rustls::server::ClientHello;
+use rustls::server::ResolvesServerCert;
+use rustls::sign::CertifiedKey;
+
+use crate::read_pem_certificates::read_pem_certificates;
+use crate::read_pem_private_key::read_pem_private_key;
+
+/// Certificate presented by the TLS listeners. It can be swapped while they are running,
+/// so the new certificate is used for the connections made after the reload.
+#[derive(Debug)]
+pub struct ReloadableTlsCertificate {
+    certified_key: RwLock<Arc<CertifiedKey>>,
+}
+
+impl ReloadableTlsCertificate {
+    pub fn new(certified_key: CertifiedKey) -> Self {
+        Self {
+            certified_key: RwLock::new(Arc::new(certified_key)),
+        }
+    }
+
+    pub fn read_certified_key(cert_file: &Path, key_file: &Path) -> Result<CertifiedKey> {
+        CertifiedKey::from_der(
+            read_pem_certificates(cert_file)?,
+            read_pem_private_key(key_file)?,
+            &default_provider(),
+        )
+        .with_context(|| {
+            format!(
+                "Private key {} does not match the certificate {}",
+                key_file.display(),
+                cert_file.display()
+            )
+        })
+    }
+
+    pub fn set_certified_key(&self, certified_key: CertifiedKey) {
+        let mut lock = self
+            .certified_key
+            .write()
+            .expect("Failed to get TLS certificate lock");
+
+        *lock = Arc::new(certified_key);
+    }
+}
+
+impl ResolvesServerCert for ReloadableTlsCertificate {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(
+            self.certified_key
+                .read()
+                .expect("Failed to get TLS certificate lock")
+                .clone(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use rcgen::CertifiedKey as GeneratedCertifiedKey;
+    use rcgen::generate_simple_self_signed;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn write_self_signed_certificate(directory: &TempDir, name: &str) -> (PathBuf, PathBuf) {
+        let GeneratedCertifiedKey { cert, key_pair } =
+            generate_simple_self_signed(vec![name.to_string()]).unwrap();
+        let cert_file = directory.path().join(format!("{name}.crt"));
+        let key_file = directory.path().join(format!("{name}.key"));
+
+        fs::write(&cert_file, cert.pem()).unwrap();
+        fs::write(&key_file, key_pair.serialize_pem()).unwrap();
+
+        (cert_file, key_file)
+    }
+
+    #[test]
+    fn test_certificate_is_replaced() -> Result<()> {
+        let directory = TempDir::new()?;
+        let (first_cert_file, first_key_file) =
+            write_self_signed_certificate(&directory, "first.localhost");
+        let (second_cert_file, second_key_file) =
+            write_self_signed_certificate(&directory, "second.localhost");
+
+        let first_certified_key =
+            ReloadableTlsCertificate::read_certified_key(&first_cert_file, &first_key_file)?;
+        let second_certified_key =
+            ReloadableTlsCertificate::read_certified_key(&second_cert_file, &second_key_file)?;
+        let second_certificate = second_certified_key.cert.clone();
+
+        let reloadable_tls_certificate = ReloadableTlsCertificate::new(first_certified_key);
+
+        reloadable_tls_certificate.set_certified_key(second_certified_key);
+
+        assert_eq!(
+            reloadable_tls_certificate
+                .certified_key
+                .read()
+                .unwrap()
+                .cert,
+            second_certificate
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mismatched_key_is_rejected() -> Result<()> {
+        let directory = TempDir::new()?;
+        let (first_cert_file, _) = write_self_signed_certificate(&directory, "first.localhost");
+        let (_, second_key_file) = write_self_signed_certificate(&directory, "second.localhost");
+
+        assert!(
+            ReloadableTlsCertificate::read_certified_key(&first_cert_file, &second_key_file)
+                .is_err()
+        );
+
+        Ok(())
+    }
+}
@@ -4,14 +4,20 @@ use std::sync::Arc;
 use anyhow::Result;
 use log::debug;
 use log::error;
+use log::info;
 use log::warn;
 use tokio::sync::broadcast;
 use tokio::time::sleep;
+use tokio::time::timeout;
 
 use crate::agent::jsonrpc::Request as AgentJsonRpcRequest;
 use crate::balancer::agent_controller::AgentController;
+use crate::balancer::agent_controller_pool::AgentControllerPool;
+use crate::balancer::api_key_usage_guard::ApiKeyUsageGuard;
 use crate::balancer::buffered_request_agent_wait_result::BufferedRequestAgentWaitResult;
 use crate::balancer::buffered_request_manager::BufferedRequestManager;
+use crate::balancer::buffered_request_priority_class::BufferedRequestPriorityClass;
+use crate::balancer::forward_responses_stream_result::ForwardResponsesStreamResult;
 use crate::balancer::handles_agent_streaming_response::HandlesAgentStreamingResponse;
 use crate::balancer::inference_client::Message as OutgoingMessage;
 use crate::balancer::inference_client::Response as OutgoingResponse;
@@ -22,77 +28,157 @@ use crate::controls_session::ControlsSession;
 use crate::jsonrpc::Error as JsonRpcError;
 use crate::jsonrpc::ErrorEnvelope;
 use crate::jsonrpc::ResponseEnvelope;
+use crate::protocol_feature::ProtocolFeature;
 use crate::streamable_result::StreamableResult;
 
 pub async fn request_from_agent<TControlsSession, TParams>(
+    api_key_usage_guard: Option<Arc<ApiKeyUsageGuard>>,
     buffered_request_manager: Arc<BufferedRequestManager>,
     connection_close_tx: broadcast::Sender<()>,
     inference_service_configuration: InferenceServiceConfiguration,
     params: TParams,
+    priority_class: Arc<BufferedRequestPriorityClass>,
     request_id: String,
     mut session_controller: TControlsSession,
 ) -> Result<()>
 where
     TControlsSession: ControlsSession<OutgoingMessage>,
-    TParams: Debug + Into<AgentJsonRpcRequest> + Send,
+    TParams: Clone + Debug + Into<AgentJsonRpcRequest> + Send,
     AgentController: HandlesAgentStreamingResponse<TParams>,
     <<AgentController as HandlesAgentStreamingResponse<TParams>>::SenderCollection as ManagesSenders>::Value: Debug + Into<OutgoingResponse> + StreamableResult,
 {
-    match wait_for_agent_controller(
-        buffered_request_manager.clone(),
-        connection_close_tx.subscribe(),
-        request_id.clone(),
-        &mut session_controller,
-    )
-    .await?
-    {
-        Some(agent_controller) => {
-            let receive_response_controller = match agent_controller
-                .handle_streaming_response(request_id.clone(), params)
-                .await
-            {
-                Ok(receive_response_controller) => receive_response_controller,
-                Err(err) => {
-                    error!("Failed to handle request {request_id:?}: {err}");
+    let mut attempt: usize = 1;
+    let queue_sequence = buffered_request_manager.next_queue_sequence();
 
-                    respond_with_error(
-                        JsonRpcError {
-                            code: 500,
-                            description: "Failed to generate response".to_string(),
-                        },
-                        request_id.clone(),
-                        &mut session_controller,
-                    )
-                    .await;
+    loop {
+        let agent_controller = match wait_for_agent_controller(
+            buffered_request_manager.clone(),
+            connection_close_tx.subscribe(),
+            priority_class.clone(),
+            queue_sequence,
+            request_id.clone(),
+            &mut session_controller,
+        )
+        .await?
+        {
+            Some(agent_controller) => agent_controller,
+            None => return Ok(()),
+        };
+
+        let receive_response_controller = match agent_controller
+            .handle_streaming_response(request_id.clone(), params.clone())
+            .await
+        {
+            Ok(receive_response_controller) => receive_response_controller,
+            Err(err) => {
+                agent_controller.circuit_breaker.record_failure();
+
+                if attempt < inference_service_configuration.max_request_attempts {
+                    warn!(
+                        "Failed to handle request {request_id:?} (attempt {attempt}), retrying: {err}"
+                    );
+
+                    buffered_request_manager
+                        .request_retry_counter
+                        .increment_retries();
+                    attempt += 1;
+
+                    continue;
+                }
+
+                error!("Failed to handle request {request_id:?}: {err}");
+
+                buffered_request_manager
+                    .request_retry_counter
+                    .increment_retries_exhausted();
+
+                respond_with_error(
+                    JsonRpcError {
+                        code: 500,
+                        description: "Failed to generate response".to_string(),
+                    },
+                    request_id.clone(),
+                    &mut session_controller,
+                )
+                .await;
+
+                return Ok(());
+            }
+        };
+
+        match forward_responses_stream(
+            agent_controller,
+            buffered_request_manager.agent_controller_pool.clone(),
+            api_key_usage_guard.as_deref(),
+            connection_close_tx.subscribe(),
+            inference_service_configuration.clone(),
+            attempt >= inference_service_configuration.max_request_attempts,
+            receive_response_controller,
+            request_id.clone(),
+            &mut session_controller,
+        )
+        .await?
+        {
+            ForwardResponsesStreamResult::AgentDisconnectedBeforeFirstChunk => {
+                if attempt < inference_service_configuration.max_request_attempts {
+                    warn!(
+                        "Agent disconnected before responding to request {request_id:?} (attempt {attempt}), retrying"
+                    );
+
+                    buffered_request_manager
+                        .request_retry_counter
+                        .increment_retries();
+                    attempt += 1;
 
-                    return Ok(());
+                    continue;
                 }
-            };
-
-            forward_responses_stream(
-                agent_controller,
-                connection_close_tx.subscribe(),
-                inference_service_configuration,
-                receive_response_controller,
-                request_id,
-                session_controller,
-            )
-            .await?;
-
-            Ok(())
+
+                error!(
+                    "Agent disconnected before responding to request {request_id:?}, no attempts left"
+                );
+
+                buffered_request_manager
+                    .request_retry_counter
+                    .increment_retries_exhausted();
+
+                respond_with_error(
+                    JsonRpcError {
+                        code: 502,
+                        description: "Agent controller connection closed".to_string(),
+                    },
+                    request_id,
+                    &mut session_controller,
+                )
+                .await;
+
+                return Ok(());
+            }
+            ForwardResponsesStreamResult::AgentFailedBeforeFirstChunk => {
+                warn!(
+                    "Agent failed before responding to request {request_id:?} (attempt {attempt}), retrying"
+                );
+
+                buffered_request_manager
+                    .request_retry_counter
+                    .increment_retries();
+                attempt += 1;
+            }
+            ForwardResponsesStreamResult::Finished => return Ok(()),
         }
-        None => Ok(()),
     }
 }
 
 async fn forward_responses_stream<TControlsSession, TManagesSenders>(
-    agent_controller: Arc<AgentController>,
+    mut agent_controller: Arc<AgentController>,
+    agent_controller_pool: Arc<AgentControllerPool>,
+    api_key_usage_guard: Option<&ApiKeyUsageGuard>,
     mut connection_close_rx: broadcast::Receiver<()>,
     inference_service_configuration: InferenceServiceConfiguration,
+    is_last_attempt: bool,
     mut receive_response_controller: ManagesSendersController<TManagesSenders>,
     request_id: String,
-    mut session_controller: TControlsSession,
-) -> Result<()>
+    session_controller: &mut TControlsSession,
+) -> Result<ForwardResponsesStreamResult>
 where
     TControlsSession: ControlsSession<OutgoingMessage>,
     TManagesSenders: ManagesSenders + Send + Sync,
@@ -102,19 +188,38 @@ where
 
     let mut agent_controller_connection_close_resubscribed =
         agent_controller.connection_close_rx.resubscribe();
+    let mut has_forwarded_chunk = false;
 
     loop {
         tokio::select! {
             _ = agent_controller_connection_close_resubscribed.recv() => {
-                error!("Agent controller connection closed");
+                if !has_forwarded_chunk {
+                    agent_controller.circuit_breaker.record_failure();
+
+                    return Ok(ForwardResponsesStreamResult::AgentDisconnectedBeforeFirstChunk);
+                }
+
+                if let Some(reconnected_agent_controller) = reattach_request(
+                    &agent_controller,
+                    &agent_controller_pool,
+                    &receive_response_controller,
+                ).await {
+                    agent_controller = reconnected_agent_controller;
+                    agent_controller_connection_close_resubscribed =
+                        agent_controller.connection_close_rx.resubscribe();
+
+                    continue;
+                }
+
+                error!("Agent controller connection closed while streaming response for request {request_id:?}");
 
                 respond_with_error(
                     JsonRpcError {
                         code: 502,
-                        description: "Agent controller connection closed".to_string(),
+                        description: "Agent controller connection closed after the response has started".to_string(),
                     },
                     request_id,
-                    &mut session_controller,
+                    session_controller,
                 ).await;
 
                 break;
@@ -129,13 +234,15 @@ where
             _ = sleep(inference_service_configuration.inference_item_timeout) => {
                 warn!("Timed out waiting for response for request {request_id:?}");
 
+                agent_controller.circuit_breaker.record_failure();
+
                 respond_with_error(
                     JsonRpcError {
                         code: 504,
                         description: "Downstream response timed out".to_string(),
                     },
                     request_id.clone(),
-                    &mut session_controller,
+                    session_controller,
                 ).await;
 
                 agent_controller.stop_responding_to(request_id.clone()).await.unwrap_or_else(|err| {
@@ -148,25 +255,148 @@ where
                 match response {
                     Some(response) => {
                         let is_done = response.is_done();
+                        let used_tokens = response.used_tokens();
+
+                        if let Some(api_key_usage_guard) =
+                            api_key_usage_guard.filter(|_| used_tokens > 0)
+                        {
+                            api_key_usage_guard.record_tokens(used_tokens);
+                        }
+
+                        agent_controller
+                            .resume_responding_if_drained(
+                                &request_id,
+                                receive_response_controller.response_sender_collection.as_ref(),
+                            )
+                            .await
+                            .unwrap_or_else(|err| {
+                                error!("Failed to resume responding to request {request_id:?}: {err}");
+                            });
+
+                        if response.is_usage_report() {
+                            continue;
+                        }
+
+                        if response.is_error() {
+                            agent_controller.circuit_breaker.record_failure();
+
+                            // Nothing reached the client yet, so a different agent can take over
+                            if !has_forwarded_chunk && !is_last_attempt {
+                                return Ok(ForwardResponsesStreamResult::AgentFailedBeforeFirstChunk);
+                            }
+                        } else if is_done {
+                            agent_controller.circuit_breaker.record_success();
+                        }
+
+                        has_forwarded_chunk = true;
 
                         send_response_to_client(
                             agent_controller.clone(),
                             response,
                             request_id.clone(),
-                            &mut session_controller,
+                            session_controller,
                         ).await;
 
                         if is_done {
                             break;
                         }
                     }
-                    None => break,
+                    None => {
+                        // The sender is deregistered when the response buffer overflows
+                        // or the agent fails to finish the request
+                        warn!("Responses to request {request_id:?} ended before they were done");
+
+                        respond_with_error(
+                            JsonRpcError {
+                                code: 503,
+                                description: "Response was aborted before it finished".to_string(),
+                            },
+                            request_id,
+                            session_controller,
+                        ).await;
+
+                        break;
+                    }
                 }
             }
         }
     }
 
-    Ok(())
+    Ok(ForwardResponsesStreamResult::Finished)
+}
+
+/// Waits for the agent to reconnect, and asks it to continue the request right after
+/// the responses that reached the balancer
+async fn reattach_request<TManagesSenders>(
+    agent_controller: &Arc<AgentController>,
+    agent_controller_pool: &AgentControllerPool,
+    receive_response_controller: &ManagesSendersController<TManagesSenders>,
+) -> Option<Arc<AgentController>>
+where
+    TManagesSenders: ManagesSenders,
+{
+    let request_id = &receive_response_controller.request_id;
+    let resume_grace_period = agent_controller_pool.agent_resume_grace_period;
+
+    if resume_grace_period.is_zero()
+        || !agent_controller.supports(ProtocolFeature::RequestResumption)
+    {
+        return None;
+    }
+
+    info!(
+        "Agent {} disconnected, request {request_id:?} waits for it to reconnect",
+        agent_controller.id
+    );
+
+    let reconnected_agent_controller = match timeout(
+        resume_grace_period,
+        agent_controller_pool.wait_for_agent_to_reconnect(agent_controller),
+    )
+    .await
+    {
+        Ok(reconnected_agent_controller) => reconnected_agent_controller,
+        Err(_) => {
+            warn!(
+                "Agent {} did not reconnect in time to resume request {request_id:?}",
+                agent_controller.id
+            );
+
+            return None;
+        }
+    };
+
+    if !reconnected_agent_controller.supports(ProtocolFeature::RequestResumption) {
+        warn!(
+            "Agent {} reconnected without supporting the request resumption",
+            agent_controller.id
+        );
+
+        return None;
+    }
+
+    let received_responses = match receive_response_controller
+        .response_sender_collection
+        .get_forwarded_responses(request_id)
+    {
+        Ok(received_responses) => received_responses,
+        Err(err) => {
+            error!("Failed to resume request {request_id:?}: {err}");
+
+            return None;
+        }
+    };
+
+    if let Err(err) = reconnected_agent_controller
+        .reattach_request(request_id.clone(), received_responses)
+        .await
+    {
+        error!("Failed to resume request {request_id:?}: {err}");
+
+        return None;
+    }
+
+    Some(reconnected_agent_controller)
 }
 
 async fn respond_with_error<TControlsSession>(
@@ -217,6 +447,8 @@ async fn send_response_to_client<TControlsSession, TResponse>(
 async fn wait_for_agent_controller<TControlsSession>(
     buffered_request_manager: Arc<BufferedRequestManager>,
     mut connection_close_rx: broadcast::Receiver<()>,
+    priority_class: Arc<BufferedRequestPriorityClass>,
+    queue_sequence: u64,
     request_id: String,
     session_controller: &mut TControlsSession,
 ) -> Result<Option<Arc<AgentController>>>
@@ -231,11 +463,14 @@ where
 
             Ok(None)
         },
-        buffered_request_agent_wait_result = buffered_request_manager.wait_for_available_agent() => {
+        buffered_request_agent_wait_result = buffered_request_manager.wait_for_available_agent(&priority_class, queue_sequence, request_id.clone()) => {
             match buffered_request_agent_wait_result {
                 Ok(BufferedRequestAgentWaitResult::Found(agent_controller)) => Ok(Some(agent_controller)),
                 Ok(BufferedRequestAgentWaitResult::BufferOverflow) => {
-                    warn!("Too many buffered requests, dropping request: {request_id:?}");
+                    warn!(
+                        "Too many buffered requests in priority class {:?}, dropping request: {request_id:?}",
+                        priority_class.name
+                    );
 
                     respond_with_error(
                         JsonRpcError {
@@ -248,6 +483,20 @@ where
 
                     Ok(None)
                 }
+                Ok(BufferedRequestAgentWaitResult::ShuttingDown) => {
+                    warn!("Balancer is shutting down, rejecting request: {request_id:?}");
+
+                    respond_with_error(
+                        JsonRpcError {
+                            code: 503,
+                            description: "Balancer is shutting down, retry the request".to_string(),
+                        },
+                        request_id.clone(),
+                        session_controller,
+                    ).await;
+
+                    Ok(None)
+                }
                 Ok(BufferedRequestAgentWaitResult::Timeout(err)) => {
                     warn!("Buffered request {request_id:?} timed out: {err:?}");
 
@@ -0,0 +1,37 @@
+use std::sync::atomic::AtomicUsize;
+
+use crate::atomic_value::AtomicValue;
+
+/// Counts requests that were handed to another agent because the previous one failed
+/// before producing any output.
+pub struct RequestRetryCounter {
+    retries: AtomicValue<AtomicUsize>,
+    retries_exhausted: AtomicValue<AtomicUsize>,
+}
+
+impl RequestRetryCounter {
+    pub fn get_retries(&self) -> usize {
+        self.retries.get()
+    }
+
+    pub fn get_retries_exhausted(&self) -> usize {
+        self.retries_exhausted.get()
+    }
+
+    pub fn increment_retries(&self) {
+        self.retries.increment_by(1);
+    }
+
+    pub fn increment_retries_exhausted(&self) {
+        self.retries_exhausted.increment_by(1);
+    }
+}
+
+impl Default for RequestRetryCounter {
+    fn default() -> Self {
+        Self {
+            retries: AtomicValue::<AtomicUsize>::new(0),
+            retries_exhausted: AtomicValue::<AtomicUsize>::new(0),
+        }
+    }
+}
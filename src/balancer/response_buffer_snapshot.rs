@@ -0,0 +1,11 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResponseBufferSnapshot {
+    pub buffer_capacity: usize,
+    /// Responses received from the agent that the client did not read yet
+    pub buffer_depth: usize,
+    pub request_id: String,
+}
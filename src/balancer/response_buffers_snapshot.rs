@@ -0,0 +1,11 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::balancer::response_buffer_snapshot::ResponseBufferSnapshot;
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResponseBuffersSnapshot {
+    pub embedding_batches: Vec<ResponseBufferSnapshot>,
+    pub generated_tokens: Vec<ResponseBufferSnapshot>,
+}
@@ -0,0 +1,25 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+
+use tokio::sync::mpsc;
+
+use crate::atomic_value::AtomicValue;
+
+pub struct ResponseSender<TValue> {
+    /// Responses are counted as soon as they arrive from the agent, even if they still wait
+    /// for room in the buffer, so a resumed request continues right after them
+    pub forwarded_responses: AtomicValue<AtomicUsize>,
+    /// Set while the agent is asked to stop sending the responses
+    pub is_paused: AtomicValue<AtomicBool>,
+    pub response_tx: mpsc::Sender<TValue>,
+}
+
+impl<TValue> ResponseSender<TValue> {
+    pub fn new(response_tx: mpsc::Sender<TValue>) -> Self {
+        Self {
+            forwarded_responses: AtomicValue::<AtomicUsize>::new(0),
+            is_paused: AtomicValue::<AtomicBool>::new(false),
+            response_tx,
+        }
+    }
+}
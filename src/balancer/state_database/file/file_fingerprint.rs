@@ -0,0 +1,18 @@
+use std::hash::DefaultHasher;
+use std::hash::Hash as _;
+use std::hash::Hasher as _;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileFingerprint {
+    pub content_hash: u64,
+}
+
+impl FileFingerprint {
+    pub fn hash_content(content: &[u8]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        content.hash(&mut hasher);
+
+        hasher.finish()
+    }
+}
@@ -1,50 +1,215 @@
+mod file_fingerprint;
 mod schema;
+mod upgrade_schema;
 
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 use anyhow::Context;
 use anyhow::Result;
+use anyhow::anyhow;
 use async_trait::async_trait;
+use log::info;
 use log::warn;
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
-use tokio::sync::RwLock;
+use tokio::sync::Mutex as AsyncMutex;
 use tokio::sync::broadcast;
 
+use self::file_fingerprint::FileFingerprint;
 use self::schema::Schema;
+use self::upgrade_schema::CURRENT_SCHEMA_VERSION;
+use self::upgrade_schema::read_schema_version;
+use self::upgrade_schema::upgrade_schema;
 use super::StateDatabase;
+use crate::balancer::agent_token::AgentToken;
+use crate::balancer::api_key::ApiKey;
+use crate::balancer::balancer_desired_state_profile_delete_result::BalancerDesiredStateProfileDeleteResult;
+use crate::balancer::balancer_desired_state_schedule_entry::BalancerDesiredStateScheduleEntry;
+use crate::balancer::balancer_desired_state_schedule_store_result::BalancerDesiredStateScheduleStoreResult;
+use crate::balancer::management_token::ManagementToken;
 use crate::balancer_desired_state::BalancerDesiredState;
 
+fn path_with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path_with_suffix = OsString::from(path.as_os_str());
+
+    path_with_suffix.push(suffix);
+
+    PathBuf::from(path_with_suffix)
+}
+
+fn parse_schema(content: &str) -> Result<(Schema, bool)> {
+    let value: serde_json::Value = serde_json::from_str(content)?;
+    let is_outdated = read_schema_version(&value)? < CURRENT_SCHEMA_VERSION;
+
+    Ok((upgrade_schema(value)?, is_outdated))
+}
+
+/// Writes to a temporary file first and then renames it over the target, so a crash
+/// in the middle of a write never leaves a truncated database behind.
+async fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    let temporary_path = path_with_suffix(path, ".tmp");
+    let mut file = fs::File::create(&temporary_path).await?;
+
+    file.write_all(contents).await?;
+    file.sync_all().await?;
+    drop(file);
+
+    fs::rename(&temporary_path, path).await?;
+
+    if let Some(parent) = path.parent() {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+
+        // Persists the rename itself; not every platform allows opening directories
+        if let Ok(directory) = fs::File::open(parent).await {
+            directory.sync_all().await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Keeps a backup of the last file that was read successfully, and falls back to it
+/// if the database file gets corrupted.
 pub struct File {
+    backup_path: PathBuf,
     balancer_desired_state_notify_tx: broadcast::Sender<BalancerDesiredState>,
+    initial_balancer_desired_state: BalancerDesiredState,
+    last_known_fingerprint: Mutex<Option<FileFingerprint>>,
     path: PathBuf,
-    write_lock: RwLock<()>,
+    /// Held for the whole read-modify-write, so concurrent updates never overwrite each other
+    schema_lock: AsyncMutex<()>,
 }
 
 impl File {
     pub fn new(
         balancer_desired_state_notify_tx: broadcast::Sender<BalancerDesiredState>,
+        initial_balancer_desired_state: BalancerDesiredState,
         path: PathBuf,
     ) -> Self {
         File {
+            backup_path: path_with_suffix(&path, ".bak"),
             balancer_desired_state_notify_tx,
+            initial_balancer_desired_state,
+            last_known_fingerprint: Mutex::new(None),
             path,
-            write_lock: RwLock::new(()),
+            schema_lock: AsyncMutex::new(()),
         }
     }
 
-    async fn read_schema_from_file(&self) -> Result<Schema> {
+    /// Picks up the edits made by other tools (for example, configuration management).
+    /// Invalid edits are logged and not applied.
+    pub async fn check_for_external_changes(&self) -> Result<()> {
+        // Paddler's own writes are atomic, but their fingerprint is recorded only
+        // after the rename; do not mistake them for external changes
+        let _lock = self.schema_lock.lock().await;
+        // The modification time alone misses the edits made within its resolution
+        let content = match fs::read(&self.path).await {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        let fingerprint = FileFingerprint {
+            content_hash: FileFingerprint::hash_content(&content),
+        };
+        let last_known_fingerprint = self.get_last_known_fingerprint();
+
+        self.set_last_known_fingerprint(fingerprint.clone());
+
+        if let Some(last_known_fingerprint) = last_known_fingerprint {
+            if last_known_fingerprint.content_hash == fingerprint.content_hash {
+                return Ok(());
+            }
+        }
+
+        match parse_schema(&String::from_utf8_lossy(&content)) {
+            Ok((schema, _)) => {
+                info!(
+                    "State database file changed; applying the new state: '{}'",
+                    self.path.display()
+                );
+
+                self.balancer_desired_state_notify_tx
+                    .send(schema.balancer_desired_state)?;
+            }
+            Err(err) => {
+                warn!(
+                    "State database file changed, but it is not valid; ignoring the change: '{}': {err}",
+                    self.path.display()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_last_known_fingerprint(&self) -> Option<FileFingerprint> {
+        self.last_known_fingerprint
+            .lock()
+            .expect("Failed to get last known fingerprint lock")
+            .clone()
+    }
+
+    fn set_last_known_fingerprint(&self, fingerprint: FileFingerprint) {
+        *self
+            .last_known_fingerprint
+            .lock()
+            .expect("Failed to get last known fingerprint lock") = Some(fingerprint);
+    }
+
+    fn remember_written_content(&self, content: &[u8]) {
+        self.set_last_known_fingerprint(FileFingerprint {
+            content_hash: FileFingerprint::hash_content(content),
+        });
+    }
+
+    /// Caller has to hold the schema lock.
+    async fn load_schema(&self) -> Result<Schema> {
         match fs::read_to_string(&self.path).await {
             Ok(content) => {
-                if content.is_empty() {
-                    return self.store_default_schema().await;
+                // A crash in the middle of a write made by another tool leaves an empty file
+                if content.trim().is_empty() {
+                    return self
+                        .recover_from_backup(anyhow!("State database file is empty"))
+                        .await;
                 }
 
-                let schema: Schema = serde_json::from_str(&content).context(format!("Unable to parse database file contents: '{}'. Either that is not a valid database file, or this version of Paddler is incompatible with it.", self.path.display()))?;
+                match parse_schema(&content) {
+                    Ok((schema, is_outdated)) => {
+                        if self.get_last_known_fingerprint().is_none() {
+                            self.remember_written_content(content.as_bytes());
+                        }
+
+                        if is_outdated {
+                            info!(
+                                "Upgrading state database file to schema version {CURRENT_SCHEMA_VERSION}: '{}'",
+                                self.path.display()
+                            );
 
-                Ok(schema)
+                            self.store_schema(&schema).await?;
+                        }
+
+                        Ok(schema)
+                    }
+                    Err(err) => self.recover_from_backup(err).await,
+                }
             }
             Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                if fs::try_exists(&self.backup_path).await? {
+                    return self
+                        .recover_from_backup(anyhow!("State database file is missing"))
+                        .await;
+                }
+
                 warn!(
                     "State database file not found; trying to store the default state: '{}'",
                     self.path.display()
@@ -56,8 +221,74 @@ impl File {
         }
     }
 
+    /// Keeps the file that could not be parsed, so an invalid edit can still be fixed
+    /// after the backup is restored in its place.
+    async fn move_corrupted_file_aside(&self) -> Result<()> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let corrupted_path = path_with_suffix(&self.path, &format!(".corrupt-{timestamp}"));
+
+        match fs::rename(&self.path, &corrupted_path).await {
+            Ok(()) => {
+                warn!(
+                    "Moved the corrupted state database file aside: '{}'",
+                    corrupted_path.display()
+                );
+
+                Ok(())
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err).context("Failed to move the corrupted state database file aside"),
+        }
+    }
+
+    async fn read_schema_from_file(&self) -> Result<Schema> {
+        let _lock = self.schema_lock.lock().await;
+
+        self.load_schema().await
+    }
+
+    async fn recover_from_backup(&self, parse_error: anyhow::Error) -> Result<Schema> {
+        let incompatible_file_error = || {
+            format!(
+                "Unable to parse database file contents: '{}'. Either that is not a valid database file, or this version of Paddler is incompatible with it. No usable backup exists at '{}'.",
+                self.path.display(),
+                self.backup_path.display()
+            )
+        };
+
+        let backup_content = match fs::read_to_string(&self.backup_path).await {
+            Ok(backup_content) => backup_content,
+            Err(_) => return Err(parse_error.context(incompatible_file_error())),
+        };
+
+        let schema = match parse_schema(&backup_content) {
+            Ok((schema, _)) => schema,
+            Err(_) => return Err(parse_error.context(incompatible_file_error())),
+        };
+
+        warn!(
+            "State database file is corrupted ({parse_error}); restoring the backup: '{}'",
+            self.backup_path.display()
+        );
+
+        self.move_corrupted_file_aside().await?;
+
+        let serialized_schema = serde_json::to_string_pretty(&schema)?;
+
+        write_atomically(&self.path, serialized_schema.as_bytes())
+            .await
+            .context("Failed to restore state database backup")?;
+
+        self.remember_written_content(serialized_schema.as_bytes());
+
+        Ok(schema)
+    }
+
     async fn store_default_schema(&self) -> Result<Schema> {
-        let schema = Schema::default();
+        let schema = Schema {
+            balancer_desired_state: self.initial_balancer_desired_state.clone(),
+            ..Schema::default()
+        };
 
         self.store_schema(&schema)
             .await
@@ -67,38 +298,181 @@ impl File {
     }
 
     async fn store_schema(&self, schema: &Schema) -> Result<()> {
-        let balancer_desired_state = schema.balancer_desired_state.clone();
-        let _lock = self.write_lock.write().await;
+        self.write_schema(schema).await?;
+        self.balancer_desired_state_notify_tx
+            .send(schema.balancer_desired_state.clone())?;
+
+        Ok(())
+    }
+
+    /// Only call after the current file was read successfully, so the backup always
+    /// holds a valid database. Caller has to hold the schema lock.
+    async fn write_schema(&self, schema: &Schema) -> Result<()> {
+        match fs::read(&self.path).await {
+            Ok(current_content) if !current_content.is_empty() => {
+                write_atomically(&self.backup_path, &current_content)
+                    .await
+                    .context("Failed to back up state database file")?;
+            }
+            Ok(_) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
 
         let serialized_schema = serde_json::to_string_pretty(schema)?;
-        let mut file = fs::File::create(&self.path).await?;
 
-        file.write_all(serialized_schema.as_bytes()).await?;
-        file.sync_all().await?;
+        write_atomically(&self.path, serialized_schema.as_bytes()).await?;
 
-        self.balancer_desired_state_notify_tx
-            .send(balancer_desired_state)?;
+        self.remember_written_content(serialized_schema.as_bytes());
 
         Ok(())
     }
 
+    /// The modifier returns whether it changed the desired state, so the change is sent
+    /// to the subscribers.
     async fn update_schema<TModifier>(&self, modifier: TModifier) -> Result<()>
+    where
+        TModifier: FnOnce(&mut Schema) -> bool,
+    {
+        let _lock = self.schema_lock.lock().await;
+        let mut schema = self
+            .load_schema()
+            .await
+            .context("Unable to read current state from file")?;
+
+        if modifier(&mut schema) {
+            self.store_schema(&schema).await
+        } else {
+            self.write_schema(&schema).await
+        }
+    }
+
+    /// For the changes that do not affect the current desired state.
+    async fn update_schema_without_notifying<TModifier>(&self, modifier: TModifier) -> Result<()>
     where
         TModifier: FnOnce(&mut Schema),
     {
+        let _lock = self.schema_lock.lock().await;
         let mut schema = self
-            .read_schema_from_file()
+            .load_schema()
             .await
             .context("Unable to read current state from file")?;
 
         modifier(&mut schema);
 
-        self.store_schema(&schema).await
+        self.write_schema(&schema).await
     }
 }
 
 #[async_trait]
 impl StateDatabase for File {
+    async fn activate_balancer_desired_state_profile(
+        &self,
+        name: &str,
+    ) -> Result<Option<BalancerDesiredState>> {
+        let mut activated_profile = None;
+
+        self.update_schema(|schema| {
+            activated_profile = schema.balancer_desired_state_profiles.get(name).cloned();
+
+            match &activated_profile {
+                Some(profile) => {
+                    schema.active_balancer_desired_state_profile = Some(name.to_string());
+                    schema.balancer_desired_state = profile.clone();
+
+                    true
+                }
+                None => false,
+            }
+        })
+        .await?;
+
+        Ok(activated_profile)
+    }
+
+    async fn delete_agent_token(&self, name: &str) -> Result<bool> {
+        let mut is_deleted = false;
+
+        self.update_schema_without_notifying(|schema| {
+            is_deleted = schema.agent_tokens.remove(name).is_some();
+        })
+        .await?;
+
+        Ok(is_deleted)
+    }
+
+    async fn delete_api_key(&self, name: &str) -> Result<bool> {
+        let mut is_deleted = false;
+
+        self.update_schema_without_notifying(|schema| {
+            is_deleted = schema.api_keys.remove(name).is_some();
+        })
+        .await?;
+
+        Ok(is_deleted)
+    }
+
+    async fn delete_balancer_desired_state_profile(
+        &self,
+        name: &str,
+    ) -> Result<BalancerDesiredStateProfileDeleteResult> {
+        let mut delete_result = BalancerDesiredStateProfileDeleteResult::NotFound;
+
+        self.update_schema_without_notifying(|schema| {
+            if schema
+                .balancer_desired_state_schedule
+                .iter()
+                .any(|entry| entry.profile == name)
+            {
+                delete_result = BalancerDesiredStateProfileDeleteResult::UsedBySchedule;
+            } else if schema
+                .balancer_desired_state_profiles
+                .remove(name)
+                .is_some()
+            {
+                delete_result = BalancerDesiredStateProfileDeleteResult::Deleted;
+            }
+        })
+        .await?;
+
+        Ok(delete_result)
+    }
+
+    async fn delete_management_token(&self, name: &str) -> Result<bool> {
+        let mut is_deleted = false;
+
+        self.update_schema_without_notifying(|schema| {
+            is_deleted = schema.management_tokens.remove(name).is_some();
+        })
+        .await?;
+
+        Ok(is_deleted)
+    }
+
+    async fn read_active_balancer_desired_state_profile(&self) -> Result<Option<String>> {
+        Ok(self
+            .read_schema_from_file()
+            .await
+            .context("Unable to read active profile from file")?
+            .active_balancer_desired_state_profile)
+    }
+
+    async fn read_agent_tokens(&self) -> Result<BTreeMap<String, AgentToken>> {
+        Ok(self
+            .read_schema_from_file()
+            .await
+            .context("Unable to read agent tokens from file")?
+            .agent_tokens)
+    }
+
+    async fn read_api_keys(&self) -> Result<BTreeMap<String, ApiKey>> {
+        Ok(self
+            .read_schema_from_file()
+            .await
+            .context("Unable to read API keys from file")?
+            .api_keys)
+    }
+
     async fn read_balancer_desired_state(&self) -> Result<BalancerDesiredState> {
         Ok(self
             .read_schema_from_file()
@@ -108,13 +482,422 @@ impl StateDatabase for File {
             .clone())
     }
 
+    async fn read_balancer_desired_state_profiles(
+        &self,
+    ) -> Result<BTreeMap<String, BalancerDesiredState>> {
+        Ok(self
+            .read_schema_from_file()
+            .await
+            .context("Unable to read profiles from file")?
+            .balancer_desired_state_profiles)
+    }
+
+    async fn read_balancer_desired_state_schedule(
+        &self,
+    ) -> Result<Vec<BalancerDesiredStateScheduleEntry>> {
+        Ok(self
+            .read_schema_from_file()
+            .await
+            .context("Unable to read schedule from file")?
+            .balancer_desired_state_schedule)
+    }
+
+    async fn read_management_tokens(&self) -> Result<BTreeMap<String, ManagementToken>> {
+        Ok(self
+            .read_schema_from_file()
+            .await
+            .context("Unable to read management tokens from file")?
+            .management_tokens)
+    }
+
+    async fn store_agent_token(&self, name: &str, agent_token: &AgentToken) -> Result<()> {
+        self.update_schema_without_notifying(|schema| {
+            schema
+                .agent_tokens
+                .insert(name.to_string(), agent_token.clone());
+        })
+        .await
+    }
+
+    async fn store_api_key(&self, name: &str, api_key: &ApiKey) -> Result<()> {
+        self.update_schema_without_notifying(|schema| {
+            schema.api_keys.insert(name.to_string(), api_key.clone());
+        })
+        .await
+    }
+
     async fn store_balancer_desired_state(
         &self,
         balancer_desired_state: &BalancerDesiredState,
     ) -> Result<()> {
         self.update_schema(|schema| {
+            schema.active_balancer_desired_state_profile = None;
             schema.balancer_desired_state = balancer_desired_state.clone();
+
+            true
+        })
+        .await
+    }
+
+    async fn store_balancer_desired_state_profile(
+        &self,
+        name: &str,
+        state: &BalancerDesiredState,
+    ) -> Result<()> {
+        self.update_schema_without_notifying(|schema| {
+            schema
+                .balancer_desired_state_profiles
+                .insert(name.to_string(), state.clone());
+        })
+        .await
+    }
+
+    async fn store_balancer_desired_state_profiles(
+        &self,
+        profiles: &BTreeMap<String, BalancerDesiredState>,
+    ) -> Result<()> {
+        self.update_schema_without_notifying(|schema| {
+            schema
+                .balancer_desired_state_profiles
+                .extend(profiles.clone());
+        })
+        .await
+    }
+
+    async fn store_balancer_desired_state_schedule(
+        &self,
+        schedule: &[BalancerDesiredStateScheduleEntry],
+    ) -> Result<BalancerDesiredStateScheduleStoreResult> {
+        let mut store_result = BalancerDesiredStateScheduleStoreResult::Stored;
+
+        self.update_schema_without_notifying(|schema| {
+            match schedule.iter().find(|entry| {
+                !schema
+                    .balancer_desired_state_profiles
+                    .contains_key(&entry.profile)
+            }) {
+                Some(entry) => {
+                    store_result = BalancerDesiredStateScheduleStoreResult::ProfileNotFound(
+                        entry.profile.clone(),
+                    );
+                }
+                None => schema.balancer_desired_state_schedule = schedule.to_vec(),
+            }
+        })
+        .await?;
+
+        Ok(store_result)
+    }
+
+    async fn store_management_token(
+        &self,
+        name: &str,
+        management_token: &ManagementToken,
+    ) -> Result<()> {
+        self.update_schema_without_notifying(|schema| {
+            schema
+                .management_tokens
+                .insert(name.to_string(), management_token.clone());
         })
         .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+    use std::time::SystemTime;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::agent_desired_model::AgentDesiredModel;
+
+    fn make_desired_state(model: &str) -> BalancerDesiredState {
+        BalancerDesiredState {
+            model: AgentDesiredModel::LocalToAgent(model.to_string()),
+            ..BalancerDesiredState::default()
+        }
+    }
+
+    fn make_database(directory: &TempDir) -> (File, broadcast::Receiver<BalancerDesiredState>) {
+        let (balancer_desired_state_tx, balancer_desired_state_rx) = broadcast::channel(100);
+        let db = File::new(
+            balancer_desired_state_tx,
+            BalancerDesiredState::default(),
+            directory.path().join("state.json"),
+        );
+
+        (db, balancer_desired_state_rx)
+    }
+
+    async fn write_externally(path: &Path, content: &str) -> Result<()> {
+        fs::write(path, content).await?;
+
+        // Some filesystems have a coarse modification time resolution
+        std::fs::File::options()
+            .write(true)
+            .open(path)?
+            .set_modified(SystemTime::now() + Duration::from_secs(10))?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_recovers_from_corrupted_file() -> Result<()> {
+        let directory = TempDir::new()?;
+        let (db, _balancer_desired_state_rx) = make_database(&directory);
+
+        db.store_balancer_desired_state(&make_desired_state("first.gguf"))
+            .await?;
+        db.store_balancer_desired_state(&make_desired_state("second.gguf"))
+            .await?;
+
+        fs::write(&db.path, "{\"balancer_desired_state\": {").await?;
+
+        assert_eq!(
+            db.read_balancer_desired_state().await?,
+            make_desired_state("first.gguf")
+        );
+
+        let restored_content = fs::read_to_string(&db.path).await?;
+
+        assert!(parse_schema(&restored_content).is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_recovers_from_empty_file() -> Result<()> {
+        let directory = TempDir::new()?;
+        let (db, _balancer_desired_state_rx) = make_database(&directory);
+
+        db.store_balancer_desired_state(&make_desired_state("first.gguf"))
+            .await?;
+        db.store_balancer_desired_state(&make_desired_state("second.gguf"))
+            .await?;
+
+        fs::write(&db.path, "").await?;
+
+        assert_eq!(
+            db.read_balancer_desired_state().await?,
+            make_desired_state("first.gguf")
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_empty_file_without_backup_is_kept() -> Result<()> {
+        let directory = TempDir::new()?;
+        let (db, _balancer_desired_state_rx) = make_database(&directory);
+
+        fs::write(&db.path, "").await?;
+
+        assert!(db.read_balancer_desired_state().await.is_err());
+        assert_eq!(fs::read_to_string(&db.path).await?, "");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_updates_are_not_lost() -> Result<()> {
+        let directory = TempDir::new()?;
+        let (db, _balancer_desired_state_rx) = make_database(&directory);
+        let db = Arc::new(db);
+        let mut handles = Vec::new();
+
+        for index in 0..20 {
+            let db = db.clone();
+
+            handles.push(tokio::spawn(async move {
+                db.store_balancer_desired_state_profile(
+                    &format!("profile_{index}"),
+                    &make_desired_state("model.gguf"),
+                )
+                .await
+            }));
+        }
+
+        for handle in handles {
+            handle.await??;
+        }
+
+        assert_eq!(db.read_balancer_desired_state_profiles().await?.len(), 20);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_file_without_backup_is_kept() -> Result<()> {
+        let directory = TempDir::new()?;
+        let (db, _balancer_desired_state_rx) = make_database(&directory);
+
+        fs::write(&db.path, "not a database").await?;
+
+        assert!(db.read_balancer_desired_state().await.is_err());
+        assert_eq!(fs::read_to_string(&db.path).await?, "not a database");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_detects_external_changes() -> Result<()> {
+        let directory = TempDir::new()?;
+        let (db, mut balancer_desired_state_rx) = make_database(&directory);
+
+        db.store_balancer_desired_state(&make_desired_state("first.gguf"))
+            .await?;
+
+        // The default state is stored first, because the file did not exist
+        assert_eq!(
+            balancer_desired_state_rx.recv().await?,
+            BalancerDesiredState::default()
+        );
+        assert_eq!(
+            balancer_desired_state_rx.recv().await?,
+            make_desired_state("first.gguf")
+        );
+
+        db.check_for_external_changes().await?;
+
+        assert!(balancer_desired_state_rx.try_recv().is_err());
+
+        write_externally(
+            &db.path,
+            &serde_json::to_string(&Schema {
+                balancer_desired_state: make_desired_state("external.gguf"),
+                ..Schema::default()
+            })?,
+        )
+        .await?;
+        db.check_for_external_changes().await?;
+
+        assert_eq!(
+            balancer_desired_state_rx.try_recv()?,
+            make_desired_state("external.gguf")
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_detects_external_changes_with_same_modification_time() -> Result<()> {
+        let directory = TempDir::new()?;
+        let (db, mut balancer_desired_state_rx) = make_database(&directory);
+
+        db.store_balancer_desired_state(&make_desired_state("first.gguf"))
+            .await?;
+
+        while balancer_desired_state_rx.try_recv().is_ok() {}
+
+        let modified_at = fs::metadata(&db.path).await?.modified()?;
+
+        fs::write(
+            &db.path,
+            serde_json::to_string(&Schema {
+                balancer_desired_state: make_desired_state("external.gguf"),
+                ..Schema::default()
+            })?,
+        )
+        .await?;
+        std::fs::File::options()
+            .write(true)
+            .open(&db.path)?
+            .set_modified(modified_at)?;
+        db.check_for_external_changes().await?;
+
+        assert_eq!(
+            balancer_desired_state_rx.try_recv()?,
+            make_desired_state("external.gguf")
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ignores_invalid_external_changes() -> Result<()> {
+        let directory = TempDir::new()?;
+        let (db, mut balancer_desired_state_rx) = make_database(&directory);
+
+        db.store_balancer_desired_state(&make_desired_state("first.gguf"))
+            .await?;
+
+        while balancer_desired_state_rx.try_recv().is_ok() {}
+
+        write_externally(&db.path, "{\"balancer_desired_state\": {").await?;
+        db.check_for_external_changes().await?;
+
+        assert!(balancer_desired_state_rx.try_recv().is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_keeps_invalid_external_changes_after_restoring_the_backup() -> Result<()> {
+        let directory = TempDir::new()?;
+        let (db, mut balancer_desired_state_rx) = make_database(&directory);
+
+        db.store_balancer_desired_state(&make_desired_state("first.gguf"))
+            .await?;
+        db.store_balancer_desired_state(&make_desired_state("second.gguf"))
+            .await?;
+
+        while balancer_desired_state_rx.try_recv().is_ok() {}
+
+        write_externally(&db.path, "{\"balancer_desired_state\": {").await?;
+        db.check_for_external_changes().await?;
+
+        assert!(balancer_desired_state_rx.try_recv().is_err());
+        assert_eq!(
+            db.read_balancer_desired_state().await?,
+            make_desired_state("first.gguf")
+        );
+
+        let mut corrupted_contents = Vec::new();
+        let mut entries = fs::read_dir(directory.path()).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            if entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("state.json.corrupt-")
+            {
+                corrupted_contents.push(fs::read_to_string(entry.path()).await?);
+            }
+        }
+
+        assert_eq!(corrupted_contents, vec!["{\"balancer_desired_state\": {"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upgrades_old_schema_version() -> Result<()> {
+        let directory = TempDir::new()?;
+        let (db, _balancer_desired_state_rx) = make_database(&directory);
+        let old_content = serde_json::to_string(&serde_json::json!({
+            "balancer_desired_state": make_desired_state("old.gguf"),
+            "version": "1",
+        }))?;
+
+        fs::write(&db.path, &old_content).await?;
+
+        assert_eq!(
+            db.read_balancer_desired_state().await?,
+            make_desired_state("old.gguf")
+        );
+
+        let upgraded_content = fs::read_to_string(&db.path).await?;
+        let upgraded_value: serde_json::Value = serde_json::from_str(&upgraded_content)?;
+
+        assert_eq!(
+            read_schema_version(&upgraded_value)?,
+            CURRENT_SCHEMA_VERSION
+        );
+        assert_eq!(fs::read_to_string(&db.backup_path).await?, old_content);
+
+        Ok(())
+    }
+}
@@ -1,16 +1,39 @@
+use std::collections::BTreeMap;
+
 use serde::Deserialize;
 use serde::Serialize;
 
+use super::upgrade_schema::CURRENT_SCHEMA_VERSION;
+use crate::balancer::agent_token::AgentToken;
+use crate::balancer::api_key::ApiKey;
+use crate::balancer::balancer_desired_state_schedule_entry::BalancerDesiredStateScheduleEntry;
+use crate::balancer::management_token::ManagementToken;
 use crate::balancer_desired_state::BalancerDesiredState;
 
-fn default_version() -> String {
-    "1".into()
-}
-
-#[derive(Default, Deserialize, Serialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Schema {
+    pub active_balancer_desired_state_profile: Option<String>,
+    pub agent_tokens: BTreeMap<String, AgentToken>,
+    pub api_keys: BTreeMap<String, ApiKey>,
     pub balancer_desired_state: BalancerDesiredState,
-    #[serde(default = "default_version")]
-    pub version: String,
+    pub balancer_desired_state_profiles: BTreeMap<String, BalancerDesiredState>,
+    pub balancer_desired_state_schedule: Vec<BalancerDesiredStateScheduleEntry>,
+    pub management_tokens: BTreeMap<String, ManagementToken>,
+    pub version: u64,
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        Self {
+            active_balancer_desired_state_profile: None,
+            agent_tokens: BTreeMap::new(),
+            api_keys: BTreeMap::new(),
+            balancer_desired_state: BalancerDesiredState::default(),
+            balancer_desired_state_profiles: BTreeMap::new(),
+            balancer_desired_state_schedule: Vec::new(),
+            management_tokens: BTreeMap::new(),
+            version: CURRENT_SCHEMA_VERSION,
+        }
+    }
 }
@@ -0,0 +1,150 @@
+use anyhow::Context as _;
+use anyhow::Result;
+use anyhow::anyhow;
+use serde_json::Value;
+use serde_json::json;
+
+use super::schema::Schema;
+
+pub const CURRENT_SCHEMA_VERSION: u64 = 7;
+
+/// Version 1 stored the version as a string (or not at all)
+fn upgrade_from_version_1(mut schema: Value) -> Result<Value> {
+    schema["version"] = json!(2);
+
+    Ok(schema)
+}
+
+/// Version 3 added the profiles and the schedule
+fn upgrade_from_version_2(mut schema: Value) -> Result<Value> {
+    schema["balancer_desired_state_profiles"] = json!({});
+    schema["balancer_desired_state_schedule"] = json!([]);
+    schema["version"] = json!(3);
+
+    Ok(schema)
+}
+
+/// Version 4 records which profile was activated
+fn upgrade_from_version_3(mut schema: Value) -> Result<Value> {
+    schema["active_balancer_desired_state_profile"] = Value::Null;
+    schema["version"] = json!(4);
+
+    Ok(schema)
+}
+
+/// Version 5 added the API keys
+fn upgrade_from_version_4(mut schema: Value) -> Result<Value> {
+    schema["api_keys"] = json!({});
+    schema["version"] = json!(5);
+
+    Ok(schema)
+}
+
+/// Version 6 added the management tokens
+fn upgrade_from_version_5(mut schema: Value) -> Result<Value> {
+    schema["management_tokens"] = json!({});
+    schema["version"] = json!(6);
+
+    Ok(schema)
+}
+
+/// Version 7 added the agent tokens
+fn upgrade_from_version_6(mut schema: Value) -> Result<Value> {
+    schema["agent_tokens"] = json!({});
+    schema["version"] = json!(7);
+
+    Ok(schema)
+}
+
+pub fn read_schema_version(schema: &Value) -> Result<u64> {
+    match schema.get("version") {
+        None => Ok(1),
+        Some(Value::String(version)) => version
+            .parse()
+            .context(format!("Invalid schema version: '{version}'")),
+        Some(Value::Number(version)) => version
+            .as_u64()
+            .ok_or_else(|| anyhow!("Invalid schema version: {version}")),
+        Some(version) => Err(anyhow!("Invalid schema version: {version}")),
+    }
+}
+
+/// Upgrades the schema one version at a time, so the files written by any older
+/// version of Paddler can still be read.
+pub fn upgrade_schema(mut schema: Value) -> Result<Schema> {
+    let mut version = read_schema_version(&schema)?;
+
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(anyhow!(
+            "Schema version {version} was written by a newer version of Paddler; this one supports up to {CURRENT_SCHEMA_VERSION}"
+        ));
+    }
+
+    while version < CURRENT_SCHEMA_VERSION {
+        schema = match version {
+            1 => upgrade_from_version_1(schema)?,
+            2 => upgrade_from_version_2(schema)?,
+            3 => upgrade_from_version_3(schema)?,
+            4 => upgrade_from_version_4(schema)?,
+            5 => upgrade_from_version_5(schema)?,
+            6 => upgrade_from_version_6(schema)?,
+            _ => return Err(anyhow!("No upgrade path from schema version {version}")),
+        };
+        version = read_schema_version(&schema)?;
+    }
+
+    Ok(serde_json::from_value(schema)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent_desired_model::AgentDesiredModel;
+    use crate::balancer_desired_state::BalancerDesiredState;
+
+    fn make_version_1_schema(version: Option<&str>) -> Value {
+        let mut schema = json!({
+            "balancer_desired_state": BalancerDesiredState {
+                model: AgentDesiredModel::LocalToAgent("model.gguf".to_string()),
+                ..BalancerDesiredState::default()
+            },
+        });
+
+        if let Some(version) = version {
+            schema["version"] = json!(version);
+        }
+
+        schema
+    }
+
+    #[test]
+    fn test_upgrade_from_version_1() -> Result<()> {
+        let schema = upgrade_schema(make_version_1_schema(Some("1")))?;
+
+        assert_eq!(schema.version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(
+            schema.balancer_desired_state.model,
+            AgentDesiredModel::LocalToAgent("model.gguf".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_upgrade_without_version() -> Result<()> {
+        let schema = upgrade_schema(make_version_1_schema(None))?;
+
+        assert_eq!(schema.version, CURRENT_SCHEMA_VERSION);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_newer_version_fails() {
+        let mut schema = make_version_1_schema(None);
+
+        schema["version"] = json!(CURRENT_SCHEMA_VERSION + 1);
+
+        assert!(upgrade_schema(schema).is_err());
+    }
+}
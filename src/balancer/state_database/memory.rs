@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::sync::RwLock;
 
 use anyhow::Result;
@@ -5,24 +6,166 @@ use async_trait::async_trait;
 use tokio::sync::broadcast;
 
 use super::StateDatabase;
+use crate::balancer::agent_token::AgentToken;
+use crate::balancer::api_key::ApiKey;
+use crate::balancer::balancer_desired_state_profile_delete_result::BalancerDesiredStateProfileDeleteResult;
+use crate::balancer::balancer_desired_state_schedule_entry::BalancerDesiredStateScheduleEntry;
+use crate::balancer::balancer_desired_state_schedule_store_result::BalancerDesiredStateScheduleStoreResult;
+use crate::balancer::management_token::ManagementToken;
 use crate::balancer_desired_state::BalancerDesiredState;
 
 pub struct Memory {
+    active_balancer_desired_state_profile: RwLock<Option<String>>,
+    agent_tokens: RwLock<BTreeMap<String, AgentToken>>,
+    api_keys: RwLock<BTreeMap<String, ApiKey>>,
     balancer_desired_state: RwLock<BalancerDesiredState>,
     balancer_desired_state_notify_tx: broadcast::Sender<BalancerDesiredState>,
+    balancer_desired_state_profiles: RwLock<BTreeMap<String, BalancerDesiredState>>,
+    balancer_desired_state_schedule: RwLock<Vec<BalancerDesiredStateScheduleEntry>>,
+    management_tokens: RwLock<BTreeMap<String, ManagementToken>>,
 }
 
 impl Memory {
-    pub fn new(balancer_desired_state_notify_tx: broadcast::Sender<BalancerDesiredState>) -> Self {
+    pub fn new(
+        balancer_desired_state_notify_tx: broadcast::Sender<BalancerDesiredState>,
+        initial_balancer_desired_state: BalancerDesiredState,
+    ) -> Self {
         Memory {
-            balancer_desired_state: RwLock::new(BalancerDesiredState::default()),
+            active_balancer_desired_state_profile: RwLock::new(None),
+            agent_tokens: RwLock::new(BTreeMap::new()),
+            api_keys: RwLock::new(BTreeMap::new()),
+            balancer_desired_state: RwLock::new(initial_balancer_desired_state),
             balancer_desired_state_notify_tx,
+            balancer_desired_state_profiles: RwLock::new(BTreeMap::new()),
+            balancer_desired_state_schedule: RwLock::new(Vec::new()),
+            management_tokens: RwLock::new(BTreeMap::new()),
         }
     }
+
+    fn set_balancer_desired_state(
+        &self,
+        state: &BalancerDesiredState,
+        active_profile: Option<String>,
+    ) -> Result<()> {
+        {
+            let mut balancer_desired_state = self
+                .balancer_desired_state
+                .write()
+                .expect("Failed to acquire write lock");
+
+            *balancer_desired_state = state.clone();
+        }
+
+        *self
+            .active_balancer_desired_state_profile
+            .write()
+            .expect("Failed to acquire write lock") = active_profile;
+
+        self.balancer_desired_state_notify_tx.send(state.clone())?;
+
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl StateDatabase for Memory {
+    async fn activate_balancer_desired_state_profile(
+        &self,
+        name: &str,
+    ) -> Result<Option<BalancerDesiredState>> {
+        // Holding the profiles lock keeps the profile from being replaced in the meantime
+        let balancer_desired_state_profiles = self
+            .balancer_desired_state_profiles
+            .read()
+            .expect("Failed to acquire read lock");
+        let profile = match balancer_desired_state_profiles.get(name) {
+            Some(profile) => profile.clone(),
+            None => return Ok(None),
+        };
+
+        self.set_balancer_desired_state(&profile, Some(name.to_string()))?;
+
+        Ok(Some(profile))
+    }
+
+    async fn delete_agent_token(&self, name: &str) -> Result<bool> {
+        Ok(self
+            .agent_tokens
+            .write()
+            .expect("Failed to acquire write lock")
+            .remove(name)
+            .is_some())
+    }
+
+    async fn delete_api_key(&self, name: &str) -> Result<bool> {
+        Ok(self
+            .api_keys
+            .write()
+            .expect("Failed to acquire write lock")
+            .remove(name)
+            .is_some())
+    }
+
+    async fn delete_balancer_desired_state_profile(
+        &self,
+        name: &str,
+    ) -> Result<BalancerDesiredStateProfileDeleteResult> {
+        let mut balancer_desired_state_profiles = self
+            .balancer_desired_state_profiles
+            .write()
+            .expect("Failed to acquire write lock");
+        let balancer_desired_state_schedule = self
+            .balancer_desired_state_schedule
+            .read()
+            .expect("Failed to acquire read lock");
+
+        if balancer_desired_state_schedule
+            .iter()
+            .any(|entry| entry.profile == name)
+        {
+            return Ok(BalancerDesiredStateProfileDeleteResult::UsedBySchedule);
+        }
+
+        if balancer_desired_state_profiles.remove(name).is_some() {
+            Ok(BalancerDesiredStateProfileDeleteResult::Deleted)
+        } else {
+            Ok(BalancerDesiredStateProfileDeleteResult::NotFound)
+        }
+    }
+
+    async fn delete_management_token(&self, name: &str) -> Result<bool> {
+        Ok(self
+            .management_tokens
+            .write()
+            .expect("Failed to acquire write lock")
+            .remove(name)
+            .is_some())
+    }
+
+    async fn read_active_balancer_desired_state_profile(&self) -> Result<Option<String>> {
+        Ok(self
+            .active_balancer_desired_state_profile
+            .read()
+            .expect("Failed to acquire read lock")
+            .clone())
+    }
+
+    async fn read_agent_tokens(&self) -> Result<BTreeMap<String, AgentToken>> {
+        Ok(self
+            .agent_tokens
+            .read()
+            .expect("Failed to acquire read lock")
+            .clone())
+    }
+
+    async fn read_api_keys(&self) -> Result<BTreeMap<String, ApiKey>> {
+        Ok(self
+            .api_keys
+            .read()
+            .expect("Failed to acquire read lock")
+            .clone())
+    }
+
     async fn read_balancer_desired_state(&self) -> Result<BalancerDesiredState> {
         Ok(self
             .balancer_desired_state
@@ -31,17 +174,117 @@ impl StateDatabase for Memory {
             .clone())
     }
 
+    async fn read_balancer_desired_state_profiles(
+        &self,
+    ) -> Result<BTreeMap<String, BalancerDesiredState>> {
+        Ok(self
+            .balancer_desired_state_profiles
+            .read()
+            .expect("Failed to acquire read lock")
+            .clone())
+    }
+
+    async fn read_balancer_desired_state_schedule(
+        &self,
+    ) -> Result<Vec<BalancerDesiredStateScheduleEntry>> {
+        Ok(self
+            .balancer_desired_state_schedule
+            .read()
+            .expect("Failed to acquire read lock")
+            .clone())
+    }
+
+    async fn read_management_tokens(&self) -> Result<BTreeMap<String, ManagementToken>> {
+        Ok(self
+            .management_tokens
+            .read()
+            .expect("Failed to acquire read lock")
+            .clone())
+    }
+
+    async fn store_agent_token(&self, name: &str, agent_token: &AgentToken) -> Result<()> {
+        self.agent_tokens
+            .write()
+            .expect("Failed to acquire write lock")
+            .insert(name.to_string(), agent_token.clone());
+
+        Ok(())
+    }
+
+    async fn store_api_key(&self, name: &str, api_key: &ApiKey) -> Result<()> {
+        self.api_keys
+            .write()
+            .expect("Failed to acquire write lock")
+            .insert(name.to_string(), api_key.clone());
+
+        Ok(())
+    }
+
     async fn store_balancer_desired_state(&self, state: &BalancerDesiredState) -> Result<()> {
-        {
-            let mut balancer_desired_state = self
-                .balancer_desired_state
-                .write()
-                .expect("Failed to acquire write lock");
+        self.set_balancer_desired_state(state, None)
+    }
 
-            *balancer_desired_state = state.clone();
+    async fn store_balancer_desired_state_profile(
+        &self,
+        name: &str,
+        state: &BalancerDesiredState,
+    ) -> Result<()> {
+        self.balancer_desired_state_profiles
+            .write()
+            .expect("Failed to acquire write lock")
+            .insert(name.to_string(), state.clone());
+
+        Ok(())
+    }
+
+    async fn store_balancer_desired_state_profiles(
+        &self,
+        profiles: &BTreeMap<String, BalancerDesiredState>,
+    ) -> Result<()> {
+        self.balancer_desired_state_profiles
+            .write()
+            .expect("Failed to acquire write lock")
+            .extend(profiles.clone());
+
+        Ok(())
+    }
+
+    async fn store_balancer_desired_state_schedule(
+        &self,
+        schedule: &[BalancerDesiredStateScheduleEntry],
+    ) -> Result<BalancerDesiredStateScheduleStoreResult> {
+        // Locked in the same order as when deleting a profile
+        let balancer_desired_state_profiles = self
+            .balancer_desired_state_profiles
+            .read()
+            .expect("Failed to acquire read lock");
+        let mut balancer_desired_state_schedule = self
+            .balancer_desired_state_schedule
+            .write()
+            .expect("Failed to acquire write lock");
+
+        for entry in schedule {
+            if !balancer_desired_state_profiles.contains_key(&entry.profile) {
+                return Ok(BalancerDesiredStateScheduleStoreResult::ProfileNotFound(
+                    entry.profile.clone(),
+                ));
+            }
         }
 
-        self.balancer_desired_state_notify_tx.send(state.clone())?;
+        *balancer_desired_state_schedule = schedule.to_vec();
+
+        Ok(BalancerDesiredStateScheduleStoreResult::Stored)
+    }
+
+    async fn store_management_token(
+        &self,
+        name: &str,
+        management_token: &ManagementToken,
+    ) -> Result<()> {
+        self.management_tokens
+            .write()
+            .expect("Failed to acquire write lock")
+            .insert(name.to_string(), management_token.clone());
 
         Ok(())
     }
@@ -1,30 +1,198 @@
 mod file;
 mod memory;
+mod sqlite;
+
+use std::collections::BTreeMap;
 
 use anyhow::Result;
 use async_trait::async_trait;
 
 pub use self::file::File;
 pub use self::memory::Memory;
+pub use self::sqlite::Sqlite;
+use crate::balancer::agent_token::AgentToken;
+use crate::balancer::api_key::ApiKey;
+use crate::balancer::balancer_desired_state_history_entry::BalancerDesiredStateHistoryEntry;
+use crate::balancer::balancer_desired_state_profile_delete_result::BalancerDesiredStateProfileDeleteResult;
+use crate::balancer::balancer_desired_state_rollback_result::BalancerDesiredStateRollbackResult;
+use crate::balancer::balancer_desired_state_schedule_entry::BalancerDesiredStateScheduleEntry;
+use crate::balancer::balancer_desired_state_schedule_store_result::BalancerDesiredStateScheduleStoreResult;
+use crate::balancer::management_token::ManagementToken;
 use crate::balancer_desired_state::BalancerDesiredState;
 
 #[async_trait]
 pub trait StateDatabase: Send + Sync {
+    /// Sets the desired state from the stored profile, and records that profile as the
+    /// active one. Returns None if the profile does not exist.
+    async fn activate_balancer_desired_state_profile(
+        &self,
+        name: &str,
+    ) -> Result<Option<BalancerDesiredState>>;
+
+    /// Returns false if the agent token does not exist.
+    async fn delete_agent_token(&self, name: &str) -> Result<bool>;
+
+    /// Returns false if the API key does not exist.
+    async fn delete_api_key(&self, name: &str) -> Result<bool>;
+
+    /// Profiles that are still used by the schedule are kept.
+    async fn delete_balancer_desired_state_profile(
+        &self,
+        name: &str,
+    ) -> Result<BalancerDesiredStateProfileDeleteResult>;
+
+    /// Returns false if the management token does not exist.
+    async fn delete_management_token(&self, name: &str) -> Result<bool>;
+
+    /// Name of the profile the current desired state was activated from. Storing the
+    /// desired state directly clears it.
+    async fn read_active_balancer_desired_state_profile(&self) -> Result<Option<String>>;
+
+    /// Keyed by the name of the agent token.
+    async fn read_agent_tokens(&self) -> Result<BTreeMap<String, AgentToken>>;
+
+    /// Keyed by the name of the API key.
+    async fn read_api_keys(&self) -> Result<BTreeMap<String, ApiKey>>;
+
     async fn read_balancer_desired_state(&self) -> Result<BalancerDesiredState>;
 
+    /// Newest versions come first. Returns None if the database does not keep the history.
+    async fn read_balancer_desired_state_history(
+        &self,
+    ) -> Result<Option<Vec<BalancerDesiredStateHistoryEntry>>> {
+        Ok(None)
+    }
+
+    async fn read_balancer_desired_state_profile(
+        &self,
+        name: &str,
+    ) -> Result<Option<BalancerDesiredState>> {
+        Ok(self
+            .read_balancer_desired_state_profiles()
+            .await?
+            .remove(name))
+    }
+
+    async fn read_balancer_desired_state_profiles(
+        &self,
+    ) -> Result<BTreeMap<String, BalancerDesiredState>>;
+
+    async fn read_balancer_desired_state_schedule(
+        &self,
+    ) -> Result<Vec<BalancerDesiredStateScheduleEntry>>;
+
+    /// Keyed by the name of the management token.
+    async fn read_management_tokens(&self) -> Result<BTreeMap<String, ManagementToken>>;
+
+    /// Stores the state from the given version as the newest one.
+    async fn rollback_balancer_desired_state(
+        &self,
+        _version: i64,
+    ) -> Result<BalancerDesiredStateRollbackResult> {
+        Ok(BalancerDesiredStateRollbackResult::NotSupported)
+    }
+
+    /// Creates the agent token, or replaces the existing one with the same name.
+    async fn store_agent_token(&self, name: &str, agent_token: &AgentToken) -> Result<()>;
+
+    /// Creates the API key, or replaces the existing one with the same name.
+    async fn store_api_key(&self, name: &str, api_key: &ApiKey) -> Result<()>;
+
     async fn store_balancer_desired_state(&self, state: &BalancerDesiredState) -> Result<()>;
+
+    /// Creates the profile, or replaces the existing one with the same name.
+    async fn store_balancer_desired_state_profile(
+        &self,
+        name: &str,
+        state: &BalancerDesiredState,
+    ) -> Result<()>;
+
+    /// Creates the profiles, or replaces the existing ones with the same names, all at once.
+    async fn store_balancer_desired_state_profiles(
+        &self,
+        profiles: &BTreeMap<String, BalancerDesiredState>,
+    ) -> Result<()>;
+
+    /// Replaces the whole schedule, unless one of its entries points to a missing profile.
+    async fn store_balancer_desired_state_schedule(
+        &self,
+        schedule: &[BalancerDesiredStateScheduleEntry],
+    ) -> Result<BalancerDesiredStateScheduleStoreResult>;
+
+    /// Creates the management token, or replaces the existing one with the same name.
+    async fn store_management_token(
+        &self,
+        name: &str,
+        management_token: &ManagementToken,
+    ) -> Result<()>;
 }
 
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
     use tempfile::NamedTempFile;
+    use tempfile::TempDir;
     use tokio::sync::broadcast;
 
     use super::*;
     use crate::agent_desired_model::AgentDesiredModel;
+    use crate::balancer::api_key_limits::ApiKeyLimits;
+    use crate::balancer::hash_api_key::hash_api_key;
+    use crate::balancer::management_role::ManagementRole;
     use crate::inference_parameters::InferenceParameters;
 
+    async fn subtest_store_agent_tokens<TDatabase: StateDatabase>(db: &TDatabase) -> Result<()> {
+        let agent_token = AgentToken {
+            agent_name: Some("gpu-1".to_string()),
+            token_hash: hash_api_key("secret"),
+        };
+
+        db.store_agent_token("gpu-1", &agent_token).await?;
+
+        assert_eq!(
+            db.read_agent_tokens().await?,
+            BTreeMap::from([("gpu-1".to_string(), agent_token)])
+        );
+        assert!(db.delete_agent_token("gpu-1").await?);
+        assert!(!db.delete_agent_token("gpu-1").await?);
+        assert!(db.read_agent_tokens().await?.is_empty());
+
+        Ok(())
+    }
+
+    async fn subtest_store_api_keys<TDatabase: StateDatabase>(db: &TDatabase) -> Result<()> {
+        let api_key = ApiKey {
+            key_hash: hash_api_key("secret"),
+            limits: ApiKeyLimits {
+                max_requests_per_minute: Some(10),
+                ..ApiKeyLimits::default()
+            },
+        };
+        let updated_api_key = ApiKey {
+            limits: ApiKeyLimits::default(),
+            ..api_key.clone()
+        };
+
+        db.store_api_key("frontend", &api_key).await?;
+
+        assert_eq!(
+            db.read_api_keys().await?,
+            BTreeMap::from([("frontend".to_string(), api_key)])
+        );
+
+        db.store_api_key("frontend", &updated_api_key).await?;
+
+        assert_eq!(
+            db.read_api_keys().await?.get("frontend"),
+            Some(&updated_api_key)
+        );
+        assert!(db.delete_api_key("frontend").await?);
+        assert!(!db.delete_api_key("frontend").await?);
+        assert!(db.read_api_keys().await?.is_empty());
+
+        Ok(())
+    }
+
     async fn subtest_store_desired_state<TDatabase: StateDatabase>(db: &TDatabase) -> Result<()> {
         let desired_state = BalancerDesiredState {
             chat_template_override: None,
@@ -42,13 +210,200 @@ mod tests {
         Ok(())
     }
 
+    async fn subtest_store_management_tokens<TDatabase: StateDatabase>(
+        db: &TDatabase,
+    ) -> Result<()> {
+        let management_token = ManagementToken {
+            role: ManagementRole::ReadOnly,
+            token_hash: hash_api_key("secret"),
+        };
+
+        db.store_management_token("grafana", &management_token)
+            .await?;
+
+        assert_eq!(
+            db.read_management_tokens().await?,
+            BTreeMap::from([("grafana".to_string(), management_token)])
+        );
+        assert!(db.delete_management_token("grafana").await?);
+        assert!(!db.delete_management_token("grafana").await?);
+        assert!(db.read_management_tokens().await?.is_empty());
+
+        Ok(())
+    }
+
+    async fn subtest_store_profiles_and_schedule<TDatabase: StateDatabase>(
+        db: &TDatabase,
+    ) -> Result<()> {
+        let profile = BalancerDesiredState {
+            model: AgentDesiredModel::LocalToAgent("small_model_path".to_string()),
+            ..BalancerDesiredState::default()
+        };
+        let schedule = vec![BalancerDesiredStateScheduleEntry {
+            cron: "0 20 * * *".parse()?,
+            profile: "overnight".to_string(),
+        }];
+
+        assert!(matches!(
+            db.store_balancer_desired_state_schedule(&schedule).await?,
+            BalancerDesiredStateScheduleStoreResult::ProfileNotFound(name) if name == "overnight"
+        ));
+
+        db.store_balancer_desired_state_profiles(&BTreeMap::from([
+            ("daytime".to_string(), BalancerDesiredState::default()),
+            ("overnight".to_string(), profile.clone()),
+        ]))
+        .await?;
+        db.store_balancer_desired_state_profile("overnight", &profile)
+            .await?;
+
+        assert!(matches!(
+            db.store_balancer_desired_state_schedule(&schedule).await?,
+            BalancerDesiredStateScheduleStoreResult::Stored
+        ));
+
+        assert_eq!(
+            db.read_balancer_desired_state_profile("overnight").await?,
+            Some(profile)
+        );
+        assert_eq!(
+            db.read_balancer_desired_state_profile("missing").await?,
+            None
+        );
+        assert_eq!(db.read_balancer_desired_state_schedule().await?, schedule);
+
+        assert_eq!(
+            db.activate_balancer_desired_state_profile("overnight")
+                .await?,
+            Some(profile.clone())
+        );
+        assert_eq!(db.read_balancer_desired_state().await?, profile);
+        assert_eq!(
+            db.read_active_balancer_desired_state_profile().await?,
+            Some("overnight".to_string())
+        );
+        assert_eq!(
+            db.activate_balancer_desired_state_profile("missing")
+                .await?,
+            None
+        );
+
+        db.store_balancer_desired_state(&BalancerDesiredState::default())
+            .await?;
+
+        assert_eq!(db.read_active_balancer_desired_state_profile().await?, None);
+        assert!(matches!(
+            db.delete_balancer_desired_state_profile("overnight")
+                .await?,
+            BalancerDesiredStateProfileDeleteResult::UsedBySchedule
+        ));
+
+        db.store_balancer_desired_state_schedule(&[]).await?;
+
+        assert!(matches!(
+            db.delete_balancer_desired_state_profile("overnight")
+                .await?,
+            BalancerDesiredStateProfileDeleteResult::Deleted
+        ));
+        assert!(matches!(
+            db.delete_balancer_desired_state_profile("overnight")
+                .await?,
+            BalancerDesiredStateProfileDeleteResult::NotFound
+        ));
+        assert_eq!(
+            db.read_balancer_desired_state_profiles().await?,
+            BTreeMap::from([("daytime".to_string(), BalancerDesiredState::default())])
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_file_database() -> Result<()> {
+        let (balancer_desired_state_tx, _balancer_desired_state_rx) = broadcast::channel(100);
+        let directory = TempDir::new()?;
+        let db = File::new(
+            balancer_desired_state_tx,
+            BalancerDesiredState::default(),
+            directory.path().join("state.json"),
+        );
+
+        subtest_store_agent_tokens(&db).await?;
+        subtest_store_api_keys(&db).await?;
+        subtest_store_desired_state(&db).await?;
+        subtest_store_management_tokens(&db).await?;
+        subtest_store_profiles_and_schedule(&db).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_database() -> Result<()> {
         let (balancer_desired_state_tx, _balancer_desired_state_rx) = broadcast::channel(100);
         let tempfile = NamedTempFile::new()?;
-        let db = File::new(balancer_desired_state_tx, tempfile.path().to_path_buf());
+        let db = Sqlite::new(
+            balancer_desired_state_tx,
+            BalancerDesiredState::default(),
+            tempfile.path().to_path_buf(),
+        )?;
 
+        subtest_store_agent_tokens(&db).await?;
+        subtest_store_api_keys(&db).await?;
         subtest_store_desired_state(&db).await?;
+        subtest_store_management_tokens(&db).await?;
+        subtest_store_profiles_and_schedule(&db).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_database_history_rollback() -> Result<()> {
+        let (balancer_desired_state_tx, _balancer_desired_state_rx) = broadcast::channel(100);
+        let tempfile = NamedTempFile::new()?;
+        let db = Sqlite::new(
+            balancer_desired_state_tx.clone(),
+            BalancerDesiredState::default(),
+            tempfile.path().to_path_buf(),
+        )?;
+
+        subtest_store_desired_state(&db).await?;
+
+        let history = db.read_balancer_desired_state_history().await?.unwrap();
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(
+            history[1].balancer_desired_state.model,
+            AgentDesiredModel::None
+        );
+
+        let initial_version = history[1].version;
+
+        assert!(matches!(
+            db.rollback_balancer_desired_state(initial_version).await?,
+            BalancerDesiredStateRollbackResult::RolledBack(_)
+        ));
+        assert_eq!(
+            db.read_balancer_desired_state().await?.model,
+            AgentDesiredModel::None
+        );
+        assert!(matches!(
+            db.rollback_balancer_desired_state(1000).await?,
+            BalancerDesiredStateRollbackResult::VersionNotFound
+        ));
+
+        // Reopening the database must not apply the migrations again or lose the history
+        let reopened_db = Sqlite::new(
+            balancer_desired_state_tx,
+            BalancerDesiredState::default(),
+            tempfile.path().to_path_buf(),
+        )?;
+        let history = reopened_db
+            .read_balancer_desired_state_history()
+            .await?
+            .unwrap();
+
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].restored_from_version, Some(initial_version));
 
         Ok(())
     }
@@ -56,9 +411,13 @@ mod tests {
     #[tokio::test]
     async fn test_memory_database() -> Result<()> {
         let (balancer_desired_state_tx, _balancer_desired_state_rx) = broadcast::channel(100);
-        let db = Memory::new(balancer_desired_state_tx);
+        let db = Memory::new(balancer_desired_state_tx, BalancerDesiredState::default());
 
+        subtest_store_agent_tokens(&db).await?;
+        subtest_store_api_keys(&db).await?;
         subtest_store_desired_state(&db).await?;
+        subtest_store_management_tokens(&db).await?;
+        subtest_store_profiles_and_schedule(&db).await?;
 
         Ok(())
     }
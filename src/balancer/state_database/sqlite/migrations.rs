@@ -0,0 +1,79 @@
+use anyhow::Result;
+use anyhow::anyhow;
+use indoc::indoc;
+use rusqlite::Connection;
+
+/// Migrations are applied in order and never change once released.
+/// `PRAGMA user_version` holds the number of migrations that were already applied.
+const MIGRATIONS: &[&str] = &[
+    indoc! {"
+        CREATE TABLE balancer_desired_state_history (
+            version INTEGER PRIMARY KEY AUTOINCREMENT,
+            balancer_desired_state TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            restored_from_version INTEGER REFERENCES balancer_desired_state_history (version)
+        );
+    "},
+    indoc! {"
+        CREATE TABLE balancer_desired_state_profile (
+            name TEXT PRIMARY KEY,
+            balancer_desired_state TEXT NOT NULL
+        );
+
+        CREATE TABLE balancer_desired_state_schedule_entry (
+            position INTEGER PRIMARY KEY,
+            cron TEXT NOT NULL,
+            profile TEXT NOT NULL
+        );
+    "},
+    indoc! {"
+        ALTER TABLE balancer_desired_state_history ADD COLUMN profile TEXT;
+    "},
+    indoc! {"
+        CREATE TABLE api_key (
+            name TEXT PRIMARY KEY,
+            key_hash TEXT NOT NULL UNIQUE,
+            limits TEXT NOT NULL
+        );
+    "},
+    indoc! {"
+        CREATE TABLE management_token (
+            name TEXT PRIMARY KEY,
+            role TEXT NOT NULL,
+            token_hash TEXT NOT NULL UNIQUE
+        );
+    "},
+    indoc! {"
+        CREATE TABLE agent_token (
+            name TEXT PRIMARY KEY,
+            agent_name TEXT,
+            token_hash TEXT NOT NULL UNIQUE
+        );
+    "},
+];
+
+pub fn run_migrations(connection: &mut Connection) -> Result<()> {
+    let transaction = connection.transaction()?;
+    let applied_migrations: i64 =
+        transaction.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+    if applied_migrations > MIGRATIONS.len() as i64 {
+        return Err(anyhow!(
+            "State database schema is at version {applied_migrations}, but this version of Paddler supports only up to {}",
+            MIGRATIONS.len()
+        ));
+    }
+
+    for (index, migration) in MIGRATIONS
+        .iter()
+        .enumerate()
+        .skip(applied_migrations as usize)
+    {
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", (index + 1) as i64)?;
+    }
+
+    transaction.commit()?;
+
+    Ok(())
+}
@@ -0,0 +1,643 @@
+mod migrations;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use async_trait::async_trait;
+use rusqlite::Connection;
+use rusqlite::OptionalExtension as _;
+use rusqlite::params;
+use tokio::sync::broadcast;
+use tokio::task::spawn_blocking;
+
+use self::migrations::run_migrations;
+use super::StateDatabase;
+use crate::balancer::agent_token::AgentToken;
+use crate::balancer::api_key::ApiKey;
+use crate::balancer::balancer_desired_state_history_entry::BalancerDesiredStateHistoryEntry;
+use crate::balancer::balancer_desired_state_profile_delete_result::BalancerDesiredStateProfileDeleteResult;
+use crate::balancer::balancer_desired_state_rollback_result::BalancerDesiredStateRollbackResult;
+use crate::balancer::balancer_desired_state_schedule_entry::BalancerDesiredStateScheduleEntry;
+use crate::balancer::balancer_desired_state_schedule_store_result::BalancerDesiredStateScheduleStoreResult;
+use crate::balancer::management_token::ManagementToken;
+use crate::balancer_desired_state::BalancerDesiredState;
+
+/// Every stored state becomes a new row in the history table; the most recent one
+/// is the current state.
+pub struct Sqlite {
+    balancer_desired_state_notify_tx: broadcast::Sender<BalancerDesiredState>,
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl Sqlite {
+    pub fn new(
+        balancer_desired_state_notify_tx: broadcast::Sender<BalancerDesiredState>,
+        initial_balancer_desired_state: BalancerDesiredState,
+        path: PathBuf,
+    ) -> Result<Self> {
+        let mut connection = Connection::open(&path).context(format!(
+            "Unable to open state database: '{}'",
+            path.display()
+        ))?;
+
+        run_migrations(&mut connection).context(format!(
+            "Unable to migrate state database: '{}'",
+            path.display()
+        ))?;
+
+        let transaction = connection.transaction()?;
+        let is_empty: bool = transaction.query_row(
+            "SELECT NOT EXISTS (SELECT 1 FROM balancer_desired_state_history)",
+            [],
+            |row| row.get(0),
+        )?;
+
+        if is_empty {
+            insert_history_entry(
+                &transaction,
+                &serde_json::to_string(&initial_balancer_desired_state)?,
+                None,
+                None,
+            )?;
+        }
+
+        transaction.commit()?;
+
+        Ok(Sqlite {
+            balancer_desired_state_notify_tx,
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    async fn with_connection<TResult, TCallback>(&self, callback: TCallback) -> Result<TResult>
+    where
+        TCallback: FnOnce(&mut Connection) -> Result<TResult> + Send + 'static,
+        TResult: Send + 'static,
+    {
+        let connection = self.connection.clone();
+
+        spawn_blocking(move || {
+            let mut connection = connection
+                .lock()
+                .expect("Failed to get state database connection lock");
+
+            callback(&mut connection)
+        })
+        .await?
+    }
+}
+
+fn insert_history_entry(
+    connection: &Connection,
+    serialized_balancer_desired_state: &str,
+    restored_from_version: Option<i64>,
+    profile: Option<&str>,
+) -> Result<()> {
+    let created_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+
+    connection.execute(
+        "INSERT INTO balancer_desired_state_history (balancer_desired_state, created_at, restored_from_version, profile) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            serialized_balancer_desired_state,
+            created_at,
+            restored_from_version,
+            profile
+        ],
+    )?;
+
+    Ok(())
+}
+
+#[async_trait]
+impl StateDatabase for Sqlite {
+    async fn activate_balancer_desired_state_profile(
+        &self,
+        name: &str,
+    ) -> Result<Option<BalancerDesiredState>> {
+        let name = name.to_string();
+        let serialized_state: Option<String> = self
+            .with_connection(move |connection| {
+                let transaction = connection.transaction()?;
+                let serialized_state: Option<String> = transaction
+                    .query_row(
+                        "SELECT balancer_desired_state FROM balancer_desired_state_profile WHERE name = ?1",
+                        params![name],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+
+                if let Some(serialized_state) = &serialized_state {
+                    insert_history_entry(&transaction, serialized_state, None, Some(&name))?;
+                }
+
+                transaction.commit()?;
+
+                Ok(serialized_state)
+            })
+            .await
+            .context("Unable to activate the profile")?;
+
+        match serialized_state {
+            Some(serialized_state) => {
+                let balancer_desired_state: BalancerDesiredState =
+                    serde_json::from_str(&serialized_state)?;
+
+                self.balancer_desired_state_notify_tx
+                    .send(balancer_desired_state.clone())?;
+
+                Ok(Some(balancer_desired_state))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn delete_agent_token(&self, name: &str) -> Result<bool> {
+        let name = name.to_string();
+
+        self.with_connection(move |connection| {
+            Ok(connection.execute("DELETE FROM agent_token WHERE name = ?1", params![name])? > 0)
+        })
+        .await
+        .context("Unable to delete agent token from the database")
+    }
+
+    async fn delete_api_key(&self, name: &str) -> Result<bool> {
+        let name = name.to_string();
+
+        self.with_connection(move |connection| {
+            Ok(connection.execute("DELETE FROM api_key WHERE name = ?1", params![name])? > 0)
+        })
+        .await
+        .context("Unable to delete API key from the database")
+    }
+
+    async fn delete_balancer_desired_state_profile(
+        &self,
+        name: &str,
+    ) -> Result<BalancerDesiredStateProfileDeleteResult> {
+        let name = name.to_string();
+
+        self.with_connection(move |connection| {
+            let transaction = connection.transaction()?;
+            let is_used_by_schedule: bool = transaction.query_row(
+                "SELECT EXISTS (SELECT 1 FROM balancer_desired_state_schedule_entry WHERE profile = ?1)",
+                params![name],
+                |row| row.get(0),
+            )?;
+
+            if is_used_by_schedule {
+                return Ok(BalancerDesiredStateProfileDeleteResult::UsedBySchedule);
+            }
+
+            let deleted_rows = transaction.execute(
+                "DELETE FROM balancer_desired_state_profile WHERE name = ?1",
+                params![name],
+            )?;
+
+            transaction.commit()?;
+
+            if deleted_rows > 0 {
+                Ok(BalancerDesiredStateProfileDeleteResult::Deleted)
+            } else {
+                Ok(BalancerDesiredStateProfileDeleteResult::NotFound)
+            }
+        })
+        .await
+        .context("Unable to delete profile from the database")
+    }
+
+    async fn delete_management_token(&self, name: &str) -> Result<bool> {
+        let name = name.to_string();
+
+        self.with_connection(move |connection| {
+            Ok(connection.execute(
+                "DELETE FROM management_token WHERE name = ?1",
+                params![name],
+            )? > 0)
+        })
+        .await
+        .context("Unable to delete management token from the database")
+    }
+
+    async fn read_active_balancer_desired_state_profile(&self) -> Result<Option<String>> {
+        self.with_connection(|connection| {
+            Ok(connection.query_row(
+                "SELECT profile FROM balancer_desired_state_history ORDER BY version DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )?)
+        })
+        .await
+        .context("Unable to read active profile from the database")
+    }
+
+    async fn read_agent_tokens(&self) -> Result<BTreeMap<String, AgentToken>> {
+        let rows: Vec<(String, Option<String>, String)> = self
+            .with_connection(|connection| {
+                let mut statement =
+                    connection.prepare("SELECT name, agent_name, token_hash FROM agent_token")?;
+                let rows = statement
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(rows)
+            })
+            .await
+            .context("Unable to read agent tokens from the database")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(name, agent_name, token_hash)| {
+                (
+                    name,
+                    AgentToken {
+                        agent_name,
+                        token_hash,
+                    },
+                )
+            })
+            .collect())
+    }
+
+    async fn read_api_keys(&self) -> Result<BTreeMap<String, ApiKey>> {
+        let rows: Vec<(String, String, String)> = self
+            .with_connection(|connection| {
+                let mut statement =
+                    connection.prepare("SELECT name, key_hash, limits FROM api_key")?;
+                let rows = statement
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(rows)
+            })
+            .await
+            .context("Unable to read API keys from the database")?;
+
+        let mut api_keys = BTreeMap::new();
+
+        for (name, key_hash, serialized_limits) in rows {
+            api_keys.insert(
+                name,
+                ApiKey {
+                    key_hash,
+                    limits: serde_json::from_str(&serialized_limits)?,
+                },
+            );
+        }
+
+        Ok(api_keys)
+    }
+
+    async fn read_balancer_desired_state(&self) -> Result<BalancerDesiredState> {
+        let serialized_state: String = self
+            .with_connection(|connection| {
+                Ok(connection.query_row(
+                    "SELECT balancer_desired_state FROM balancer_desired_state_history ORDER BY version DESC LIMIT 1",
+                    [],
+                    |row| row.get(0),
+                )?)
+            })
+            .await
+            .context("Unable to read state from the database")?;
+
+        Ok(serde_json::from_str(&serialized_state)?)
+    }
+
+    async fn read_balancer_desired_state_history(
+        &self,
+    ) -> Result<Option<Vec<BalancerDesiredStateHistoryEntry>>> {
+        let rows: Vec<(i64, String, i64, Option<i64>, Option<String>)> = self
+            .with_connection(|connection| {
+                let mut statement = connection.prepare(
+                    "SELECT version, balancer_desired_state, created_at, restored_from_version, profile FROM balancer_desired_state_history ORDER BY version DESC",
+                )?;
+                let rows = statement
+                    .query_map([], |row| {
+                        Ok((
+                            row.get(0)?,
+                            row.get(1)?,
+                            row.get(2)?,
+                            row.get(3)?,
+                            row.get(4)?,
+                        ))
+                    })?
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(rows)
+            })
+            .await
+            .context("Unable to read state history from the database")?;
+
+        let mut history = Vec::with_capacity(rows.len());
+
+        for (version, serialized_state, created_at, restored_from_version, profile) in rows {
+            history.push(BalancerDesiredStateHistoryEntry {
+                balancer_desired_state: serde_json::from_str(&serialized_state)?,
+                created_at,
+                profile,
+                restored_from_version,
+                version,
+            });
+        }
+
+        Ok(Some(history))
+    }
+
+    async fn read_balancer_desired_state_profiles(
+        &self,
+    ) -> Result<BTreeMap<String, BalancerDesiredState>> {
+        let rows: Vec<(String, String)> = self
+            .with_connection(|connection| {
+                let mut statement = connection.prepare(
+                    "SELECT name, balancer_desired_state FROM balancer_desired_state_profile",
+                )?;
+                let rows = statement
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(rows)
+            })
+            .await
+            .context("Unable to read profiles from the database")?;
+
+        let mut profiles = BTreeMap::new();
+
+        for (name, serialized_state) in rows {
+            profiles.insert(name, serde_json::from_str(&serialized_state)?);
+        }
+
+        Ok(profiles)
+    }
+
+    async fn read_balancer_desired_state_schedule(
+        &self,
+    ) -> Result<Vec<BalancerDesiredStateScheduleEntry>> {
+        let rows: Vec<(String, String)> = self
+            .with_connection(|connection| {
+                let mut statement = connection.prepare(
+                    "SELECT cron, profile FROM balancer_desired_state_schedule_entry ORDER BY position",
+                )?;
+                let rows = statement
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(rows)
+            })
+            .await
+            .context("Unable to read schedule from the database")?;
+
+        let mut schedule = Vec::with_capacity(rows.len());
+
+        for (cron, profile) in rows {
+            schedule.push(BalancerDesiredStateScheduleEntry {
+                cron: cron.parse()?,
+                profile,
+            });
+        }
+
+        Ok(schedule)
+    }
+
+    async fn read_management_tokens(&self) -> Result<BTreeMap<String, ManagementToken>> {
+        let rows: Vec<(String, String, String)> = self
+            .with_connection(|connection| {
+                let mut statement =
+                    connection.prepare("SELECT name, role, token_hash FROM management_token")?;
+                let rows = statement
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(rows)
+            })
+            .await
+            .context("Unable to read management tokens from the database")?;
+
+        let mut management_tokens = BTreeMap::new();
+
+        for (name, role, token_hash) in rows {
+            management_tokens.insert(
+                name,
+                ManagementToken {
+                    role: role.parse()?,
+                    token_hash,
+                },
+            );
+        }
+
+        Ok(management_tokens)
+    }
+
+    async fn rollback_balancer_desired_state(
+        &self,
+        version: i64,
+    ) -> Result<BalancerDesiredStateRollbackResult> {
+        let serialized_state: Option<String> = self
+            .with_connection(move |connection| {
+                let transaction = connection.transaction()?;
+                let serialized_state: Option<String> = transaction
+                    .query_row(
+                        "SELECT balancer_desired_state FROM balancer_desired_state_history WHERE version = ?1",
+                        params![version],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+
+                if let Some(serialized_state) = &serialized_state {
+                    insert_history_entry(&transaction, serialized_state, Some(version), None)?;
+                }
+
+                transaction.commit()?;
+
+                Ok(serialized_state)
+            })
+            .await
+            .context("Unable to roll back the state")?;
+
+        match serialized_state {
+            Some(serialized_state) => {
+                let balancer_desired_state: BalancerDesiredState =
+                    serde_json::from_str(&serialized_state)?;
+
+                self.balancer_desired_state_notify_tx
+                    .send(balancer_desired_state.clone())?;
+
+                Ok(BalancerDesiredStateRollbackResult::RolledBack(
+                    balancer_desired_state,
+                ))
+            }
+            None => Ok(BalancerDesiredStateRollbackResult::VersionNotFound),
+        }
+    }
+
+    async fn store_agent_token(&self, name: &str, agent_token: &AgentToken) -> Result<()> {
+        let agent_name = agent_token.agent_name.clone();
+        let name = name.to_string();
+        let token_hash = agent_token.token_hash.clone();
+
+        self.with_connection(move |connection| {
+            connection.execute(
+                "INSERT INTO agent_token (name, agent_name, token_hash) VALUES (?1, ?2, ?3) ON CONFLICT (name) DO UPDATE SET agent_name = excluded.agent_name, token_hash = excluded.token_hash",
+                params![name, agent_name, token_hash],
+            )?;
+
+            Ok(())
+        })
+        .await
+        .context("Unable to store agent token in the database")
+    }
+
+    async fn store_api_key(&self, name: &str, api_key: &ApiKey) -> Result<()> {
+        let key_hash = api_key.key_hash.clone();
+        let name = name.to_string();
+        let serialized_limits = serde_json::to_string(&api_key.limits)?;
+
+        self.with_connection(move |connection| {
+            connection.execute(
+                "INSERT INTO api_key (name, key_hash, limits) VALUES (?1, ?2, ?3) ON CONFLICT (name) DO UPDATE SET key_hash = excluded.key_hash, limits = excluded.limits",
+                params![name, key_hash, serialized_limits],
+            )?;
+
+            Ok(())
+        })
+        .await
+        .context("Unable to store API key in the database")
+    }
+
+    async fn store_balancer_desired_state(
+        &self,
+        balancer_desired_state: &BalancerDesiredState,
+    ) -> Result<()> {
+        let serialized_state = serde_json::to_string(balancer_desired_state)?;
+
+        self.with_connection(move |connection| {
+            let transaction = connection.transaction()?;
+
+            insert_history_entry(&transaction, &serialized_state, None, None)?;
+            transaction.commit()?;
+
+            Ok(())
+        })
+        .await
+        .context("Unable to store state in the database")?;
+
+        self.balancer_desired_state_notify_tx
+            .send(balancer_desired_state.clone())?;
+
+        Ok(())
+    }
+
+    async fn store_balancer_desired_state_profile(
+        &self,
+        name: &str,
+        state: &BalancerDesiredState,
+    ) -> Result<()> {
+        let name = name.to_string();
+        let serialized_state = serde_json::to_string(state)?;
+
+        self.with_connection(move |connection| {
+            connection.execute(
+                "INSERT INTO balancer_desired_state_profile (name, balancer_desired_state) VALUES (?1, ?2) ON CONFLICT (name) DO UPDATE SET balancer_desired_state = excluded.balancer_desired_state",
+                params![name, serialized_state],
+            )?;
+
+            Ok(())
+        })
+        .await
+        .context("Unable to store profile in the database")
+    }
+
+    async fn store_balancer_desired_state_profiles(
+        &self,
+        profiles: &BTreeMap<String, BalancerDesiredState>,
+    ) -> Result<()> {
+        let mut serialized_profiles = Vec::with_capacity(profiles.len());
+
+        for (name, state) in profiles {
+            serialized_profiles.push((name.clone(), serde_json::to_string(state)?));
+        }
+
+        self.with_connection(move |connection| {
+            let transaction = connection.transaction()?;
+
+            for (name, serialized_state) in serialized_profiles {
+                transaction.execute(
+                    "INSERT INTO balancer_desired_state_profile (name, balancer_desired_state) VALUES (?1, ?2) ON CONFLICT (name) DO UPDATE SET balancer_desired_state = excluded.balancer_desired_state",
+                    params![name, serialized_state],
+                )?;
+            }
+
+            transaction.commit()?;
+
+            Ok(())
+        })
+        .await
+        .context("Unable to store profiles in the database")
+    }
+
+    async fn store_balancer_desired_state_schedule(
+        &self,
+        schedule: &[BalancerDesiredStateScheduleEntry],
+    ) -> Result<BalancerDesiredStateScheduleStoreResult> {
+        let schedule = schedule.to_vec();
+
+        self.with_connection(move |connection| {
+            let transaction = connection.transaction()?;
+
+            for entry in &schedule {
+                let profile_exists: bool = transaction.query_row(
+                    "SELECT EXISTS (SELECT 1 FROM balancer_desired_state_profile WHERE name = ?1)",
+                    params![entry.profile],
+                    |row| row.get(0),
+                )?;
+
+                if !profile_exists {
+                    return Ok(BalancerDesiredStateScheduleStoreResult::ProfileNotFound(
+                        entry.profile.clone(),
+                    ));
+                }
+            }
+
+            transaction.execute("DELETE FROM balancer_desired_state_schedule_entry", [])?;
+
+            for (position, entry) in schedule.into_iter().enumerate() {
+                transaction.execute(
+                    "INSERT INTO balancer_desired_state_schedule_entry (position, cron, profile) VALUES (?1, ?2, ?3)",
+                    params![position as i64, String::from(entry.cron), entry.profile],
+                )?;
+            }
+
+            transaction.commit()?;
+
+            Ok(BalancerDesiredStateScheduleStoreResult::Stored)
+        })
+        .await
+        .context("Unable to store schedule in the database")
+    }
+
+    async fn store_management_token(
+        &self,
+        name: &str,
+        management_token: &ManagementToken,
+    ) -> Result<()> {
+        let name = name.to_string();
+        let role = management_token.role.to_string();
+        let token_hash = management_token.token_hash.clone();
+
+        self.with_connection(move |connection| {
+            connection.execute(
+                "INSERT INTO management_token (name, role, token_hash) VALUES (?1, ?2, ?3) ON CONFLICT (name) DO UPDATE SET role = excluded.role, token_hash = excluded.token_hash",
+                params![name, role, token_hash],
+            )?;
+
+            Ok(())
+        })
+        .await
+        .context("Unable to store management token in the database")
+    }
+}
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use log::error;
+use tokio::sync::Mutex;
+use tokio::sync::broadcast;
+use tokio::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tokio::time::interval;
+
+use crate::balancer::state_database::File;
+use crate::service::Service;
+
+/// Polls the file instead of relying on inotify, so it also works with the
+/// symlink swaps that Kubernetes uses to update mounted ConfigMaps.
+pub struct StateDatabaseFileWatchService {
+    pub balancer_desired_state_write_lock: Arc<Mutex<()>>,
+    pub poll_interval: Duration,
+    pub state_database: Arc<File>,
+}
+
+#[async_trait]
+impl Service for StateDatabaseFileWatchService {
+    fn name(&self) -> &'static str {
+        "balancer::state_database_file_watch_service"
+    }
+
+    async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
+        let mut ticker = interval(self.poll_interval);
+
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = shutdown.recv() => break Ok(()),
+                _ = ticker.tick() => {
+                    // External edits are applied in order with the changes made through the API
+                    let _lock = self.balancer_desired_state_write_lock.lock().await;
+
+                    if let Err(err) = self.state_database.check_for_external_changes().await {
+                        error!("Failed to check the state database file for changes: {err}");
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,10 +1,12 @@
 pub mod configuration;
+pub mod reported_counters;
 
 use std::net::UdpSocket;
 use std::sync::Arc;
 
 use anyhow::Result;
 use async_trait::async_trait;
+use cadence::Counted;
 use cadence::Gauged;
 use cadence::StatsdClient;
 use cadence::UdpMetricSink;
@@ -15,27 +17,97 @@ use tokio::time::interval;
 
 use crate::balancer::agent_controller_pool::AgentControllerPool;
 use crate::balancer::agent_controller_pool_total_slots::AgentControllerPoolTotalSlots;
+use crate::balancer::api_key_manager::ApiKeyManager;
 use crate::balancer::buffered_request_manager::BufferedRequestManager;
 use crate::balancer::statsd_service::configuration::Configuration as StatsdServiceConfiguration;
+use crate::balancer::statsd_service::reported_counters::ReportedCounters;
 use crate::service::Service;
 
 pub struct StatsdService {
     pub agent_controller_pool: Arc<AgentControllerPool>,
+    pub api_key_manager: Arc<ApiKeyManager>,
     pub buffered_request_manager: Arc<BufferedRequestManager>,
     pub configuration: StatsdServiceConfiguration,
+    pub configuration_rx: broadcast::Receiver<StatsdServiceConfiguration>,
+    pub reported_counters: ReportedCounters,
 }
 
 impl StatsdService {
-    async fn report_metrics(&self, client: &StatsdClient) -> Result<()> {
+    fn create_client(&self) -> Result<StatsdClient> {
+        let statsd_sink_socket = UdpSocket::bind("0.0.0.0:0")?;
+        let statsd_sink = UdpMetricSink::from(self.configuration.statsd_addr, statsd_sink_socket)?;
+
+        Ok(
+            StatsdClient::builder(&self.configuration.statsd_prefix.to_owned(), statsd_sink)
+                .with_error_handler(|err| error!("Statsd error: {err}"))
+                .build(),
+        )
+    }
+
+    async fn report_metrics(&mut self, client: &StatsdClient) -> Result<()> {
         let AgentControllerPoolTotalSlots {
             slots_processing,
             slots_total,
         } = self.agent_controller_pool.total_slots();
         let requests_buffered = self.buffered_request_manager.buffered_request_counter.get();
+        let request_retry_counter = &self.buffered_request_manager.request_retry_counter;
 
         client.gauge("slots_processing", slots_processing as u64)?;
         client.gauge("slots_total", slots_total as u64)?;
         client.gauge("requests_buffered", requests_buffered as u64)?;
+        client.count(
+            "requests_retried",
+            self.reported_counters.take_increment(
+                "requests_retried",
+                request_retry_counter.get_retries() as u64,
+            ),
+        )?;
+        client.count(
+            "requests_retries_exhausted",
+            self.reported_counters.take_increment(
+                "requests_retries_exhausted",
+                request_retry_counter.get_retries_exhausted() as u64,
+            ),
+        )?;
+        client.count(
+            "api_key_unauthorized_requests",
+            self.reported_counters.take_increment(
+                "api_key_unauthorized_requests",
+                self.api_key_manager.get_unauthorized_requests() as u64,
+            ),
+        )?;
+
+        for usage_snapshot in self.api_key_manager.make_usage_snapshots() {
+            let name = &usage_snapshot.name;
+            let rejected_requests_key = format!("api_key_{name}_rejected_requests");
+            let requests_total_key = format!("api_key_{name}_requests_total");
+            let tokens_total_key = format!("api_key_{name}_tokens_total");
+
+            client.gauge(
+                &format!("api_key_{name}_concurrent_requests"),
+                usage_snapshot.concurrent_requests,
+            )?;
+            client.count(
+                &rejected_requests_key,
+                self.reported_counters
+                    .take_increment(&rejected_requests_key, usage_snapshot.rejected_requests),
+            )?;
+            client.count(
+                &requests_total_key,
+                self.reported_counters
+                    .take_increment(&requests_total_key, usage_snapshot.total_requests),
+            )?;
+            client.gauge(
+                &format!("api_key_{name}_tokens_in_current_day"),
+                usage_snapshot.tokens_in_current_day,
+            )?;
+            client.count(
+                &tokens_total_key,
+                self.reported_counters
+                    .take_increment(&tokens_total_key, usage_snapshot.total_tokens),
+            )?;
+        }
+
         client.flush()?;
 
         Ok(())
@@ -49,24 +121,25 @@ impl Service for StatsdService {
     }
 
     async fn run(&mut self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
-        let statsd_sink_socket = UdpSocket::bind("0.0.0.0:0")?;
-        let statsd_sink = UdpMetricSink::from(self.configuration.statsd_addr, statsd_sink_socket)?;
-
-        let client =
-            StatsdClient::builder(&self.configuration.statsd_prefix.to_owned(), statsd_sink)
-                .with_error_handler(|err| error!("Statsd error: {err}"))
-                .build();
+        loop {
+            let client = self.create_client()?;
+            let mut ticker = interval(self.configuration.statsd_reporting_interval);
 
-        let mut ticker = interval(self.configuration.statsd_reporting_interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
-        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = shutdown.recv() => return Ok(()),
+                    _ = ticker.tick() => {
+                        if let Err(err) = self.report_metrics(&client).await {
+                            error!("Failed to report metrics: {err}");
+                        }
+                    }
+                    configuration = self.configuration_rx.recv() => {
+                        self.configuration = configuration?;
 
-        loop {
-            tokio::select! {
-                _ = shutdown.recv() => break Ok(()),
-                _ = ticker.tick() => {
-                    if let Err(err) = self.report_metrics(&client).await {
-                        error!("Failed to report metrics: {err}");
+                        // Rebuild the client and the ticker with the new settings
+                        break;
                     }
                 }
             }
@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+
+/// Statsd counters take increments, while the balancer keeps running totals
+#[derive(Default)]
+pub struct ReportedCounters {
+    totals: HashMap<String, u64>,
+}
+
+impl ReportedCounters {
+    /// Total that went down was reset in the meantime, so it is reported whole
+    pub fn take_increment(&mut self, name: &str, total: u64) -> i64 {
+        let previous_total = self.totals.insert(name.to_string(), total).unwrap_or(0);
+
+        total.checked_sub(previous_total).unwrap_or(total) as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_increments_of_the_totals() {
+        let mut reported_counters = ReportedCounters::default();
+
+        assert_eq!(reported_counters.take_increment("retries", 3), 3);
+        assert_eq!(reported_counters.take_increment("retries", 5), 2);
+        assert_eq!(reported_counters.take_increment("retries", 5), 0);
+        assert_eq!(reported_counters.take_increment("retries", 1), 1);
+        assert_eq!(reported_counters.take_increment("exhausted", 4), 4);
+    }
+}
@@ -7,11 +7,14 @@ use log::error;
 use nanoid::nanoid;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc;
-use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::wrappers::ReceiverStream;
 
 use crate::agent::jsonrpc::Request as AgentJsonRpcRequest;
 use crate::balancer::agent_controller::AgentController;
+use crate::balancer::api_key_usage_guard::ApiKeyUsageGuard;
 use crate::balancer::buffered_request_manager::BufferedRequestManager;
+use crate::balancer::buffered_request_priority_class::BufferedRequestPriorityClass;
+use crate::balancer::chunk_forwarding_session_controller::CHUNK_BUFFER_CAPACITY;
 use crate::balancer::chunk_forwarding_session_controller::ChunkForwardingSessionController;
 use crate::balancer::chunk_forwarding_session_controller::transforms_outgoing_message::TransformsOutgoingMessage;
 use crate::balancer::handles_agent_streaming_response::HandlesAgentStreamingResponse;
@@ -25,30 +28,34 @@ use crate::jsonrpc::Error as JsonRpcError;
 use crate::jsonrpc::ErrorEnvelope;
 use crate::streamable_result::StreamableResult;
 
-pub fn unbounded_stream_from_agent<TParams, TTransformsOutgoingMessage>(
+pub fn stream_from_agent<TParams, TTransformsOutgoingMessage>(
+    api_key_usage_guard: Option<Arc<ApiKeyUsageGuard>>,
     buffered_request_manager: Arc<BufferedRequestManager>,
     inference_service_configuration: InferenceServiceConfiguration,
     params: TParams,
+    priority_class: Arc<BufferedRequestPriorityClass>,
     transformer: TTransformsOutgoingMessage,
-) -> Result<UnboundedReceiverStream<String>, Error>
+) -> Result<ReceiverStream<String>, Error>
 where
-    TParams: Debug + Into<AgentJsonRpcRequest> + Send + 'static,
+    TParams: Clone + Debug + Into<AgentJsonRpcRequest> + Send + 'static,
     AgentController: HandlesAgentStreamingResponse<TParams>,
     <<AgentController as HandlesAgentStreamingResponse<TParams>>::SenderCollection as ManagesSenders>::Value: Debug + Into<OutgoingResponse> + StreamableResult,
     TTransformsOutgoingMessage: Clone + TransformsOutgoingMessage + Send + Sync + 'static,
 {
     let request_id: String = nanoid!();
     let (connection_close_tx, _connection_close_rx) = broadcast::channel(1);
-    let (chunk_tx, chunk_rx) = mpsc::unbounded_channel::<String>();
+    let (chunk_tx, chunk_rx) = mpsc::channel::<String>(CHUNK_BUFFER_CAPACITY);
 
     rt::spawn(async move {
         let mut session_controller = ChunkForwardingSessionController::new(chunk_tx, transformer);
 
         if let Err(err) = request_from_agent(
+            api_key_usage_guard,
             buffered_request_manager.clone(),
             connection_close_tx,
             inference_service_configuration.clone(),
             params,
+            priority_class,
             request_id.clone(),
             session_controller.clone(),
         )
@@ -68,5 +75,5 @@ where
         }
     });
 
-    Ok(UnboundedReceiverStream::new(chunk_rx))
+    Ok(ReceiverStream::new(chunk_rx))
 }
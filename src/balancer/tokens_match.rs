@@ -0,0 +1,9 @@
+/// Compares every byte, so the time it takes does not reveal how much of the token matched.
+pub fn tokens_match(expected: &str, actual: &str) -> bool {
+    expected.len() == actual.len()
+        && expected
+            .bytes()
+            .zip(actual.bytes())
+            .fold(0, |difference, (left, right)| difference | (left ^ right))
+            == 0
+}
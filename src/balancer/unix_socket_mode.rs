@@ -0,0 +1,42 @@
+use std::str::FromStr;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use anyhow::anyhow;
+
+/// Permissions of the Unix socket files, written in octal like `660`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct UnixSocketMode(pub u32);
+
+impl FromStr for UnixSocketMode {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let mode = u32::from_str_radix(value, 8).context(format!(
+            "Invalid Unix socket mode {value:?}. Expected octal, like 660"
+        ))?;
+
+        if mode > 0o777 {
+            return Err(anyhow!(
+                "Invalid Unix socket mode {value:?}. Only the permission bits can be set"
+            ));
+        }
+
+        Ok(Self(mode))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        assert_eq!("660".parse::<UnixSocketMode>()?, UnixSocketMode(0o660));
+        assert_eq!("0600".parse::<UnixSocketMode>()?, UnixSocketMode(0o600));
+        assert!("690".parse::<UnixSocketMode>().is_err());
+        assert!("4777".parse::<UnixSocketMode>().is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,285 @@
+use hf_hub::Repo;
+use hf_hub::RepoType;
+use hf_hub::api::tokio::ApiBuilder;
+
+use crate::agent_desired_model::AgentDesiredModel;
+use crate::balancer::balancer_desired_state_validation_issue::BalancerDesiredStateValidationIssue;
+use crate::balancer_desired_state::BalancerDesiredState;
+use crate::chat_template_renderer::ChatTemplateRenderer;
+use crate::huggingface_model_reference::HuggingFaceModelReference;
+use crate::inference_parameters::InferenceParameters;
+
+fn issue(field: &str, message: String) -> BalancerDesiredStateValidationIssue {
+    BalancerDesiredStateValidationIssue {
+        field: field.to_string(),
+        message,
+    }
+}
+
+fn validate_inference_parameters(
+    InferenceParameters {
+        batch_n_tokens,
+        context_size,
+        min_p,
+        penalty_last_n,
+        penalty_repeat,
+        temperature,
+        top_k,
+        top_p,
+        ..
+    }: &InferenceParameters,
+    issues: &mut Vec<BalancerDesiredStateValidationIssue>,
+) {
+    if *context_size == 0 {
+        issues.push(issue(
+            "inference_parameters.context_size",
+            "Context size must be greater than 0".to_string(),
+        ));
+    }
+
+    if *batch_n_tokens == 0 {
+        issues.push(issue(
+            "inference_parameters.batch_n_tokens",
+            "Batch size must be greater than 0".to_string(),
+        ));
+    } else if *batch_n_tokens > *context_size as usize {
+        issues.push(issue(
+            "inference_parameters.batch_n_tokens",
+            format!(
+                "Batch size ({batch_n_tokens}) must not exceed the context size ({context_size})"
+            ),
+        ));
+    }
+
+    if !(0.0..=1.0).contains(min_p) {
+        issues.push(issue(
+            "inference_parameters.min_p",
+            format!("Min P must be between 0.0 and 1.0, got {min_p}"),
+        ));
+    }
+
+    if *penalty_last_n < -1 {
+        issues.push(issue(
+            "inference_parameters.penalty_last_n",
+            format!("Penalty last N must be -1 (context size), 0 (disabled) or positive, got {penalty_last_n}"),
+        ));
+    }
+
+    if *penalty_repeat <= 0.0 {
+        issues.push(issue(
+            "inference_parameters.penalty_repeat",
+            format!("Repeat penalty must be greater than 0.0, got {penalty_repeat}"),
+        ));
+    }
+
+    if *temperature < 0.0 {
+        issues.push(issue(
+            "inference_parameters.temperature",
+            format!("Temperature must not be negative, got {temperature}"),
+        ));
+    }
+
+    if *top_k < 0 {
+        issues.push(issue(
+            "inference_parameters.top_k",
+            format!("Top K must not be negative, got {top_k}"),
+        ));
+    }
+
+    if !(0.0..=1.0).contains(top_p) {
+        issues.push(issue(
+            "inference_parameters.top_p",
+            format!("Top P must be between 0.0 and 1.0, got {top_p}"),
+        ));
+    }
+}
+
+fn validate_chat_template_override(
+    balancer_desired_state: &BalancerDesiredState,
+    issues: &mut Vec<BalancerDesiredStateValidationIssue>,
+) {
+    match &balancer_desired_state.chat_template_override {
+        Some(chat_template) => {
+            if let Err(err) = ChatTemplateRenderer::new(chat_template.clone()) {
+                issues.push(issue(
+                    "chat_template_override",
+                    format!("Chat template does not compile: {err}"),
+                ));
+            }
+        }
+        None => {
+            if balancer_desired_state.use_chat_template_override {
+                issues.push(issue(
+                    "use_chat_template_override",
+                    "Chat template override is enabled, but no template is set".to_string(),
+                ));
+            }
+        }
+    }
+}
+
+/// Checks the repository metadata only, without downloading the model.
+async fn check_huggingface_model_reference(
+    HuggingFaceModelReference {
+        filename,
+        repo_id,
+        revision,
+    }: &HuggingFaceModelReference,
+    issues: &mut Vec<BalancerDesiredStateValidationIssue>,
+) {
+    let hf_api = match ApiBuilder::new().build() {
+        Ok(hf_api) => hf_api,
+        Err(err) => {
+            issues.push(issue(
+                "model.HuggingFace",
+                format!("Unable to connect to Hugging Face: {err}"),
+            ));
+
+            return;
+        }
+    };
+
+    match hf_api
+        .repo(Repo::with_revision(
+            repo_id.to_owned(),
+            RepoType::Model,
+            revision.to_owned(),
+        ))
+        .info()
+        .await
+    {
+        Ok(repo_info) => {
+            if !repo_info
+                .siblings
+                .iter()
+                .any(|sibling| sibling.rfilename == *filename)
+            {
+                issues.push(issue(
+                    "model.HuggingFace.filename",
+                    format!(
+                        "File '{filename}' does not exist in '{repo_id}' at revision '{revision}'"
+                    ),
+                ));
+            }
+        }
+        Err(err) => {
+            issues.push(issue(
+                "model.HuggingFace",
+                format!("Unable to resolve '{repo_id}' at revision '{revision}': {err}"),
+            ));
+        }
+    }
+}
+
+fn validate_model(
+    model: &AgentDesiredModel,
+    issues: &mut Vec<BalancerDesiredStateValidationIssue>,
+) {
+    match model {
+        AgentDesiredModel::HuggingFace(HuggingFaceModelReference {
+            filename,
+            repo_id,
+            revision,
+        }) => {
+            if filename.is_empty() || repo_id.is_empty() || revision.is_empty() {
+                issues.push(issue(
+                    "model.HuggingFace",
+                    "Filename, repository and revision must all be set".to_string(),
+                ));
+            } else if !filename.ends_with(".gguf") {
+                issues.push(issue(
+                    "model.HuggingFace.filename",
+                    format!("Only GGUF models are supported, got '{filename}'"),
+                ));
+            }
+        }
+        AgentDesiredModel::LocalToAgent(path) => {
+            if path.is_empty() {
+                issues.push(issue(
+                    "model.LocalToAgent",
+                    "Model path must not be empty".to_string(),
+                ));
+            }
+        }
+        AgentDesiredModel::None => {}
+    }
+}
+
+pub async fn validate_balancer_desired_state(
+    balancer_desired_state: &BalancerDesiredState,
+    check_huggingface: bool,
+) -> Vec<BalancerDesiredStateValidationIssue> {
+    let mut issues = Vec::new();
+
+    validate_chat_template_override(balancer_desired_state, &mut issues);
+    validate_inference_parameters(&balancer_desired_state.inference_parameters, &mut issues);
+    validate_model(&balancer_desired_state.model, &mut issues);
+
+    if check_huggingface && issues.is_empty() {
+        if let AgentDesiredModel::HuggingFace(model_reference) = &balancer_desired_state.model {
+            check_huggingface_model_reference(model_reference, &mut issues).await;
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat_template::ChatTemplate;
+
+    #[tokio::test]
+    async fn test_default_state_is_valid() {
+        assert!(
+            validate_balancer_desired_state(&BalancerDesiredState::default(), false)
+                .await
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reports_invalid_fields() {
+        let mut balancer_desired_state = BalancerDesiredState {
+            chat_template_override: Some(ChatTemplate {
+                content: "{% for message in messages %}".to_string(),
+            }),
+            model: AgentDesiredModel::LocalToAgent("".to_string()),
+            use_chat_template_override: true,
+            ..BalancerDesiredState::default()
+        };
+
+        balancer_desired_state.inference_parameters.batch_n_tokens = 8192;
+        balancer_desired_state.inference_parameters.context_size = 4096;
+        balancer_desired_state.inference_parameters.top_p = 1.5;
+
+        let fields: Vec<String> = validate_balancer_desired_state(&balancer_desired_state, false)
+            .await
+            .into_iter()
+            .map(|issue| issue.field)
+            .collect();
+
+        assert_eq!(
+            fields,
+            vec![
+                "chat_template_override",
+                "inference_parameters.batch_n_tokens",
+                "inference_parameters.top_p",
+                "model.LocalToAgent",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_enabled_override_requires_template() {
+        let balancer_desired_state = BalancerDesiredState {
+            use_chat_template_override: true,
+            ..BalancerDesiredState::default()
+        };
+
+        let issues = validate_balancer_desired_state(&balancer_desired_state, false).await;
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].field, "use_chat_template_override");
+    }
+}
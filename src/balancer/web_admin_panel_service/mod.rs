@@ -9,6 +9,7 @@ use actix_web::web::Data;
 use anyhow::Result;
 use async_trait::async_trait;
 use log::error;
+use rustls::ServerConfig;
 use tokio::sync::broadcast;
 
 use crate::balancer::web_admin_panel_service::app_data::AppData;
@@ -17,6 +18,7 @@ use crate::service::Service;
 
 pub struct WebAdminPanelService {
     pub configuration: WebAdminPanelServiceConfiguration,
+    pub tls_server_config: Option<ServerConfig>,
 }
 
 #[async_trait]
@@ -30,7 +32,7 @@ impl Service for WebAdminPanelService {
             template_data: self.configuration.template_data.clone(),
         });
 
-        HttpServer::new(move || {
+        let http_server = HttpServer::new(move || {
             App::new()
                 .app_data(app_data.clone())
                 .configure(http_route::favicon::register)
@@ -41,8 +43,14 @@ impl Service for WebAdminPanelService {
             if let Err(err) = shutdown.recv().await {
                 error!("Failed to receive shutdown signal: {err}");
             }
-        })
-        .bind(self.configuration.addr)
+        });
+
+        let addr = self.configuration.addr;
+
+        match self.tls_server_config.clone() {
+            Some(tls_server_config) => http_server.bind_rustls_0_23(addr, tls_server_config),
+            None => http_server.bind(addr),
+        }
         .expect("Unable to bind server to address")
         .run()
         .await?;
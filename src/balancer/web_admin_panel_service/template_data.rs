@@ -1,12 +1,14 @@
 use std::net::SocketAddr;
 use std::time::Duration;
 
+use crate::server_addr::ServerAddr;
+
 #[derive(Clone)]
 pub struct TemplateData {
     pub buffered_request_timeout: Duration,
-    pub compat_openai_addr: Option<SocketAddr>,
-    pub inference_addr: SocketAddr,
-    pub management_addr: SocketAddr,
+    pub compat_openai_addr: Option<ServerAddr>,
+    pub inference_addr: ServerAddr,
+    pub management_addr: ServerAddr,
     pub max_buffered_requests: i32,
     pub statsd_addr: Option<SocketAddr>,
     pub statsd_prefix: String,
@@ -10,7 +10,7 @@ use crate::chat_template::ChatTemplate;
 use crate::converts_to_applicable_state::ConvertsToApplicableState;
 use crate::inference_parameters::InferenceParameters;
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct BalancerDesiredState {
     pub chat_template_override: Option<ChatTemplate>,
@@ -1,45 +1,358 @@
-use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
+use anyhow::anyhow;
 use async_trait::async_trait;
+use clap::ArgMatches;
+use clap::FromArgMatches as _;
 use clap::Parser;
+use log::error;
+use log::info;
+use log::warn;
 use nanoid::nanoid;
+use rustls::ClientConfig;
+use tokio::sync::broadcast;
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
+use tokio::time::timeout;
 
+use super::configuration_file::ConfigurationFile;
 use super::handler::Handler;
-use super::parse_socket_addr;
+use super::merge_configuration_file_value;
+use super::parse_duration;
+use crate::agent::agent_token_holder::AgentTokenHolder;
 use crate::agent::continue_from_conversation_history_request::ContinueFromConversationHistoryRequest;
 use crate::agent::continue_from_raw_prompt_request::ContinueFromRawPromptRequest;
+use crate::agent::create_tls_client_config::create_tls_client_config;
 use crate::agent::generate_embedding_batch_request::GenerateEmbeddingBatchRequest;
 use crate::agent::llamacpp_arbiter_service::LlamaCppArbiterService;
+use crate::agent::management_addr::ManagementAddr;
 use crate::agent::management_socket_client_service::ManagementSocketClientService;
 use crate::agent::model_metadata_holder::ModelMetadataHolder;
 use crate::agent::reconciliation_service::ReconciliationService;
 use crate::agent_applicable_state_holder::AgentApplicableStateHolder;
 use crate::agent_desired_state::AgentDesiredState;
+use crate::drain_status::DrainStatus;
+use crate::full_buffer_policy::FullBufferPolicy;
+use crate::heartbeat_configuration::HeartbeatConfiguration;
+use crate::response_buffer_configuration::ResponseBufferConfiguration;
 use crate::service_manager::ServiceManager;
 use crate::slot_aggregated_status_manager::SlotAggregatedStatusManager;
 
 #[derive(Parser)]
 pub struct Agent {
-    #[arg(long, value_parser = parse_socket_addr)]
-    /// Address of the management server that the agent will connect to
-    management_addr: SocketAddr,
+    #[arg(long, env = "PADDLER_AGENT_TOKEN", hide_env_values = true)]
+    /// Token presented to the management server, if the balancer requires agents to authenticate.
+    /// Read again from the configuration file on SIGHUP, and used from the next connection on
+    agent_token: Option<String>,
 
-    #[arg(long)]
+    #[arg(skip)]
+    arg_matches: ArgMatches,
+
+    #[arg(long, env = "PADDLER_CONFIG")]
+    /// Path to a TOML or YAML file with the agent options in the `agent` section.
+    /// Options set on the command line or through environment variables take precedence over the file
+    config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        env = "PADDLER_DRAIN_TIMEOUT",
+        default_value = "30000",
+        value_parser = parse_duration
+    )]
+    /// After receiving the shutdown signal, the agent stops accepting new requests and waits
+    /// this long (in milliseconds) for the requests it is processing to finish
+    drain_timeout: Duration,
+
+    #[arg(
+        long,
+        env = "PADDLER_HEARTBEAT_INTERVAL",
+        default_value = "5000",
+        value_parser = parse_duration
+    )]
+    /// How often (in milliseconds) the agent sends a heartbeat to the management server.
+    /// Should be the same as the interval configured on the balancer
+    heartbeat_interval: Duration,
+
+    #[arg(long, env = "PADDLER_HEARTBEAT_MISSED_BEATS", default_value = "3")]
+    /// Number of heartbeat intervals without any message from the management server after which
+    /// the agent reconnects (0 disables the detection)
+    heartbeat_missed_beats: u32,
+
+    #[arg(
+        long = "management-addr",
+        action = clap::ArgAction::Append,
+        env = "PADDLER_MANAGEMENT_ADDR",
+        required_unless_present = "config",
+        value_delimiter = ','
+    )]
+    /// Address of the management server that the agent will connect to (can be specified
+    /// multiple times, the agent moves on to the next one when the connection fails).
+    /// Host names are resolved again on every reconnect.
+    /// Can be a Unix socket, written as `unix:/path/to/socket`
+    management_addrs: Vec<ManagementAddr>,
+
+    #[arg(long, env = "PADDLER_MANAGEMENT_CA_FILE")]
+    /// Path to a PEM file with the certificate authorities to verify the management server with,
+    /// instead of the public ones (used only with --management-tls)
+    management_ca_file: Option<PathBuf>,
+
+    #[arg(long, env = "PADDLER_MANAGEMENT_TLS")]
+    /// Connect to the management server over TLS (not available over Unix sockets)
+    management_tls: bool,
+
+    #[arg(long, env = "PADDLER_NAME")]
     /// Name of the agent (optional)
     name: Option<String>,
 
-    #[arg(long)]
+    #[arg(
+        long,
+        env = "PADDLER_RECONNECT_INITIAL_DELAY",
+        default_value = "1000",
+        value_parser = parse_duration
+    )]
+    /// Delay (in milliseconds) before the first reconnect to the management server.
+    /// It doubles with every consecutive failure, with a random jitter
+    reconnect_initial_delay: Duration,
+
+    #[arg(
+        long,
+        env = "PADDLER_RECONNECT_MAX_DELAY",
+        default_value = "30000",
+        value_parser = parse_duration
+    )]
+    /// The longest delay (in milliseconds) between the reconnects to the management server
+    reconnect_max_delay: Duration,
+
+    #[arg(long, env = "PADDLER_RESPONSE_BUFFER_CAPACITY", default_value = "256")]
+    /// Number of responses of a single request (tokens or embeddings) that the agent buffers
+    /// while they wait to be sent to the management server
+    response_buffer_capacity: usize,
+
+    #[arg(
+        long,
+        env = "PADDLER_RESPONSE_BUFFER_FULL_POLICY",
+        default_value = "pause"
+    )]
+    /// What happens to a request when its response buffer is full: `pause` stops generating
+    /// until there is room in the buffer, `abort` fails the request
+    response_buffer_full_policy: FullBufferPolicy,
+
+    #[arg(
+        long,
+        env = "PADDLER_RESUME_GRACE_PERIOD",
+        default_value = "10000",
+        value_parser = parse_duration
+    )]
+    /// How long (in milliseconds) the requests keep running after the connection to the management
+    /// server is lost, so they can be resumed once the agent reconnects (0 stops them right away).
+    /// The responses generated during the last grace period are kept to be sent again.
+    /// Should be the same as the grace period configured on the balancer
+    resume_grace_period: Duration,
+
+    #[arg(long, env = "PADDLER_SLOTS", required_unless_present = "config")]
     /// Number of parallel requests of any kind that the agent can handle at once
-    slots: i32,
+    slots: Option<i32>,
+
+    #[arg(long, env = "PADDLER_TLS_CERT_FILE")]
+    /// Path to a PEM file with the client certificate presented to the management server
+    /// (used only with --management-tls). If the balancer trusts its issuer,
+    /// the certificate replaces the agent token, and it has to be issued for the agent name
+    tls_cert_file: Option<PathBuf>,
+
+    #[arg(long, env = "PADDLER_TLS_KEY_FILE")]
+    /// Path to a PEM file with the private key of the client certificate
+    tls_key_file: Option<PathBuf>,
+}
+
+impl Agent {
+    /// Has to be called with the matches the options were parsed from, so the configuration
+    /// file does not override the options that were set explicitly.
+    pub fn apply_configuration_file(&mut self, arg_matches: &ArgMatches) -> Result<()> {
+        self.arg_matches = arg_matches.clone();
+
+        let agent_configuration_file = match &self.config {
+            Some(config) => match ConfigurationFile::read(config)?.agent {
+                Some(agent_configuration_file) => agent_configuration_file,
+                None => return Ok(()),
+            },
+            None => return Ok(()),
+        };
+
+        merge_configuration_file_value(
+            arg_matches,
+            "agent_token",
+            &mut self.agent_token,
+            agent_configuration_file.agent_token.map(Some),
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "drain_timeout",
+            &mut self.drain_timeout,
+            agent_configuration_file.drain_timeout,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "heartbeat_interval",
+            &mut self.heartbeat_interval,
+            agent_configuration_file.heartbeat_interval,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "heartbeat_missed_beats",
+            &mut self.heartbeat_missed_beats,
+            agent_configuration_file.heartbeat_missed_beats,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "management_addrs",
+            &mut self.management_addrs,
+            agent_configuration_file.management_addrs,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "management_ca_file",
+            &mut self.management_ca_file,
+            agent_configuration_file.management_ca_file.map(Some),
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "management_tls",
+            &mut self.management_tls,
+            agent_configuration_file.management_tls,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "name",
+            &mut self.name,
+            agent_configuration_file.name.map(Some),
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "reconnect_initial_delay",
+            &mut self.reconnect_initial_delay,
+            agent_configuration_file.reconnect_initial_delay,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "reconnect_max_delay",
+            &mut self.reconnect_max_delay,
+            agent_configuration_file.reconnect_max_delay,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "response_buffer_capacity",
+            &mut self.response_buffer_capacity,
+            agent_configuration_file.response_buffer_capacity,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "response_buffer_full_policy",
+            &mut self.response_buffer_full_policy,
+            agent_configuration_file.response_buffer_full_policy,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "resume_grace_period",
+            &mut self.resume_grace_period,
+            agent_configuration_file.resume_grace_period,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "slots",
+            &mut self.slots,
+            agent_configuration_file.slots.map(Some),
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "tls_cert_file",
+            &mut self.tls_cert_file,
+            agent_configuration_file.tls_cert_file.map(Some),
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "tls_key_file",
+            &mut self.tls_key_file,
+            agent_configuration_file.tls_key_file.map(Some),
+        );
+
+        Ok(())
+    }
+
+    /// Checks what the command line parser cannot, and builds everything derived from
+    /// the options, so `config validate` reports the same errors as the startup
+    pub fn validate_configuration(&self) -> Result<()> {
+        if self.slots.is_none() {
+            return Err(anyhow!("Number of slots is not set"));
+        }
+
+        if self.heartbeat_interval.is_zero() {
+            return Err(anyhow!("Heartbeat interval has to be greater than zero"));
+        }
+
+        if self.response_buffer_capacity == 0 {
+            return Err(anyhow!(
+                "Response buffer capacity has to be greater than zero"
+            ));
+        }
+
+        if self.management_addrs.is_empty() {
+            return Err(anyhow!("Management address is not set"));
+        }
+
+        if self.management_tls
+            && self
+                .management_addrs
+                .iter()
+                .any(|management_addr| matches!(management_addr, ManagementAddr::Unix(_)))
+        {
+            return Err(anyhow!(
+                "TLS is not available for the management server on a Unix socket"
+            ));
+        }
+
+        self.get_tls_client_config()?;
+
+        Ok(())
+    }
+
+    fn get_tls_client_config(&self) -> Result<Option<Arc<ClientConfig>>> {
+        if !self.management_tls {
+            return Ok(None);
+        }
+
+        Ok(Some(Arc::new(create_tls_client_config(
+            self.management_ca_file.as_deref(),
+            self.tls_cert_file.as_deref(),
+            self.tls_key_file.as_deref(),
+        )?)))
+    }
+
+    /// Reads the options again the same way they were read at startup
+    fn load_agent_token(arg_matches: &ArgMatches) -> Result<Option<String>> {
+        let mut agent = Agent::from_arg_matches(arg_matches)?;
+
+        agent.apply_configuration_file(arg_matches)?;
+
+        Ok(agent.agent_token)
+    }
 }
 
 #[async_trait]
 impl Handler for Agent {
-    async fn handle(&self, shutdown_rx: oneshot::Receiver<()>) -> Result<()> {
+    async fn handle(
+        &self,
+        mut reload_rx: broadcast::Receiver<()>,
+        shutdown_rx: oneshot::Receiver<()>,
+    ) -> Result<()> {
+        self.validate_configuration()?;
+
+        let slots = self
+            .slots
+            .ok_or_else(|| anyhow!("Number of slots is not set"))?;
+        let tls_client_config = self.get_tls_client_config()?;
         let (agent_desired_state_tx, agent_desired_state_rx) =
             mpsc::unbounded_channel::<AgentDesiredState>();
         let (
@@ -52,9 +365,11 @@ impl Handler for Agent {
             mpsc::unbounded_channel::<GenerateEmbeddingBatchRequest>();
 
         let agent_applicable_state_holder = Arc::new(AgentApplicableStateHolder::default());
+        let agent_token_holder = Arc::new(AgentTokenHolder::new(self.agent_token.clone()));
+        let drain_status = Arc::new(DrainStatus::default());
         let model_metadata_holder = Arc::new(ModelMetadataHolder::default());
         let mut service_manager = ServiceManager::default();
-        let slot_aggregated_status_manager = Arc::new(SlotAggregatedStatusManager::new(self.slots));
+        let slot_aggregated_status_manager = Arc::new(SlotAggregatedStatusManager::new(slots));
 
         service_manager.add_service(LlamaCppArbiterService {
             agent_applicable_state: None,
@@ -62,7 +377,7 @@ impl Handler for Agent {
             agent_name: self.name.clone(),
             continue_from_conversation_history_request_rx,
             continue_from_raw_prompt_request_rx,
-            desired_slots_total: self.slots,
+            desired_slots_total: slots,
             generate_embedding_batch_request_rx,
             llamacpp_arbiter_handle: None,
             model_metadata_holder: model_metadata_holder.clone(),
@@ -72,20 +387,34 @@ impl Handler for Agent {
         service_manager.add_service(ManagementSocketClientService {
             agent_applicable_state_holder: agent_applicable_state_holder.clone(),
             agent_desired_state_tx,
+            agent_id: nanoid!(),
+            agent_token_holder: agent_token_holder.clone(),
             continue_from_conversation_history_request_tx,
             continue_from_raw_prompt_request_tx,
+            drain_status: drain_status.clone(),
             generate_embedding_batch_request_tx,
+            heartbeat_configuration: HeartbeatConfiguration {
+                interval: self.heartbeat_interval,
+                missed_beats_threshold: self.heartbeat_missed_beats,
+            },
+            management_addrs: self.management_addrs.clone(),
             model_metadata_holder,
             name: self.name.clone(),
+            receive_stream_pauser_collection: Default::default(),
+            receive_stream_reattacher_collection: Default::default(),
             receive_stream_stopper_collection: Default::default(),
+            reconnect_initial_delay: self.reconnect_initial_delay,
+            reconnect_max_delay: self.reconnect_max_delay,
+            response_buffer_configuration: ResponseBufferConfiguration {
+                capacity: self.response_buffer_capacity,
+                full_buffer_policy: self.response_buffer_full_policy,
+            },
+            resume_grace_period: self.resume_grace_period,
+            resume_secret_holder: Default::default(),
             slot_aggregated_status: slot_aggregated_status_manager
                 .slot_aggregated_status
                 .clone(),
-            socket_url: format!(
-                "ws://{}/api/v1/agent_socket/{}",
-                self.management_addr,
-                nanoid!()
-            ),
+            tls_client_config,
         });
 
         service_manager.add_service(ReconciliationService {
@@ -98,6 +427,59 @@ impl Handler for Agent {
                 .clone(),
         });
 
-        service_manager.run_forever(shutdown_rx).await
+        let arg_matches = self.arg_matches.clone();
+
+        tokio::spawn(async move {
+            loop {
+                // Signals that arrived while reloading are coalesced into a single reload
+                if let Err(broadcast::error::RecvError::Closed) = reload_rx.recv().await {
+                    break;
+                }
+
+                match Self::load_agent_token(&arg_matches) {
+                    Ok(agent_token) => {
+                        agent_token_holder.set_agent_token(agent_token);
+
+                        info!("Agent token reloaded, it is used from the next connection on");
+                    }
+                    Err(err) => error!("Failed to reload the agent token: {err}"),
+                }
+            }
+        });
+
+        let (service_manager_shutdown_tx, service_manager_shutdown_rx) = oneshot::channel::<()>();
+        let drain_timeout = self.drain_timeout;
+        let slot_aggregated_status = slot_aggregated_status_manager
+            .slot_aggregated_status
+            .clone();
+
+        tokio::spawn(async move {
+            if shutdown_rx.await.is_ok() {
+                info!("Draining agent before shutting down");
+
+                drain_status.start_draining();
+
+                if timeout(
+                    drain_timeout,
+                    slot_aggregated_status.wait_for_slots_to_be_released(),
+                )
+                .await
+                .is_err()
+                {
+                    warn!(
+                        "Drain timed out with {} slots still processing",
+                        slot_aggregated_status.get_slots_processing()
+                    );
+                }
+            }
+
+            if service_manager_shutdown_tx.send(()).is_err() {
+                error!("Failed to send shutdown signal to the services");
+            }
+        });
+
+        service_manager
+            .run_forever(service_manager_shutdown_rx)
+            .await
     }
 }
@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use super::deserialize_optional_duration;
+use super::deserialize_optional_from_str;
+use super::deserialize_optional_vec_from_str;
+use crate::agent::management_addr::ManagementAddr;
+use crate::full_buffer_policy::FullBufferPolicy;
+
+/// Mirrors the command line options of the agent.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AgentConfigurationFile {
+    pub agent_token: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub drain_timeout: Option<Duration>,
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub heartbeat_interval: Option<Duration>,
+    pub heartbeat_missed_beats: Option<u32>,
+    #[serde(default, deserialize_with = "deserialize_optional_vec_from_str")]
+    pub management_addrs: Option<Vec<ManagementAddr>>,
+    pub management_ca_file: Option<PathBuf>,
+    pub management_tls: Option<bool>,
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub reconnect_initial_delay: Option<Duration>,
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub reconnect_max_delay: Option<Duration>,
+    pub response_buffer_capacity: Option<usize>,
+    #[serde(default, deserialize_with = "deserialize_optional_from_str")]
+    pub response_buffer_full_policy: Option<FullBufferPolicy>,
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub resume_grace_period: Option<Duration>,
+    pub slots: Option<i32>,
+    pub tls_cert_file: Option<PathBuf>,
+    pub tls_key_file: Option<PathBuf>,
+}
@@ -1,35 +1,62 @@
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Result;
+use anyhow::anyhow;
 use async_trait::async_trait;
+use clap::ArgMatches;
+use clap::FromArgMatches as _;
 use clap::Parser;
+use log::error;
+use log::info;
+use log::warn;
+use tokio::sync::Mutex;
 use tokio::sync::broadcast;
 use tokio::sync::oneshot;
+use tokio::time::timeout;
 
+use super::configuration_file::ConfigurationFile;
 use super::handler::Handler;
+use super::merge_configuration_file_value;
 use super::parse_duration;
+use super::parse_server_addr;
 use super::parse_socket_addr;
+use crate::balancer::agent_circuit_breaker_configuration::AgentCircuitBreakerConfiguration;
 use crate::balancer::agent_controller_pool::AgentControllerPool;
+use crate::balancer::agent_token_registry_holder::AgentTokenRegistryHolder;
+use crate::balancer::api_key_manager::ApiKeyManager;
 use crate::balancer::buffered_request_manager::BufferedRequestManager;
+use crate::balancer::buffered_request_priority_class::BufferedRequestPriorityClass;
+use crate::balancer::buffered_request_priority_class_api_key::BufferedRequestPriorityClassApiKey;
 use crate::balancer::chat_template_override_sender_collection::ChatTemplateOverrideSenderCollection;
 use crate::balancer::compatibility::openai_service::OpenAIService;
 use crate::balancer::compatibility::openai_service::configuration::Configuration as OpenAIServiceConfiguration;
+use crate::balancer::create_tls_server_config::create_tls_server_config;
 use crate::balancer::embedding_sender_collection::EmbeddingSenderCollection;
 use crate::balancer::generate_tokens_sender_collection::GenerateTokensSenderCollection;
 use crate::balancer::inference_service::InferenceService;
-use crate::balancer::inference_service::configuration::Configuration as InferenceServiceConfiguration;
+use crate::balancer::inference_service::configuration_holder::ConfigurationHolder as InferenceServiceConfigurationHolder;
+use crate::balancer::loads_reloadable_configuration::LoadsReloadableConfiguration;
+use crate::balancer::management_access_control::ManagementAccessControl;
+use crate::balancer::management_audit_log::ManagementAuditLog;
 use crate::balancer::management_service::ManagementService;
 use crate::balancer::management_service::configuration::Configuration as ManagementServiceConfiguration;
 use crate::balancer::model_metadata_sender_collection::ModelMetadataSenderCollection;
 use crate::balancer::reconciliation_service::ReconciliationService;
+use crate::balancer::reload_service::ReloadService;
+use crate::balancer::reloadable_configuration::ReloadableConfiguration;
+use crate::balancer::reloadable_tls_certificate::ReloadableTlsCertificate;
 use crate::balancer::state_database::File;
 use crate::balancer::state_database::Memory;
+use crate::balancer::state_database::Sqlite;
 use crate::balancer::state_database::StateDatabase;
+use crate::balancer::state_database_file_watch_service::StateDatabaseFileWatchService;
 use crate::balancer::state_database_type::StateDatabaseType;
 use crate::balancer::statsd_service::StatsdService;
-use crate::balancer::statsd_service::configuration::Configuration as StatsdServiceConfiguration;
+use crate::balancer::statsd_service::reported_counters::ReportedCounters;
+use crate::balancer::unix_socket_mode::UnixSocketMode;
 #[cfg(feature = "web_admin_panel")]
 use crate::balancer::web_admin_panel_service::WebAdminPanelService;
 #[cfg(feature = "web_admin_panel")]
@@ -37,85 +64,651 @@ use crate::balancer::web_admin_panel_service::configuration::Configuration as We
 #[cfg(feature = "web_admin_panel")]
 use crate::balancer::web_admin_panel_service::template_data::TemplateData;
 use crate::balancer_applicable_state_holder::BalancerApplicableStateHolder;
+use crate::balancer_desired_state::BalancerDesiredState;
+use crate::cors_allowed_hosts::CorsAllowedHosts;
+use crate::drain_status::DrainStatus;
+use crate::full_buffer_policy::FullBufferPolicy;
+use crate::heartbeat_configuration::HeartbeatConfiguration;
+use crate::response_buffer_configuration::ResponseBufferConfiguration;
+use crate::server_addr::ServerAddr;
 use crate::service_manager::ServiceManager;
 
-#[derive(Parser)]
+#[derive(Clone, Parser)]
 pub struct Balancer {
-    #[arg(long, default_value = "10000", value_parser = parse_duration)]
+    #[arg(
+        long,
+        env = "PADDLER_AGENT_CIRCUIT_BREAKER_COOLDOWN",
+        default_value = "30000",
+        value_parser = parse_duration
+    )]
+    /// How long (in milliseconds) an agent with an open circuit is excluded from the selection
+    /// before a single probe request is let through to check if it recovered
+    agent_circuit_breaker_cooldown: Duration,
+
+    #[arg(
+        long,
+        env = "PADDLER_AGENT_CIRCUIT_BREAKER_FAILURE_RATIO",
+        default_value = "0.5"
+    )]
+    /// Share (from 0 to 1) of the recent requests that have to fail for the agent to stop
+    /// receiving traffic, even if they do not fail in a row (0 disables this check)
+    agent_circuit_breaker_failure_ratio: f64,
+
+    #[arg(
+        long,
+        env = "PADDLER_AGENT_CIRCUIT_BREAKER_FAILURE_RATIO_MIN_REQUESTS",
+        default_value = "10"
+    )]
+    /// Number of the recent requests the agent has to handle before its failure ratio is checked
+    agent_circuit_breaker_failure_ratio_min_requests: usize,
+
+    #[arg(
+        long,
+        env = "PADDLER_AGENT_CIRCUIT_BREAKER_FAILURE_RATIO_WINDOW",
+        default_value = "20"
+    )]
+    /// Number of the most recent requests of each agent the failure ratio is computed from
+    agent_circuit_breaker_failure_ratio_window: usize,
+
+    #[arg(
+        long,
+        env = "PADDLER_AGENT_CIRCUIT_BREAKER_FAILURE_THRESHOLD",
+        default_value = "5"
+    )]
+    /// Number of consecutive failed requests after which the agent stops receiving traffic
+    /// (0 disables this check)
+    agent_circuit_breaker_failure_threshold: usize,
+
+    #[arg(
+        long,
+        env = "PADDLER_AGENT_HEARTBEAT_INTERVAL",
+        default_value = "5000",
+        value_parser = parse_duration
+    )]
+    /// How often (in milliseconds) the balancer sends a heartbeat to each agent.
+    /// Agents should be configured with the same interval
+    agent_heartbeat_interval: Duration,
+
+    #[arg(
+        long,
+        env = "PADDLER_AGENT_HEARTBEAT_MISSED_BEATS",
+        default_value = "3"
+    )]
+    /// Number of heartbeat intervals without any message from an agent after which it is
+    /// disconnected and its requests fail (0 disables the detection)
+    agent_heartbeat_missed_beats: u32,
+
+    #[arg(
+        long,
+        env = "PADDLER_AGENT_RESUME_GRACE_PERIOD",
+        default_value = "10000",
+        value_parser = parse_duration
+    )]
+    /// How long (in milliseconds) a request that already started streaming waits for its agent
+    /// to reconnect and resume it (0 fails it right away).
+    /// Agents should be configured with the same grace period
+    agent_resume_grace_period: Duration,
+
+    #[arg(long, env = "PADDLER_AGENT_TOKENS_FILE")]
+    /// Path to a file with the tokens the agents have to present to connect (one per line).
+    /// A line is either a shared `token` or `agent_name:token` that only the agent with that name can use.
+    /// Tokens can also be created through the management API. Without either, agents can connect
+    /// without a token
+    agent_tokens_file: Option<PathBuf>,
+
+    #[arg(long, env = "PADDLER_ALLOW_ANONYMOUS_MANAGEMENT_ACCESS")]
+    /// Let everyone use the management API as an admin while there are no management tokens,
+    /// so the first token can be created through the API.
+    /// Otherwise the management API stays closed until a tokens file is provided
+    allow_anonymous_management_access: bool,
+
+    #[arg(skip)]
+    arg_matches: ArgMatches,
+
+    #[arg(
+        long,
+        env = "PADDLER_BUFFERED_REQUEST_TIMEOUT",
+        default_value = "10000",
+        value_parser = parse_duration
+    )]
     /// Specifies how long a request can stay in the buffer before it is processed.
     /// If the request stays in the buffer longer than this time, it is rejected with the 504 error
     buffered_request_timeout: Duration,
 
-    #[arg(long, value_parser = parse_socket_addr)]
-    /// Address of the OpenAI-compatible API server (enabled only if this address is specified)
-    compat_openai_addr: Option<SocketAddr>,
+    #[arg(long, env = "PADDLER_COMPAT_OPENAI_ADDR", value_parser = parse_server_addr)]
+    /// Address of the OpenAI-compatible API server (enabled only if this address is specified).
+    /// Can be a Unix socket, written as `unix:/path/to/socket`
+    compat_openai_addr: Option<ServerAddr>,
+
+    #[arg(long, env = "PADDLER_CONFIG")]
+    /// Path to a TOML or YAML file with the balancer options in the `balancer` section
+    /// and the initial desired state in `balancer.desired_state`.
+    /// Options set on the command line or through environment variables take precedence over the file
+    config: Option<PathBuf>,
 
-    #[arg(long, default_value = "127.0.0.1:8061", value_parser = parse_socket_addr)]
-    /// Address of the inference server
-    inference_addr: SocketAddr,
+    #[arg(
+        long,
+        env = "PADDLER_INFERENCE_ADDR",
+        default_value = "127.0.0.1:8061",
+        value_parser = parse_server_addr
+    )]
+    /// Address of the inference server. Can be a Unix socket, written as `unix:/path/to/socket`
+    inference_addr: ServerAddr,
 
-    #[arg(long, default_value = "5000", value_parser = parse_duration)]
+    #[arg(
+        long,
+        env = "PADDLER_INFERENCE_ITEM_TIMEOUT",
+        default_value = "5000",
+        value_parser = parse_duration
+    )]
     /// The timeout (in milliseconds) for generating a single token or a single embedding
     inference_item_timeout: Duration,
 
+    #[arg(skip)]
+    initial_balancer_desired_state: Option<BalancerDesiredState>,
+
     #[arg(
         long = "inference-cors-allowed-host",
-        action = clap::ArgAction::Append
+        action = clap::ArgAction::Append,
+        env = "PADDLER_INFERENCE_CORS_ALLOWED_HOSTS",
+        value_delimiter = ','
     )]
     /// Allowed CORS host for the inference service (can be specified multiple times)
     inference_cors_allowed_hosts: Vec<String>,
 
-    #[arg(long, default_value = "127.0.0.1:8060", value_parser = parse_socket_addr)]
-    /// This is where you can manage your Paddler setup and the agents connect to
-    management_addr: SocketAddr,
+    #[arg(
+        long,
+        env = "PADDLER_MANAGEMENT_ADDR",
+        default_value = "127.0.0.1:8060",
+        value_parser = parse_server_addr
+    )]
+    /// This is where you can manage your Paddler setup and the agents connect to.
+    /// Can be a Unix socket, written as `unix:/path/to/socket`
+    management_addr: ServerAddr,
+
+    #[arg(long, env = "PADDLER_MANAGEMENT_AUDIT_LOG_FILE")]
+    /// Path to a file the state-changing management API calls are appended to (as JSON lines).
+    /// If not specified, they are written to the log
+    management_audit_log_file: Option<PathBuf>,
 
     #[arg(
         long = "management-cors-allowed-host",
-        action = clap::ArgAction::Append
+        action = clap::ArgAction::Append,
+        env = "PADDLER_MANAGEMENT_CORS_ALLOWED_HOSTS",
+        value_delimiter = ','
     )]
     /// Allowed CORS host for the management service (can be specified multiple times)
     management_cors_allowed_hosts: Vec<String>,
 
-    #[arg(long, default_value = "30")]
+    #[arg(long, env = "PADDLER_MANAGEMENT_TOKENS_FILE")]
+    /// Path to a file with the tokens that grant access to the management API, one `name:role:token` per line.
+    /// The role is `admin` or `read_only`. Tokens can also be created through the management API.
+    /// If there are no tokens at all, the management API is closed unless --allow-anonymous-management-access is set
+    management_tokens_file: Option<PathBuf>,
+
+    #[arg(long, env = "PADDLER_MAX_BUFFERED_REQUESTS", default_value = "30")]
     /// The maximum number of buffered requests.
     /// If the buffer is full then new requests are rejected with the 503 error
     max_buffered_requests: i32,
 
-    #[arg(long, default_value = "memory://")]
-    /// Balancer state database URL. Supported: memory, memory://, or file:///path (optional)
+    #[arg(long, env = "PADDLER_MAX_REQUEST_ATTEMPTS", default_value = "3")]
+    /// How many agents can attempt to handle a request in total. The request is handed
+    /// to another agent only if the previous one disconnected before producing any output
+    max_request_attempts: usize,
+
+    #[arg(
+        long = "priority-class",
+        action = clap::ArgAction::Append,
+        env = "PADDLER_PRIORITY_CLASSES",
+        value_delimiter = ','
+    )]
+    /// Buffered request priority class in the format
+    /// `name:priority:max_buffered_requests:buffered_request_timeout_millis` (can be specified multiple times).
+    /// Requests without a priority class use the "default" class built from --max-buffered-requests and --buffered-request-timeout
+    priority_classes: Vec<BufferedRequestPriorityClass>,
+
+    #[arg(
+        long = "priority-class-api-key",
+        action = clap::ArgAction::Append,
+        env = "PADDLER_PRIORITY_CLASS_API_KEYS",
+        value_delimiter = ','
+    )]
+    /// Assigns requests authorized with the given bearer token to a priority class,
+    /// in the format `priority_class:api_key` (can be specified multiple times)
+    priority_class_api_keys: Vec<BufferedRequestPriorityClassApiKey>,
+
+    #[arg(long, env = "PADDLER_REQUIRE_API_KEY")]
+    /// Reject the inference requests without a valid API key (keys are managed through the management API,
+    /// and the keys assigned to the priority classes are valid too, without limits).
+    /// Otherwise the requests without a key are allowed, and the keys only apply their limits
+    require_api_key: bool,
+
+    #[arg(long, env = "PADDLER_REQUIRE_IF_MATCH")]
+    /// Reject the desired state updates that do not send the `If-Match` header with the ETag
+    /// of the current desired state. Otherwise such updates overwrite the state unconditionally
+    require_if_match: bool,
+
+    #[arg(long, env = "PADDLER_RESPONSE_BUFFER_CAPACITY", default_value = "256")]
+    /// Number of responses of a single request (tokens or embeddings) that the balancer buffers
+    /// while they wait for the client to receive them
+    response_buffer_capacity: usize,
+
+    #[arg(
+        long,
+        env = "PADDLER_RESPONSE_BUFFER_FULL_POLICY",
+        default_value = "pause"
+    )]
+    /// What happens to a request when its response buffer is full: `pause` asks the agent to stop
+    /// generating until there is room in the buffer, `abort` fails the request
+    response_buffer_full_policy: FullBufferPolicy,
+
+    #[arg(long, env = "PADDLER_STATE_DATABASE", default_value = "memory://")]
+    /// Balancer state database URL. Supported: memory, memory://, file:///path, or sqlite:///path (optional).
+    /// Only the sqlite database keeps the history of the desired states. The file database picks up
+    /// external edits to the file
     state_database: StateDatabaseType,
 
-    #[arg(long, value_parser = parse_socket_addr)]
+    #[arg(
+        long,
+        env = "PADDLER_STATE_DATABASE_POLL_INTERVAL",
+        default_value = "1000",
+        value_parser = parse_duration
+    )]
+    /// How often (in milliseconds) the file state database is checked for external edits
+    state_database_poll_interval: Duration,
+
+    #[arg(
+        long,
+        env = "PADDLER_SHUTDOWN_GRACE_PERIOD",
+        default_value = "30000",
+        value_parser = parse_duration
+    )]
+    /// After receiving the shutdown signal, the balancer stops accepting new requests and waits
+    /// this long (in milliseconds) for the buffered and in-flight requests to finish
+    shutdown_grace_period: Duration,
+
+    #[arg(long, env = "PADDLER_STATSD_ADDR", value_parser = parse_socket_addr)]
     /// Address for the statsd server to report metrics to (enabled only if this address is specified)
     statsd_addr: Option<SocketAddr>,
 
-    #[arg(long, default_value = "paddler_")]
+    #[arg(long, env = "PADDLER_STATSD_PREFIX", default_value = "paddler_")]
     /// Prefix for statsd metrics
     statsd_prefix: String,
 
-    #[arg(long, default_value = "10000", value_parser = parse_duration)]
+    #[arg(
+        long,
+        env = "PADDLER_STATSD_REPORTING_INTERVAL",
+        default_value = "10000",
+        value_parser = parse_duration
+    )]
     /// Interval (in milliseconds) at which the balancer will report metrics to statsd
     statsd_reporting_interval: Duration,
 
-    #[arg(long, default_value = None, value_parser = parse_socket_addr)]
+    #[arg(long, env = "PADDLER_TLS_CERT_FILE")]
+    /// Path to a PEM file with the certificate chain. If specified, all the TCP listeners use TLS
+    /// (Unix sockets do not).
+    /// The certificate and the key are read again on SIGHUP, so they can be renewed without a restart
+    tls_cert_file: Option<PathBuf>,
+
+    #[arg(long, env = "PADDLER_TLS_CLIENT_CA_FILE")]
+    /// Path to a PEM file with the certificate authorities the management service trusts
+    /// to issue agent certificates. An agent with such a certificate does not need a token,
+    /// but its name has to match the certificate
+    tls_client_ca_file: Option<PathBuf>,
+
+    #[arg(long, env = "PADDLER_TLS_KEY_FILE")]
+    /// Path to a PEM file with the private key of the certificate
+    tls_key_file: Option<PathBuf>,
+
+    #[arg(long, env = "PADDLER_UNIX_SOCKET_MODE")]
+    /// Permissions of the Unix socket files in octal, like 660.
+    /// If not specified, they depend on the umask of the balancer process
+    unix_socket_mode: Option<UnixSocketMode>,
+
+    #[arg(
+        long,
+        env = "PADDLER_WEB_ADMIN_PANEL_ADDR",
+        default_value = None,
+        value_parser = parse_socket_addr
+    )]
     /// Address of the web admin panel (enabled only if this address is specified)
     web_admin_panel_addr: Option<SocketAddr>,
 }
 
 impl Balancer {
+    /// Has to be called with the matches the options were parsed from, so the configuration
+    /// file does not override the options that were set explicitly.
+    pub fn apply_configuration_file(&mut self, arg_matches: &ArgMatches) -> Result<()> {
+        self.arg_matches = arg_matches.clone();
+
+        let balancer_configuration_file = match &self.config {
+            Some(config) => match ConfigurationFile::read(config)?.balancer {
+                Some(balancer_configuration_file) => balancer_configuration_file,
+                None => return Ok(()),
+            },
+            None => return Ok(()),
+        };
+
+        merge_configuration_file_value(
+            arg_matches,
+            "agent_circuit_breaker_cooldown",
+            &mut self.agent_circuit_breaker_cooldown,
+            balancer_configuration_file.agent_circuit_breaker_cooldown,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "agent_circuit_breaker_failure_ratio",
+            &mut self.agent_circuit_breaker_failure_ratio,
+            balancer_configuration_file.agent_circuit_breaker_failure_ratio,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "agent_circuit_breaker_failure_ratio_min_requests",
+            &mut self.agent_circuit_breaker_failure_ratio_min_requests,
+            balancer_configuration_file.agent_circuit_breaker_failure_ratio_min_requests,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "agent_circuit_breaker_failure_ratio_window",
+            &mut self.agent_circuit_breaker_failure_ratio_window,
+            balancer_configuration_file.agent_circuit_breaker_failure_ratio_window,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "agent_circuit_breaker_failure_threshold",
+            &mut self.agent_circuit_breaker_failure_threshold,
+            balancer_configuration_file.agent_circuit_breaker_failure_threshold,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "agent_heartbeat_interval",
+            &mut self.agent_heartbeat_interval,
+            balancer_configuration_file.agent_heartbeat_interval,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "agent_heartbeat_missed_beats",
+            &mut self.agent_heartbeat_missed_beats,
+            balancer_configuration_file.agent_heartbeat_missed_beats,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "agent_resume_grace_period",
+            &mut self.agent_resume_grace_period,
+            balancer_configuration_file.agent_resume_grace_period,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "agent_tokens_file",
+            &mut self.agent_tokens_file,
+            balancer_configuration_file.agent_tokens_file.map(Some),
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "allow_anonymous_management_access",
+            &mut self.allow_anonymous_management_access,
+            balancer_configuration_file.allow_anonymous_management_access,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "buffered_request_timeout",
+            &mut self.buffered_request_timeout,
+            balancer_configuration_file.buffered_request_timeout,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "compat_openai_addr",
+            &mut self.compat_openai_addr,
+            balancer_configuration_file.compat_openai_addr.map(Some),
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "inference_addr",
+            &mut self.inference_addr,
+            balancer_configuration_file.inference_addr,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "inference_cors_allowed_hosts",
+            &mut self.inference_cors_allowed_hosts,
+            balancer_configuration_file.inference_cors_allowed_hosts,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "inference_item_timeout",
+            &mut self.inference_item_timeout,
+            balancer_configuration_file.inference_item_timeout,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "management_addr",
+            &mut self.management_addr,
+            balancer_configuration_file.management_addr,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "management_cors_allowed_hosts",
+            &mut self.management_cors_allowed_hosts,
+            balancer_configuration_file.management_cors_allowed_hosts,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "management_audit_log_file",
+            &mut self.management_audit_log_file,
+            balancer_configuration_file
+                .management_audit_log_file
+                .map(Some),
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "management_tokens_file",
+            &mut self.management_tokens_file,
+            balancer_configuration_file.management_tokens_file.map(Some),
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "max_buffered_requests",
+            &mut self.max_buffered_requests,
+            balancer_configuration_file.max_buffered_requests,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "max_request_attempts",
+            &mut self.max_request_attempts,
+            balancer_configuration_file.max_request_attempts,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "priority_class_api_keys",
+            &mut self.priority_class_api_keys,
+            balancer_configuration_file.priority_class_api_keys,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "priority_classes",
+            &mut self.priority_classes,
+            balancer_configuration_file.priority_classes,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "require_api_key",
+            &mut self.require_api_key,
+            balancer_configuration_file.require_api_key,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "require_if_match",
+            &mut self.require_if_match,
+            balancer_configuration_file.require_if_match,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "response_buffer_capacity",
+            &mut self.response_buffer_capacity,
+            balancer_configuration_file.response_buffer_capacity,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "response_buffer_full_policy",
+            &mut self.response_buffer_full_policy,
+            balancer_configuration_file.response_buffer_full_policy,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "shutdown_grace_period",
+            &mut self.shutdown_grace_period,
+            balancer_configuration_file.shutdown_grace_period,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "state_database",
+            &mut self.state_database,
+            balancer_configuration_file.state_database,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "state_database_poll_interval",
+            &mut self.state_database_poll_interval,
+            balancer_configuration_file.state_database_poll_interval,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "statsd_addr",
+            &mut self.statsd_addr,
+            balancer_configuration_file.statsd_addr.map(Some),
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "statsd_prefix",
+            &mut self.statsd_prefix,
+            balancer_configuration_file.statsd_prefix,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "statsd_reporting_interval",
+            &mut self.statsd_reporting_interval,
+            balancer_configuration_file.statsd_reporting_interval,
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "tls_cert_file",
+            &mut self.tls_cert_file,
+            balancer_configuration_file.tls_cert_file.map(Some),
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "tls_client_ca_file",
+            &mut self.tls_client_ca_file,
+            balancer_configuration_file.tls_client_ca_file.map(Some),
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "tls_key_file",
+            &mut self.tls_key_file,
+            balancer_configuration_file.tls_key_file.map(Some),
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "unix_socket_mode",
+            &mut self.unix_socket_mode,
+            balancer_configuration_file.unix_socket_mode.map(Some),
+        );
+        merge_configuration_file_value(
+            arg_matches,
+            "web_admin_panel_addr",
+            &mut self.web_admin_panel_addr,
+            balancer_configuration_file.web_admin_panel_addr.map(Some),
+        );
+
+        self.initial_balancer_desired_state = balancer_configuration_file.desired_state;
+
+        Ok(())
+    }
+
     fn get_management_service_configuration(&self) -> ManagementServiceConfiguration {
         ManagementServiceConfiguration {
-            addr: self.management_addr,
-            cors_allowed_hosts: self.management_cors_allowed_hosts.clone(),
+            addr: self.management_addr.clone(),
         }
     }
 
-    fn get_inference_service_configuration(&self) -> InferenceServiceConfiguration {
-        InferenceServiceConfiguration {
-            addr: self.inference_addr,
-            cors_allowed_hosts: self.inference_cors_allowed_hosts.clone(),
+    fn get_reloadable_configuration(&self) -> ReloadableConfiguration {
+        ReloadableConfiguration {
+            agent_tokens_file: self.agent_tokens_file.clone(),
+            buffered_request_timeout: self.buffered_request_timeout,
+            compat_openai_addr: self.compat_openai_addr.clone(),
+            inference_addr: self.inference_addr.clone(),
+            inference_cors_allowed_hosts: self.inference_cors_allowed_hosts.clone(),
             inference_item_timeout: self.inference_item_timeout,
+            management_addr: self.management_addr.clone(),
+            management_cors_allowed_hosts: self.management_cors_allowed_hosts.clone(),
+            management_tokens_file: self.management_tokens_file.clone(),
+            max_buffered_requests: self.max_buffered_requests,
+            max_request_attempts: self.max_request_attempts,
+            priority_class_api_keys: self.priority_class_api_keys.clone(),
+            priority_classes: self.priority_classes.clone(),
+            require_api_key: self.require_api_key,
+            statsd_addr: self.statsd_addr,
+            statsd_prefix: self.statsd_prefix.clone(),
+            statsd_reporting_interval: self.statsd_reporting_interval,
+            tls_cert_file: self.tls_cert_file.clone(),
+            tls_client_ca_file: self.tls_client_ca_file.clone(),
+            tls_key_file: self.tls_key_file.clone(),
+            web_admin_panel_addr: self.web_admin_panel_addr,
+        }
+    }
+
+    /// Checks what the command line parser cannot, and builds everything derived from
+    /// the options, so `config validate` reports the same errors as the startup
+    pub fn validate_configuration(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.agent_circuit_breaker_failure_ratio) {
+            return Err(anyhow!(
+                "Agent circuit breaker failure ratio has to be between 0 and 1"
+            ));
+        }
+
+        if self.agent_circuit_breaker_failure_ratio > 0.0
+            && self.agent_circuit_breaker_failure_ratio_window == 0
+        {
+            return Err(anyhow!(
+                "Agent circuit breaker failure ratio window has to be greater than zero"
+            ));
+        }
+
+        if self.agent_circuit_breaker_failure_ratio > 0.0
+            && self.agent_circuit_breaker_failure_ratio_min_requests
+                > self.agent_circuit_breaker_failure_ratio_window
+        {
+            return Err(anyhow!(
+                "Agent circuit breaker failure ratio minimum requests cannot exceed its window"
+            ));
         }
+
+        if self.agent_heartbeat_interval.is_zero() {
+            return Err(anyhow!(
+                "Agent heartbeat interval has to be greater than zero"
+            ));
+        }
+
+        if self.response_buffer_capacity == 0 {
+            return Err(anyhow!(
+                "Response buffer capacity has to be greater than zero"
+            ));
+        }
+
+        if self.state_database_poll_interval.is_zero() {
+            return Err(anyhow!(
+                "State database poll interval has to be greater than zero"
+            ));
+        }
+
+        let configuration = self.get_reloadable_configuration();
+
+        configuration.get_agent_token_registry()?;
+        configuration.get_management_token_registry()?;
+        configuration.get_priority_class_registry()?;
+        configuration.get_tls_certified_key()?;
+
+        Ok(())
     }
 
     #[cfg(feature = "web_admin_panel")]
@@ -127,10 +720,10 @@ impl Balancer {
                 addr: web_admin_panel_addr,
                 template_data: TemplateData {
                     buffered_request_timeout: self.buffered_request_timeout,
-                    compat_openai_addr: self.compat_openai_addr,
+                    compat_openai_addr: self.compat_openai_addr.clone(),
                     max_buffered_requests: self.max_buffered_requests,
-                    management_addr: self.management_addr,
-                    inference_addr: self.inference_addr,
+                    management_addr: self.management_addr.clone(),
+                    inference_addr: self.inference_addr.clone(),
                     statsd_addr: self.statsd_addr,
                     statsd_prefix: self.statsd_prefix.clone(),
                     statsd_reporting_interval: self.statsd_reporting_interval,
@@ -139,53 +732,176 @@ impl Balancer {
     }
 }
 
+impl LoadsReloadableConfiguration for Balancer {
+    fn load_reloadable_configuration(&self) -> Result<ReloadableConfiguration> {
+        if self.config.is_none() {
+            return Ok(self.get_reloadable_configuration());
+        }
+
+        // Start over from the command line and environment, so the options removed
+        // from the file fall back to their defaults
+        let mut balancer = Balancer::from_arg_matches(&self.arg_matches)?;
+
+        balancer.apply_configuration_file(&self.arg_matches)?;
+
+        Ok(balancer.get_reloadable_configuration())
+    }
+}
+
 #[async_trait]
 impl Handler for Balancer {
-    async fn handle(&self, shutdown_rx: oneshot::Receiver<()>) -> Result<()> {
+    async fn handle(
+        &self,
+        reload_rx: broadcast::Receiver<()>,
+        shutdown_rx: oneshot::Receiver<()>,
+    ) -> Result<()> {
         let (balancer_desired_state_tx, balancer_desired_state_rx) = broadcast::channel(100);
+        let (statsd_service_configuration_tx, statsd_service_configuration_rx) =
+            broadcast::channel(1);
+        let configuration = self.get_reloadable_configuration();
+
+        self.validate_configuration()?;
 
-        let agent_controller_pool = Arc::new(AgentControllerPool::default());
+        let agent_controller_pool = Arc::new(AgentControllerPool::new(
+            AgentCircuitBreakerConfiguration {
+                cooldown: self.agent_circuit_breaker_cooldown,
+                failure_ratio: self.agent_circuit_breaker_failure_ratio,
+                failure_ratio_min_requests: self.agent_circuit_breaker_failure_ratio_min_requests,
+                failure_ratio_window: self.agent_circuit_breaker_failure_ratio_window,
+                failure_threshold: self.agent_circuit_breaker_failure_threshold,
+            },
+            HeartbeatConfiguration {
+                interval: self.agent_heartbeat_interval,
+                missed_beats_threshold: self.agent_heartbeat_missed_beats,
+            },
+            self.agent_resume_grace_period,
+            ResponseBufferConfiguration {
+                capacity: self.response_buffer_capacity,
+                full_buffer_policy: self.response_buffer_full_policy,
+            },
+        ));
+        let agent_token_registry_holder = Arc::new(AgentTokenRegistryHolder::new(
+            configuration.get_agent_token_registry()?,
+        ));
+        let api_key_manager = Arc::new(ApiKeyManager::new(configuration.require_api_key));
         let balancer_applicable_state_holder = Arc::new(BalancerApplicableStateHolder::default());
+        let drain_status = Arc::new(DrainStatus::default());
         let buffered_request_manager = Arc::new(BufferedRequestManager::new(
             agent_controller_pool.clone(),
-            self.buffered_request_timeout,
-            self.max_buffered_requests,
+            drain_status.clone(),
+            configuration.get_priority_class_registry()?,
         ));
         let chat_template_override_sender_collection =
             Arc::new(ChatTemplateOverrideSenderCollection::default());
         let embedding_sender_collection = Arc::new(EmbeddingSenderCollection::default());
         let generate_tokens_sender_collection = Arc::new(GenerateTokensSenderCollection::default());
+        let inference_cors_allowed_hosts = Arc::new(CorsAllowedHosts::new(
+            configuration.get_inference_cors_allowed_hosts(),
+        ));
+        let inference_service_configuration_holder =
+            Arc::new(InferenceServiceConfigurationHolder::new(
+                configuration.get_inference_service_configuration(),
+            ));
+        let management_cors_allowed_hosts = Arc::new(CorsAllowedHosts::new(
+            configuration.get_management_cors_allowed_hosts(),
+        ));
+        let management_access_control = Arc::new(ManagementAccessControl::new(
+            self.allow_anonymous_management_access,
+            configuration.get_management_token_registry()?,
+        ));
+        let management_audit_log =
+            Arc::new(ManagementAuditLog::open(self.management_audit_log_file.as_deref()).await?);
         let model_metadata_sender_collection = Arc::new(ModelMetadataSenderCollection::default());
+        let reloadable_tls_certificate = configuration
+            .get_tls_certified_key()?
+            .map(|tls_certified_key| Arc::new(ReloadableTlsCertificate::new(tls_certified_key)));
+        let management_tls_server_config = reloadable_tls_certificate
+            .clone()
+            .map(|reloadable_tls_certificate| {
+                create_tls_server_config(
+                    configuration.tls_client_ca_file.as_deref(),
+                    reloadable_tls_certificate,
+                )
+            })
+            .transpose()?;
+        let tls_server_config = reloadable_tls_certificate
+            .clone()
+            .map(|reloadable_tls_certificate| {
+                create_tls_server_config(None, reloadable_tls_certificate)
+            })
+            .transpose()?;
+        let initial_balancer_desired_state = self
+            .initial_balancer_desired_state
+            .clone()
+            .unwrap_or_default();
+        // Every service that changes the desired state holds it, so none of them
+        // overwrites a change it has not seen
+        let balancer_desired_state_write_lock = Arc::new(Mutex::new(()));
         let mut service_manager = ServiceManager::default();
         let state_database: Arc<dyn StateDatabase> = match &self.state_database {
-            StateDatabaseType::File(path) => Arc::new(File::new(
+            StateDatabaseType::File(path) => {
+                let file = Arc::new(File::new(
+                    balancer_desired_state_tx.clone(),
+                    initial_balancer_desired_state,
+                    path.to_owned(),
+                ));
+
+                service_manager.add_service(StateDatabaseFileWatchService {
+                    balancer_desired_state_write_lock: balancer_desired_state_write_lock.clone(),
+                    poll_interval: self.state_database_poll_interval,
+                    state_database: file.clone(),
+                });
+
+                file
+            }
+            StateDatabaseType::Memory => Arc::new(Memory::new(
                 balancer_desired_state_tx.clone(),
-                path.to_owned(),
+                initial_balancer_desired_state,
             )),
-            StateDatabaseType::Memory => Arc::new(Memory::new(balancer_desired_state_tx.clone())),
+            StateDatabaseType::Sqlite(path) => Arc::new(Sqlite::new(
+                balancer_desired_state_tx.clone(),
+                initial_balancer_desired_state,
+                path.to_owned(),
+            )?),
         };
 
+        agent_token_registry_holder
+            .set_stored_agent_tokens(state_database.read_agent_tokens().await?);
+        api_key_manager.set_api_keys(state_database.read_api_keys().await?);
+        api_key_manager.set_priority_class_api_keys(&configuration.priority_class_api_keys);
+        management_access_control.set_stored_tokens(state_database.read_management_tokens().await?);
+
         service_manager.add_service(InferenceService {
+            api_key_manager: api_key_manager.clone(),
             balancer_applicable_state_holder: balancer_applicable_state_holder.clone(),
             buffered_request_manager: buffered_request_manager.clone(),
-            configuration: self.get_inference_service_configuration(),
-            #[cfg(feature = "web_admin_panel")]
-            web_admin_panel_service_configuration: self.get_web_admin_panel_service_configuration(),
+            configuration_holder: inference_service_configuration_holder.clone(),
+            cors_allowed_hosts: inference_cors_allowed_hosts.clone(),
+            drain_status: drain_status.clone(),
+            tls_server_config: tls_server_config.clone(),
+            unix_socket_mode: self.unix_socket_mode,
         });
 
         service_manager.add_service(ManagementService {
             agent_controller_pool: agent_controller_pool.clone(),
+            agent_token_registry_holder: agent_token_registry_holder.clone(),
+            api_key_manager: api_key_manager.clone(),
             balancer_applicable_state_holder: balancer_applicable_state_holder.clone(),
+            balancer_desired_state_write_lock: balancer_desired_state_write_lock.clone(),
             buffered_request_manager: buffered_request_manager.clone(),
             chat_template_override_sender_collection,
             configuration: self.get_management_service_configuration(),
+            cors_allowed_hosts: management_cors_allowed_hosts.clone(),
             embedding_sender_collection,
             generate_tokens_sender_collection,
+            management_access_control: management_access_control.clone(),
+            management_audit_log,
             model_metadata_sender_collection,
+            requires_if_match: self.require_if_match,
             state_database: state_database.clone(),
             statsd_prefix: self.statsd_prefix.clone(),
-            #[cfg(feature = "web_admin_panel")]
-            web_admin_panel_service_configuration: self.get_web_admin_panel_service_configuration(),
+            tls_server_config: management_tls_server_config,
+            unix_socket_mode: self.unix_socket_mode,
         });
 
         service_manager.add_service(ReconciliationService {
@@ -193,36 +909,147 @@ impl Handler for Balancer {
             balancer_applicable_state_holder,
             balancer_desired_state: state_database.read_balancer_desired_state().await?,
             balancer_desired_state_rx,
+            balancer_desired_state_write_lock: balancer_desired_state_write_lock.clone(),
             is_converted_to_applicable_state: false,
+            last_scheduled_minute: None,
+            state_database: state_database.clone(),
         });
 
-        if let Some(statsd_addr) = self.statsd_addr {
+        if let Some(statsd_service_configuration) = configuration.get_statsd_service_configuration()
+        {
             service_manager.add_service(StatsdService {
                 agent_controller_pool,
+                api_key_manager: api_key_manager.clone(),
                 buffered_request_manager: buffered_request_manager.clone(),
-                configuration: StatsdServiceConfiguration {
-                    statsd_addr,
-                    statsd_prefix: self.statsd_prefix.clone(),
-                    statsd_reporting_interval: self.statsd_reporting_interval,
-                },
+                configuration: statsd_service_configuration,
+                configuration_rx: statsd_service_configuration_rx,
+                reported_counters: ReportedCounters::default(),
             });
         }
 
         #[cfg(feature = "web_admin_panel")]
         if let Some(configuration) = self.get_web_admin_panel_service_configuration() {
-            service_manager.add_service(WebAdminPanelService { configuration });
+            service_manager.add_service(WebAdminPanelService {
+                configuration,
+                tls_server_config: tls_server_config.clone(),
+            });
         }
 
-        if let Some(compat_openai_addr) = self.compat_openai_addr {
+        if let Some(compat_openai_addr) = self.compat_openai_addr.clone() {
             service_manager.add_service(OpenAIService {
-                buffered_request_manager,
-                inference_service_configuration: self.get_inference_service_configuration(),
+                api_key_manager: api_key_manager.clone(),
+                buffered_request_manager: buffered_request_manager.clone(),
+                cors_allowed_hosts: inference_cors_allowed_hosts.clone(),
+                drain_status: drain_status.clone(),
+                inference_service_configuration_holder: inference_service_configuration_holder
+                    .clone(),
                 openai_service_configuration: OpenAIServiceConfiguration {
                     addr: compat_openai_addr,
                 },
+                tls_server_config,
+                unix_socket_mode: self.unix_socket_mode,
             });
         }
 
-        service_manager.run_forever(shutdown_rx).await
+        service_manager.add_service(ReloadService {
+            agent_token_registry_holder,
+            api_key_manager,
+            balancer_desired_state_tx,
+            balancer_desired_state_write_lock,
+            buffered_request_manager: buffered_request_manager.clone(),
+            configuration,
+            configuration_loader: Arc::new(self.clone()),
+            inference_cors_allowed_hosts,
+            inference_service_configuration_holder,
+            management_access_control,
+            management_cors_allowed_hosts,
+            reload_rx,
+            reloadable_tls_certificate,
+            state_database: state_database.clone(),
+            statsd_service_configuration_tx,
+        });
+
+        let (service_manager_shutdown_tx, service_manager_shutdown_rx) = oneshot::channel::<()>();
+        let shutdown_grace_period = self.shutdown_grace_period;
+
+        tokio::spawn(async move {
+            if shutdown_rx.await.is_ok() {
+                info!("Draining requests before shutting down");
+
+                drain_status.start_draining();
+
+                if timeout(
+                    shutdown_grace_period,
+                    buffered_request_manager.wait_for_requests_to_finish(),
+                )
+                .await
+                .is_err()
+                {
+                    warn!(
+                        "Shutdown grace period is over, rejecting the remaining buffered requests"
+                    );
+                }
+
+                buffered_request_manager.reject_buffered_requests();
+            }
+
+            if service_manager_shutdown_tx.send(()).is_err() {
+                error!("Failed to send shutdown signal to the services");
+            }
+        });
+
+        service_manager
+            .run_forever(service_manager_shutdown_rx)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use clap::CommandFactory as _;
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    fn parse_balancer(configuration_file: &NamedTempFile) -> Result<Balancer> {
+        let arg_matches = Balancer::command().try_get_matches_from([
+            "balancer".to_string(),
+            "--config".to_string(),
+            configuration_file.path().display().to_string(),
+        ])?;
+        let mut balancer = Balancer::from_arg_matches(&arg_matches)?;
+
+        balancer.apply_configuration_file(&arg_matches)?;
+
+        Ok(balancer)
+    }
+
+    #[test]
+    fn test_reload_reads_the_configuration_file_again() -> Result<()> {
+        let configuration_file = tempfile::Builder::new().suffix(".toml").tempfile()?;
+
+        fs::write(
+            configuration_file.path(),
+            "[balancer]\nmax_buffered_requests = 5\n",
+        )?;
+
+        let balancer = parse_balancer(&configuration_file)?;
+
+        fs::write(
+            configuration_file.path(),
+            "[balancer]\nmax_buffered_requests = 10\n",
+        )?;
+
+        assert_eq!(balancer.max_buffered_requests, 5);
+        assert_eq!(
+            balancer
+                .load_reloadable_configuration()?
+                .max_buffered_requests,
+            10
+        );
+
+        Ok(())
     }
 }
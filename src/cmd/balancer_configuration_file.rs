@@ -0,0 +1,82 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use super::deserialize_optional_duration;
+use super::deserialize_optional_from_str;
+use super::deserialize_optional_server_addr;
+use super::deserialize_optional_socket_addr;
+use super::deserialize_optional_vec_from_str;
+use crate::balancer::buffered_request_priority_class::BufferedRequestPriorityClass;
+use crate::balancer::buffered_request_priority_class_api_key::BufferedRequestPriorityClassApiKey;
+use crate::balancer::state_database_type::StateDatabaseType;
+use crate::balancer::unix_socket_mode::UnixSocketMode;
+use crate::balancer_desired_state::BalancerDesiredState;
+use crate::full_buffer_policy::FullBufferPolicy;
+use crate::server_addr::ServerAddr;
+
+/// Mirrors the command line options of the balancer. Durations are in milliseconds.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BalancerConfigurationFile {
+    pub agent_tokens_file: Option<PathBuf>,
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub agent_circuit_breaker_cooldown: Option<Duration>,
+    pub agent_circuit_breaker_failure_ratio: Option<f64>,
+    pub agent_circuit_breaker_failure_ratio_min_requests: Option<usize>,
+    pub agent_circuit_breaker_failure_ratio_window: Option<usize>,
+    pub agent_circuit_breaker_failure_threshold: Option<usize>,
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub agent_heartbeat_interval: Option<Duration>,
+    pub agent_heartbeat_missed_beats: Option<u32>,
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub agent_resume_grace_period: Option<Duration>,
+    pub allow_anonymous_management_access: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub buffered_request_timeout: Option<Duration>,
+    #[serde(default, deserialize_with = "deserialize_optional_server_addr")]
+    pub compat_openai_addr: Option<ServerAddr>,
+    /// Used only if the state database does not hold any state yet
+    pub desired_state: Option<BalancerDesiredState>,
+    #[serde(default, deserialize_with = "deserialize_optional_server_addr")]
+    pub inference_addr: Option<ServerAddr>,
+    pub inference_cors_allowed_hosts: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub inference_item_timeout: Option<Duration>,
+    #[serde(default, deserialize_with = "deserialize_optional_server_addr")]
+    pub management_addr: Option<ServerAddr>,
+    pub management_audit_log_file: Option<PathBuf>,
+    pub management_cors_allowed_hosts: Option<Vec<String>>,
+    pub management_tokens_file: Option<PathBuf>,
+    pub max_buffered_requests: Option<i32>,
+    pub max_request_attempts: Option<usize>,
+    #[serde(default, deserialize_with = "deserialize_optional_vec_from_str")]
+    pub priority_class_api_keys: Option<Vec<BufferedRequestPriorityClassApiKey>>,
+    #[serde(default, deserialize_with = "deserialize_optional_vec_from_str")]
+    pub priority_classes: Option<Vec<BufferedRequestPriorityClass>>,
+    pub require_api_key: Option<bool>,
+    pub require_if_match: Option<bool>,
+    pub response_buffer_capacity: Option<usize>,
+    #[serde(default, deserialize_with = "deserialize_optional_from_str")]
+    pub response_buffer_full_policy: Option<FullBufferPolicy>,
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub shutdown_grace_period: Option<Duration>,
+    #[serde(default, deserialize_with = "deserialize_optional_from_str")]
+    pub state_database: Option<StateDatabaseType>,
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub state_database_poll_interval: Option<Duration>,
+    #[serde(default, deserialize_with = "deserialize_optional_socket_addr")]
+    pub statsd_addr: Option<SocketAddr>,
+    pub statsd_prefix: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub statsd_reporting_interval: Option<Duration>,
+    pub tls_cert_file: Option<PathBuf>,
+    pub tls_client_ca_file: Option<PathBuf>,
+    pub tls_key_file: Option<PathBuf>,
+    #[serde(default, deserialize_with = "deserialize_optional_from_str")]
+    pub unix_socket_mode: Option<UnixSocketMode>,
+    #[serde(default, deserialize_with = "deserialize_optional_socket_addr")]
+    pub web_admin_panel_addr: Option<SocketAddr>,
+}
@@ -0,0 +1,75 @@
+use std::ffi::OsString;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use anyhow::anyhow;
+use clap::CommandFactory;
+use clap::FromArgMatches as _;
+use clap::Parser;
+use clap::Subcommand;
+
+use super::agent::Agent;
+use super::balancer::Balancer;
+use super::configuration_file::ConfigurationFile;
+
+#[derive(Parser)]
+pub struct Config {
+    #[command(subcommand)]
+    command: ConfigCommand,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Checks the configuration file and reports the errors with their location in the file
+    Validate {
+        /// Path to the TOML or YAML configuration file
+        path: PathBuf,
+    },
+}
+
+impl Config {
+    pub fn handle(&self) -> Result<()> {
+        match &self.command {
+            ConfigCommand::Validate { path } => validate(path),
+        }
+    }
+}
+
+fn config_args(name: &str, path: &Path) -> Vec<OsString> {
+    vec![name.into(), "--config".into(), path.into()]
+}
+
+fn validate(path: &Path) -> Result<()> {
+    let configuration_file = ConfigurationFile::read(path)?;
+
+    // The options are built the same way the commands build them at startup, so the values
+    // that only fail once they are combined with the environment and defaults are caught too
+    if configuration_file.agent.is_some() {
+        let arg_matches = Agent::command()
+            .try_get_matches_from(config_args("agent", path))
+            .map_err(|err| anyhow!("{}: agent: {err}", path.display()))?;
+        let mut agent = Agent::from_arg_matches(&arg_matches)?;
+
+        agent.apply_configuration_file(&arg_matches)?;
+        agent
+            .validate_configuration()
+            .map_err(|err| anyhow!("{}: agent: {err}", path.display()))?;
+    }
+
+    if configuration_file.balancer.is_some() {
+        let arg_matches = Balancer::command()
+            .try_get_matches_from(config_args("balancer", path))
+            .map_err(|err| anyhow!("{}: balancer: {err}", path.display()))?;
+        let mut balancer = Balancer::from_arg_matches(&arg_matches)?;
+
+        balancer.apply_configuration_file(&arg_matches)?;
+        balancer
+            .validate_configuration()
+            .map_err(|err| anyhow!("{}: balancer: {err}", path.display()))?;
+    }
+
+    println!("Configuration file '{}' is valid", path.display());
+
+    Ok(())
+}
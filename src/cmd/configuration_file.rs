@@ -0,0 +1,432 @@
+use std::borrow::Cow;
+use std::env;
+use std::env::VarError;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use anyhow::anyhow;
+use serde::Deserialize;
+use shellexpand::LookupError;
+
+use super::agent_configuration_file::AgentConfigurationFile;
+use super::balancer_configuration_file::BalancerConfigurationFile;
+use super::configuration_line_segment::ConfigurationLineSegment;
+
+/// Both the agent and the balancer can share a single file; each of them reads
+/// only its own section.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigurationFile {
+    pub agent: Option<AgentConfigurationFile>,
+    pub balancer: Option<BalancerConfigurationFile>,
+}
+
+impl ConfigurationFile {
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path).context(format!(
+            "Unable to read configuration file '{}'",
+            path.display()
+        ))?;
+
+        Self::parse(path, &contents)
+    }
+
+    fn parse(path: &Path, contents: &str) -> Result<Self> {
+        let interpolated_contents = interpolate_environment_variables(path, contents)?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => toml::from_str(&interpolated_contents)
+                .map_err(|err| anyhow!("{}: {err}", path.display())),
+            Some("yaml") | Some("yml") => serde_norway::from_str(&interpolated_contents)
+                .map_err(|err| anyhow!("{}: {err}", path.display())),
+            _ => Err(anyhow!(
+                "Unsupported configuration file format: '{}'. Use a .toml, .yaml or .yml file",
+                path.display()
+            )),
+        }
+    }
+}
+
+/// Expands `$NAME`, `${NAME}` and `${NAME:-default}` line by line, so the line numbers
+/// reported by the parsers still point to the original file.
+fn interpolate_environment_variables(path: &Path, contents: &str) -> Result<String> {
+    let mut interpolated_lines = Vec::new();
+
+    for (line_index, line) in contents.split('\n').enumerate() {
+        match interpolate_line(line) {
+            Ok(interpolated_line) => interpolated_lines.push(interpolated_line),
+            Err(err) => {
+                return Err(anyhow!(
+                    "{}: line {}: environment variable '{}' is not set",
+                    path.display(),
+                    line_index + 1,
+                    err.var_name
+                ));
+            }
+        }
+    }
+
+    Ok(interpolated_lines.join("\n"))
+}
+
+/// Comments and single-quoted strings are literal in both TOML and YAML, so they are copied
+/// unchanged, and `$$` stands for a literal `$` everywhere else. The values are escaped, so
+/// they cannot change the structure of the file.
+fn interpolate_line(line: &str) -> Result<String, LookupError<VarError>> {
+    let mut interpolated_line = String::new();
+
+    for segment in split_configuration_line(line) {
+        match segment {
+            ConfigurationLineSegment::Bare(segment) => {
+                interpolated_line.push_str(&expand_segment(segment, quote_unless_plain)?);
+            }
+            ConfigurationLineSegment::DoubleQuoted(segment) => {
+                interpolated_line.push_str(&expand_segment(segment, escape_double_quoted)?);
+            }
+            ConfigurationLineSegment::Literal(segment) => interpolated_line.push_str(segment),
+        }
+    }
+
+    Ok(interpolated_line)
+}
+
+fn expand_segment(
+    segment: &str,
+    escape: fn(&str) -> String,
+) -> Result<String, LookupError<VarError>> {
+    let expanded_parts = segment
+        .split("$$")
+        .map(|part| {
+            shellexpand::env_with_context(part, |name| {
+                env::var(name).map(|value| Some(escape(&value)))
+            })
+            .map(Cow::into_owned)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(expanded_parts.join("$"))
+}
+
+/// Uses only the escape sequences that TOML and YAML double-quoted strings have in common.
+fn escape_double_quoted(value: &str) -> String {
+    let mut escaped_value = String::with_capacity(value.len());
+
+    for character in value.chars() {
+        match character {
+            '"' => escaped_value.push_str("\\\""),
+            '\\' => escaped_value.push_str("\\\\"),
+            '\n' => escaped_value.push_str("\\n"),
+            '\r' => escaped_value.push_str("\\r"),
+            '\t' => escaped_value.push_str("\\t"),
+            character if character.is_control() => {
+                escaped_value.push_str(&format!("\\u{:04X}", u32::from(character)));
+            }
+            character => escaped_value.push(character),
+        }
+    }
+
+    escaped_value
+}
+
+/// Numbers, booleans and simple words are inserted as they are; anything else becomes
+/// a double-quoted string.
+fn quote_unless_plain(value: &str) -> String {
+    let is_plain = !value.is_empty()
+        && value.chars().all(|character| {
+            character.is_ascii_alphanumeric()
+                || matches!(character, '+' | '-' | '.' | '/' | ':' | '_')
+        });
+
+    if is_plain {
+        value.to_string()
+    } else {
+        format!("\"{}\"", escape_double_quoted(value))
+    }
+}
+
+/// Single quotes inside double-quoted strings do not start a literal. A `#` starts a comment
+/// at the beginning of the line or after whitespace, outside of quotes.
+fn split_configuration_line(line: &str) -> Vec<ConfigurationLineSegment<'_>> {
+    let mut segments = Vec::new();
+    let mut is_escaped = false;
+    let mut is_in_double_quotes = false;
+    let mut is_in_single_quotes = false;
+    let mut segment_start = 0;
+
+    for (index, character) in line.char_indices() {
+        if is_in_single_quotes {
+            if character == '\'' {
+                segments.push(ConfigurationLineSegment::Literal(
+                    &line[segment_start..=index],
+                ));
+                segment_start = index + 1;
+                is_in_single_quotes = false;
+            }
+        } else if is_in_double_quotes {
+            if is_escaped {
+                is_escaped = false;
+            } else if character == '\\' {
+                is_escaped = true;
+            } else if character == '"' {
+                segments.push(ConfigurationLineSegment::DoubleQuoted(
+                    &line[segment_start..=index],
+                ));
+                segment_start = index + 1;
+                is_in_double_quotes = false;
+            }
+        } else if character == '"' || character == '\'' {
+            segments.push(ConfigurationLineSegment::Bare(&line[segment_start..index]));
+            segment_start = index;
+            is_in_double_quotes = character == '"';
+            is_in_single_quotes = character == '\'';
+        } else if character == '#'
+            && line[..index]
+                .chars()
+                .next_back()
+                .is_none_or(char::is_whitespace)
+        {
+            segments.push(ConfigurationLineSegment::Bare(&line[segment_start..index]));
+            segments.push(ConfigurationLineSegment::Literal(&line[index..]));
+
+            return segments;
+        }
+    }
+
+    let remainder = &line[segment_start..];
+
+    segments.push(if is_in_double_quotes {
+        ConfigurationLineSegment::DoubleQuoted(remainder)
+    } else if is_in_single_quotes {
+        ConfigurationLineSegment::Literal(remainder)
+    } else {
+        ConfigurationLineSegment::Bare(remainder)
+    });
+
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::agent::management_addr::ManagementAddr;
+
+    #[test]
+    fn test_parse_toml() -> Result<()> {
+        let configuration_file = ConfigurationFile::parse(
+            &PathBuf::from("paddler.toml"),
+            r#"
+                [agent]
+                management_addrs = ["127.0.0.1:8060", "unix:/run/paddler.sock"]
+                slots = 4
+
+                [balancer]
+                buffered_request_timeout = 2000
+                inference_cors_allowed_hosts = ["http://example.com"]
+
+                [balancer.desired_state]
+                model = { LocalToAgent = "/models/model.gguf" }
+                use_chat_template_override = false
+
+                [balancer.desired_state.inference_parameters]
+                batch_n_tokens = 512
+                context_size = 4096
+                enable_embeddings = false
+                min_p = 0.05
+                penalty_frequency = 0.0
+                penalty_last_n = -1
+                penalty_presence = 1.5
+                penalty_repeat = 1.0
+                pooling_type = "Last"
+                temperature = 0.6
+                top_k = 40
+                top_p = 0.8
+            "#,
+        )?;
+
+        let agent = configuration_file.agent.unwrap();
+        let balancer = configuration_file.balancer.unwrap();
+
+        assert_eq!(
+            agent.management_addrs,
+            Some(vec![
+                ManagementAddr::Tcp {
+                    host: "127.0.0.1".to_string(),
+                    port: 8060,
+                },
+                ManagementAddr::Unix(PathBuf::from("/run/paddler.sock")),
+            ])
+        );
+        assert_eq!(agent.slots, Some(4));
+        assert_eq!(
+            balancer.buffered_request_timeout,
+            Some(Duration::from_millis(2000))
+        );
+        assert_eq!(
+            balancer.inference_cors_allowed_hosts,
+            Some(vec!["http://example.com".to_string()])
+        );
+        assert!(balancer.desired_state.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_yaml() -> Result<()> {
+        let configuration_file = ConfigurationFile::parse(
+            &PathBuf::from("paddler.yaml"),
+            "balancer:\n  max_buffered_requests: 50\n  priority_classes:\n    - interactive:10:50:2000\n",
+        )?;
+
+        let balancer = configuration_file.balancer.unwrap();
+
+        assert_eq!(balancer.max_buffered_requests, Some(50));
+        assert_eq!(balancer.priority_classes.unwrap()[0].name, "interactive");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_field_reports_location() {
+        let result = ConfigurationFile::parse(
+            &PathBuf::from("paddler.toml"),
+            "[balancer]\nmax_buffered_requests = 5\nunknown_option = 1\n",
+        );
+
+        let message = result.err().unwrap().to_string();
+
+        assert!(message.contains("line 3"), "{message}");
+    }
+
+    #[test]
+    fn test_missing_environment_variable_reports_line() {
+        let result = ConfigurationFile::parse(
+            &PathBuf::from("paddler.toml"),
+            "[balancer]\nstatsd_prefix = \"${PADDLER_TEST_SURELY_UNSET_VARIABLE}\"\n",
+        );
+
+        let message = result.err().unwrap().to_string();
+
+        assert!(message.contains("line 2"), "{message}");
+        assert!(
+            message.contains("PADDLER_TEST_SURELY_UNSET_VARIABLE"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn test_environment_variable_default_value() -> Result<()> {
+        let configuration_file = ConfigurationFile::parse(
+            &PathBuf::from("paddler.toml"),
+            "[balancer]\nstatsd_prefix = \"${PADDLER_TEST_SURELY_UNSET_VARIABLE:-custom_}\"\n",
+        )?;
+
+        assert_eq!(
+            configuration_file.balancer.unwrap().statsd_prefix,
+            Some("custom_".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_comment_lines_are_not_interpolated() -> Result<()> {
+        let configuration_file = ConfigurationFile::parse(
+            &PathBuf::from("paddler.toml"),
+            "[balancer]\n# statsd_prefix = \"${PADDLER_TEST_SURELY_UNSET_VARIABLE}\"\nmax_buffered_requests = 5\n",
+        )?;
+
+        assert_eq!(
+            configuration_file.balancer.unwrap().max_buffered_requests,
+            Some(5)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_trailing_comments_are_not_interpolated() -> Result<()> {
+        let configuration_file = ConfigurationFile::parse(
+            &PathBuf::from("paddler.toml"),
+            "[balancer]\nmax_buffered_requests = 5 # ${PADDLER_TEST_SURELY_UNSET_VARIABLE}\nstatsd_prefix = \"a#${PADDLER_TEST_SURELY_UNSET_VARIABLE:-b}\" # c\n",
+        )?;
+        let balancer = configuration_file.balancer.unwrap();
+
+        assert_eq!(balancer.max_buffered_requests, Some(5));
+        assert_eq!(balancer.statsd_prefix, Some("a#b".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_escaped_values_keep_the_toml_structure() -> Result<()> {
+        let value = "a\"b\\c\nd = 1\u{7}";
+        let configuration_file = ConfigurationFile::parse(
+            &PathBuf::from("paddler.toml"),
+            &format!(
+                "[balancer]\nstatsd_prefix = \"{}\"\n",
+                escape_double_quoted(value)
+            ),
+        )?;
+
+        assert_eq!(
+            configuration_file.balancer.unwrap().statsd_prefix,
+            Some(value.to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_quoted_values_keep_the_yaml_structure() -> Result<()> {
+        let value = "a: b\n  max_buffered_requests: 1 # \"c\"";
+        let configuration_file = ConfigurationFile::parse(
+            &PathBuf::from("paddler.yaml"),
+            &format!(
+                "balancer:\n  statsd_prefix: {}\n  max_buffered_requests: {}\n",
+                quote_unless_plain(value),
+                quote_unless_plain("5")
+            ),
+        )?;
+        let balancer = configuration_file.balancer.unwrap();
+
+        assert_eq!(balancer.max_buffered_requests, Some(5));
+        assert_eq!(balancer.statsd_prefix, Some(value.to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_single_quoted_values_are_not_interpolated() -> Result<()> {
+        let configuration_file = ConfigurationFile::parse(
+            &PathBuf::from("paddler.yaml"),
+            "balancer:\n  statsd_prefix: '${PADDLER_TEST_SURELY_UNSET_VARIABLE}'\n",
+        )?;
+
+        assert_eq!(
+            configuration_file.balancer.unwrap().statsd_prefix,
+            Some("${PADDLER_TEST_SURELY_UNSET_VARIABLE}".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_double_dollar_is_a_literal_dollar() -> Result<()> {
+        let configuration_file = ConfigurationFile::parse(
+            &PathBuf::from("paddler.toml"),
+            "[balancer]\nstatsd_prefix = \"price$$PADDLER_TEST_SURELY_UNSET_VARIABLE's_\"\n",
+        )?;
+
+        assert_eq!(
+            configuration_file.balancer.unwrap().statsd_prefix,
+            Some("price$PADDLER_TEST_SURELY_UNSET_VARIABLE's_".to_string())
+        );
+
+        Ok(())
+    }
+}
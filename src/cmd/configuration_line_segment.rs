@@ -0,0 +1,9 @@
+/// Part of a configuration file line, split by how the environment variables in it are expanded.
+pub enum ConfigurationLineSegment<'line> {
+    /// Outside of quotes and comments
+    Bare(&'line str),
+    /// Double-quoted string, together with its quotes
+    DoubleQuoted(&'line str),
+    /// Single-quoted string or a comment, copied unchanged
+    Literal(&'line str),
+}
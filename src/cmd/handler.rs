@@ -1,8 +1,13 @@
 use anyhow::Result;
 use async_trait::async_trait;
+use tokio::sync::broadcast;
 use tokio::sync::oneshot;
 
 #[async_trait]
 pub trait Handler {
-    async fn handle(&self, shutdown_rx: oneshot::Receiver<()>) -> Result<()>;
+    async fn handle(
+        &self,
+        reload_rx: broadcast::Receiver<()>,
+        shutdown_rx: oneshot::Receiver<()>,
+    ) -> Result<()>;
 }
@@ -1,13 +1,26 @@
 pub mod agent;
+mod agent_configuration_file;
 pub mod balancer;
+mod balancer_configuration_file;
+pub mod config;
+mod configuration_file;
+mod configuration_line_segment;
 pub mod handler;
 
 use std::net::SocketAddr;
 use std::net::ToSocketAddrs;
+use std::str::FromStr;
 use std::time::Duration;
 
 use anyhow::Result;
 use anyhow::anyhow;
+use clap::ArgMatches;
+use clap::parser::ValueSource;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::de::Error as _;
+
+use crate::server_addr::ServerAddr;
 
 fn resolve_socket_addr(s: &str) -> Result<SocketAddr> {
     let addrs: Vec<SocketAddr> = s.to_socket_addrs()?.collect();
@@ -33,9 +46,148 @@ fn parse_duration(arg: &str) -> Result<Duration> {
     Ok(std::time::Duration::from_millis(milliseconds))
 }
 
+fn parse_server_addr(arg: &str) -> Result<ServerAddr> {
+    if arg.starts_with("unix:") {
+        return arg.parse();
+    }
+
+    Ok(ServerAddr::Tcp(parse_socket_addr(arg)?))
+}
+
 fn parse_socket_addr(arg: &str) -> Result<SocketAddr> {
     match arg.parse() {
         Ok(socketaddr) => Ok(socketaddr),
         Err(_) => Ok(resolve_socket_addr(arg)?),
     }
 }
+
+fn deserialize_optional_duration<'de, TDeserializer>(
+    deserializer: TDeserializer,
+) -> Result<Option<Duration>, TDeserializer::Error>
+where
+    TDeserializer: Deserializer<'de>,
+{
+    Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_millis))
+}
+
+fn deserialize_optional_from_str<'de, TDeserializer, TValue>(
+    deserializer: TDeserializer,
+) -> Result<Option<TValue>, TDeserializer::Error>
+where
+    TDeserializer: Deserializer<'de>,
+    TValue: FromStr<Err = anyhow::Error>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| TValue::from_str(&value).map_err(TDeserializer::Error::custom))
+        .transpose()
+}
+
+fn deserialize_optional_server_addr<'de, TDeserializer>(
+    deserializer: TDeserializer,
+) -> Result<Option<ServerAddr>, TDeserializer::Error>
+where
+    TDeserializer: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| parse_server_addr(&value).map_err(TDeserializer::Error::custom))
+        .transpose()
+}
+
+fn deserialize_optional_socket_addr<'de, TDeserializer>(
+    deserializer: TDeserializer,
+) -> Result<Option<SocketAddr>, TDeserializer::Error>
+where
+    TDeserializer: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| parse_socket_addr(&value).map_err(TDeserializer::Error::custom))
+        .transpose()
+}
+
+fn deserialize_optional_vec_from_str<'de, TDeserializer, TValue>(
+    deserializer: TDeserializer,
+) -> Result<Option<Vec<TValue>>, TDeserializer::Error>
+where
+    TDeserializer: Deserializer<'de>,
+    TValue: FromStr<Err = anyhow::Error>,
+{
+    Option::<Vec<String>>::deserialize(deserializer)?
+        .map(|values| {
+            values
+                .iter()
+                .map(|value| TValue::from_str(value).map_err(TDeserializer::Error::custom))
+                .collect()
+        })
+        .transpose()
+}
+
+/// Values from the configuration file are used only when the option was not set
+/// on the command line or through an environment variable.
+fn merge_configuration_file_value<TValue>(
+    arg_matches: &ArgMatches,
+    id: &str,
+    target: &mut TValue,
+    configuration_file_value: Option<TValue>,
+) {
+    if let Some(configuration_file_value) = configuration_file_value {
+        match arg_matches.value_source(id) {
+            Some(ValueSource::CommandLine) | Some(ValueSource::EnvVariable) => {}
+            _ => *target = configuration_file_value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Arg;
+    use clap::Command;
+
+    use super::*;
+
+    fn merge_into_option(environment_variable: Option<&'static str>, args: &[&str]) -> String {
+        let mut arg = Arg::new("option").long("option").default_value("default");
+
+        if let Some(environment_variable) = environment_variable {
+            arg = arg.env(environment_variable);
+        }
+
+        let arg_matches = Command::new("paddler").arg(arg).get_matches_from(args);
+        let mut value = arg_matches
+            .get_one::<String>("option")
+            .cloned()
+            .unwrap_or_default();
+
+        merge_configuration_file_value(
+            &arg_matches,
+            "option",
+            &mut value,
+            Some("file".to_string()),
+        );
+
+        value
+    }
+
+    #[test]
+    fn test_configuration_file_overrides_the_default() {
+        assert_eq!(merge_into_option(None, &["paddler"]), "file");
+    }
+
+    // PATH is set in every test environment, so it stands in for the PADDLER_* variables
+    #[test]
+    fn test_environment_variable_overrides_the_configuration_file() -> Result<()> {
+        assert_eq!(
+            merge_into_option(Some("PATH"), &["paddler"]),
+            std::env::var("PATH")?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_line_overrides_the_environment_variable() {
+        assert_eq!(
+            merge_into_option(Some("PATH"), &["paddler", "--option", "flag"]),
+            "flag"
+        );
+    }
+}
@@ -46,6 +46,25 @@ pub trait ControlsWebSocketEndpoint: Send + Sync + 'static {
         websocket_session_controller: WebSocketSessionController<Self::OutgoingMessage>,
     ) -> Result<ContinuationDecision>;
 
+    /// Every message is handled in its own task by default. Endpoints that have to handle
+    /// some of the messages in order can queue them instead; the socket is not read while
+    /// this waits, so a full queue slows down the peer
+    async fn dispatch_deserialized_message(
+        connection_close_tx: broadcast::Sender<()>,
+        context: Arc<Self::Context>,
+        deserialized_message: Self::IncomingMessage,
+        websocket_session_controller: WebSocketSessionController<Self::OutgoingMessage>,
+    ) -> Result<ContinuationDecision> {
+        Self::spawn_deserialized_message_handler(
+            connection_close_tx,
+            context,
+            deserialized_message,
+            websocket_session_controller,
+        );
+
+        Ok(ContinuationDecision::Continue)
+    }
+
     async fn handle_aggregated_message(
         connection_close_tx: broadcast::Sender<()>,
         context: Arc<Self::Context>,
@@ -53,10 +72,14 @@ pub trait ControlsWebSocketEndpoint: Send + Sync + 'static {
         session: &mut Session,
     ) -> Result<ContinuationDecision> {
         match msg {
-            Some(Ok(AggregatedMessage::Binary(_))) => {
-                debug!("Received binary message, but only text messages are supported");
-
-                Ok(ContinuationDecision::Continue)
+            Some(Ok(AggregatedMessage::Binary(bytes))) => {
+                Self::handle_binary_message(
+                    connection_close_tx,
+                    context,
+                    &bytes,
+                    WebSocketSessionController::<Self::OutgoingMessage>::new(session.clone()),
+                )
+                .await
             }
             Some(Ok(AggregatedMessage::Close(_))) => return Ok(ContinuationDecision::Stop),
             Some(Ok(AggregatedMessage::Ping(msg))) => {
@@ -97,6 +120,18 @@ pub trait ControlsWebSocketEndpoint: Send + Sync + 'static {
         }
     }
 
+    /// Only endpoints that negotiate a binary framing accept binary messages
+    async fn handle_binary_message(
+        _connection_close_tx: broadcast::Sender<()>,
+        _context: Arc<Self::Context>,
+        _bytes: &[u8],
+        _websocket_session_controller: WebSocketSessionController<Self::OutgoingMessage>,
+    ) -> Result<ContinuationDecision> {
+        debug!("Received binary message, but only text messages are supported");
+
+        Ok(ContinuationDecision::Continue)
+    }
+
     async fn handle_serialization_error(
         _connection_close_tx: broadcast::Sender<()>,
         _context: Arc<Self::Context>,
@@ -116,34 +151,13 @@ pub trait ControlsWebSocketEndpoint: Send + Sync + 'static {
     ) -> Result<ContinuationDecision> {
         match serde_json::from_str::<Self::IncomingMessage>(text) {
             Ok(deserialized_message) => {
-                rt::spawn(async move {
-                    match Self::handle_deserialized_message(
-                        connection_close_tx.clone(),
-                        context,
-                        deserialized_message,
-                        websocket_session_controller,
-                    )
-                    .await
-                    {
-                        Ok(ContinuationDecision::Continue) => {
-                            // Continue processing messages
-                        }
-                        Ok(ContinuationDecision::Stop) => {
-                            if let Err(close_err) = connection_close_tx.send(()) {
-                                error!("Failed to send continuation shutdown signal: {close_err}");
-                            }
-                        }
-                        Err(err) => {
-                            error!("Error handling deserialized message: {err:?}");
-
-                            if let Err(close_err) = connection_close_tx.send(()) {
-                                error!("Failed to send error shutdown signal: {close_err}");
-                            }
-                        }
-                    }
-                });
-
-                Ok(ContinuationDecision::Continue)
+                Self::dispatch_deserialized_message(
+                    connection_close_tx,
+                    context,
+                    deserialized_message,
+                    websocket_session_controller,
+                )
+                .await
             }
             Err(err @ serde_json::Error { .. }) if err.is_data() || err.is_syntax() => {
                 error!("JSON-RPC syntax error: {err:?}");
@@ -241,4 +255,38 @@ pub trait ControlsWebSocketEndpoint: Send + Sync + 'static {
 
         Ok(res)
     }
+
+    fn spawn_deserialized_message_handler(
+        connection_close_tx: broadcast::Sender<()>,
+        context: Arc<Self::Context>,
+        deserialized_message: Self::IncomingMessage,
+        websocket_session_controller: WebSocketSessionController<Self::OutgoingMessage>,
+    ) {
+        rt::spawn(async move {
+            match Self::handle_deserialized_message(
+                connection_close_tx.clone(),
+                context,
+                deserialized_message,
+                websocket_session_controller,
+            )
+            .await
+            {
+                Ok(ContinuationDecision::Continue) => {
+                    // Continue processing messages
+                }
+                Ok(ContinuationDecision::Stop) => {
+                    if let Err(close_err) = connection_close_tx.send(()) {
+                        error!("Failed to send continuation shutdown signal: {close_err}");
+                    }
+                }
+                Err(err) => {
+                    error!("Error handling deserialized message: {err:?}");
+
+                    if let Err(close_err) = connection_close_tx.send(()) {
+                        error!("Failed to send error shutdown signal: {close_err}");
+                    }
+                }
+            }
+        });
+    }
 }
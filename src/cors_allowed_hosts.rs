@@ -0,0 +1,32 @@
+use std::sync::RwLock;
+
+use actix_web::http::header::HeaderValue;
+
+pub struct CorsAllowedHosts {
+    hosts: RwLock<Vec<String>>,
+}
+
+impl CorsAllowedHosts {
+    pub fn new(hosts: Vec<String>) -> Self {
+        Self {
+            hosts: RwLock::new(hosts),
+        }
+    }
+
+    pub fn is_allowed(&self, origin: &HeaderValue) -> bool {
+        self.hosts
+            .read()
+            .expect("Failed to get CORS allowed hosts lock")
+            .iter()
+            .any(|host| host.as_bytes() == origin.as_bytes())
+    }
+
+    pub fn set_hosts(&self, hosts: Vec<String>) {
+        let mut lock = self
+            .hosts
+            .write()
+            .expect("Failed to get CORS allowed hosts lock");
+
+        *lock = hosts;
+    }
+}
@@ -3,19 +3,18 @@ use std::sync::Arc;
 use actix_cors::Cors;
 use actix_web::http::header;
 
-pub fn create_cors_middleware(allowed_hosts: Arc<Vec<String>>) -> Cors {
-    let mut cors = Cors::default()
-        .allowed_methods(vec!["DELETE", "GET", "POST", "PUT", "OPTIONS"])
+use crate::cors_allowed_hosts::CorsAllowedHosts;
+
+pub fn create_cors_middleware(cors_allowed_hosts: Arc<CorsAllowedHosts>) -> Cors {
+    Cors::default()
+        .allowed_origin_fn(move |origin, _request_head| cors_allowed_hosts.is_allowed(origin))
+        .allowed_methods(vec!["DELETE", "GET", "PATCH", "POST", "PUT", "OPTIONS"])
         .allowed_headers(vec![
             header::ACCEPT,
             header::AUTHORIZATION,
             header::CONTENT_TYPE,
+            header::IF_MATCH,
         ])
-        .max_age(3600);
-
-    for host in allowed_hosts.iter() {
-        cors = cors.allowed_origin(host);
-    }
-
-    cors
+        .expose_headers(vec![header::ETAG])
+        .max_age(3600)
 }
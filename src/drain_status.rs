@@ -0,0 +1,44 @@
+use std::sync::atomic::AtomicBool;
+
+use tokio::sync::Notify;
+
+use crate::atomic_value::AtomicValue;
+
+pub struct DrainStatus {
+    is_draining: AtomicValue<AtomicBool>,
+    pub update_notifier: Notify,
+}
+
+impl DrainStatus {
+    pub fn is_draining(&self) -> bool {
+        self.is_draining.get()
+    }
+
+    pub fn start_draining(&self) {
+        if self.is_draining.set_check(true) {
+            self.update_notifier.notify_waiters();
+        }
+    }
+
+    pub async fn wait_for_draining(&self) {
+        loop {
+            // Created before the check, so a notification sent in between is not lost
+            let update_notified = self.update_notifier.notified();
+
+            if self.is_draining() {
+                return;
+            }
+
+            update_notified.await;
+        }
+    }
+}
+
+impl Default for DrainStatus {
+    fn default() -> Self {
+        Self {
+            is_draining: AtomicValue::<AtomicBool>::new(false),
+            update_notifier: Notify::new(),
+        }
+    }
+}
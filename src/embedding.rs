@@ -8,7 +8,7 @@ use crate::normalization::l2;
 use crate::normalization::rms_norm;
 use crate::pooling_type::PoolingType;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Embedding {
     pub embedding: Vec<f32>,
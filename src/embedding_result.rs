@@ -4,16 +4,33 @@ use serde::Serialize;
 use crate::embedding::Embedding;
 use crate::streamable_result::StreamableResult;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub enum EmbeddingResult {
     Done,
     Embedding(Embedding),
     Error(String),
+    /// Sent only to the balancers that support the token usage reporting
+    InputTokens(usize),
 }
 
 impl StreamableResult for EmbeddingResult {
     fn is_done(&self) -> bool {
         matches!(self, EmbeddingResult::Done | EmbeddingResult::Error(_))
     }
+
+    fn is_error(&self) -> bool {
+        matches!(self, EmbeddingResult::Error(_))
+    }
+
+    fn is_usage_report(&self) -> bool {
+        matches!(self, EmbeddingResult::InputTokens(_))
+    }
+
+    fn used_tokens(&self) -> usize {
+        match self {
+            EmbeddingResult::InputTokens(input_tokens) => *input_tokens,
+            _ => 0,
+        }
+    }
 }
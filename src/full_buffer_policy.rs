@@ -0,0 +1,59 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::Error;
+use anyhow::Result;
+use anyhow::anyhow;
+
+/// What happens to a request when the client does not read its responses fast enough
+/// and the response buffer fills up.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FullBufferPolicy {
+    /// Fail the request and stop generating its responses
+    Abort,
+    /// Stop generating the responses until the client catches up
+    Pause,
+}
+
+impl fmt::Display for FullBufferPolicy {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FullBufferPolicy::Abort => write!(formatter, "abort"),
+            FullBufferPolicy::Pause => write!(formatter, "pause"),
+        }
+    }
+}
+
+impl FromStr for FullBufferPolicy {
+    type Err = Error;
+
+    fn from_str(policy: &str) -> Result<Self> {
+        match policy {
+            "abort" => Ok(FullBufferPolicy::Abort),
+            "pause" => Ok(FullBufferPolicy::Pause),
+            _ => Err(anyhow!(
+                "Unknown full buffer policy: '{policy}'. Expected 'abort' or 'pause'"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        assert_eq!(
+            "abort".parse::<FullBufferPolicy>()?,
+            FullBufferPolicy::Abort
+        );
+        assert_eq!(
+            "pause".parse::<FullBufferPolicy>()?,
+            FullBufferPolicy::Pause
+        );
+        assert!("drop".parse::<FullBufferPolicy>().is_err());
+
+        Ok(())
+    }
+}
@@ -3,11 +3,13 @@ use serde::Serialize;
 
 use crate::streamable_result::StreamableResult;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub enum GeneratedTokenResult {
     ChatTemplateError(String),
     Done,
+    /// Sent only to the balancers that support the token usage reporting
+    PromptTokens(usize),
     Token(String),
 }
 
@@ -18,4 +20,20 @@ impl StreamableResult for GeneratedTokenResult {
             GeneratedTokenResult::ChatTemplateError(_) | GeneratedTokenResult::Done
         )
     }
+
+    fn is_error(&self) -> bool {
+        matches!(self, GeneratedTokenResult::ChatTemplateError(_))
+    }
+
+    fn is_usage_report(&self) -> bool {
+        matches!(self, GeneratedTokenResult::PromptTokens(_))
+    }
+
+    fn used_tokens(&self) -> usize {
+        match self {
+            GeneratedTokenResult::PromptTokens(prompt_tokens) => *prompt_tokens,
+            GeneratedTokenResult::Token(_) => 1,
+            _ => 0,
+        }
+    }
 }
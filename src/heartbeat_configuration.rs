@@ -0,0 +1,9 @@
+use std::time::Duration;
+
+#[derive(Clone)]
+pub struct HeartbeatConfiguration {
+    pub interval: Duration,
+    /// Number of intervals without any message from the other side after which
+    /// the connection is considered dead (0 disables the detection)
+    pub missed_beats_threshold: u32,
+}
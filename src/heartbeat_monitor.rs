@@ -0,0 +1,82 @@
+use std::sync::Mutex;
+
+use tokio::time::Instant;
+
+use crate::heartbeat_configuration::HeartbeatConfiguration;
+
+/// Tracks when the other side of a connection was last heard from. Every received message
+/// counts as a beat, not only the heartbeat notifications.
+pub struct HeartbeatMonitor {
+    configuration: HeartbeatConfiguration,
+    last_beat_at: Mutex<Instant>,
+}
+
+impl HeartbeatMonitor {
+    pub fn new(configuration: HeartbeatConfiguration) -> Self {
+        Self {
+            configuration,
+            last_beat_at: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn has_missed_beats(&self) -> bool {
+        if self.configuration.missed_beats_threshold == 0 {
+            return false;
+        }
+
+        self.last_beat_at
+            .lock()
+            .expect("Poisoned lock on last heartbeat")
+            .elapsed()
+            > self.configuration.interval * self.configuration.missed_beats_threshold
+    }
+
+    pub fn record_beat(&self) {
+        *self
+            .last_beat_at
+            .lock()
+            .expect("Poisoned lock on last heartbeat") = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::advance;
+
+    use super::*;
+
+    fn make_heartbeat_monitor(missed_beats_threshold: u32) -> HeartbeatMonitor {
+        HeartbeatMonitor::new(HeartbeatConfiguration {
+            interval: Duration::from_secs(1),
+            missed_beats_threshold,
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_misses_beats_after_threshold() {
+        let heartbeat_monitor = make_heartbeat_monitor(2);
+
+        advance(Duration::from_secs(2)).await;
+
+        assert!(!heartbeat_monitor.has_missed_beats());
+
+        advance(Duration::from_millis(1)).await;
+
+        assert!(heartbeat_monitor.has_missed_beats());
+
+        heartbeat_monitor.record_beat();
+
+        assert!(!heartbeat_monitor.has_missed_beats());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_zero_threshold_disables_detection() {
+        let heartbeat_monitor = make_heartbeat_monitor(0);
+
+        advance(Duration::from_secs(60)).await;
+
+        assert!(!heartbeat_monitor.has_missed_beats());
+    }
+}
@@ -3,7 +3,7 @@ use serde::Serialize;
 
 use crate::pooling_type::PoolingType;
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct InferenceParameters {
     pub batch_n_tokens: usize,
@@ -7,6 +7,7 @@ pub mod agent_issue;
 pub mod agent_issue_fix;
 pub mod agent_issue_params;
 pub mod agent_state_application_status;
+pub mod apply_json_merge_patch;
 pub mod atomic_value;
 pub mod balancer;
 pub mod balancer_applicable_state;
@@ -19,26 +20,39 @@ pub mod controls_session;
 pub mod controls_websocket_endpoint;
 pub mod conversation_message;
 pub mod converts_to_applicable_state;
+pub mod cors_allowed_hosts;
 pub mod create_cors_middleware;
 pub mod dispenses_slots;
+pub mod drain_status;
 pub mod embedding;
 pub mod embedding_input_document;
 pub mod embedding_input_tokenized;
 pub mod embedding_normalization_method;
 pub mod embedding_result;
+pub mod full_buffer_policy;
 pub mod generated_token_result;
 pub mod grammar_parser;
 pub mod grammar_service;
+pub mod heartbeat_configuration;
+pub mod heartbeat_monitor;
 pub mod huggingface_model_reference;
 pub mod inference_parameters;
 pub mod jsonrpc;
+pub mod message_framing;
 pub mod model_metadata;
 pub mod normalization;
 pub mod pooling_type;
 pub mod produces_snapshot;
+pub mod protocol_feature;
+pub mod protocol_version_range;
+pub mod read_pem_certificates;
+pub mod read_pem_private_key;
+pub mod read_root_cert_store;
 pub mod request_params;
+pub mod response_buffer_configuration;
 pub mod rpc_message;
 pub mod sends_rpc_message;
+pub mod server_addr;
 pub mod service;
 pub mod service_manager;
 pub mod sets_desired_state;
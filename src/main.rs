@@ -1,14 +1,21 @@
 use anyhow::Result;
+use anyhow::anyhow;
+use clap::ArgMatches;
+use clap::CommandFactory as _;
+use clap::FromArgMatches as _;
 use clap::Parser;
 use clap::Subcommand;
 #[cfg(feature = "web_admin_panel")]
 use esbuild_metafile::instance::initialize_instance;
 use log::info;
+use log::warn;
 use paddler::cmd::agent::Agent;
 use paddler::cmd::balancer::Balancer;
+use paddler::cmd::config::Config;
 use paddler::cmd::handler::Handler as _;
 use tokio::signal::unix::SignalKind;
 use tokio::signal::unix::signal;
+use tokio::sync::broadcast;
 use tokio::sync::oneshot;
 
 #[cfg(feature = "web_admin_panel")]
@@ -29,23 +36,36 @@ enum Commands {
     Agent(Agent),
     /// Distributes incoming requests among agents
     Balancer(Balancer),
+    /// Works with the configuration files
+    Config(Config),
 }
 
 #[actix_web::main]
 async fn main() -> Result<()> {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
 
+    let (reload_tx, reload_rx) = broadcast::channel::<()>(1);
     let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
 
+    tokio::spawn(async move {
+        let mut sighup = signal(SignalKind::hangup()).expect("Failed to listen for SIGHUP");
+
+        while sighup.recv().await.is_some() {
+            info!("Received SIGHUP, reloading configuration");
+
+            if reload_tx.send(()).is_err() {
+                warn!("Nothing to reload");
+            }
+        }
+    });
+
     tokio::spawn(async move {
         let mut sigterm = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM");
         let mut sigint = signal(SignalKind::interrupt()).expect("Failed to listen for SIGINT");
-        let mut sighup = signal(SignalKind::hangup()).expect("Failed to listen for SIGHUP");
 
         tokio::select! {
             _ = sigterm.recv() => info!("Received SIGTERM"),
             _ = sigint.recv() => info!("Received SIGINT (Ctrl+C)"),
-            _ = sighup.recv() => info!("Received SIGHUP"),
         }
 
         shutdown_tx
@@ -53,14 +73,30 @@ async fn main() -> Result<()> {
             .expect("Failed to send shutdown signal");
     });
 
-    match Cli::parse().command {
-        Some(Commands::Agent(handler)) => Ok(handler.handle(shutdown_rx).await?),
-        Some(Commands::Balancer(handler)) => {
+    let arg_matches = Cli::command().get_matches();
+
+    match Cli::from_arg_matches(&arg_matches)?.command {
+        Some(Commands::Agent(mut handler)) => {
+            handler.apply_configuration_file(get_subcommand_arg_matches(&arg_matches)?)?;
+
+            Ok(handler.handle(reload_rx, shutdown_rx).await?)
+        }
+        Some(Commands::Balancer(mut handler)) => {
+            handler.apply_configuration_file(get_subcommand_arg_matches(&arg_matches)?)?;
+
             #[cfg(feature = "web_admin_panel")]
             initialize_instance(ESBUILD_META_CONTENTS);
 
-            Ok(handler.handle(shutdown_rx).await?)
+            Ok(handler.handle(reload_rx, shutdown_rx).await?)
         }
+        Some(Commands::Config(handler)) => handler.handle(),
         None => Ok(()),
     }
 }
+
+fn get_subcommand_arg_matches(arg_matches: &ArgMatches) -> Result<&ArgMatches> {
+    arg_matches
+        .subcommand()
+        .map(|(_name, subcommand_arg_matches)| subcommand_arg_matches)
+        .ok_or_else(|| anyhow!("Subcommand arguments are missing"))
+}
@@ -0,0 +1,41 @@
+use std::collections::BTreeSet;
+
+use crate::protocol_feature::ProtocolFeature;
+
+/// Encoding of the messages sent over the agent socket. Received messages are decoded
+/// based on the frame type, so each side picks the framing of its outgoing messages
+/// from the features the other side announced.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MessageFraming {
+    /// JSON in text frames, understood by every protocol version
+    Json,
+    /// MessagePack in binary frames
+    MessagePack,
+}
+
+impl MessageFraming {
+    pub fn from_features(features: &BTreeSet<ProtocolFeature>) -> Self {
+        if features.contains(&ProtocolFeature::MessagePackFraming) {
+            MessageFraming::MessagePack
+        } else {
+            MessageFraming::Json
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_falls_back_to_json() {
+        assert_eq!(
+            MessageFraming::from_features(&BTreeSet::from([ProtocolFeature::Heartbeat])),
+            MessageFraming::Json
+        );
+        assert_eq!(
+            MessageFraming::from_features(&BTreeSet::from(ProtocolFeature::SUPPORTED)),
+            MessageFraming::MessagePack
+        );
+    }
+}
@@ -2,7 +2,7 @@ use llama_cpp_2::context::params::LlamaPoolingType;
 use serde::Deserialize;
 use serde::Serialize;
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[repr(i8)]
 pub enum PoolingType {
     Unspecified = -1,
@@ -0,0 +1,51 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Optional parts of the agent protocol. Each side announces the features it implements
+/// during the handshake, and only uses a feature if the other side announced it too.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum ProtocolFeature {
+    Heartbeat,
+    MessagePackFraming,
+    /// Resuming the requests that were in flight when the agent reconnects
+    RequestResumption,
+    /// Pausing and resuming the responses of a single request
+    ResponseFlowControl,
+    /// Reporting the prompt and embedding input tokens, so they count towards the API key limits
+    TokenUsageReporting,
+    /// Feature announced by a newer peer that this build does not know about
+    #[serde(other)]
+    Unknown,
+}
+
+impl ProtocolFeature {
+    pub const SUPPORTED: [ProtocolFeature; 5] = [
+        ProtocolFeature::Heartbeat,
+        ProtocolFeature::MessagePackFraming,
+        ProtocolFeature::RequestResumption,
+        ProtocolFeature::ResponseFlowControl,
+        ProtocolFeature::TokenUsageReporting,
+    ];
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn test_deserializes_unknown_features() -> Result<()> {
+        let features: BTreeSet<ProtocolFeature> =
+            serde_json::from_str(r#"["Heartbeat", "FeatureFromTheFuture"]"#)?;
+
+        assert_eq!(
+            features,
+            BTreeSet::from([ProtocolFeature::Heartbeat, ProtocolFeature::Unknown])
+        );
+
+        Ok(())
+    }
+}
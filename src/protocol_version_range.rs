@@ -0,0 +1,71 @@
+use std::fmt;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Versions of the agent protocol a build can speak. Both sides announce their range during
+/// the handshake, and the connection uses the newest version they have in common.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProtocolVersionRange {
+    pub max: u32,
+    pub min: u32,
+}
+
+impl ProtocolVersionRange {
+    /// Sent by the agent when it connects, so the balancer knows it can announce its own range
+    pub const HEADER_NAME: &'static str = "paddler-protocol-version-range";
+    pub const SUPPORTED: ProtocolVersionRange = ProtocolVersionRange { max: 2, min: 1 };
+
+    /// Peers from before the negotiation was introduced do not announce any range
+    pub fn legacy() -> Self {
+        Self { max: 1, min: 1 }
+    }
+
+    pub fn negotiate(&self, other: &ProtocolVersionRange) -> Option<u32> {
+        let version = self.max.min(other.max);
+
+        if version >= self.min.max(other.min) {
+            Some(version)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for ProtocolVersionRange {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.min == self.max {
+            write!(formatter, "{}", self.min)
+        } else {
+            write!(formatter, "{}-{}", self.min, self.max)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiates_newest_common_version() {
+        assert_eq!(
+            ProtocolVersionRange { max: 3, min: 1 }
+                .negotiate(&ProtocolVersionRange { max: 5, min: 2 }),
+            Some(3)
+        );
+        assert_eq!(
+            ProtocolVersionRange::SUPPORTED.negotiate(&ProtocolVersionRange::legacy()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_rejects_disjoint_ranges() {
+        assert_eq!(
+            ProtocolVersionRange { max: 2, min: 1 }
+                .negotiate(&ProtocolVersionRange { max: 4, min: 3 }),
+            None
+        );
+    }
+}
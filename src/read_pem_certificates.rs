@@ -0,0 +1,21 @@
+use std::path::Path;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use anyhow::anyhow;
+use rustls_pki_types::CertificateDer;
+use rustls_pki_types::pem::PemObject as _;
+
+/// Reads every certificate from a PEM file, in the order they appear.
+pub fn read_pem_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certificates = CertificateDer::pem_file_iter(path)
+        .with_context(|| format!("Unable to read certificates from {}", path.display()))?
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Invalid certificate in {}", path.display()))?;
+
+    if certificates.is_empty() {
+        return Err(anyhow!("No certificates found in {}", path.display()));
+    }
+
+    Ok(certificates)
+}
@@ -0,0 +1,12 @@
+use std::path::Path;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use rustls_pki_types::PrivateKeyDer;
+use rustls_pki_types::pem::PemObject as _;
+
+/// Reads the first PKCS#1, PKCS#8 or SEC1 private key from a PEM file.
+pub fn read_pem_private_key(path: &Path) -> Result<PrivateKeyDer<'static>> {
+    PrivateKeyDer::from_pem_file(path)
+        .with_context(|| format!("Unable to read private key from {}", path.display()))
+}
@@ -0,0 +1,20 @@
+use std::path::Path;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use rustls::RootCertStore;
+
+use crate::read_pem_certificates::read_pem_certificates;
+
+/// Trusts only the certificate authorities from the given PEM bundle.
+pub fn read_root_cert_store(path: &Path) -> Result<RootCertStore> {
+    let mut root_cert_store = RootCertStore::empty();
+
+    for certificate in read_pem_certificates(path)? {
+        root_cert_store
+            .add(certificate)
+            .with_context(|| format!("Invalid certificate authority in {}", path.display()))?;
+    }
+
+    Ok(root_cert_store)
+}
@@ -0,0 +1,8 @@
+use crate::full_buffer_policy::FullBufferPolicy;
+
+#[derive(Clone)]
+pub struct ResponseBufferConfiguration {
+    /// Number of responses of a single request that can wait for the client
+    pub capacity: usize,
+    pub full_buffer_policy: FullBufferPolicy,
+}
@@ -0,0 +1,64 @@
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::Result;
+use anyhow::anyhow;
+
+/// Either a TCP address, or a Unix domain socket written as `unix:/path/to/socket`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ServerAddr {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl fmt::Display for ServerAddr {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerAddr::Tcp(socket_addr) => write!(formatter, "{socket_addr}"),
+            ServerAddr::Unix(path) => write!(formatter, "unix:{}", path.display()),
+        }
+    }
+}
+
+impl FromStr for ServerAddr {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.strip_prefix("unix:") {
+            Some("") => Err(anyhow!("Unix socket path is empty")),
+            Some(path) => Ok(ServerAddr::Unix(PathBuf::from(path))),
+            None => Ok(ServerAddr::Tcp(value.parse()?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        assert_eq!(
+            "127.0.0.1:8060".parse::<ServerAddr>()?,
+            ServerAddr::Tcp("127.0.0.1:8060".parse()?)
+        );
+        assert_eq!(
+            "unix:/run/paddler/management.sock".parse::<ServerAddr>()?,
+            ServerAddr::Unix(PathBuf::from("/run/paddler/management.sock"))
+        );
+        assert!("unix:".parse::<ServerAddr>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_display_round_trips() -> Result<()> {
+        let server_addr: ServerAddr = "unix:/run/paddler/inference.sock".parse()?;
+
+        assert_eq!(server_addr.to_string().parse::<ServerAddr>()?, server_addr);
+
+        Ok(())
+    }
+}
@@ -56,10 +56,31 @@ impl SlotAggregatedStatus {
         self.update_notifier.notify_waiters();
     }
 
+    pub fn get_slots_processing(&self) -> i32 {
+        self.slots_processing.get()
+    }
+
     pub fn get_state_application_status(&self) -> Result<AgentStateApplicationStatus> {
         self.state_application_status_code.get().try_into()
     }
 
+    pub async fn wait_for_slots_to_be_released(&self) {
+        loop {
+            let notified = self.update_notifier.notified();
+
+            tokio::pin!(notified);
+
+            // Register interest before checking, so the last released slot is not missed
+            notified.as_mut().enable();
+
+            if self.slots_processing.get() < 1 {
+                return;
+            }
+
+            notified.await;
+        }
+    }
+
     pub fn has_issue(&self, issue: &AgentIssue) -> bool {
         self.issues.contains(issue)
     }
@@ -1,3 +1,11 @@
 pub trait StreamableResult {
     fn is_done(&self) -> bool;
+
+    fn is_error(&self) -> bool;
+
+    /// Only reports the usage to the balancer, and is not forwarded to the clients.
+    fn is_usage_report(&self) -> bool;
+
+    /// Counts towards the token limits of the API key.
+    fn used_tokens(&self) -> usize;
 }
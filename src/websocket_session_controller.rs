@@ -6,12 +6,14 @@ use async_trait::async_trait;
 use serde::Serialize;
 
 use crate::controls_session::ControlsSession;
+use crate::message_framing::MessageFraming;
 use crate::rpc_message::RpcMessage;
 
 pub struct WebSocketSessionController<TResponse>
 where
     TResponse: RpcMessage + Send + Serialize + Sync,
 {
+    message_framing: MessageFraming,
     session: Session,
     _marker: PhantomData<TResponse>,
 }
@@ -22,10 +24,15 @@ where
 {
     pub fn new(session: Session) -> Self {
         WebSocketSessionController {
+            message_framing: MessageFraming::Json,
             session,
             _marker: PhantomData,
         }
     }
+
+    pub fn set_message_framing(&mut self, message_framing: MessageFraming) {
+        self.message_framing = message_framing;
+    }
 }
 
 #[async_trait]
@@ -34,9 +41,16 @@ where
     TResponse: RpcMessage + Send + Serialize + Sync + 'static,
 {
     async fn send_response(&mut self, message: TResponse) -> Result<()> {
-        let serialized_message = serde_json::to_string(&message)?;
-
-        self.session.text(serialized_message).await?;
+        match self.message_framing {
+            MessageFraming::Json => {
+                self.session.text(serde_json::to_string(&message)?).await?;
+            }
+            MessageFraming::MessagePack => {
+                self.session
+                    .binary(rmp_serde::to_vec_named(&message)?)
+                    .await?;
+            }
+        }
 
         Ok(())
     }